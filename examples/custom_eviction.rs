@@ -0,0 +1,167 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal custom LRU eviction algorithm plugged into the raw in-memory cache, without `unsafe`.
+//!
+//! Run with `cargo run --example custom_eviction --features custom-eviction`.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use foyer::{
+    CacheProperties, DefaultHasher, Eviction, Key, MemoryError, Metrics, Op, RawCache, RawCacheConfig, Record,
+    StateOwner, Value,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SeqLruConfig;
+
+/// Per-entry state, links the record to its position in the queue.
+#[derive(Debug, Default)]
+struct SeqLruState {
+    link: Option<u64>,
+}
+
+/// LRU that orders the records by the sequence of their last access.
+struct SeqLru<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    queue: BTreeMap<u64, Arc<Record<Self>>>,
+    seq: u64,
+    capacity: usize,
+    /// Owner of the states of the records held by this instance.
+    owner: StateOwner,
+}
+
+impl<K, V> SeqLru<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    fn link(&mut self, record: Arc<Record<Self>>) {
+        let seq = self.seq;
+        self.seq += 1;
+        record.state_mut(&mut self.owner).unwrap().link = Some(seq);
+        self.queue.insert(seq, record);
+    }
+
+    fn unlink(&mut self, record: &Arc<Record<Self>>) -> Arc<Record<Self>> {
+        let seq = record.state_mut(&mut self.owner).unwrap().link.take().unwrap();
+        self.queue.remove(&seq).unwrap()
+    }
+}
+
+impl<K, V> Eviction for SeqLru<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    type Config = SeqLruConfig;
+    type Key = K;
+    type Value = V;
+    type Properties = CacheProperties;
+    type State = SeqLruState;
+
+    fn new(capacity: usize, _: &Self::Config) -> Self {
+        Self {
+            queue: BTreeMap::new(),
+            seq: 0,
+            capacity,
+            owner: StateOwner::new(),
+        }
+    }
+
+    fn update(&mut self, capacity: usize, _: Option<&Self::Config>) -> Result<(), MemoryError> {
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
+    fn push(&mut self, record: Arc<Record<Self>>) {
+        assert!(record.bind(&self.owner));
+        record.set_in_eviction(true);
+        self.link(record);
+    }
+
+    fn pop(&mut self) -> Option<Arc<Record<Self>>> {
+        let (_, record) = self.queue.pop_first()?;
+        record.state_mut(&mut self.owner).unwrap().link = None;
+        record.unbind(&mut self.owner);
+        record.set_in_eviction(false);
+        Some(record)
+    }
+
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        self.queue.values().take(n).cloned().collect()
+    }
+
+    fn remove(&mut self, record: &Arc<Record<Self>>) {
+        self.unlink(record);
+        record.unbind(&mut self.owner);
+        record.set_in_eviction(false);
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        self.queue.values().cloned().collect()
+    }
+
+    fn acquire() -> Op<Self> {
+        Op::mutable(|this: &mut Self, record| {
+            // The entry may have been evicted or removed while it is still referenced.
+            if record.is_in_eviction() {
+                let record = this.unlink(record);
+                this.link(record);
+            }
+        })
+    }
+
+    fn release() -> Op<Self> {
+        Op::noop()
+    }
+}
+
+fn main() {
+    let cache: RawCache<SeqLru<u64, String>, DefaultHasher> = RawCache::new(RawCacheConfig {
+        capacity: 2,
+        shards: 1,
+        shard_capacities: None,
+        hot_entry: false,
+        eviction_config: SeqLruConfig,
+        hash_builder: DefaultHasher::default(),
+        hash_domain: 0,
+        weighter: Arc::new(|_, _| 1),
+        count_entries: false,
+        filter: Arc::new(|_, _| true),
+        max_admit_weight: None,
+        skip_referenced: false,
+        negative_ttl: None,
+        event_listener: None,
+        metrics: Arc::new(Metrics::noop()),
+    });
+
+    cache.insert(1, "one".to_string());
+    cache.insert(2, "two".to_string());
+    // Access 1, so 2 becomes the least recently used entry.
+    assert_eq!(cache.get(&1).as_deref().map(String::as_str), Some("one"));
+    cache.insert(3, "three".to_string());
+
+    assert!(cache.get(&2).is_none());
+    assert!(cache.contains(&1));
+    assert!(cache.contains(&3));
+}
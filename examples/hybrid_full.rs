@@ -47,6 +47,7 @@ async fn main() -> anyhow::Result<()> {
         .with_shards(4)
         .with_eviction_config(LruConfig {
             high_priority_pool_ratio: 0.1,
+            scan_resistant: false,
         })
         .with_hash_builder(BuildHasherDefault::default())
         .with_weighter(|_key, value: &String| value.len())
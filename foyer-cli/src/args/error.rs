@@ -14,15 +14,17 @@
 
 use std::fmt::Debug;
 
+use crate::args::fio::FioError;
+
 /// Disk cache error type.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// I/O error.
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
-    /// `fio` not available.
-    #[error("fio not available")]
-    FioNotAvailable,
+    /// `fio` error.
+    #[error("fio error: {0}")]
+    Fio(#[from] FioError),
     /// Other error.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -12,14 +12,105 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashSet, process::Command};
-
-use anyhow::anyhow;
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    process::{Command, Output},
+};
 
 use crate::args::error::{Error, Result};
 
 type IoEngine = String;
 
+/// Static information of a `fio` io engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineInfo {
+    /// Name of the io engine.
+    pub name: String,
+    /// If the io engine submits ios asynchronously.
+    pub asynchronous: bool,
+    /// Typical io depth for the io engine. Synchronous io engines can only have 1 inflight io per job.
+    pub typical_queue_depth: usize,
+}
+
+/// Describe an io engine.
+pub trait Describe {
+    /// Get the static information of the io engine.
+    fn describe(&self) -> EngineInfo;
+}
+
+impl Describe for IoEngine {
+    fn describe(&self) -> EngineInfo {
+        let asynchronous = matches!(
+            self.as_str(),
+            "io_uring" | "io_uring_cmd" | "libaio" | "posixaio" | "solarisaio" | "windowsaio"
+        );
+        let typical_queue_depth = if asynchronous { 32 } else { 1 };
+        EngineInfo {
+            name: self.clone(),
+            asynchronous,
+            typical_queue_depth,
+        }
+    }
+}
+
+/// Io engines grouped by whether they are asynchronous, sorted by name in each group.
+#[derive(Debug)]
+pub struct IoEngineGroups {
+    asynchronous: Vec<EngineInfo>,
+    synchronous: Vec<EngineInfo>,
+}
+
+impl IoEngineGroups {
+    pub fn new<'a>(io_engines: impl IntoIterator<Item = &'a IoEngine>) -> Self {
+        let (mut asynchronous, mut synchronous): (Vec<_>, Vec<_>) = io_engines
+            .into_iter()
+            .map(|io_engine| io_engine.describe())
+            .partition(|info| info.asynchronous);
+        asynchronous.sort_by(|a, b| a.name.cmp(&b.name));
+        synchronous.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            asynchronous,
+            synchronous,
+        }
+    }
+}
+
+impl Display for IoEngineGroups {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (title, infos) in [("asynchronous", &self.asynchronous), ("synchronous", &self.synchronous)] {
+            writeln!(f, "{title} io engines:")?;
+            for info in infos {
+                writeln!(f, "  {} (typical queue depth: {})", info.name, info.typical_queue_depth)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `fio` error type.
+#[derive(thiserror::Error, Debug)]
+pub enum FioError {
+    /// `fio` is not installed or cannot be executed.
+    #[error("fio is not installed")]
+    NotInstalled,
+    /// Fail to parse the io engine list reported by `fio`.
+    #[error("fail to parse fio io engine list: {0:?}")]
+    EngineListParse(String),
+    /// `fio` exits with a failure status.
+    #[error("fio run failed: {stderr}")]
+    RunFailed {
+        /// The stderr output of `fio`.
+        stderr: String,
+    },
+    /// Fail to parse the json output of `fio`.
+    ///
+    /// The json output is only parsed in tests for now, so the error keeps the parser message only.
+    #[cfg_attr(not(test), expect(dead_code))]
+    #[error("fail to parse fio json output: {0}")]
+    JsonParse(String),
+}
+
 #[derive(Debug)]
 pub struct Fio {
     io_engines: HashSet<IoEngine>,
@@ -28,7 +119,7 @@ pub struct Fio {
 impl Fio {
     pub fn init() -> Result<Self> {
         if !Self::available() {
-            return Err(Error::FioNotAvailable);
+            return Err(FioError::NotInstalled.into());
         }
 
         let io_engines = Self::list_io_engines()?;
@@ -48,20 +139,143 @@ impl Fio {
         &self.io_engines
     }
 
+    pub fn io_engine_groups(&self) -> IoEngineGroups {
+        IoEngineGroups::new(self.io_engines())
+    }
+
     fn list_io_engines() -> Result<HashSet<IoEngine>> {
-        let output = Command::new("fio").arg("--enghelp").output()?;
-        if !output.status.success() {
-            return Err(anyhow!("fail to get available io engines with fio").into());
+        let output = Command::new("fio")
+            .arg("--enghelp")
+            .output()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Error::from(FioError::NotInstalled),
+                _ => Error::from(e),
+            })?;
+        let io_engines = Self::parse_io_engines(&output)?;
+        Ok(io_engines)
+    }
+
+    /// Parse the output of `fio --enghelp`.
+    ///
+    /// The first line is the header, and each following line is an available io engine.
+    fn parse_io_engines(output: &Output) -> std::result::Result<HashSet<IoEngine>, FioError> {
+        let stdout = Self::check_output(output)?;
+
+        let mut lines = stdout.split('\n');
+        if !lines.next().is_some_and(|header| header.trim_end().ends_with(':')) {
+            return Err(FioError::EngineListParse(stdout));
         }
 
-        let io_engines = String::from_utf8_lossy(&output.stdout)
-            .split('\n')
-            .skip(1)
+        let io_engines: HashSet<IoEngine> = lines
             .map(|s| s.trim())
             .filter(|s| !s.is_empty())
             .map(String::from)
             .collect();
+        if io_engines.is_empty() {
+            return Err(FioError::EngineListParse(stdout));
+        }
 
         Ok(io_engines)
     }
+
+    fn check_output(output: &Output) -> std::result::Result<String, FioError> {
+        if !output.status.success() {
+            return Err(FioError::RunFailed {
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    fn output(code: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: std::process::ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// Parse the output of `fio --output-format=json`.
+    fn parse_json_output(output: &Output) -> std::result::Result<serde_json::Value, FioError> {
+        let stdout = Fio::check_output(output)?;
+        serde_json::from_str(&stdout).map_err(|e| FioError::JsonParse(e.to_string()))
+    }
+
+    #[test]
+    fn test_parse_io_engines() {
+        let io_engines =
+            Fio::parse_io_engines(&output(0, "Available IO engines:\n\tpsync\n\tio_uring\n\n", "")).unwrap();
+        assert_eq!(
+            io_engines,
+            HashSet::from_iter(["psync".to_string(), "io_uring".to_string()])
+        );
+
+        assert!(matches!(
+            Fio::parse_io_engines(&output(0, "", "")),
+            Err(FioError::EngineListParse(_))
+        ));
+        assert!(matches!(
+            Fio::parse_io_engines(&output(0, "Available IO engines:\n", "")),
+            Err(FioError::EngineListParse(_))
+        ));
+        assert!(matches!(
+            Fio::parse_io_engines(&output(1, "", "fio: unrecognized option")),
+            Err(FioError::RunFailed { stderr }) if stderr == "fio: unrecognized option"
+        ));
+    }
+
+    #[test]
+    fn test_io_engine_groups() {
+        let io_engines = Fio::parse_io_engines(&output(
+            0,
+            "Available IO engines:\n\tpsync\n\tio_uring\n\tlibaio\n\tposixaio\n\tsync\n",
+            "",
+        ))
+        .unwrap();
+        let groups = IoEngineGroups::new(&io_engines);
+
+        fn names(infos: &[EngineInfo]) -> Vec<&str> {
+            infos.iter().map(|info| info.name.as_str()).collect()
+        }
+
+        assert_eq!(names(&groups.asynchronous), ["io_uring", "libaio", "posixaio"]);
+        assert_eq!(names(&groups.synchronous), ["psync", "sync"]);
+        assert!(groups.asynchronous.iter().all(|info| info.typical_queue_depth > 1));
+        assert!(groups.synchronous.iter().all(|info| info.typical_queue_depth == 1));
+
+        assert_eq!(
+            groups.to_string(),
+            "asynchronous io engines:\n  io_uring (typical queue depth: 32)\n  libaio (typical queue depth: 32)\n  \
+             posixaio (typical queue depth: 32)\nsynchronous io engines:\n  psync (typical queue depth: 1)\n  sync \
+             (typical queue depth: 1)\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_json_output() {
+        let value = parse_json_output(&output(0, r#"{"fio version": "fio-3.36"}"#, "")).unwrap();
+        assert_eq!(value["fio version"], "fio-3.36");
+
+        assert!(matches!(
+            parse_json_output(&output(0, "fio: bad json", "")),
+            Err(FioError::JsonParse(_))
+        ));
+        assert!(matches!(
+            parse_json_output(&output(1, "", "fio: no job")),
+            Err(FioError::RunFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_not_installed() {
+        let e: Error = FioError::NotInstalled.into();
+        assert!(matches!(e, Error::Fio(FioError::NotInstalled)));
+    }
 }
@@ -44,5 +44,5 @@ pub fn run(args: ArgsArgs) {
 
     let fio = Fio::init().unwrap();
 
-    println!("{:#?}", fio.io_engines());
+    print!("{}", fio.io_engine_groups());
 }
@@ -0,0 +1,147 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// The source of the current time.
+///
+/// Time-based components read the time through the clock, so they can be driven deterministically in tests.
+pub trait Clock: Send + Sync + 'static + Debug {
+    /// Get the current time.
+    fn now(&self) -> Instant;
+}
+
+/// The clock that reads the monotonic system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The clock that caches the monotonic system time, and refreshes it by a background thread at the given resolution.
+///
+/// Reading a coarse clock is an atomic load, which is cheaper than reading the system time on the hot path, at the
+/// cost that the time read lags behind by up to the resolution. The clones of a coarse clock share the same time, and
+/// the background thread exits after all of them are dropped.
+#[derive(Debug, Clone)]
+pub struct CoarseClock {
+    inner: Arc<CoarseClockInner>,
+}
+
+#[derive(Debug)]
+struct CoarseClockInner {
+    base: Instant,
+    /// Nanoseconds elapsed since `base`.
+    elapsed: AtomicU64,
+}
+
+impl CoarseClock {
+    /// Create a coarse clock that refreshes its time every `resolution`.
+    pub fn new(resolution: Duration) -> Self {
+        let inner = Arc::new(CoarseClockInner {
+            base: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        });
+        let weak = Arc::downgrade(&inner);
+        std::thread::Builder::new()
+            .name("foyer-coarse-clock".to_string())
+            .spawn(move || {
+                while let Some(inner) = weak.upgrade() {
+                    inner
+                        .elapsed
+                        .store(inner.base.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                    drop(inner);
+                    std::thread::sleep(resolution);
+                }
+            })
+            .expect("failed to spawn the coarse clock thread");
+        Self { inner }
+    }
+}
+
+impl Clock for CoarseClock {
+    fn now(&self) -> Instant {
+        self.inner.base + Duration::from_nanos(self.inner.elapsed.load(Ordering::Relaxed))
+    }
+}
+
+/// A manually advanced clock for tests.
+///
+/// The clones of a mock clock share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+}
+
+impl MockClock {
+    /// Advance the time of the mock clock.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock() += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::default();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        let cloned = clock.clone();
+        cloned.advance(Duration::from_secs(1));
+        assert_eq!(clock.now().duration_since(start), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_coarse_clock() {
+        let clock = CoarseClock::new(Duration::from_millis(1));
+        let start = clock.now();
+        assert!(start <= Instant::now());
+
+        std::thread::sleep(Duration::from_millis(50));
+        let now = clock.now();
+        assert!(now > start);
+        assert!(now <= Instant::now());
+        assert!(clock.clone().now() >= now);
+    }
+}
@@ -25,9 +25,18 @@ pub enum Event {
     Remove,
     /// Cache clear.
     Clear,
+    /// Cache expiration of the entries with ttl.
+    Expire,
+    /// Cache shutdown.
+    Shutdown,
 }
 
 /// Trait for the customized event listener.
+///
+/// The listener is called out of the lock critical section. It can read the cache, and modify it by key, e.g. insert
+/// or remove entries, or clear it. The listener calls triggered by these modifications are deferred until the
+/// outermost call returns. Cache-wide operations, e.g. resizing, flushing, evicting all entries or shutting down the
+/// cache, must not be called from within the listener, they panic with debug assertions enabled.
 pub trait EventListener: Send + Sync + 'static {
     /// Associated key type.
     type Key;
@@ -88,6 +88,23 @@ impl BuildHasher for ModHasher {
     }
 }
 
+/// Mix the hash domain into the hash of a key.
+///
+/// Caches sharing the same hash builder produce the same hash for the same key. Mixing a distinct domain into the
+/// hash separates them if the hashes are used as keys across caches or tiers.
+///
+/// Domain `0` leaves the hash unchanged. Different domains always produce different hashes for the same key.
+pub fn hash_with_domain(hash: u64, domain: u64) -> u64 {
+    // The finalizer of murmur3 is a bijection that maps `0` to `0`.
+    let mut d = domain;
+    d ^= d >> 33;
+    d = d.wrapping_mul(0xff51afd7ed558ccd);
+    d ^= d >> 33;
+    d = d.wrapping_mul(0xc4ceb9fe1a85ec53);
+    d ^= d >> 33;
+    hash ^ d
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +115,12 @@ mod tests {
             assert_eq!(i, ModHasher::default().hash_one(i) as u8,)
         }
     }
+
+    #[test]
+    fn test_hash_with_domain() {
+        let hash = ModHasher::default().hash_one(42u64);
+        assert_eq!(hash_with_domain(hash, 0), hash);
+        assert_ne!(hash_with_domain(hash, 1), hash);
+        assert_ne!(hash_with_domain(hash, 1), hash_with_domain(hash, 2));
+    }
 }
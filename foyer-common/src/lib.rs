@@ -22,6 +22,8 @@ pub mod asyncify;
 pub mod bits;
 /// The [`bytes::Buf`] and [`bytes::BufMut`] extensions.
 pub mod buf;
+/// The clock abstraction to read the current time.
+pub mod clock;
 /// The trait for the key and value encoding and decoding.
 pub mod code;
 /// Components for monitoring internal events.
@@ -25,6 +25,8 @@ pub struct Metrics {
     pub memory_miss: BoxedCounter,
     pub memory_remove: BoxedCounter,
     pub memory_evict: BoxedCounter,
+    pub memory_overflow: BoxedCounter,
+    pub memory_expire: BoxedCounter,
     pub memory_reinsert: BoxedCounter,
     pub memory_release: BoxedCounter,
     pub memory_queue: BoxedCounter,
@@ -122,6 +124,8 @@ impl Metrics {
         let memory_miss = foyer_memory_op_total.counter(&[name.clone(), "miss".into()]);
         let memory_remove = foyer_memory_op_total.counter(&[name.clone(), "remove".into()]);
         let memory_evict = foyer_memory_op_total.counter(&[name.clone(), "evict".into()]);
+        let memory_overflow = foyer_memory_op_total.counter(&[name.clone(), "overflow".into()]);
+        let memory_expire = foyer_memory_op_total.counter(&[name.clone(), "expire".into()]);
         let memory_reinsert = foyer_memory_op_total.counter(&[name.clone(), "reinsert".into()]);
         let memory_release = foyer_memory_op_total.counter(&[name.clone(), "release".into()]);
         let memory_queue = foyer_memory_op_total.counter(&[name.clone(), "queue".into()]);
@@ -307,6 +311,8 @@ impl Metrics {
             memory_miss,
             memory_remove,
             memory_evict,
+            memory_overflow,
+            memory_expire,
             memory_reinsert,
             memory_release,
             memory_queue,
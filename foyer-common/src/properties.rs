@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 /// Hint for the cache eviction algorithm to decide the priority of the specific entry if needed.
 ///
@@ -144,4 +144,36 @@ pub trait Properties: Send + Sync + 'static + Clone + Default + Debug {
 
     /// Entry source.
     fn source(&self) -> Option<Source>;
+
+    /// Set entry cost.
+    ///
+    /// The cost is the relative expense to recompute the entry on a miss. Cost-aware cache eviction algorithms bias
+    /// against evicting high-cost entries.
+    ///
+    /// The default implementation ignores the cost.
+    fn with_cost(self, cost: u32) -> Self {
+        let _ = cost;
+        self
+    }
+
+    /// Entry cost, `None` if the properties don't support the cost.
+    fn cost(&self) -> Option<u32> {
+        None
+    }
+
+    /// Set entry ttl.
+    ///
+    /// The entry expires after the ttl since it is inserted. Expired entries are treated as misses, and are removed
+    /// before the other entries when evicting.
+    ///
+    /// The default implementation ignores the ttl, so the entries never expire.
+    fn with_ttl(self, ttl: Duration) -> Self {
+        let _ = ttl;
+        self
+    }
+
+    /// Entry ttl, `None` if the entry never expires or the properties don't support the ttl.
+    fn ttl(&self) -> Option<Duration> {
+        None
+    }
 }
@@ -12,15 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use parking_lot::Mutex;
 
+use crate::clock::{Clock, SystemClock};
+
 /// A rate limiter that returns the wait duration for limitation.
 #[derive(Debug)]
 pub struct RateLimiter {
     inner: Mutex<Inner>,
     rate: f64,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
@@ -33,13 +39,19 @@ struct Inner {
 impl RateLimiter {
     /// Create a rate limiter that returns the wait duration for limitation.
     pub fn new(rate: f64) -> Self {
+        Self::with_clock(rate, Arc::new(SystemClock))
+    }
+
+    /// Create a rate limiter that returns the wait duration for limitation that reads the time from the given clock.
+    pub fn with_clock(rate: f64, clock: Arc<dyn Clock>) -> Self {
         let inner = Inner {
             quota: 0.0,
-            last: Instant::now(),
+            last: clock.now(),
         };
         Self {
             rate,
             inner: Mutex::new(inner),
+            clock,
         }
     }
 
@@ -49,7 +61,7 @@ impl RateLimiter {
     /// Otherwise, return [`Duration::ZERO`] indicating no need to wait.
     pub fn consume(&self, weight: f64) -> Duration {
         let mut inner = self.inner.lock();
-        let now = Instant::now();
+        let now = self.clock.now();
         let refill = now.duration_since(inner.last).as_secs_f64() * self.rate;
         inner.last = now;
         inner.quota = f64::min(inner.quota + refill, self.rate);
@@ -71,12 +83,29 @@ mod tests {
     use rand::{rng, Rng};
 
     use super::*;
+    use crate::clock::MockClock;
 
     const ERATIO: f64 = 0.05;
     const THREADS: usize = 8;
     const RATE: usize = 1000;
     const DURATION: Duration = Duration::from_secs(10);
 
+    #[test]
+    fn test_rate_limiter_with_mock_clock() {
+        let clock = MockClock::default();
+        let limiter = RateLimiter::with_clock(10.0, Arc::new(clock.clone()));
+
+        assert_eq!(limiter.consume(5.0), Duration::from_millis(500));
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(limiter.consume(5.0), Duration::from_millis(500));
+
+        // The refilled quota is capped by the rate.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(limiter.consume(5.0), Duration::ZERO);
+        assert_eq!(limiter.consume(5.0), Duration::ZERO);
+        assert_eq!(limiter.consume(5.0), Duration::from_millis(500));
+    }
+
     #[ignore]
     #[test]
     fn test_rate_limiter() {
@@ -12,15 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use parking_lot::Mutex;
 
+use crate::clock::{Clock, SystemClock};
+
 ///  A ticket-based rate limiter.
 #[derive(Debug)]
 pub struct RatedTicket {
     inner: Mutex<Inner>,
     rate: f64,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
@@ -33,13 +36,19 @@ struct Inner {
 impl RatedTicket {
     /// Create a ticket-based rate limiter.
     pub fn new(rate: f64) -> Self {
+        Self::with_clock(rate, Arc::new(SystemClock))
+    }
+
+    /// Create a ticket-based rate limiter that reads the time from the given clock.
+    pub fn with_clock(rate: f64, clock: Arc<dyn Clock>) -> Self {
         let inner = Inner {
             quota: 0.0,
-            last: Instant::now(),
+            last: clock.now(),
         };
         Self {
             rate,
             inner: Mutex::new(inner),
+            clock,
         }
     }
 
@@ -47,7 +56,7 @@ impl RatedTicket {
     pub fn probe(&self) -> bool {
         let mut inner = self.inner.lock();
 
-        let now = Instant::now();
+        let now = self.clock.now();
         let refill = now.duration_since(inner.last).as_secs_f64() * self.rate;
         inner.last = now;
         inner.quota = f64::min(inner.quota + refill, self.rate);
@@ -66,7 +75,7 @@ impl RatedTicket {
     pub fn consume(&self, weight: f64) -> bool {
         let mut inner = self.inner.lock();
 
-        let now = Instant::now();
+        let now = self.clock.now();
         let refill = now.duration_since(inner.last).as_secs_f64() * self.rate;
         inner.last = now;
         inner.quota = f64::min(inner.quota + refill, self.rate);
@@ -95,6 +104,25 @@ mod tests {
     use rand::{rng, Rng};
 
     use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn test_rated_ticket_with_mock_clock() {
+        let clock = MockClock::default();
+        let ticket = RatedTicket::with_clock(10.0, Arc::new(clock.clone()));
+
+        assert!(!ticket.probe());
+        clock.advance(Duration::from_secs(1));
+        assert!(ticket.probe());
+
+        assert!(ticket.consume(20.0));
+        assert!(!ticket.consume(1.0));
+
+        clock.advance(Duration::from_secs(1));
+        assert!(!ticket.probe());
+        clock.advance(Duration::from_millis(100));
+        assert!(ticket.probe());
+    }
 
     #[ignore]
     #[test]
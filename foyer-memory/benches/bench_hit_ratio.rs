@@ -17,7 +17,7 @@
 use std::sync::Arc;
 
 use csv::Reader;
-use foyer_memory::{Cache, CacheBuilder, FifoConfig, LfuConfig, LruConfig, S3FifoConfig};
+use foyer_memory::{Cache, CacheBuilder, FifoConfig, FrequencyBackend, LfuConfig, LruConfig, S3FifoConfig};
 use rand::{distr::Distribution, rng};
 
 type CacheKey = String;
@@ -97,6 +97,7 @@ fn new_lru_cache(capacity: usize) -> Cache<CacheKey, CacheValue> {
         .with_shards(SHARDS)
         .with_eviction_config(LruConfig {
             high_priority_pool_ratio: 0.1,
+            scan_resistant: false,
         })
         .build()
 }
@@ -109,6 +110,13 @@ fn new_lfu_cache(capacity: usize) -> Cache<CacheKey, CacheValue> {
             protected_capacity_ratio: 0.8,
             cmsketch_eps: 0.001,
             cmsketch_confidence: 0.9,
+            update_frequency_on_push: true,
+            bypass_window_size: 0,
+            initial_frequency: 0,
+            decay_factor: 2,
+            frequency_backend: FrequencyBackend::CMSketch,
+            resize_sketch: false,
+            protected_idle_timeout: None,
         })
         .build()
 }
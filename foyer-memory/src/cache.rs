@@ -12,11 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{borrow::Cow, fmt::Debug, future::Future, hash::Hash, ops::Deref, sync::Arc};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    future::Future,
+    hash::Hash,
+    io::{Read, Write},
+    ops::Deref,
+    sync::Arc,
+    time::Duration,
+};
 
 use equivalent::Equivalent;
 use foyer_common::{
-    code::{DefaultHasher, HashBuilder, Key, Value},
+    clock::Clock,
+    code::{Code, DefaultHasher, HashBuilder, Key, Value},
     event::EventListener,
     future::Diversion,
     metrics::Metrics,
@@ -30,13 +40,19 @@ use serde::{Deserialize, Serialize};
 use crate::{
     error::Error,
     eviction::{
+        decay_lfu::{DecayLfu, DecayLfuConfig},
         fifo::{Fifo, FifoConfig},
         lfu::{Lfu, LfuConfig},
         lru::{Lru, LruConfig},
         s3fifo::{S3Fifo, S3FifoConfig},
         sieve::{Sieve, SieveConfig},
+        QueueTag,
+    },
+    raw::{
+        FetchContext, FetchState, FetchTarget, Fetcher, Filter, Lookup, RawCache, RawCacheConfig, RawCacheEntry,
+        RawFetch, Weighter,
     },
-    raw::{FetchContext, FetchState, FetchTarget, Filter, RawCache, RawCacheConfig, RawCacheEntry, RawFetch, Weighter},
+    stats::CacheStats,
     Piece, Pipe, Result,
 };
 
@@ -46,6 +62,8 @@ pub struct CacheProperties {
     disposable: bool,
     ephemeral: bool,
     hint: Hint,
+    cost: u32,
+    ttl: Option<Duration>,
 }
 
 impl CacheProperties {
@@ -88,6 +106,39 @@ impl CacheProperties {
     pub fn hint(&self) -> Hint {
         self.hint
     }
+
+    /// Set entry cost.
+    ///
+    /// The cost is the relative expense to recompute the entry on a miss. The LFU eviction algorithm scales the
+    /// frequency of the entry with its cost, so high-cost entries are kept longer than low-cost ones with the same
+    /// access frequency.
+    ///
+    /// Default: `0`, which is treated the same as `1`.
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Get entry cost.
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    /// Set entry ttl.
+    ///
+    /// The entry expires after the ttl since it is inserted. An expired entry is treated as a miss, and is removed
+    /// before the other entries when evicting.
+    ///
+    /// Default: `None`, the entry never expires.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Get entry ttl.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
 }
 
 impl Properties for CacheProperties {
@@ -130,6 +181,22 @@ impl Properties for CacheProperties {
     fn source(&self) -> Option<Source> {
         None
     }
+
+    fn with_cost(self, cost: u32) -> Self {
+        self.with_cost(cost)
+    }
+
+    fn cost(&self) -> Option<u32> {
+        Some(self.cost())
+    }
+
+    fn with_ttl(self, ttl: Duration) -> Self {
+        self.with_ttl(ttl)
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        self.ttl()
+    }
 }
 
 pub type FifoCache<K, V, S = DefaultHasher, P = CacheProperties> = RawCache<Fifo<K, V, P>, S>;
@@ -152,6 +219,10 @@ pub type SieveCache<K, V, S = DefaultHasher, P = CacheProperties> = RawCache<Sie
 pub type SieveCacheEntry<K, V, S = DefaultHasher, P = CacheProperties> = RawCacheEntry<Sieve<K, V, P>, S>;
 pub type SieveFetch<K, V, ER, S = DefaultHasher, P = CacheProperties> = RawFetch<Sieve<K, V, P>, ER, S>;
 
+pub type DecayLfuCache<K, V, S = DefaultHasher, P = CacheProperties> = RawCache<DecayLfu<K, V, P>, S>;
+pub type DecayLfuCacheEntry<K, V, S = DefaultHasher, P = CacheProperties> = RawCacheEntry<DecayLfu<K, V, P>, S>;
+pub type DecayLfuFetch<K, V, ER, S = DefaultHasher, P = CacheProperties> = RawFetch<DecayLfu<K, V, P>, ER, S>;
+
 /// A cached entry holder of the in-memory cache.
 #[derive(Debug)]
 pub enum CacheEntry<K, V, S = DefaultHasher, P = CacheProperties>
@@ -171,6 +242,8 @@ where
     Lfu(LfuCacheEntry<K, V, S, P>),
     /// A cached entry holder of the in-memory Sieve cache.
     Sieve(SieveCacheEntry<K, V, S, P>),
+    /// A cached entry holder of the in-memory decaying LFU cache.
+    DecayLfu(DecayLfuCacheEntry<K, V, S, P>),
 }
 
 impl<K, V, S, P> Clone for CacheEntry<K, V, S, P>
@@ -187,6 +260,7 @@ where
             Self::Lfu(entry) => Self::Lfu(entry.clone()),
             Self::S3Fifo(entry) => Self::S3Fifo(entry.clone()),
             Self::Sieve(entry) => Self::Sieve(entry.clone()),
+            Self::DecayLfu(entry) => Self::DecayLfu(entry.clone()),
         }
     }
 }
@@ -207,6 +281,7 @@ where
             CacheEntry::Lfu(entry) => entry.deref(),
             CacheEntry::S3Fifo(entry) => entry.deref(),
             CacheEntry::Sieve(entry) => entry.deref(),
+            CacheEntry::DecayLfu(entry) => entry.deref(),
         }
     }
 }
@@ -271,6 +346,18 @@ where
     }
 }
 
+impl<K, V, S, P> From<DecayLfuCacheEntry<K, V, S, P>> for CacheEntry<K, V, S, P>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    fn from(entry: DecayLfuCacheEntry<K, V, S, P>) -> Self {
+        Self::DecayLfu(entry)
+    }
+}
+
 impl<K, V, S, P> CacheEntry<K, V, S, P>
 where
     K: Key,
@@ -286,6 +373,7 @@ where
             CacheEntry::Lfu(entry) => entry.hash(),
             CacheEntry::S3Fifo(entry) => entry.hash(),
             CacheEntry::Sieve(entry) => entry.hash(),
+            CacheEntry::DecayLfu(entry) => entry.hash(),
         }
     }
 
@@ -297,6 +385,7 @@ where
             CacheEntry::Lfu(entry) => entry.key(),
             CacheEntry::S3Fifo(entry) => entry.key(),
             CacheEntry::Sieve(entry) => entry.key(),
+            CacheEntry::DecayLfu(entry) => entry.key(),
         }
     }
 
@@ -308,6 +397,7 @@ where
             CacheEntry::Lfu(entry) => entry.value(),
             CacheEntry::S3Fifo(entry) => entry.value(),
             CacheEntry::Sieve(entry) => entry.value(),
+            CacheEntry::DecayLfu(entry) => entry.value(),
         }
     }
 
@@ -319,6 +409,7 @@ where
             CacheEntry::Lfu(entry) => entry.properties(),
             CacheEntry::S3Fifo(entry) => entry.properties(),
             CacheEntry::Sieve(entry) => entry.properties(),
+            CacheEntry::DecayLfu(entry) => entry.properties(),
         }
     }
 
@@ -330,6 +421,7 @@ where
             CacheEntry::Lfu(entry) => entry.weight(),
             CacheEntry::S3Fifo(entry) => entry.weight(),
             CacheEntry::Sieve(entry) => entry.weight(),
+            CacheEntry::DecayLfu(entry) => entry.weight(),
         }
     }
 
@@ -341,6 +433,7 @@ where
             CacheEntry::Lfu(entry) => entry.refs(),
             CacheEntry::S3Fifo(entry) => entry.refs(),
             CacheEntry::Sieve(entry) => entry.refs(),
+            CacheEntry::DecayLfu(entry) => entry.refs(),
         }
     }
 
@@ -352,6 +445,7 @@ where
             CacheEntry::Lfu(entry) => entry.is_outdated(),
             CacheEntry::S3Fifo(entry) => entry.is_outdated(),
             CacheEntry::Sieve(entry) => entry.is_outdated(),
+            CacheEntry::DecayLfu(entry) => entry.is_outdated(),
         }
     }
 
@@ -363,6 +457,21 @@ where
             CacheEntry::Lfu(entry) => entry.piece(),
             CacheEntry::S3Fifo(entry) => entry.piece(),
             CacheEntry::Sieve(entry) => entry.piece(),
+            CacheEntry::DecayLfu(entry) => entry.piece(),
+        }
+    }
+
+    /// Release the entry without blocking the async runtime on the shard lock.
+    ///
+    /// Prefer it to dropping the entry inside a future if the entry may be the last reference.
+    pub async fn release(self) {
+        match self {
+            CacheEntry::Fifo(entry) => entry.release().await,
+            CacheEntry::Lru(entry) => entry.release().await,
+            CacheEntry::Lfu(entry) => entry.release().await,
+            CacheEntry::S3Fifo(entry) => entry.release().await,
+            CacheEntry::Sieve(entry) => entry.release().await,
+            CacheEntry::DecayLfu(entry) => entry.release().await,
         }
     }
 }
@@ -380,6 +489,8 @@ pub enum EvictionConfig {
     Lfu(LfuConfig),
     /// Sieve eviction algorithm config.
     Sieve(SieveConfig),
+    /// Decaying LFU eviction algorithm config.
+    DecayLfu(DecayLfuConfig),
 }
 
 impl From<FifoConfig> for EvictionConfig {
@@ -412,6 +523,33 @@ impl From<SieveConfig> for EvictionConfig {
     }
 }
 
+impl From<DecayLfuConfig> for EvictionConfig {
+    fn from(value: DecayLfuConfig) -> EvictionConfig {
+        EvictionConfig::DecayLfu(value)
+    }
+}
+
+/// Capacity of the in-memory cache with an explicit unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Capacity {
+    /// The capacity is the total weight of the entries measured by the weighter, e.g. the total bytes of the entries
+    /// with a weighter that returns the size of an entry.
+    Bytes(usize),
+    /// The capacity is the count of the entries, every entry weighs `1`.
+    ///
+    /// A custom weighter is rejected in this mode.
+    Entries(usize),
+}
+
+impl Capacity {
+    /// Get the capacity value regardless of the unit.
+    pub fn value(&self) -> usize {
+        match self {
+            Capacity::Bytes(capacity) | Capacity::Entries(capacity) => *capacity,
+        }
+    }
+}
+
 /// In-memory cache builder.
 pub struct CacheBuilder<K, V, S>
 where
@@ -421,18 +559,28 @@ where
 {
     name: Cow<'static, str>,
 
-    capacity: usize,
-    shards: usize,
+    capacity: Capacity,
+    shards: Option<usize>,
+    shard_capacities: Option<Vec<usize>>,
+    hot_entry: bool,
     eviction_config: EvictionConfig,
 
     hash_builder: S,
-    weighter: Arc<dyn Weighter<K, V>>,
+    hash_domain: u64,
+    weighter: Option<Arc<dyn Weighter<K, V>>>,
     filter: Arc<dyn Filter<K, V>>,
+    max_admit_weight: Option<usize>,
+    skip_referenced: bool,
+    negative_ttl: Option<Duration>,
+    clock: Option<Arc<dyn Clock>>,
 
     event_listener: Option<Arc<dyn EventListener<Key = K, Value = V>>>,
 
     registry: BoxedRegistry,
     metrics: Option<Arc<Metrics>>,
+
+    #[cfg(any(test, feature = "test_utils"))]
+    force_shard: Option<usize>,
 }
 
 impl<K, V> CacheBuilder<K, V, DefaultHasher>
@@ -441,21 +589,34 @@ where
     V: Value,
 {
     /// Create a new in-memory cache builder.
+    ///
+    /// The capacity is measured by the weighter, the same as [`Capacity::Bytes`]. Use [`CacheBuilder::with_capacity`]
+    /// to make the unit explicit.
     pub fn new(capacity: usize) -> Self {
         Self {
             name: "foyer".into(),
 
-            capacity,
-            shards: 8,
+            capacity: Capacity::Bytes(capacity),
+            shards: None,
+            shard_capacities: None,
+            hot_entry: false,
             eviction_config: LruConfig::default().into(),
 
             hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
+            hash_domain: 0,
+            weighter: None,
             filter: Arc::new(|_, _| true),
+            max_admit_weight: None,
+            skip_referenced: false,
+            negative_ttl: None,
+            clock: None,
             event_listener: None,
 
             registry: Box::new(NoopMetricsRegistry),
             metrics: None,
+
+            #[cfg(any(test, feature = "test_utils"))]
+            force_shard: None,
         }
     }
 }
@@ -478,8 +639,43 @@ where
 
     /// Set in-memory cache sharding count. Entries will be distributed to different shards based on their hash.
     /// Operations on different shard can be parallelized.
+    ///
+    /// Default: `8`, or the length of the shard capacities if set.
     pub fn with_shards(mut self, shards: usize) -> Self {
-        self.shards = shards;
+        self.shards = Some(shards);
+        self
+    }
+
+    /// Set the capacity of the in-memory cache with an explicit unit.
+    ///
+    /// With [`Capacity::Entries`], every entry weighs `1` and a custom weighter is rejected.
+    pub fn with_capacity(mut self, capacity: Capacity) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of each shard of the in-memory cache.
+    ///
+    /// The shard count is the length of `shard_capacities`, and the total capacity is the sum of them. It overrides
+    /// the equal split of the capacity among shards. Useful when some shards deserve more capacity.
+    ///
+    /// The unit of the capacity is kept. If the shard count is also set with [`CacheBuilder::with_shards`], it must be
+    /// equal to the length of `shard_capacities`, or the build fails, regardless of the call order.
+    pub fn with_shard_capacities(mut self, shard_capacities: Vec<usize>) -> Self {
+        self.shard_capacities = Some(shard_capacities);
+        self
+    }
+
+    /// Enable the single-entry hot cache of each shard.
+    ///
+    /// The hot cache holds the most recently accessed entry of the shard, so repeated `get` of the same hot key can
+    /// return without taking the shard lock. Only works with the eviction algorithms that don't observe the accesses,
+    /// e.g. FIFO. The hits on the hot cache are invisible to the eviction algorithm, so the algorithms that count the
+    /// accesses (e.g. S3FIFO, SIEVE) are excluded.
+    ///
+    /// Default: `false`.
+    pub fn with_hot_entry(mut self, enable: bool) -> Self {
+        self.hot_entry = enable;
         self
     }
 
@@ -500,19 +696,44 @@ where
             name: self.name,
             capacity: self.capacity,
             shards: self.shards,
+            shard_capacities: self.shard_capacities,
+            hot_entry: self.hot_entry,
             eviction_config: self.eviction_config,
             hash_builder,
+            hash_domain: self.hash_domain,
             weighter: self.weighter,
             filter: self.filter,
+            max_admit_weight: self.max_admit_weight,
+            skip_referenced: self.skip_referenced,
+            negative_ttl: self.negative_ttl,
+            clock: self.clock,
             event_listener: self.event_listener,
             registry: self.registry,
             metrics: self.metrics,
+            #[cfg(any(test, feature = "test_utils"))]
+            force_shard: self.force_shard,
         }
     }
 
+    /// Set the hash domain of the in-memory cache.
+    ///
+    /// The domain is mixed into the hash of every key, so caches with different domains produce different hashes for
+    /// the same key even if they share the same hash builder. Useful if the hashes are used as keys across caches or
+    /// tiers.
+    ///
+    /// Default: `0`, which leaves the hashes unchanged.
+    pub fn with_hash_domain(mut self, hash_domain: u64) -> Self {
+        self.hash_domain = hash_domain;
+        self
+    }
+
     /// Set in-memory cache weighter.
+    ///
+    /// The weighter is only allowed with [`Capacity::Bytes`].
+    ///
+    /// Default: every entry weighs `1`.
     pub fn with_weighter(mut self, weighter: impl Weighter<K, V>) -> Self {
-        self.weighter = Arc::new(weighter);
+        self.weighter = Some(Arc::new(weighter));
         self
     }
 
@@ -531,6 +752,58 @@ where
         self
     }
 
+    /// Set the max weight of the entries that can be admitted into the in-memory cache.
+    ///
+    /// Entries that weigh more than it are treated as uncacheable, the same as the entries rejected by the filter. It
+    /// protects the working set from being flushed by a few large and cold entries, even if they fit in the capacity.
+    ///
+    /// Default: `None`, which admits entries of any weight.
+    pub fn with_max_admit_weight(mut self, max_admit_weight: usize) -> Self {
+        self.max_admit_weight = Some(max_admit_weight);
+        self
+    }
+
+    /// Skip the entries that are still referenced when evicting.
+    ///
+    /// Evicting an entry that is still in use rarely frees memory immediately, and it is likely to be fetched again.
+    /// If enabled, the referenced entries are deferred and the unreferenced ones are evicted first. The referenced
+    /// entries are only evicted as the last resort.
+    ///
+    /// NOTE: If enabled, the eviction algorithm decides the victims by popping, instead of its own admission on push.
+    ///
+    /// Default: `false`.
+    pub fn with_skip_referenced(mut self, enable: bool) -> Self {
+        self.skip_referenced = enable;
+        self
+    }
+
+    /// Enable the negative cache with the given ttl.
+    ///
+    /// If enabled, the keys that the fetcher of [`Cache::get_or_fetch`] doesn't find are kept as tombstones for the
+    /// ttl. The tombstones take no capacity, [`Cache::lookup`] reports them as [`Lookup::Absent`], and
+    /// [`Cache::get_or_fetch`] returns `None` for them without calling the fetcher again.
+    ///
+    /// The count of the tombstones of each shard is bounded by the shard capacity, the oldest ones are dropped first.
+    /// The count is reported by [`CacheStats::tombstones`].
+    ///
+    /// Default: disabled.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
+    /// Set the clock to expire the entries inserted with a ttl (see [`CacheProperties::with_ttl`]) and the tombstones
+    /// of the negative cache.
+    ///
+    /// A [`CoarseClock`](foyer_common::clock::CoarseClock) saves the cost of reading the system clock on every lookup
+    /// of the entries with ttl, at the cost of precision.
+    ///
+    /// Default: the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Set event listener.
     pub fn with_event_listener(mut self, event_listener: Arc<dyn EventListener<Key = K, Value = V>>) -> Self {
         self.event_listener = Some(event_listener);
@@ -554,75 +827,188 @@ where
         self
     }
 
+    /// Route all keys to the given shard if `Some`.
+    ///
+    /// For testing only, e.g. to put multiple keys into the same shard to exercise intra-shard contention.
+    ///
+    /// Default: `None`, which selects the shard by the hash of the key.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn with_force_shard(mut self, shard: Option<usize>) -> Self {
+        self.force_shard = shard;
+        self
+    }
+
     /// Build in-memory cache with the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid, see [`CacheBuilder::try_build`].
     pub fn build<P>(self) -> Cache<K, V, S, P>
     where
         P: Properties,
     {
-        if self.capacity < self.shards {
-            tracing::warn!(
-                "The in-memory cache capacity({}) < shards({}).",
-                self.capacity,
-                self.shards
-            );
+        self.try_build().unwrap()
+    }
+
+    /// Build in-memory cache with the given configuration, or return an error if the configuration is invalid.
+    ///
+    /// A custom weighter with [`Capacity::Entries`] is invalid. Empty shard capacities, or shard capacities whose
+    /// length differs from the shard count set with [`CacheBuilder::with_shards`], are invalid.
+    pub fn try_build<P>(self) -> Result<Cache<K, V, S, P>>
+    where
+        P: Properties,
+    {
+        #[cfg(any(test, feature = "test_utils"))]
+        let force_shard = self.force_shard;
+        let clock = self.clock;
+
+        let (capacity, shards) = match (&self.shard_capacities, self.shards) {
+            (Some(shard_capacities), _) if shard_capacities.is_empty() => {
+                return Err(Error::ConfigError("the shard capacities must not be empty".to_string()))
+            }
+            (Some(shard_capacities), Some(shards)) if shard_capacities.len() != shards => {
+                return Err(Error::ConfigError(format!(
+                    "the count of shard capacities ({}) must be equal to the count of shards ({shards})",
+                    shard_capacities.len()
+                )))
+            }
+            (Some(shard_capacities), _) => (shard_capacities.iter().sum(), shard_capacities.len()),
+            (None, shards) => (self.capacity.value(), shards.unwrap_or(8)),
+        };
+        if capacity < shards {
+            tracing::warn!("The in-memory cache capacity({}) < shards({}).", capacity, shards);
         }
 
+        let weighter: Arc<dyn Weighter<K, V>> = match (self.capacity, self.weighter) {
+            (Capacity::Entries(_), Some(_)) => {
+                return Err(Error::ConfigError(
+                    "a custom weighter is not allowed with `Capacity::Entries`, use `Capacity::Bytes` instead"
+                        .to_string(),
+                ))
+            }
+            (_, weighter) => weighter.unwrap_or_else(|| Arc::new(|_, _| 1)),
+        };
+
         let metrics = self
             .metrics
             .unwrap_or_else(|| Arc::new(Metrics::new(self.name, &self.registry)));
 
-        match self.eviction_config {
-            EvictionConfig::Fifo(eviction_config) => Cache::Fifo(Arc::new(RawCache::new(RawCacheConfig {
-                capacity: self.capacity,
-                shards: self.shards,
+        #[cfg_attr(not(any(test, feature = "test_utils")), expect(unused_mut))]
+        let mut cache = match self.eviction_config {
+            EvictionConfig::Fifo(eviction_config) => Cache::Fifo(Arc::new(RawCache::try_new(RawCacheConfig {
+                capacity,
+                shards,
+                shard_capacities: self.shard_capacities,
+                hot_entry: self.hot_entry,
                 eviction_config,
                 hash_builder: self.hash_builder,
-                weighter: self.weighter,
+                hash_domain: self.hash_domain,
+                weighter,
+                count_entries: matches!(self.capacity, Capacity::Entries(_)),
                 filter: self.filter,
+                max_admit_weight: self.max_admit_weight,
+                skip_referenced: self.skip_referenced,
+                negative_ttl: self.negative_ttl,
                 event_listener: self.event_listener,
                 metrics,
-            }))),
-            EvictionConfig::S3Fifo(eviction_config) => Cache::S3Fifo(Arc::new(RawCache::new(RawCacheConfig {
-                capacity: self.capacity,
-                shards: self.shards,
+            })?)),
+            EvictionConfig::S3Fifo(eviction_config) => Cache::S3Fifo(Arc::new(RawCache::try_new(RawCacheConfig {
+                capacity,
+                shards,
+                shard_capacities: self.shard_capacities,
+                hot_entry: self.hot_entry,
                 eviction_config,
                 hash_builder: self.hash_builder,
-                weighter: self.weighter,
+                hash_domain: self.hash_domain,
+                weighter,
+                count_entries: matches!(self.capacity, Capacity::Entries(_)),
                 filter: self.filter,
+                max_admit_weight: self.max_admit_weight,
+                skip_referenced: self.skip_referenced,
+                negative_ttl: self.negative_ttl,
                 event_listener: self.event_listener,
                 metrics,
-            }))),
-            EvictionConfig::Lru(eviction_config) => Cache::Lru(Arc::new(RawCache::new(RawCacheConfig {
-                capacity: self.capacity,
-                shards: self.shards,
+            })?)),
+            EvictionConfig::Lru(eviction_config) => Cache::Lru(Arc::new(RawCache::try_new(RawCacheConfig {
+                capacity,
+                shards,
+                shard_capacities: self.shard_capacities,
+                hot_entry: self.hot_entry,
                 eviction_config,
                 hash_builder: self.hash_builder,
-                weighter: self.weighter,
+                hash_domain: self.hash_domain,
+                weighter,
+                count_entries: matches!(self.capacity, Capacity::Entries(_)),
                 filter: self.filter,
+                max_admit_weight: self.max_admit_weight,
+                skip_referenced: self.skip_referenced,
+                negative_ttl: self.negative_ttl,
                 event_listener: self.event_listener,
                 metrics,
-            }))),
-            EvictionConfig::Lfu(eviction_config) => Cache::Lfu(Arc::new(RawCache::new(RawCacheConfig {
-                capacity: self.capacity,
-                shards: self.shards,
+            })?)),
+            EvictionConfig::Lfu(eviction_config) => Cache::Lfu(Arc::new(RawCache::try_new(RawCacheConfig {
+                capacity,
+                shards,
+                shard_capacities: self.shard_capacities,
+                hot_entry: self.hot_entry,
                 eviction_config,
                 hash_builder: self.hash_builder,
-                weighter: self.weighter,
+                hash_domain: self.hash_domain,
+                weighter,
+                count_entries: matches!(self.capacity, Capacity::Entries(_)),
                 filter: self.filter,
+                max_admit_weight: self.max_admit_weight,
+                skip_referenced: self.skip_referenced,
+                negative_ttl: self.negative_ttl,
                 event_listener: self.event_listener,
                 metrics,
-            }))),
-            EvictionConfig::Sieve(eviction_config) => Cache::Sieve(Arc::new(RawCache::new(RawCacheConfig {
-                capacity: self.capacity,
-                shards: self.shards,
+            })?)),
+            EvictionConfig::Sieve(eviction_config) => Cache::Sieve(Arc::new(RawCache::try_new(RawCacheConfig {
+                capacity,
+                shards,
+                shard_capacities: self.shard_capacities,
+                hot_entry: self.hot_entry,
                 eviction_config,
                 hash_builder: self.hash_builder,
-                weighter: self.weighter,
+                hash_domain: self.hash_domain,
+                weighter,
+                count_entries: matches!(self.capacity, Capacity::Entries(_)),
                 filter: self.filter,
+                max_admit_weight: self.max_admit_weight,
+                skip_referenced: self.skip_referenced,
+                negative_ttl: self.negative_ttl,
                 event_listener: self.event_listener,
                 metrics,
-            }))),
+            })?)),
+            EvictionConfig::DecayLfu(eviction_config) => {
+                Cache::DecayLfu(Arc::new(RawCache::try_new(RawCacheConfig {
+                    capacity,
+                    shards,
+                    shard_capacities: self.shard_capacities,
+                    hot_entry: self.hot_entry,
+                    eviction_config,
+                    hash_builder: self.hash_builder,
+                    hash_domain: self.hash_domain,
+                    weighter,
+                    count_entries: matches!(self.capacity, Capacity::Entries(_)),
+                    filter: self.filter,
+                    max_admit_weight: self.max_admit_weight,
+                    skip_referenced: self.skip_referenced,
+                    negative_ttl: self.negative_ttl,
+                    event_listener: self.event_listener,
+                    metrics,
+                })?))
+            }
+        };
+
+        #[cfg(any(test, feature = "test_utils"))]
+        cache.force_shard(force_shard);
+
+        if let Some(clock) = clock {
+            cache.set_clock(clock);
         }
+
+        Ok(cache)
     }
 }
 
@@ -644,6 +1030,8 @@ where
     S3Fifo(Arc<S3FifoCache<K, V, S, P>>),
     /// In-memory Sieve cache.
     Sieve(Arc<SieveCache<K, V, S, P>>),
+    /// In-memory decaying LFU cache.
+    DecayLfu(Arc<DecayLfuCache<K, V, S, P>>),
 }
 
 impl<K, V, S, P> Debug for Cache<K, V, S, P>
@@ -660,6 +1048,7 @@ where
             Self::Lru(_) => f.debug_tuple("Cache::LruCache").finish(),
             Self::Lfu(_) => f.debug_tuple("Cache::LfuCache").finish(),
             Self::Sieve(_) => f.debug_tuple("Cache::SieveCache").finish(),
+            Self::DecayLfu(_) => f.debug_tuple("Cache::DecayLfuCache").finish(),
         }
     }
 }
@@ -678,10 +1067,131 @@ where
             Self::Lru(cache) => Self::Lru(cache.clone()),
             Self::Lfu(cache) => Self::Lfu(cache.clone()),
             Self::Sieve(cache) => Self::Sieve(cache.clone()),
+            Self::DecayLfu(cache) => Self::DecayLfu(cache.clone()),
+        }
+    }
+}
+
+/// Read-only view of the in-memory cache.
+///
+/// The view shares the same cache with the [`Cache`] it is created from, and reflects its live state, but only
+/// exposes the methods that don't modify the entries. It can be passed to the subsystems that are only supposed to read
+/// the cache.
+///
+/// ```
+/// use foyer_memory::{Cache, CacheBuilder};
+///
+/// let cache: Cache<u64, u64> = CacheBuilder::new(16).build();
+/// let view = cache.view();
+/// cache.insert(1, 1);
+/// assert_eq!(*view.get(&1).unwrap(), 1);
+/// ```
+///
+/// The view cannot modify the cache:
+///
+/// ```compile_fail
+/// use foyer_memory::{Cache, CacheBuilder};
+///
+/// let cache: Cache<u64, u64> = CacheBuilder::new(16).build();
+/// let view = cache.view();
+/// view.insert(1, 1);
+/// ```
+///
+/// ```compile_fail
+/// use foyer_memory::{Cache, CacheBuilder};
+///
+/// let cache: Cache<u64, u64> = CacheBuilder::new(16).build();
+/// let view = cache.view();
+/// view.remove(&1);
+/// ```
+pub struct CacheView<K, V, S = DefaultHasher, P = CacheProperties>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    cache: Cache<K, V, S, P>,
+}
+
+impl<K, V, S, P> Debug for CacheView<K, V, S, P>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheView").finish()
+    }
+}
+
+impl<K, V, S, P> Clone for CacheView<K, V, S, P>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
         }
     }
 }
 
+impl<K, V, S, P> CacheView<K, V, S, P>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    /// Get cached entry with the given key, see [`Cache::get`].
+    pub fn get<Q>(&self, key: &Q) -> Option<CacheEntry<K, V, S, P>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.cache.get(key)
+    }
+
+    /// Get cached entry with the given key without updating the eviction state, see [`Cache::peek`].
+    pub fn peek<Q>(&self, key: &Q) -> Option<CacheEntry<K, V, S, P>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.cache.peek(key)
+    }
+
+    /// Check if the cache contains a cached entry with the given key.
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.cache.contains(key)
+    }
+
+    /// Get the count of the entries in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Check if the cache holds no entry.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Get the usage of the cache.
+    pub fn usage(&self) -> usize {
+        self.cache.usage()
+    }
+
+    /// Get the capacity of the cache.
+    pub fn capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+}
+
 impl<K, V> Cache<K, V, DefaultHasher, CacheProperties>
 where
     K: Key,
@@ -709,6 +1219,23 @@ where
             Cache::Lru(cache) => cache.resize(capacity),
             Cache::Lfu(cache) => cache.resize(capacity),
             Cache::Sieve(cache) => cache.resize(capacity),
+            Cache::DecayLfu(cache) => cache.resize(capacity),
+        }
+    }
+
+    /// Update the capacity of the given shard and evict overflowed entries of the shard.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::cache::set_shard_capacity")
+    )]
+    pub fn set_shard_capacity(&self, shard: usize, capacity: usize) -> Result<()> {
+        match self {
+            Cache::Fifo(cache) => cache.set_shard_capacity(shard, capacity),
+            Cache::S3Fifo(cache) => cache.set_shard_capacity(shard, capacity),
+            Cache::Lru(cache) => cache.set_shard_capacity(shard, capacity),
+            Cache::Lfu(cache) => cache.set_shard_capacity(shard, capacity),
+            Cache::Sieve(cache) => cache.set_shard_capacity(shard, capacity),
+            Cache::DecayLfu(cache) => cache.set_shard_capacity(shard, capacity),
         }
     }
 
@@ -721,6 +1248,60 @@ where
             Cache::Lru(cache) => cache.insert(key, value).into(),
             Cache::Lfu(cache) => cache.insert(key, value).into(),
             Cache::Sieve(cache) => cache.insert(key, value).into(),
+            Cache::DecayLfu(cache) => cache.insert(key, value).into(),
+        }
+    }
+
+    /// Insert cache entries to the in-memory cache, the returned entries are aligned with the items.
+    ///
+    /// Each shard is locked at most once, which is cheaper than calling [`Cache::insert`] for each entry. The entries
+    /// with the same key are inserted in order, so the last one is resident.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::insert_many"))]
+    pub fn insert_many(&self, items: Vec<(K, V)>) -> Vec<CacheEntry<K, V, S, P>> {
+        match self {
+            Cache::Fifo(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+            Cache::S3Fifo(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+            Cache::Lru(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+            Cache::Lfu(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+            Cache::Sieve(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+            Cache::DecayLfu(cache) => cache.insert_many(items).into_iter().map(CacheEntry::from).collect(),
+        }
+    }
+
+    /// Insert cache entry to the in-memory cache only if there is no entry with the same key.
+    ///
+    /// Returns the resident entry and `true` if this call inserted it, or the existing entry and `false` otherwise.
+    /// The lookup and the insertion are atomic, so only one of the racing calls with the same key reports `true`.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::cache::insert_if_absent")
+    )]
+    pub fn insert_if_absent(&self, key: K, value: V) -> (CacheEntry<K, V, S, P>, bool) {
+        match self {
+            Cache::Fifo(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
+            Cache::S3Fifo(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
+            Cache::Lru(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
+            Cache::Lfu(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
+            Cache::Sieve(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
+            Cache::DecayLfu(cache) => {
+                let (entry, inserted) = cache.insert_if_absent(key, value);
+                (entry.into(), inserted)
+            }
         }
     }
 
@@ -736,9 +1317,51 @@ where
             Cache::Lru(cache) => cache.insert_with_properties(key, value, properties).into(),
             Cache::Lfu(cache) => cache.insert_with_properties(key, value, properties).into(),
             Cache::Sieve(cache) => cache.insert_with_properties(key, value, properties).into(),
+            Cache::DecayLfu(cache) => cache.insert_with_properties(key, value, properties).into(),
+        }
+    }
+
+    #[cfg(any(test, feature = "test_utils"))]
+    fn force_shard(&mut self, shard: Option<usize>) {
+        match self {
+            Cache::Fifo(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+            Cache::S3Fifo(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+            Cache::Lru(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+            Cache::Lfu(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+            Cache::Sieve(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+            Cache::DecayLfu(cache) => Arc::get_mut(cache).unwrap().force_shard(shard),
+        }
+    }
+
+    /// Insert cache entry to the in-memory cache with the given weight, bypassing the weighter.
+    ///
+    /// Useful when the caller knows the real cost of the entry better than the weighter, e.g. including the external
+    /// resources held by the value. The weight is ignored with [`Capacity::Entries`], every entry weighs `1`.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::cache::insert_with_weight")
+    )]
+    pub fn insert_with_weight(&self, key: K, value: V, weight: usize) -> CacheEntry<K, V, S, P> {
+        match self {
+            Cache::Fifo(cache) => cache.insert_with_weight(key, value, weight).into(),
+            Cache::S3Fifo(cache) => cache.insert_with_weight(key, value, weight).into(),
+            Cache::Lru(cache) => cache.insert_with_weight(key, value, weight).into(),
+            Cache::Lfu(cache) => cache.insert_with_weight(key, value, weight).into(),
+            Cache::Sieve(cache) => cache.insert_with_weight(key, value, weight).into(),
+            Cache::DecayLfu(cache) => cache.insert_with_weight(key, value, weight).into(),
         }
     }
 
+    /// Insert cache entry to the in-memory cache with the cost to recompute it on a miss.
+    ///
+    /// Cost-aware cache eviction algorithms (LFU) bias against evicting high-cost entries.
+    ///
+    /// The cost is ignored if the properties don't support it (see [`Properties::with_cost`]), e.g. the properties of
+    /// the in-memory cache of the hybrid cache.
+    pub fn insert_with_cost(&self, key: K, value: V, cost: u32) -> CacheEntry<K, V, S, P> {
+        self.insert_with_properties(key, value, P::default().with_cost(cost))
+    }
+
     #[doc(hidden)]
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::insert_inner"))]
     pub fn insert_piece(&self, piece: Piece<K, V, P>) -> CacheEntry<K, V, S, P> {
@@ -748,6 +1371,7 @@ where
             Cache::Lru(cache) => cache.insert_piece(piece).into(),
             Cache::Lfu(cache) => cache.insert_piece(piece).into(),
             Cache::Sieve(cache) => cache.insert_piece(piece).into(),
+            Cache::DecayLfu(cache) => cache.insert_piece(piece).into(),
         }
     }
 
@@ -763,6 +1387,68 @@ where
             Cache::Lru(cache) => cache.remove(key).map(CacheEntry::from),
             Cache::Lfu(cache) => cache.remove(key).map(CacheEntry::from),
             Cache::Sieve(cache) => cache.remove(key).map(CacheEntry::from),
+            Cache::DecayLfu(cache) => cache.remove(key).map(CacheEntry::from),
+        }
+    }
+
+    /// Remove the cached entries with the given keys from the in-memory cache, and return the count of the removed
+    /// entries.
+    ///
+    /// Each shard is locked at most once, which is cheaper than calling [`Cache::remove`] for each key.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::remove_many"))]
+    pub fn remove_many<Q>(&self, keys: &[Q]) -> usize
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        match self {
+            Cache::Fifo(cache) => cache.remove_many(keys),
+            Cache::S3Fifo(cache) => cache.remove_many(keys),
+            Cache::Lru(cache) => cache.remove_many(keys),
+            Cache::Lfu(cache) => cache.remove_many(keys),
+            Cache::Sieve(cache) => cache.remove_many(keys),
+            Cache::DecayLfu(cache) => cache.remove_many(keys),
+        }
+    }
+
+    /// Get the cached entries with the given keys from the in-memory cache, the results are aligned with the keys.
+    ///
+    /// Each shard is locked at most once, which is cheaper than calling [`Cache::get`] for each key.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::get_many"))]
+    pub fn get_many<Q>(&self, keys: &[Q]) -> Vec<Option<CacheEntry<K, V, S, P>>>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        match self {
+            Cache::Fifo(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
+            Cache::S3Fifo(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
+            Cache::Lru(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
+            Cache::Lfu(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
+            Cache::Sieve(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
+            Cache::DecayLfu(cache) => cache
+                .get_many(keys)
+                .into_iter()
+                .map(|e| e.map(CacheEntry::from))
+                .collect(),
         }
     }
 
@@ -778,6 +1464,26 @@ where
             Cache::Lru(cache) => cache.get(key).map(CacheEntry::from),
             Cache::Lfu(cache) => cache.get(key).map(CacheEntry::from),
             Cache::Sieve(cache) => cache.get(key).map(CacheEntry::from),
+            Cache::DecayLfu(cache) => cache.get(key).map(CacheEntry::from),
+        }
+    }
+
+    /// Get cached entry with the given key from the in-memory cache without updating the eviction state.
+    ///
+    /// Unlike [`Cache::get`], peeking an entry doesn't make it less likely to be evicted, and is not counted as a hit
+    /// or a miss.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::peek"))]
+    pub fn peek<Q>(&self, key: &Q) -> Option<CacheEntry<K, V, S, P>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        match self {
+            Cache::Fifo(cache) => cache.peek(key).map(CacheEntry::from),
+            Cache::S3Fifo(cache) => cache.peek(key).map(CacheEntry::from),
+            Cache::Lru(cache) => cache.peek(key).map(CacheEntry::from),
+            Cache::Lfu(cache) => cache.peek(key).map(CacheEntry::from),
+            Cache::Sieve(cache) => cache.peek(key).map(CacheEntry::from),
+            Cache::DecayLfu(cache) => cache.peek(key).map(CacheEntry::from),
         }
     }
 
@@ -793,23 +1499,62 @@ where
             Cache::Lru(cache) => cache.contains(key),
             Cache::Lfu(cache) => cache.contains(key),
             Cache::Sieve(cache) => cache.contains(key),
+            Cache::DecayLfu(cache) => cache.contains(key),
         }
     }
 
-    /// Access the cached entry with the given key but don't return.
+    /// Look up the key in the in-memory cache, and tell the key known to be absent by the negative cache from a plain
+    /// miss.
     ///
-    /// Note: This method can be used to update the cache eviction information and order based on the algorithm.
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::touch"))]
-    pub fn touch<Q>(&self, key: &Q) -> bool
+    /// See [`CacheBuilder::with_negative_ttl`].
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::lookup"))]
+    pub fn lookup<Q>(&self, key: &Q) -> Lookup<CacheEntry<K, V, S, P>>
     where
         Q: Hash + Equivalent<K> + ?Sized,
     {
         match self {
-            Cache::Fifo(cache) => cache.touch(key),
-            Cache::S3Fifo(cache) => cache.touch(key),
-            Cache::Lru(cache) => cache.touch(key),
-            Cache::Lfu(cache) => cache.touch(key),
-            Cache::Sieve(cache) => cache.touch(key),
+            Cache::Fifo(cache) => cache.lookup(key).map(CacheEntry::from),
+            Cache::S3Fifo(cache) => cache.lookup(key).map(CacheEntry::from),
+            Cache::Lru(cache) => cache.lookup(key).map(CacheEntry::from),
+            Cache::Lfu(cache) => cache.lookup(key).map(CacheEntry::from),
+            Cache::Sieve(cache) => cache.lookup(key).map(CacheEntry::from),
+            Cache::DecayLfu(cache) => cache.lookup(key).map(CacheEntry::from),
+        }
+    }
+
+    /// Get the tag of the eviction queue that holds the entry with the given key, for debugging.
+    ///
+    /// e.g. whether an entry is in the window, probation or protected queue of LFU. Returns `None` if the key is not
+    /// in the cache.
+    pub fn queue_of<Q>(&self, key: &Q) -> Option<QueueTag>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        match self {
+            Cache::Fifo(cache) => cache.queue_of(key),
+            Cache::S3Fifo(cache) => cache.queue_of(key),
+            Cache::Lru(cache) => cache.queue_of(key),
+            Cache::Lfu(cache) => cache.queue_of(key),
+            Cache::Sieve(cache) => cache.queue_of(key),
+            Cache::DecayLfu(cache) => cache.queue_of(key),
+        }
+    }
+
+    /// Access the cached entry with the given key but don't return.
+    ///
+    /// Note: This method can be used to update the cache eviction information and order based on the algorithm.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::touch"))]
+    pub fn touch<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        match self {
+            Cache::Fifo(cache) => cache.touch(key),
+            Cache::S3Fifo(cache) => cache.touch(key),
+            Cache::Lru(cache) => cache.touch(key),
+            Cache::Lfu(cache) => cache.touch(key),
+            Cache::Sieve(cache) => cache.touch(key),
+            Cache::DecayLfu(cache) => cache.touch(key),
         }
     }
 
@@ -822,6 +1567,45 @@ where
             Cache::Lru(cache) => cache.clear(),
             Cache::Lfu(cache) => cache.clear(),
             Cache::Sieve(cache) => cache.clear(),
+            Cache::DecayLfu(cache) => cache.clear(),
+        }
+    }
+
+    /// Invalidate all entries in the in-memory cache, alias of [`Cache::clear`].
+    pub fn invalidate_all(&self) {
+        self.clear();
+    }
+
+    /// Clear the in-memory cache by removing at most `budget` entries per lock critical section, and yield to the
+    /// runtime between the critical sections.
+    ///
+    /// Prefer it to [`Cache::clear`] to clear a huge cache within an async context.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::cache::clear_cooperative")
+    )]
+    pub async fn clear_cooperative(&self, budget: usize) {
+        match self {
+            Cache::Fifo(cache) => cache.clear_cooperative(budget).await,
+            Cache::S3Fifo(cache) => cache.clear_cooperative(budget).await,
+            Cache::Lru(cache) => cache.clear_cooperative(budget).await,
+            Cache::Lfu(cache) => cache.clear_cooperative(budget).await,
+            Cache::Sieve(cache) => cache.clear_cooperative(budget).await,
+            Cache::DecayLfu(cache) => cache.clear_cooperative(budget).await,
+        }
+    }
+
+    /// Reserve the indexer capacity for at least `additional` more entries, evenly split among the shards.
+    ///
+    /// Reserving before a bulk load avoids repeated rehashing of the indexers while inserting.
+    pub fn reserve(&self, additional: usize) {
+        match self {
+            Cache::Fifo(cache) => cache.reserve(additional),
+            Cache::S3Fifo(cache) => cache.reserve(additional),
+            Cache::Lru(cache) => cache.reserve(additional),
+            Cache::Lfu(cache) => cache.reserve(additional),
+            Cache::Sieve(cache) => cache.reserve(additional),
+            Cache::DecayLfu(cache) => cache.reserve(additional),
         }
     }
 
@@ -833,6 +1617,7 @@ where
             Cache::Lru(cache) => cache.capacity(),
             Cache::Lfu(cache) => cache.capacity(),
             Cache::Sieve(cache) => cache.capacity(),
+            Cache::DecayLfu(cache) => cache.capacity(),
         }
     }
 
@@ -844,15 +1629,143 @@ where
             Cache::Lru(cache) => cache.usage(),
             Cache::Lfu(cache) => cache.usage(),
             Cache::Sieve(cache) => cache.usage(),
+            Cache::DecayLfu(cache) => cache.usage(),
+        }
+    }
+
+    /// Create a read-only view of the in-memory cache, which shares the same cache.
+    pub fn view(&self) -> CacheView<K, V, S, P> {
+        CacheView { cache: self.clone() }
+    }
+
+    /// Verify the invariants of the in-memory cache, panic if violated.
+    ///
+    /// For catching state corruption in debug builds and tests.
+    #[cfg(debug_assertions)]
+    pub fn audit(&self) {
+        match self {
+            Cache::Fifo(cache) => cache.audit(),
+            Cache::S3Fifo(cache) => cache.audit(),
+            Cache::Lru(cache) => cache.audit(),
+            Cache::Lfu(cache) => cache.audit(),
+            Cache::Sieve(cache) => cache.audit(),
+            Cache::DecayLfu(cache) => cache.audit(),
+        }
+    }
+
+    /// Get the count of the entries in the in-memory cache.
+    pub fn len(&self) -> usize {
+        match self {
+            Cache::Fifo(cache) => cache.len(),
+            Cache::S3Fifo(cache) => cache.len(),
+            Cache::Lru(cache) => cache.len(),
+            Cache::Lfu(cache) => cache.len(),
+            Cache::Sieve(cache) => cache.len(),
+            Cache::DecayLfu(cache) => cache.len(),
+        }
+    }
+
+    /// Check if the in-memory cache holds no entry.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Cache::Fifo(cache) => cache.is_empty(),
+            Cache::S3Fifo(cache) => cache.is_empty(),
+            Cache::Lru(cache) => cache.is_empty(),
+            Cache::Lfu(cache) => cache.is_empty(),
+            Cache::Sieve(cache) => cache.is_empty(),
+            Cache::DecayLfu(cache) => cache.is_empty(),
+        }
+    }
+
+    /// Get the usage of each shard of the in-memory cache as a fraction of its capacity.
+    pub fn shard_balance(&self) -> Vec<f64> {
+        match self {
+            Cache::Fifo(cache) => cache.shard_balance(),
+            Cache::S3Fifo(cache) => cache.shard_balance(),
+            Cache::Lru(cache) => cache.shard_balance(),
+            Cache::Lfu(cache) => cache.shard_balance(),
+            Cache::Sieve(cache) => cache.shard_balance(),
+            Cache::DecayLfu(cache) => cache.shard_balance(),
+        }
+    }
+
+    /// Get the difference between the most and the least occupied shards of the in-memory cache.
+    ///
+    /// See [`Cache::shard_balance`].
+    pub fn max_shard_skew(&self) -> f64 {
+        match self {
+            Cache::Fifo(cache) => cache.max_shard_skew(),
+            Cache::S3Fifo(cache) => cache.max_shard_skew(),
+            Cache::Lru(cache) => cache.max_shard_skew(),
+            Cache::Lfu(cache) => cache.max_shard_skew(),
+            Cache::Sieve(cache) => cache.max_shard_skew(),
+            Cache::DecayLfu(cache) => cache.max_shard_skew(),
+        }
+    }
+
+    /// Get the statistics of each shard of the in-memory cache.
+    pub fn shard_stats(&self) -> Vec<CacheStats> {
+        match self {
+            Cache::Fifo(cache) => cache.shard_stats(),
+            Cache::S3Fifo(cache) => cache.shard_stats(),
+            Cache::Lru(cache) => cache.shard_stats(),
+            Cache::Lfu(cache) => cache.shard_stats(),
+            Cache::Sieve(cache) => cache.shard_stats(),
+            Cache::DecayLfu(cache) => cache.shard_stats(),
+        }
+    }
+
+    /// Get the statistics of the in-memory cache, aggregated from all shards.
+    pub fn stats(&self) -> CacheStats {
+        match self {
+            Cache::Fifo(cache) => cache.stats(),
+            Cache::S3Fifo(cache) => cache.stats(),
+            Cache::Lru(cache) => cache.stats(),
+            Cache::Lfu(cache) => cache.stats(),
+            Cache::Sieve(cache) => cache.stats(),
+            Cache::DecayLfu(cache) => cache.stats(),
+        }
+    }
+
+    /// Use the given clock to expire the entries with ttl and the tombstones of the negative cache.
+    ///
+    /// The entries and the tombstones inserted before are kept with their deadlines.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        match self {
+            Cache::Fifo(cache) => cache.set_clock(clock),
+            Cache::S3Fifo(cache) => cache.set_clock(clock),
+            Cache::Lru(cache) => cache.set_clock(clock),
+            Cache::Lfu(cache) => cache.set_clock(clock),
+            Cache::Sieve(cache) => cache.set_clock(clock),
+            Cache::DecayLfu(cache) => cache.set_clock(clock),
         }
     }
 
-    /// Hash the given key with the hash builder of the cache.
+    /// Hash the given key with the hash builder and the hash domain of the cache.
     pub fn hash<Q>(&self, key: &Q) -> u64
     where
         Q: Hash + ?Sized,
     {
-        self.hash_builder().hash_one(key)
+        match self {
+            Cache::Fifo(cache) => cache.hash(key),
+            Cache::S3Fifo(cache) => cache.hash(key),
+            Cache::Lru(cache) => cache.hash(key),
+            Cache::Lfu(cache) => cache.hash(key),
+            Cache::Sieve(cache) => cache.hash(key),
+            Cache::DecayLfu(cache) => cache.hash(key),
+        }
+    }
+
+    /// Get the hash domain of the in-memory cache.
+    pub fn hash_domain(&self) -> u64 {
+        match self {
+            Cache::Fifo(cache) => cache.hash_domain(),
+            Cache::S3Fifo(cache) => cache.hash_domain(),
+            Cache::Lru(cache) => cache.hash_domain(),
+            Cache::Lfu(cache) => cache.hash_domain(),
+            Cache::Sieve(cache) => cache.hash_domain(),
+            Cache::DecayLfu(cache) => cache.hash_domain(),
+        }
     }
 
     /// Get the hash builder of the in-memory cache.
@@ -863,6 +1776,7 @@ where
             Cache::Lru(cache) => cache.hash_builder(),
             Cache::Lfu(cache) => cache.hash_builder(),
             Cache::Sieve(cache) => cache.hash_builder(),
+            Cache::DecayLfu(cache) => cache.hash_builder(),
         }
     }
 
@@ -874,6 +1788,7 @@ where
             Cache::Lru(cache) => cache.shards(),
             Cache::Lfu(cache) => cache.shards(),
             Cache::Sieve(cache) => cache.shards(),
+            Cache::DecayLfu(cache) => cache.shards(),
         }
     }
 
@@ -886,6 +1801,7 @@ where
             Cache::Lru(cache) => cache.set_pipe(pipe),
             Cache::Lfu(cache) => cache.set_pipe(pipe),
             Cache::Sieve(cache) => cache.set_pipe(pipe),
+            Cache::DecayLfu(cache) => cache.set_pipe(pipe),
         }
     }
 
@@ -900,6 +1816,39 @@ where
             Cache::Lru(cache) => cache.evict_all(),
             Cache::Lfu(cache) => cache.evict_all(),
             Cache::Sieve(cache) => cache.evict_all(),
+            Cache::DecayLfu(cache) => cache.evict_all(),
+        }
+    }
+
+    /// Snapshot the estimated frequencies of the resident entries, for warm restarts.
+    ///
+    /// Only the LFU eviction algorithm keeps a frequency sketch. Returns `None` with the other eviction algorithms.
+    pub fn snapshot_frequencies(&self) -> Option<Vec<u8>> {
+        match self {
+            Cache::Lfu(cache) => Some(cache.snapshot_frequencies()),
+            Cache::Fifo(_) | Cache::S3Fifo(_) | Cache::Lru(_) | Cache::Sieve(_) | Cache::DecayLfu(_) => None,
+        }
+    }
+
+    /// Restore the estimated frequencies from a snapshot taken by [`Cache::snapshot_frequencies`].
+    ///
+    /// Returns an error if the snapshot is invalid or the eviction algorithm is not LFU.
+    pub fn restore_frequencies(&self, snapshot: &[u8]) -> Result<()> {
+        match self {
+            Cache::Lfu(cache) => cache.restore_frequencies(snapshot),
+            Cache::Fifo(_) | Cache::S3Fifo(_) | Cache::Lru(_) | Cache::Sieve(_) | Cache::DecayLfu(_) => Err(
+                Error::ConfigError("frequency snapshot is only supported by the lfu eviction algorithm".to_string()),
+            ),
+        }
+    }
+
+    /// Reset the estimated frequencies to `0` without evicting any entry.
+    ///
+    /// No-op with the eviction algorithms other than LFU.
+    pub fn reset_frequencies(&self) {
+        match self {
+            Cache::Lfu(cache) => cache.reset_frequencies(),
+            Cache::Fifo(_) | Cache::S3Fifo(_) | Cache::Lru(_) | Cache::Sieve(_) | Cache::DecayLfu(_) => {}
         }
     }
 
@@ -915,10 +1864,64 @@ where
             Cache::Lru(cache) => cache.flush().await,
             Cache::Lfu(cache) => cache.flush().await,
             Cache::Sieve(cache) => cache.flush().await,
+            Cache::DecayLfu(cache) => cache.flush().await,
+        }
+    }
+
+    /// Shut down the in-memory cache.
+    ///
+    /// All entries are removed, notified to the event listener with [`Event::Shutdown`], and offloaded into the disk
+    /// cache via the pipe if needed. The function returns after the offloading finishes.
+    ///
+    /// [`Event::Shutdown`]: foyer_common::event::Event::Shutdown
+    pub async fn shutdown(self) {
+        match self {
+            Cache::Fifo(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
+            Cache::S3Fifo(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
+            Cache::Lru(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
+            Cache::Lfu(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
+            Cache::Sieve(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
+            Cache::DecayLfu(cache) => Arc::unwrap_or_clone(cache).shutdown().await,
         }
     }
 }
 
+impl<K, V, S, P> Cache<K, V, S, P>
+where
+    K: Key + Code,
+    V: Value + Code,
+    S: HashBuilder,
+    P: Properties,
+{
+    /// Dump all entries of the in-memory cache into the writer in a compact binary format.
+    ///
+    /// Keys, values, hints and the eviction order are dumped. Useful for fast process restarts.
+    pub fn dump_to<W: Write>(&self, w: W) -> std::io::Result<()> {
+        match self {
+            Cache::Fifo(cache) => cache.dump_to(w),
+            Cache::S3Fifo(cache) => cache.dump_to(w),
+            Cache::Lru(cache) => cache.dump_to(w),
+            Cache::Lfu(cache) => cache.dump_to(w),
+            Cache::Sieve(cache) => cache.dump_to(w),
+            Cache::DecayLfu(cache) => cache.dump_to(w),
+        }
+    }
+
+    /// Build an in-memory cache with the builder and load the entries dumped by [`Cache::dump_to`] into it.
+    pub fn load_from<R: Read>(builder: CacheBuilder<K, V, S>, r: R) -> std::io::Result<Self> {
+        let cache = builder.build();
+        match &cache {
+            Cache::Fifo(cache) => cache.load_from(r)?,
+            Cache::S3Fifo(cache) => cache.load_from(r)?,
+            Cache::Lru(cache) => cache.load_from(r)?,
+            Cache::Lfu(cache) => cache.load_from(r)?,
+            Cache::Sieve(cache) => cache.load_from(r)?,
+            Cache::DecayLfu(cache) => cache.load_from(r)?,
+        }
+        Ok(cache)
+    }
+}
+
 /// A future that is used to get entry value from the remote storage for the in-memory cache.
 #[pin_project(project = FetchProj)]
 pub enum Fetch<K, V, ER, S = DefaultHasher, P = CacheProperties>
@@ -938,6 +1941,8 @@ where
     Lfu(#[pin] LfuFetch<K, V, ER, S, P>),
     /// A future that is used to get entry value from the remote storage for the in-memory sieve cache.
     Sieve(#[pin] SieveFetch<K, V, ER, S, P>),
+    /// A future that is used to get entry value from the remote storage for the in-memory decaying LFU cache.
+    DecayLfu(#[pin] DecayLfuFetch<K, V, ER, S, P>),
 }
 
 impl<K, V, ER, S, P> From<FifoFetch<K, V, ER, S, P>> for Fetch<K, V, ER, S, P>
@@ -1000,6 +2005,18 @@ where
     }
 }
 
+impl<K, V, ER, S, P> From<DecayLfuFetch<K, V, ER, S, P>> for Fetch<K, V, ER, S, P>
+where
+    K: Key,
+    V: Value,
+    S: HashBuilder,
+    P: Properties,
+{
+    fn from(entry: DecayLfuFetch<K, V, ER, S, P>) -> Self {
+        Self::DecayLfu(entry)
+    }
+}
+
 impl<K, V, ER, S, P> Future for Fetch<K, V, ER, S, P>
 where
     K: Key,
@@ -1017,6 +2034,7 @@ where
             FetchProj::Lru(entry) => entry.poll(cx).map(|res| res.map(CacheEntry::from)),
             FetchProj::Lfu(entry) => entry.poll(cx).map(|res| res.map(CacheEntry::from)),
             FetchProj::Sieve(entry) => entry.poll(cx).map(|res| res.map(CacheEntry::from)),
+            FetchProj::DecayLfu(entry) => entry.poll(cx).map(|res| res.map(CacheEntry::from)),
         }
     }
 }
@@ -1036,6 +2054,7 @@ where
             Fetch::Lru(fetch) => fetch.state(),
             Fetch::Lfu(fetch) => fetch.state(),
             Fetch::Sieve(fetch) => fetch.state(),
+            Fetch::DecayLfu(fetch) => fetch.state(),
         }
     }
 
@@ -1048,6 +2067,185 @@ where
             Fetch::Lru(fetch) => fetch.store(),
             Fetch::Lfu(fetch) => fetch.store(),
             Fetch::Sieve(fetch) => fetch.store(),
+            Fetch::DecayLfu(fetch) => fetch.store(),
+        }
+    }
+}
+
+impl<K, V, S, P> Cache<K, V, S, P>
+where
+    K: Key + Clone,
+    V: Value + Clone,
+    S: HashBuilder,
+    P: Properties,
+{
+    /// Remove all entries from the in-memory cache and return them, draining the shards in parallel.
+    ///
+    /// Each shard is drained by its own task that only locks that shard. The entries are notified to the event
+    /// listener with [`Event::Shutdown`], but not offloaded to the disk cache.
+    ///
+    /// [`Event::Shutdown`]: foyer_common::event::Event::Shutdown
+    pub async fn drain_parallel(self) -> Vec<(K, V)> {
+        match self {
+            Cache::Fifo(cache) => RawCache::clone(&cache).drain_parallel().await,
+            Cache::S3Fifo(cache) => RawCache::clone(&cache).drain_parallel().await,
+            Cache::Lru(cache) => RawCache::clone(&cache).drain_parallel().await,
+            Cache::Lfu(cache) => RawCache::clone(&cache).drain_parallel().await,
+            Cache::Sieve(cache) => RawCache::clone(&cache).drain_parallel().await,
+            Cache::DecayLfu(cache) => RawCache::clone(&cache).drain_parallel().await,
+        }
+    }
+
+    /// Get a snapshot of the entries in the given shard.
+    ///
+    /// Together with [`Cache::shards`], the callers can process the entries shard by shard in parallel.
+    ///
+    /// The snapshot is weakly consistent: each shard is snapshotted under its own lock, so the snapshots of different
+    /// shards may be taken at different moments. Ephemeral entries are not included.
+    pub fn shard_entries(&self, shard: usize) -> Vec<(K, V)> {
+        match self {
+            Cache::Fifo(cache) => cache.shard_entries(shard),
+            Cache::S3Fifo(cache) => cache.shard_entries(shard),
+            Cache::Lru(cache) => cache.shard_entries(shard),
+            Cache::Lfu(cache) => cache.shard_entries(shard),
+            Cache::Sieve(cache) => cache.shard_entries(shard),
+            Cache::DecayLfu(cache) => cache.shard_entries(shard),
+        }
+    }
+
+    /// Get the next `n` eviction candidates of the given shard after skipping the first `skip` ones.
+    ///
+    /// Only the given shard is locked while peeking.
+    pub fn shard_eviction_candidates(&self, shard: usize, skip: usize, n: usize) -> Vec<(K, V)> {
+        match self {
+            Cache::Fifo(cache) => cache.shard_eviction_candidates(shard, skip, n),
+            Cache::S3Fifo(cache) => cache.shard_eviction_candidates(shard, skip, n),
+            Cache::Lru(cache) => cache.shard_eviction_candidates(shard, skip, n),
+            Cache::Lfu(cache) => cache.shard_eviction_candidates(shard, skip, n),
+            Cache::Sieve(cache) => cache.shard_eviction_candidates(shard, skip, n),
+            Cache::DecayLfu(cache) => cache.shard_eviction_candidates(shard, skip, n),
+        }
+    }
+
+    /// Iterate the entries that would be evicted next lazily, fetching `batch` candidates from a shard at a time.
+    ///
+    /// See [`EvictionCandidates`] for the ordering and consistency guarantees.
+    pub fn eviction_candidates(&self, batch: usize) -> EvictionCandidates<'_, K, V, S, P> {
+        assert!(batch > 0, "batch must be positive");
+        EvictionCandidates {
+            cache: self,
+            batch,
+            cursors: (0..self.shards()).map(|_| ShardCursor::Unvisited).collect(),
+            shard: 0,
+            buffer: vec![].into_iter(),
+        }
+    }
+
+    fn shard_eviction_batches(&self, shard: usize, batch: usize) -> Box<dyn Iterator<Item = Vec<(K, V)>> + Send> {
+        match self {
+            Cache::Fifo(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+            Cache::S3Fifo(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+            Cache::Lru(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+            Cache::Lfu(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+            Cache::Sieve(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+            Cache::DecayLfu(cache) => Box::new(cache.shard_eviction_batches(shard, batch)),
+        }
+    }
+}
+
+/// Position of [`EvictionCandidates`] in the eviction order of a shard.
+enum ShardCursor<K, V> {
+    Unvisited,
+    Visiting(Box<dyn Iterator<Item = Vec<(K, V)>> + Send>),
+    Exhausted,
+}
+
+impl<K, V> Debug for ShardCursor<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unvisited => write!(f, "Unvisited"),
+            Self::Visiting(_) => write!(f, "Visiting"),
+            Self::Exhausted => write!(f, "Exhausted"),
+        }
+    }
+}
+
+/// A lazy iterator over the eviction candidates of the in-memory cache.
+///
+/// The candidates are yielded in approximate eviction order: the shards are visited round-robin, and each visit
+/// yields the next batch of candidates of one shard. Only one shard is locked at a time.
+///
+/// Each visit locks the shard once and peeks the next batch after the last candidate yielded from the shard, see
+/// [`Eviction::peek_n_after`]. For the eviction algorithms that cannot resume from a candidate, the candidates already
+/// yielded are peeked again and skipped. The keys and values are cloned batch by batch. The cache may be modified
+/// between the visits, so the candidates are not a consistent snapshot: an entry may be skipped or yielded twice if it
+/// is moved in the eviction order, and an entry inserted later may or may not be yielded.
+pub struct EvictionCandidates<'a, K, V, S, P>
+where
+    K: Key + Clone,
+    V: Value + Clone,
+    S: HashBuilder,
+    P: Properties,
+{
+    cache: &'a Cache<K, V, S, P>,
+    batch: usize,
+    cursors: Vec<ShardCursor<K, V>>,
+    shard: usize,
+    buffer: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V, S, P> Debug for EvictionCandidates<'_, K, V, S, P>
+where
+    K: Key + Clone,
+    V: Value + Clone,
+    S: HashBuilder,
+    P: Properties,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvictionCandidates")
+            .field("batch", &self.batch)
+            .field("cursors", &self.cursors)
+            .field("shard", &self.shard)
+            .finish()
+    }
+}
+
+impl<K, V, S, P> Iterator for EvictionCandidates<'_, K, V, S, P>
+where
+    K: Key + Clone,
+    V: Value + Clone,
+    S: HashBuilder,
+    P: Properties,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.next() {
+                return Some(item);
+            }
+            if self
+                .cursors
+                .iter()
+                .all(|cursor| matches!(cursor, ShardCursor::Exhausted))
+            {
+                return None;
+            }
+
+            let shard = self.shard;
+            self.shard = (self.shard + 1) % self.cursors.len();
+            let cursor = &mut self.cursors[shard];
+            if let ShardCursor::Unvisited = cursor {
+                *cursor = ShardCursor::Visiting(self.cache.shard_eviction_batches(shard, self.batch));
+            }
+            let ShardCursor::Visiting(batches) = cursor else {
+                continue;
+            };
+            let candidates = batches.next().unwrap_or_default();
+            if candidates.len() < self.batch {
+                *cursor = ShardCursor::Exhausted;
+            }
+            self.buffer = candidates.into_iter();
         }
     }
 }
@@ -1077,6 +2275,7 @@ where
             Cache::Lru(cache) => Fetch::from(cache.fetch(key, fetch)),
             Cache::Lfu(cache) => Fetch::from(cache.fetch(key, fetch)),
             Cache::Sieve(cache) => Fetch::from(cache.fetch(key, fetch)),
+            Cache::DecayLfu(cache) => Fetch::from(cache.fetch(key, fetch)),
         }
     }
 
@@ -1098,6 +2297,7 @@ where
             Cache::Lru(cache) => Fetch::from(cache.fetch_with_properties(key, properties, fetch)),
             Cache::Lfu(cache) => Fetch::from(cache.fetch_with_properties(key, properties, fetch)),
             Cache::Sieve(cache) => Fetch::from(cache.fetch_with_properties(key, properties, fetch)),
+            Cache::DecayLfu(cache) => Fetch::from(cache.fetch_with_properties(key, properties, fetch)),
         }
     }
 
@@ -1129,20 +2329,47 @@ where
             Cache::Lfu(cache) => Fetch::from(cache.fetch_inner(key, properties, fetch, runtime)),
             Cache::S3Fifo(cache) => Fetch::from(cache.fetch_inner(key, properties, fetch, runtime)),
             Cache::Sieve(cache) => Fetch::from(cache.fetch_inner(key, properties, fetch, runtime)),
+            Cache::DecayLfu(cache) => Fetch::from(cache.fetch_inner(key, properties, fetch, runtime)),
+        }
+    }
+
+    /// Get the cached entry with the given key, or fetch it with the fetcher and insert it on cache miss.
+    ///
+    /// The concurrent misses of the same key are deduplicated, so the fetcher is called only once.
+    ///
+    /// Returns `None` if the fetcher doesn't find the key.
+    ///
+    /// If the negative cache is enabled, the key that the fetcher doesn't find is kept as a tombstone, see
+    /// [`CacheBuilder::with_negative_ttl`].
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::cache::get_or_fetch"))]
+    pub async fn get_or_fetch<F>(&self, key: &K, fetcher: Arc<F>) -> Option<CacheEntry<K, V, S, P>>
+    where
+        F: Fetcher<K, V>,
+    {
+        match self {
+            Cache::Fifo(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
+            Cache::S3Fifo(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
+            Cache::Lru(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
+            Cache::Lfu(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
+            Cache::Sieve(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
+            Cache::DecayLfu(cache) => cache.get_or_fetch(key, fetcher).await.map(CacheEntry::from),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{ops::Range, time::Duration};
+    use std::{hash::BuildHasher, ops::Range, time::Duration};
 
+    use foyer_common::clock::MockClock;
     use futures_util::future::join_all;
     use itertools::Itertools;
     use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
     use super::*;
-    use crate::eviction::{fifo::FifoConfig, lfu::LfuConfig, lru::LruConfig, s3fifo::S3FifoConfig};
+    use crate::eviction::{
+        fifo::FifoConfig, lfu::LfuConfig, lru::LruConfig, s3fifo::S3FifoConfig, sketch::FrequencyBackend,
+    };
 
     const CAPACITY: usize = 100;
     const SHARDS: usize = 4;
@@ -1162,6 +2389,7 @@ mod tests {
             .with_shards(SHARDS)
             .with_eviction_config(LruConfig {
                 high_priority_pool_ratio: 0.1,
+                scan_resistant: false,
             })
             .build()
     }
@@ -1174,6 +2402,13 @@ mod tests {
                 protected_capacity_ratio: 0.8,
                 cmsketch_eps: 0.001,
                 cmsketch_confidence: 0.9,
+                update_frequency_on_push: true,
+                bypass_window_size: 0,
+                initial_frequency: 0,
+                decay_factor: 2,
+                frequency_backend: FrequencyBackend::CMSketch,
+                resize_sketch: false,
+                protected_idle_timeout: None,
             })
             .build()
     }
@@ -1196,6 +2431,13 @@ mod tests {
             .build()
     }
 
+    fn decay_lfu() -> Cache<u64, u64> {
+        CacheBuilder::new(CAPACITY)
+            .with_shards(SHARDS)
+            .with_eviction_config(DecayLfuConfig::default())
+            .build()
+    }
+
     fn init_cache(cache: &Cache<u64, u64>, rng: &mut StdRng) {
         let mut v = RANGE.collect_vec();
         v.shuffle(rng);
@@ -1254,6 +2496,9 @@ mod tests {
             .collect_vec();
 
         join_all(handles).await;
+
+        #[cfg(debug_assertions)]
+        cache.audit();
     }
 
     #[tokio::test]
@@ -1280,4 +2525,432 @@ mod tests {
     async fn test_sieve_cache() {
         case(sieve()).await
     }
+
+    #[tokio::test]
+    async fn test_decay_lfu_cache() {
+        case(decay_lfu()).await
+    }
+
+    #[test]
+    fn test_queue_of() {
+        // window: 1, protected: 7, probation: 2
+        let cache: Cache<u64, u64> = CacheBuilder::new(10)
+            .with_shards(1)
+            .with_eviction_config(LfuConfig {
+                window_capacity_ratio: 0.1,
+                protected_capacity_ratio: 0.8,
+                ..Default::default()
+            })
+            .build();
+
+        cache.insert(1, 1);
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Window));
+
+        // The window overflows into probation.
+        cache.insert(2, 2);
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Probation));
+        assert_eq!(cache.queue_of(&2), Some(QueueTag::Window));
+
+        // An access in probation promotes the entry to protected.
+        cache.get(&1);
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Protected));
+
+        cache.remove(&1);
+        assert_eq!(cache.queue_of(&1), None);
+        assert_eq!(cache.queue_of(&3), None);
+
+        let cache = fifo();
+        cache.insert(1, 1);
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Main));
+    }
+
+    #[test]
+    fn test_cache_view() {
+        let cache: Cache<u64, u64> = CacheBuilder::new(2)
+            .with_shards(1)
+            .with_eviction_config(LruConfig {
+                high_priority_pool_ratio: 0.0,
+                scan_resistant: false,
+            })
+            .build();
+        let view = cache.view();
+        assert!(view.is_empty());
+
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.usage(), 2);
+        assert_eq!(view.capacity(), 2);
+        assert!(view.contains(&1));
+        assert_eq!(view.clone().get(&2).map(|e| *e.value()), Some(2));
+
+        // Peeking doesn't refresh the entry, so it is still the first to be evicted.
+        assert_eq!(view.peek(&1).map(|e| *e.value()), Some(1));
+        cache.insert(3, 3);
+        assert!(!view.contains(&1));
+        assert!(view.peek(&1).is_none());
+        assert!(view.contains(&2));
+
+        cache.remove(&2);
+        assert!(view.get(&2).is_none());
+        assert_eq!(view.len(), 1);
+    }
+
+    #[test]
+    fn test_capacity_unit() {
+        let cache: Cache<u64, u64> = CacheBuilder::new(0)
+            .with_capacity(Capacity::Entries(10))
+            .with_shards(1)
+            .with_eviction_config(FifoConfig {})
+            .build();
+        for i in 0..10 {
+            cache.insert(i, i * 100);
+        }
+        assert_eq!(cache.len(), 10);
+        assert!(cache.contains(&0));
+        // The 11th entry triggers the eviction.
+        cache.insert(10, 1000);
+        assert_eq!(cache.len(), 10);
+        assert_eq!(cache.usage(), 10);
+        assert!(!cache.contains(&0));
+
+        let cache: Cache<u64, u64> = CacheBuilder::new(0)
+            .with_capacity(Capacity::Bytes(100))
+            .with_shards(1)
+            .with_eviction_config(FifoConfig {})
+            .with_weighter(|_, v: &u64| *v as usize)
+            .build();
+        for i in 0..3 {
+            cache.insert(i, 30);
+        }
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.usage(), 90);
+        // The 4th entry exceeds 100 bytes and triggers the eviction.
+        cache.insert(3, 30);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.usage(), 90);
+        assert!(!cache.contains(&0));
+
+        // The unit is kept with the shard capacities.
+        let cache: Cache<u64, u64> = CacheBuilder::new(0)
+            .with_capacity(Capacity::Entries(0))
+            .with_shard_capacities(vec![4, 4])
+            .build();
+        assert_eq!(cache.capacity(), 8);
+    }
+
+    #[test]
+    fn test_shard_capacities_with_shards() {
+        // The combination is validated regardless of the call order.
+        let cache: Cache<u64, u64> = CacheBuilder::new(0)
+            .with_shard_capacities(vec![4, 12])
+            .with_shards(2)
+            .build();
+        assert_eq!(cache.shards(), 2);
+        assert_eq!(cache.capacity(), 16);
+
+        let res: Result<Cache<u64, u64>> = CacheBuilder::new(0)
+            .with_shard_capacities(vec![4, 12])
+            .with_shards(4)
+            .try_build();
+        assert!(matches!(res, Err(Error::ConfigError(_))));
+
+        let res: Result<Cache<u64, u64>> = CacheBuilder::new(0)
+            .with_shards(4)
+            .with_shard_capacities(vec![4, 12])
+            .try_build();
+        assert!(matches!(res, Err(Error::ConfigError(_))));
+
+        let res: Result<Cache<u64, u64>> = CacheBuilder::new(0).with_shard_capacities(vec![]).try_build();
+        assert!(matches!(res, Err(Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_capacity_entries_reject_weighter() {
+        let res: Result<Cache<u64, u64>> = CacheBuilder::new(0)
+            .with_capacity(Capacity::Entries(10))
+            .with_weighter(|_, v: &u64| *v as usize)
+            .try_build();
+        assert!(matches!(res, Err(Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_capacity_entries_ignore_explicit_weight() {
+        let cache: Cache<u64, u64> = CacheBuilder::new(0)
+            .with_capacity(Capacity::Entries(4))
+            .with_shards(1)
+            .with_eviction_config(FifoConfig {})
+            .build();
+        for i in 0..4 {
+            cache.insert_with_weight(i, i, 100);
+        }
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.usage(), 4);
+        assert_eq!(cache.get(&0).unwrap().weight(), 1);
+    }
+
+    #[test]
+    fn test_get_many() {
+        for cache in [fifo(), lru(), lfu(), s3fifo(), sieve(), decay_lfu()] {
+            for i in (0..20).step_by(2) {
+                cache.insert(i, i * 10);
+            }
+
+            let keys = [9, 0, 4, 5, 18, 100, 4, 2];
+            // The keys span all shards.
+            let hash_builder = DefaultHasher::default();
+            let shards = keys
+                .iter()
+                .map(|key| hash_builder.hash_one(key) as usize % SHARDS)
+                .unique()
+                .count();
+            assert!(shards > 1);
+
+            let entries = cache.get_many(&keys);
+            assert_eq!(entries.len(), keys.len());
+            for (key, entry) in keys.iter().zip_eq(entries.iter()) {
+                match entry {
+                    Some(entry) => {
+                        assert_eq!(entry.key(), key);
+                        assert_eq!(*entry.value(), key * 10);
+                    }
+                    None => assert!(key % 2 == 1 || *key >= 20),
+                }
+            }
+            assert_eq!(entries.iter().filter(|e| e.is_some()).count(), 5);
+        }
+
+        // The acquire op is applied to each hit.
+        let cache: Cache<u64, u64> = CacheBuilder::new(10)
+            .with_shards(1)
+            .with_eviction_config(LfuConfig {
+                window_capacity_ratio: 0.1,
+                protected_capacity_ratio: 0.8,
+                ..Default::default()
+            })
+            .build();
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Probation));
+        assert!(cache.get_many(&[3, 1])[1].is_some());
+        assert_eq!(cache.queue_of(&1), Some(QueueTag::Protected));
+    }
+
+    #[test]
+    fn test_insert_many() {
+        for (batched, single) in [
+            (fifo(), fifo()),
+            (lru(), lru()),
+            (lfu(), lfu()),
+            (s3fifo(), s3fifo()),
+            (sieve(), sieve()),
+            (decay_lfu(), decay_lfu()),
+        ] {
+            // The items span all shards and overflow the capacity, with duplicated keys.
+            let items = (0..CAPACITY as u64 * 2)
+                .map(|i| (i, i))
+                .chain((0..CAPACITY as u64 * 2).step_by(7).map(|i| (i, i * 10)))
+                .collect_vec();
+
+            let entries = batched.insert_many(items.clone());
+            assert_eq!(entries.len(), items.len());
+            for ((key, value), entry) in items.iter().zip_eq(entries.iter()) {
+                assert_eq!(entry.key(), key);
+                assert_eq!(entry.value(), value);
+            }
+            drop(entries);
+            for (key, value) in items {
+                single.insert(key, value);
+            }
+
+            // The batched insertion ends up with the same state as the single-key insertions.
+            assert_eq!(batched.usage(), single.usage());
+            let keys = (0..CAPACITY as u64 * 2).collect_vec();
+            for (b, s) in batched.get_many(&keys).into_iter().zip_eq(single.get_many(&keys)) {
+                assert_eq!(b.map(|e| *e.value()), s.map(|e| *e.value()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_force_shard() {
+        let cache: Cache<u64, u64> = CacheBuilder::new(CAPACITY)
+            .with_shards(SHARDS)
+            .with_eviction_config(FifoConfig {})
+            .with_force_shard(Some(2))
+            .build();
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+
+        let mut entries = cache.shard_entries(2);
+        entries.sort();
+        assert_eq!(entries, vec![(1, 1), (2, 2)]);
+        assert!([0, 1, 3].into_iter().all(|shard| cache.shard_entries(shard).is_empty()));
+    }
+
+    #[test]
+    fn test_dump_and_load() {
+        for cache in [fifo(), lru(), lfu(), s3fifo(), sieve(), decay_lfu()] {
+            for i in 0..CAPACITY as u64 * 2 {
+                cache.insert(i, i);
+            }
+            let mut buf = vec![];
+            cache.dump_to(&mut buf).unwrap();
+
+            let builder = CacheBuilder::new(CAPACITY).with_shards(SHARDS);
+            let builder = match &cache {
+                Cache::Fifo(_) => builder.with_eviction_config(FifoConfig {}),
+                Cache::Lru(_) => builder.with_eviction_config(LruConfig::default()),
+                Cache::Lfu(_) => builder.with_eviction_config(LfuConfig::default()),
+                Cache::S3Fifo(_) => builder.with_eviction_config(S3FifoConfig::default()),
+                Cache::Sieve(_) => builder.with_eviction_config(SieveConfig {}),
+                Cache::DecayLfu(_) => builder.with_eviction_config(DecayLfuConfig::default()),
+            };
+            let restored: Cache<u64, u64> = Cache::load_from(builder, &buf[..]).unwrap();
+            assert_eq!(restored.usage(), cache.usage());
+            for i in 0..CAPACITY as u64 * 2 {
+                assert_eq!(restored.contains(&i), cache.contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_entries() {
+        for cache in [fifo(), lru(), lfu(), s3fifo(), sieve(), decay_lfu()] {
+            for i in 0..CAPACITY as u64 * 2 {
+                cache.insert(i, i);
+            }
+            let entries = (0..cache.shards())
+                .flat_map(|shard| {
+                    let entries = cache.shard_entries(shard);
+                    assert!(entries
+                        .iter()
+                        .all(|(k, _)| cache.hash(k) as usize % cache.shards() == shard));
+                    entries
+                })
+                .collect_vec();
+            assert_eq!(entries.len(), cache.usage());
+            assert!(entries.iter().all(|(k, v)| k == v));
+            let keys = entries.into_iter().map(|(k, _)| k).sorted().collect_vec();
+            let expected = (0..CAPACITY as u64 * 2).filter(|i| cache.contains(i)).collect_vec();
+            assert_eq!(keys, expected);
+        }
+    }
+
+    #[test]
+    fn test_eviction_candidates() {
+        for cache in [fifo(), lru(), lfu(), s3fifo(), sieve(), decay_lfu()] {
+            for i in 0..CAPACITY as u64 * 2 {
+                cache.insert(i, i);
+            }
+
+            let candidates = cache.eviction_candidates(7).collect_vec();
+            assert_eq!(candidates.len(), cache.usage());
+            assert!(candidates.iter().all(|(k, v)| k == v));
+
+            // Each shard yields its candidates in its own eviction order.
+            for shard in 0..cache.shards() {
+                let expected = cache.shard_eviction_candidates(shard, 0, CAPACITY);
+                let candidates = candidates
+                    .iter()
+                    .filter(|(k, _)| cache.hash(k) as usize % cache.shards() == shard)
+                    .cloned()
+                    .collect_vec();
+                assert_eq!(candidates, expected);
+            }
+
+            // The first batch of each shard comes before the second batch of any shard.
+            let firsts = candidates[..7 * cache.shards()]
+                .iter()
+                .map(|(k, _)| cache.hash(k) as usize % cache.shards())
+                .counts();
+            assert!((0..cache.shards()).all(|shard| firsts[&shard] == 7));
+        }
+    }
+
+    #[test]
+    fn test_remove_many() {
+        for cache in [fifo(), lru(), lfu(), s3fifo(), sieve(), decay_lfu()] {
+            for i in 0..CAPACITY as u64 {
+                cache.insert(i, i);
+            }
+            let usage = cache.usage();
+            let survivors = (CAPACITY as u64 / 2..CAPACITY as u64)
+                .filter(|i| cache.contains(i))
+                .collect_vec();
+
+            // Keys that span all shards, with missing and duplicated keys.
+            let keys = (0..CAPACITY as u64 / 2)
+                .chain([CAPACITY as u64 * 2, CAPACITY as u64 * 2 + 1])
+                .chain([0, 1])
+                .collect_vec();
+            let removed = keys.iter().filter(|key| cache.contains(*key)).unique().count();
+            assert_eq!(cache.remove_many(&keys), removed);
+            assert_eq!(cache.usage(), usage - removed);
+
+            for i in 0..CAPACITY as u64 / 2 {
+                assert!(!cache.contains(&i));
+            }
+            assert!(survivors.iter().all(|i| cache.contains(i)));
+        }
+    }
+
+    #[test]
+    fn test_hash_domain() {
+        let cache = |hash_domain: u64| -> Cache<u64, u64> {
+            CacheBuilder::new(CAPACITY)
+                .with_shards(SHARDS)
+                .with_hash_domain(hash_domain)
+                .build()
+        };
+        let (c0, c1, c2) = (cache(0), cache(1), cache(2));
+
+        // The default domain leaves the hash unchanged.
+        assert_eq!(c0.hash(&42u64), c0.hash_builder().hash_one(42u64));
+        assert_ne!(c1.hash(&42u64), c0.hash(&42u64));
+        assert_ne!(c1.hash(&42u64), c2.hash(&42u64));
+
+        // The entries carry the hash of their own domain.
+        for (cache, domain) in [(&c1, 1), (&c2, 2)] {
+            assert_eq!(cache.hash_domain(), domain);
+            let entry = cache.insert(42, 42);
+            assert_eq!(entry.hash(), cache.hash(&42u64));
+            assert_eq!(cache.get(&42).unwrap().value(), &42);
+        }
+    }
+
+    #[test]
+    fn test_ttl() {
+        let clock = MockClock::default();
+        let caches: [Cache<u64, u64>; 6] = [
+            CacheBuilder::new(CAPACITY).with_eviction_config(FifoConfig {}),
+            CacheBuilder::new(CAPACITY).with_eviction_config(LruConfig::default()),
+            CacheBuilder::new(CAPACITY).with_eviction_config(LfuConfig::default()),
+            CacheBuilder::new(CAPACITY).with_eviction_config(S3FifoConfig::default()),
+            CacheBuilder::new(CAPACITY).with_eviction_config(SieveConfig {}),
+            CacheBuilder::new(CAPACITY).with_eviction_config(DecayLfuConfig::default()),
+        ]
+        .map(|builder| builder.with_shards(SHARDS).with_clock(Arc::new(clock.clone())).build());
+
+        for cache in caches.iter() {
+            // Insert few entries, so none of them is evicted.
+            for i in 0..10 {
+                cache.insert_with_properties(i, i, CacheProperties::default().with_ttl(Duration::from_secs(10)));
+            }
+            for i in 10..20 {
+                cache.insert(i, i);
+            }
+        }
+
+        clock.advance(Duration::from_secs(10));
+        for cache in caches.iter() {
+            for i in 0..10 {
+                assert!(cache.get(&i).is_none());
+            }
+            for i in 10..20 {
+                assert_eq!(cache.get(&i).unwrap().value(), &i);
+            }
+            assert_eq!(cache.usage(), 10);
+        }
+    }
 }
@@ -26,6 +26,9 @@ pub enum Error {
     /// Wait error.
     #[error("wait for concurrent fetch result error: {0}")]
     Wait(Box<dyn std::error::Error + Send + Sync + 'static>),
+    /// Invalid snapshot error.
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
 }
 
 impl Error {
@@ -0,0 +1,401 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
+
+use foyer_common::{
+    code::{Key, Value},
+    properties::Properties,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{Eviction, Op};
+use crate::{
+    error::{Error, Result},
+    record::Record,
+};
+
+/// The scores are renormalized only when the gain of a new access exceeds the threshold, to keep them away from
+/// overflow.
+///
+/// The threshold is close to the range of `f64` (about `1.8e308`), leaving room for the sum of the gains of an entry,
+/// which is at most `gain / (1 - decay)`. So the renormalization is rare even with a small decay, e.g. once every ~950
+/// accesses with `decay = 0.5`.
+const RENORMALIZE_THRESHOLD: f64 = 1e288;
+
+/// Decaying LFU eviction algorithm config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecayLfuConfig {
+    /// Factor to multiply the scores of all entries by on each access to the cache shard.
+    ///
+    /// A smaller factor forgets the access history more aggressively.
+    ///
+    /// Must be in (0, 1).
+    pub decay: f64,
+}
+
+impl Default for DecayLfuConfig {
+    fn default() -> Self {
+        Self { decay: 0.9999 }
+    }
+}
+
+/// Decaying LFU eviction algorithm state.
+#[derive(Debug, Default)]
+pub struct DecayLfuState {
+    /// Access score scaled by the gain of the current landmark.
+    score: f64,
+    /// Sequence to break the ties of the scores, smaller is older.
+    seq: u64,
+}
+
+impl DecayLfuState {
+    fn key(&self) -> (u64, u64) {
+        // The bit representation of a non-negative float preserves its order.
+        (self.score.to_bits(), self.seq)
+    }
+}
+
+/// Records ordered by their scores and sequences.
+type Queue<K, V, P> = BTreeMap<(u64, u64), Arc<Record<DecayLfu<K, V, P>>>>;
+
+/// LFU eviction algorithm that keeps an exact exponentially weighted moving average of the accesses per entry.
+///
+/// Every access to the cache shard multiplies the scores of all entries by `decay`, and an access to an entry adds `1`
+/// to its score. The entry with the lowest score is evicted first, ties are broken by insertion order.
+///
+/// Unlike [`super::lfu::Lfu`], the scores are tracked per entry instead of in a shared count-min sketch, so the
+/// entries with different access rates are never conflated by hash collisions, at the cost of `O(log n)` operations.
+///
+/// The decay is applied lazily with forward decay: instead of decaying all scores on each access, each access gains
+/// `decay^-t` where `t` is the count of the accesses since the landmark, and the scores are rescaled only when the gain
+/// approaches the range of `f64`.
+pub struct DecayLfu<K, V, P>
+where
+    K: Key,
+    V: Value,
+    P: Properties,
+{
+    queue: Queue<K, V, P>,
+    /// Count of the accesses since the landmark.
+    tick: u64,
+    seq: u64,
+    capacity: usize,
+    config: DecayLfuConfig,
+}
+
+impl<K, V, P> DecayLfu<K, V, P>
+where
+    K: Key,
+    V: Value,
+    P: Properties,
+{
+    /// Advance the clock by one access and return the gain of the access.
+    fn advance(&mut self) -> f64 {
+        self.tick += 1;
+        let gain = self.gain();
+        if gain < RENORMALIZE_THRESHOLD {
+            return gain;
+        }
+        self.renormalize();
+        self.gain()
+    }
+
+    fn gain(&self) -> f64 {
+        self.config.decay.powf(-(self.tick as f64))
+    }
+
+    /// Move the landmark to now, rescale the scores to the new landmark.
+    ///
+    /// Rescaling keeps the order of the scores, except for the ones that underflow to the same value, so the queue is
+    /// rebuilt from the nearly sorted records.
+    fn renormalize(&mut self) {
+        let gain = self.gain();
+        self.queue = std::mem::take(&mut self.queue)
+            .into_values()
+            .map(|record| {
+                let state = unsafe { &mut *record.state().get() };
+                state.score /= gain;
+                (state.key(), record)
+            })
+            .collect();
+        self.tick = 0;
+    }
+
+    /// Get the decayed score of the record, the caller guarantees that the record is in the eviction container.
+    #[cfg(test)]
+    fn score(&self, record: &Arc<Record<Self>>) -> f64 {
+        let state = unsafe { &*record.state().get() };
+        state.score / self.gain()
+    }
+}
+
+impl<K, V, P> Eviction for DecayLfu<K, V, P>
+where
+    K: Key,
+    V: Value,
+    P: Properties,
+{
+    type Config = DecayLfuConfig;
+    type Key = K;
+    type Value = V;
+    type Properties = P;
+    type State = DecayLfuState;
+
+    fn new(capacity: usize, config: &Self::Config) -> Self
+    where
+        Self: Sized,
+    {
+        assert!(
+            config.decay > 0.0 && config.decay < 1.0,
+            "decay must be in (0, 1), given: {}",
+            config.decay
+        );
+
+        Self {
+            queue: BTreeMap::new(),
+            tick: 0,
+            seq: 0,
+            capacity,
+            config: config.clone(),
+        }
+    }
+
+    fn update(&mut self, capacity: usize, config: Option<&Self::Config>) -> Result<()> {
+        if let Some(config) = config {
+            if config.decay <= 0.0 || config.decay >= 1.0 {
+                return Err(Error::ConfigError(format!(
+                    "decay must be in (0, 1), given: {}, new config ignored",
+                    config.decay
+                )));
+            }
+            // Rescale the scores with the old decay before switching to the new one.
+            self.renormalize();
+            self.config = config.clone();
+        }
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
+    fn push(&mut self, record: Arc<Record<Self>>) {
+        let gain = self.advance();
+        let state = unsafe { &mut *record.state().get() };
+        state.score = gain;
+        state.seq = self.seq;
+        self.seq += 1;
+        record.set_in_eviction(true);
+        self.queue.insert(state.key(), record);
+    }
+
+    fn pop(&mut self) -> Option<Arc<Record<Self>>> {
+        self.pop_with(|_| false)
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        let key = self
+            .queue
+            .iter()
+            .find_map(|(key, record)| (!skip(record)).then_some(*key))?;
+        let record = self.queue.remove(&key).unwrap();
+        let state = unsafe { &mut *record.state().get() };
+        *state = DecayLfuState::default();
+        record.set_in_eviction(false);
+        Some(record)
+    }
+
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        self.queue.values().take(n).cloned().collect()
+    }
+
+    fn peek_n_after(&self, record: &Arc<Record<Self>>, n: usize) -> Option<Vec<Arc<Record<Self>>>> {
+        if !record.is_in_eviction() {
+            return None;
+        }
+        let key = unsafe { &*record.state().get() }.key();
+        let records = self
+            .queue
+            .range((Bound::Excluded(key), Bound::Unbounded))
+            .take(n)
+            .map(|(_, record)| record.clone())
+            .collect();
+        Some(records)
+    }
+
+    fn remove(&mut self, record: &Arc<Record<Self>>) {
+        let state = unsafe { &mut *record.state().get() };
+        self.queue.remove(&state.key());
+        *state = DecayLfuState::default();
+        record.set_in_eviction(false);
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+        self.tick = 0;
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        self.queue.values().cloned().collect()
+    }
+
+    fn acquire() -> Op<Self> {
+        Op::mutable(|this: &mut Self, record| {
+            if !record.is_in_eviction() {
+                return;
+            }
+            // Advance first, the renormalization may rescale the score of the record.
+            let gain = this.advance();
+            let state = unsafe { &mut *record.state().get() };
+            let record = this.queue.remove(&state.key()).unwrap();
+            state.score += gain;
+            this.queue.insert(state.key(), record);
+        })
+    }
+
+    fn release() -> Op<Self> {
+        Op::noop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        eviction::test_utils::{assert_ptr_eq, assert_ptr_vec_eq, Dump, OpExt, TestProperties},
+        record::Data,
+    };
+
+    impl<K, V> Dump for DecayLfu<K, V, TestProperties>
+    where
+        K: Key + Clone,
+        V: Value + Clone,
+    {
+        type Output = Vec<Arc<Record<Self>>>;
+        fn dump(&self) -> Self::Output {
+            self.dump_order()
+        }
+    }
+
+    type TestDecayLfu = DecayLfu<u64, u64, TestProperties>;
+
+    fn records(n: u64) -> Vec<Arc<Record<TestDecayLfu>>> {
+        (0..n)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_decay_lfu() {
+        let rs = records(4);
+        let r = |i: usize| rs[i].clone();
+
+        let mut lfu = TestDecayLfu::new(4, &DecayLfuConfig::default());
+
+        (0..4).for_each(|i| lfu.push(r(i)));
+        assert_ptr_vec_eq(lfu.dump(), vec![r(0), r(1), r(2), r(3)]);
+
+        lfu.acquire_mutable(&r(0));
+        lfu.acquire_mutable(&r(0));
+        lfu.acquire_mutable(&r(1));
+        assert_ptr_vec_eq(lfu.dump(), vec![r(2), r(3), r(1), r(0)]);
+
+        let r2 = lfu.pop().unwrap();
+        assert_ptr_eq(&r2, &r(2));
+        assert!(!r2.is_in_eviction());
+
+        lfu.remove(&r(1));
+        assert!(!r(1).is_in_eviction());
+        assert_ptr_vec_eq(lfu.dump(), vec![r(3), r(0)]);
+
+        lfu.clear();
+        assert!(lfu.dump().is_empty());
+        assert!(!r(0).is_in_eviction());
+    }
+
+    #[test]
+    fn test_decay_lfu_steady_access_rates() {
+        let rs = records(3);
+        let r = |i: usize| rs[i].clone();
+
+        let mut lfu = TestDecayLfu::new(3, &DecayLfuConfig { decay: 0.9 });
+        (0..3).for_each(|i| lfu.push(r(i)));
+
+        // `r(0)` is accessed 3 times and `r(1)` is accessed 2 times out of every 6 accesses. A count-min sketch may
+        // hash both keys into the same counters, or saturate both counters between decays, and rank them equally.
+        for _ in 0..1000 {
+            for i in [0, 1, 0, 2, 0, 1] {
+                lfu.acquire_mutable(&r(i));
+            }
+        }
+
+        // The EWMA score of a steady access rate `p` converges to `p / (1 - decay)`.
+        let (s0, s1, s2) = (lfu.score(&r(0)), lfu.score(&r(1)), lfu.score(&r(2)));
+        assert!(s0 > s1 && s1 > s2, "scores: {s0}, {s1}, {s2}");
+        assert!((s0 - 5.0).abs() < 1.0, "score: {s0}");
+        assert!((s1 - 10.0 / 3.0).abs() < 1.0, "score: {s1}");
+        assert_ptr_vec_eq(lfu.dump(), vec![r(2), r(1), r(0)]);
+
+        // The entry that was hot only in the past decays below the entry that is warm now.
+        for _ in 0..1000 {
+            for i in [1, 2, 2] {
+                lfu.acquire_mutable(&r(i));
+            }
+        }
+        assert_ptr_vec_eq(lfu.dump(), vec![r(0), r(1), r(2)]);
+        lfu.clear();
+    }
+
+    #[test]
+    fn test_decay_lfu_renormalize() {
+        let rs = records(2);
+        let r = |i: usize| rs[i].clone();
+
+        // A small decay to renormalize as often as possible.
+        let mut lfu = TestDecayLfu::new(2, &DecayLfuConfig { decay: 0.5 });
+        lfu.push(r(0));
+        lfu.push(r(1));
+
+        let mut ticks = 0;
+        for _ in 0..1000 {
+            lfu.acquire_mutable(&r(0));
+            lfu.acquire_mutable(&r(0));
+            lfu.acquire_mutable(&r(1));
+            assert!(lfu.gain() < RENORMALIZE_THRESHOLD);
+            ticks = ticks.max(lfu.tick);
+        }
+        // The scores are only renormalized when the gain approaches the range of `f64`.
+        assert!(ticks > 900, "ticks: {ticks}");
+        assert!(lfu.score(&r(1)).is_finite() && lfu.score(&r(0)).is_finite());
+        // `r(0)` is accessed more often, but the small decay favors the most recent access to `r(1)`.
+        assert_ptr_vec_eq(lfu.dump(), vec![r(0), r(1)]);
+
+        assert!(lfu.update(2, Some(&DecayLfuConfig { decay: 1.0 })).is_err());
+        lfu.update(2, Some(&DecayLfuConfig { decay: 0.9 })).unwrap();
+        assert_ptr_vec_eq(lfu.dump(), vec![r(0), r(1)]);
+        lfu.clear();
+    }
+}
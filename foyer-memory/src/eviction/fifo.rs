@@ -21,7 +21,7 @@ use foyer_common::{
 use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListAtomicLink};
 use serde::{Deserialize, Serialize};
 
-use super::{Eviction, Op};
+use super::{unlink_first, Eviction, Op};
 use crate::{error::Result, record::Record};
 
 /// Fifo eviction algorithm config.
@@ -43,6 +43,7 @@ where
     P: Properties,
 {
     queue: LinkedList<Adapter<K, V, P>>,
+    capacity: usize,
 }
 
 impl<K, V, P> Eviction for Fifo<K, V, P>
@@ -57,26 +58,62 @@ where
     type Properties = P;
     type State = FifoState;
 
-    fn new(_capacity: usize, _config: &Self::Config) -> Self
+    fn new(capacity: usize, _config: &Self::Config) -> Self
     where
         Self: Sized,
     {
         Self {
             queue: LinkedList::new(Adapter::new()),
+            capacity,
         }
     }
 
-    fn update(&mut self, _: usize, _: Option<&Self::Config>) -> Result<()> {
+    fn update(&mut self, capacity: usize, _: Option<&Self::Config>) -> Result<()> {
+        self.capacity = capacity;
         Ok(())
     }
 
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
     fn push(&mut self, record: Arc<Record<Self>>) {
         record.set_in_eviction(true);
         self.queue.push_back(record);
     }
 
     fn pop(&mut self) -> Option<Arc<Record<Self>>> {
-        self.queue.pop_front().inspect(|record| record.set_in_eviction(false))
+        self.pop_with(|_| false)
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        unlink_first(&mut self.queue, skip).inspect(|record| record.set_in_eviction(false))
+    }
+
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.queue.front();
+        while res.len() < n {
+            let Some(record) = cursor.clone_pointer() else { break };
+            res.push(record);
+            cursor.move_next();
+        }
+        res
+    }
+
+    fn peek_n_after(&self, record: &Arc<Record<Self>>, n: usize) -> Option<Vec<Arc<Record<Self>>>> {
+        if !record.is_in_eviction() {
+            return None;
+        }
+        let mut res = vec![];
+        let mut cursor = unsafe { self.queue.cursor_from_ptr(Arc::as_ptr(record)) };
+        cursor.move_next();
+        while res.len() < n {
+            let Some(record) = cursor.clone_pointer() else { break };
+            res.push(record);
+            cursor.move_next();
+        }
+        Some(res)
     }
 
     fn remove(&mut self, record: &Arc<Record<Self>>) {
@@ -84,6 +121,16 @@ where
         record.set_in_eviction(false);
     }
 
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.queue.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+        res
+    }
+
     fn acquire() -> Op<Self> {
         Op::noop()
     }
@@ -96,7 +143,10 @@ where
 #[cfg(test)]
 pub mod tests {
 
+    use std::collections::VecDeque;
+
     use itertools::Itertools;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
 
     use super::*;
     use crate::{
@@ -171,4 +221,65 @@ pub mod tests {
 
         assert_ptr_vec_eq(fifo.dump(), vec![]);
     }
+
+    #[test]
+    fn test_fifo_fuzzy() {
+        const RECORDS: usize = 64;
+
+        let rs = (0..RECORDS as u64)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut fifo = TestFifo::new(100, &FifoConfig {});
+        // Reference model of the queue.
+        let mut model: VecDeque<usize> = VecDeque::new();
+
+        for _ in 0..10000 {
+            match rng.random_range(0..4) {
+                // push a record that is not in the queue
+                0 | 1 => {
+                    let i = rng.random_range(0..RECORDS);
+                    if model.contains(&i) {
+                        continue;
+                    }
+                    fifo.push(rs[i].clone());
+                    model.push_back(i);
+                }
+                // pop
+                2 => {
+                    let record = fifo.pop();
+                    match model.pop_front() {
+                        Some(i) => assert_ptr_eq(&rs[i], &record.unwrap()),
+                        None => assert!(record.is_none()),
+                    }
+                }
+                // remove a random held record
+                3 => {
+                    if model.is_empty() {
+                        continue;
+                    }
+                    let i = model.remove(rng.random_range(0..model.len())).unwrap();
+                    fifo.remove(&rs[i]);
+                    assert!(!rs[i].is_in_eviction());
+                }
+                _ => unreachable!(),
+            }
+
+            let expected = model.iter().map(|&i| rs[i].clone()).collect_vec();
+            assert_ptr_vec_eq(fifo.dump(), expected.clone());
+            assert_ptr_vec_eq(fifo.dump_order(), expected);
+        }
+
+        fifo.clear();
+        assert_ptr_vec_eq(fifo.dump(), vec![]);
+    }
 }
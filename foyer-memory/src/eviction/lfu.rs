@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{mem::offset_of, sync::Arc};
+use std::{
+    mem::offset_of,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use cmsketch::CMSketchU16;
 use foyer_common::{
+    clock::{Clock, SystemClock},
     code::{Key, Value},
     properties::Properties,
     strict_assert, strict_assert_eq, strict_assert_ne,
@@ -23,7 +27,10 @@ use foyer_common::{
 use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListAtomicLink};
 use serde::{Deserialize, Serialize};
 
-use super::{Eviction, Op};
+use super::{
+    sketch::{Frequencies, FrequencyBackend},
+    unlink_first, Eviction, Op, QueueTag,
+};
 use crate::{
     error::{Error, Result},
     record::Record,
@@ -47,13 +54,72 @@ pub struct LfuConfig {
 
     /// Error of the count-min sketch.
     ///
-    /// See [`CMSketchU16::new`].
+    /// See [`cmsketch::CMSketchU16::new`].
     pub cmsketch_eps: f64,
 
     /// Confidence of the count-min sketch.
     ///
-    /// See [`CMSketchU16::new`].
+    /// See [`cmsketch::CMSketchU16::new`].
     pub cmsketch_confidence: f64,
+
+    /// Whether to update the frequency of the key when an entry is inserted.
+    ///
+    /// Disable it to prevent write-heavy and read-light workloads from inflating the admission frequencies.
+    #[serde(default = "default_update_frequency_on_push")]
+    pub update_frequency_on_push: bool,
+
+    /// Weight of the bypass window of each shard. `0` means the bypass window is disabled.
+    ///
+    /// If enabled, new entries are kept in the bypass window first, and only enter `window` if they are accessed
+    /// again while in the bypass window. Entries overflowed from the bypass window are evicted first without ever
+    /// entering `probation` or `protected`, which protects the cache from being polluted by one-hit-wonders.
+    #[serde(default)]
+    pub bypass_window_size: usize,
+
+    /// Initial frequency of the key when an entry is inserted. `0` means no initial frequency.
+    ///
+    /// If set, the frequency of a newly inserted key is raised to at least `initial_frequency`, which gives the fresh
+    /// entries a grace period against being evicted immediately by the incumbents.
+    #[serde(default)]
+    pub initial_frequency: u16,
+
+    /// Factor to divide the frequencies of the count-min sketch by when the sketch decays.
+    ///
+    /// A larger factor forgets the access history more aggressively.
+    ///
+    /// Must be a power of 2 and at least 2.
+    #[serde(default = "default_decay_factor")]
+    pub decay_factor: u16,
+
+    /// Backend of the count-min sketch.
+    ///
+    /// See [`FrequencyBackend`].
+    #[serde(default)]
+    pub frequency_backend: FrequencyBackend,
+
+    /// Whether to bound the width of the count-min sketch by the capacity, and rebuild the sketch when the capacity
+    /// changes.
+    ///
+    /// If enabled, a shrunk cache doesn't keep a sketch sized for the old capacity, whose stale counters may misjudge
+    /// the admission. The frequencies of the resident entries are carried over into the rebuilt sketch, the others are
+    /// forgotten. The width never exceeds the width derived from `cmsketch_eps`.
+    #[serde(default)]
+    pub resize_sketch: bool,
+
+    /// Idle timeout of the entries in `protected`. `None` means the idle demotion is disabled.
+    ///
+    /// If set, the entries in `protected` that are not accessed for longer than the timeout are demoted to
+    /// `probation` during the maintenance on insertion, which frees `protected` for the actively hot keys.
+    #[serde(default)]
+    pub protected_idle_timeout: Option<Duration>,
+}
+
+fn default_update_frequency_on_push() -> bool {
+    true
+}
+
+fn default_decay_factor() -> u16 {
+    2
 }
 
 impl Default for LfuConfig {
@@ -63,6 +129,13 @@ impl Default for LfuConfig {
             protected_capacity_ratio: 0.8,
             cmsketch_eps: 0.001,
             cmsketch_confidence: 0.9,
+            update_frequency_on_push: true,
+            bypass_window_size: 0,
+            initial_frequency: 0,
+            decay_factor: 2,
+            frequency_backend: FrequencyBackend::default(),
+            resize_sketch: false,
+            protected_idle_timeout: None,
         }
     }
 }
@@ -70,6 +143,7 @@ impl Default for LfuConfig {
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Queue {
     None,
+    Bypass,
     Window,
     Probation,
     Protected,
@@ -86,6 +160,8 @@ impl Default for Queue {
 pub struct LfuState {
     link: LinkedListAtomicLink,
     queue: Queue,
+    /// Last access time in `protected`, only recorded if the idle demotion is enabled.
+    last_access: Option<Instant>,
 }
 
 intrusive_adapter! { Adapter<K, V, P> = Arc<Record<Lfu<K, V, P>>>: Record<Lfu<K, V, P>> { ?offset = Record::<Lfu<K, V, P>>::STATE_OFFSET + offset_of!(LfuState, link) => LinkedListAtomicLink } where K: Key, V: Value, P: Properties }
@@ -102,16 +178,21 @@ intrusive_adapter! { Adapter<K, V, P> = Arc<Record<Lfu<K, V, P>>>: Record<Lfu<K,
 ///
 /// When evicting, the entry with a lower frequency from `window` or `probation` will be evicted first, then from
 /// `protected`.
+///
+/// If the bypass window is enabled, a new entry is kept in `bypass` first, and only enters `window` if it is accessed
+/// again. Entries overflowed from `bypass` are evicted before any others.
 pub struct Lfu<K, V, P>
 where
     K: Key,
     V: Value,
     P: Properties,
 {
+    bypass: LinkedList<Adapter<K, V, P>>,
     window: LinkedList<Adapter<K, V, P>>,
     probation: LinkedList<Adapter<K, V, P>>,
     protected: LinkedList<Adapter<K, V, P>>,
 
+    bypass_weight: usize,
     window_weight: usize,
     probation_weight: usize,
     protected_weight: usize,
@@ -119,12 +200,19 @@ where
     window_weight_capacity: usize,
     protected_weight_capacity: usize,
 
-    // TODO(MrCroxx): use a count-min-sketch impl with atomic u16
-    frequencies: CMSketchU16,
+    frequencies: Frequencies,
+    capacity: usize,
 
     step: usize,
     decay: usize,
 
+    /// Count of the admission decisions that admit the `window` candidate into the main space since the last decay.
+    admitted: usize,
+    /// Count of the admission decisions that reject the `window` candidate since the last decay.
+    rejected: usize,
+
+    clock: Arc<dyn Clock>,
+
     config: LfuConfig,
 }
 
@@ -137,6 +225,7 @@ where
     fn increase_queue_weight(&mut self, queue: Queue, weight: usize) {
         match queue {
             Queue::None => unreachable!(),
+            Queue::Bypass => self.bypass_weight += weight,
             Queue::Window => self.window_weight += weight,
             Queue::Probation => self.probation_weight += weight,
             Queue::Protected => self.protected_weight += weight,
@@ -144,20 +233,217 @@ where
     }
 
     fn decrease_queue_weight(&mut self, queue: Queue, weight: usize) {
-        match queue {
+        let queue_weight = match queue {
             Queue::None => unreachable!(),
-            Queue::Window => self.window_weight -= weight,
-            Queue::Probation => self.probation_weight -= weight,
-            Queue::Protected => self.protected_weight -= weight,
+            Queue::Bypass => &mut self.bypass_weight,
+            Queue::Window => &mut self.window_weight,
+            Queue::Probation => &mut self.probation_weight,
+            Queue::Protected => &mut self.protected_weight,
+        };
+        strict_assert!(
+            *queue_weight >= weight,
+            "lfu {queue:?} weight underflow: {queue_weight} - {weight}"
+        );
+        // Clamp instead of wrapping around to keep the eviction working if the weight accounting is broken.
+        *queue_weight = queue_weight.checked_sub(weight).unwrap_or_else(|| {
+            tracing::error!("[lfu]: {queue:?} weight underflow: {queue_weight} - {weight}, clamp to 0");
+            0
+        });
+    }
+
+    /// Get the error of the count-min sketch for the capacity.
+    fn cmsketch_eps(config: &LfuConfig, capacity: usize) -> f64 {
+        if config.resize_sketch {
+            // The width of the sketch is `2 / eps`, bound it by the capacity.
+            config.cmsketch_eps.max(2.0 / capacity.max(1) as f64)
+        } else {
+            config.cmsketch_eps
+        }
+    }
+
+    /// Rebuild the count-min sketch for the capacity, carrying over the frequencies of the resident records.
+    fn rebuild_sketch(&mut self, capacity: usize) {
+        let mut frequencies = Frequencies::new(
+            self.config.frequency_backend,
+            Self::cmsketch_eps(&self.config, capacity),
+            self.config.cmsketch_confidence,
+        );
+        if frequencies.width() == self.frequencies.width() {
+            return;
+        }
+
+        for (hash, frequency) in self.resident_frequencies() {
+            frequencies.raise(hash, frequency);
+        }
+
+        self.decay = frequencies.width();
+        self.step = 0;
+        self.frequencies = frequencies;
+    }
+
+    /// Get the estimated frequencies of the resident records.
+    ///
+    /// The counters of the count-min sketch are opaque, so the frequencies of the resident records, which are the ones
+    /// worth carrying over, are used to snapshot the sketch.
+    pub fn resident_frequencies(&self) -> Vec<(u64, u16)> {
+        self.bypass
+            .iter()
+            .chain(self.window.iter())
+            .chain(self.probation.iter())
+            .chain(self.protected.iter())
+            .map(|record| (record.hash(), self.frequencies.estimate(record.hash())))
+            .collect()
+    }
+
+    /// Raise the estimated frequencies of the hashes to at least the given frequencies, e.g. to rehydrate the sketch
+    /// with a snapshot after a restart.
+    ///
+    /// The queues are not touched.
+    pub fn restore_frequencies(&mut self, frequencies: impl IntoIterator<Item = (u64, u16)>) {
+        for (hash, frequency) in frequencies {
+            self.frequencies.raise(hash, frequency);
         }
     }
 
+    /// Reset all frequencies of the sketch to `0` without touching the queues.
+    pub fn reset_frequencies(&mut self) {
+        self.frequencies.clear();
+        self.step = 0;
+        self.admitted = 0;
+        self.rejected = 0;
+    }
+
     fn update_frequencies(&mut self, hash: u64) {
         self.frequencies.inc(hash);
         self.step += 1;
         if self.step >= self.decay {
-            self.step >>= 1;
-            self.frequencies.halve();
+            // `decay_factor` is a power of 2, so dividing by it equals halving `log2(decay_factor)` times.
+            let shift = self.config.decay_factor.trailing_zeros();
+            self.step >>= shift;
+            for _ in 0..shift {
+                self.frequencies.halve();
+            }
+            self.admitted = 0;
+            self.rejected = 0;
+        }
+    }
+
+    /// Get the estimated hit ratio from the admission decisions since the last sketch decay.
+    ///
+    /// An admission decision is made when the eviction needs to choose between the `window` candidate and the
+    /// `probation` victim. The candidate is admitted only if the sketch estimates it to be accessed more frequently than
+    /// the victim, so the admitted ratio is a cheap signal of how likely the newly inserted entries are to be hit again
+    /// without instrumenting every `get`.
+    ///
+    /// Returns `0.0` if no admission decision has been made since the last decay.
+    pub fn estimated_hit_ratio(&self) -> f64 {
+        let total = self.admitted + self.rejected;
+        if total == 0 {
+            return 0.0;
+        }
+        self.admitted as f64 / total as f64
+    }
+
+    /// Get the frequency of the record scaled by its cost, so high-cost records are kept longer than low-cost records
+    /// with the same access frequency.
+    fn effective_frequency(frequencies: &Frequencies, record: &Record<Self>) -> u64 {
+        let cost = record.properties().cost().unwrap_or_default().max(1);
+        frequencies.estimate(record.hash()) as u64 * cost as u64
+    }
+
+    /// Compare the effective frequency of the front element of `window` and `probation` queue, and pop the lower one.
+    ///
+    /// The elements that `skip` returns `true` for are passed over in place.
+    fn pop_window_or_probation(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        let mut cw = self.window.front_mut();
+        while cw.get().is_some_and(&skip) {
+            cw.move_next();
+        }
+        let mut cp = self.probation.front_mut();
+        while cp.get().is_some_and(&skip) {
+            cp.move_next();
+        }
+        match (cw.get(), cp.get()) {
+            (None, None) => None,
+            (None, Some(_)) => cp.remove(),
+            (Some(_), None) => cw.remove(),
+            (Some(w), Some(p)) => {
+                if Self::effective_frequency(&self.frequencies, w) < Self::effective_frequency(&self.frequencies, p) {
+                    self.rejected += 1;
+                    cw.remove()
+
+                    // TODO(MrCroxx): Rotate probation to prevent a high frequency but cold head holds back promotion
+                    // too long like CacheLib does?
+                } else {
+                    self.admitted += 1;
+                    cp.remove()
+                }
+            }
+        }
+    }
+
+    /// Record the access time of the record in `protected` if the idle demotion is enabled.
+    fn touch_protected(&self, state: &mut LfuState) {
+        if self.config.protected_idle_timeout.is_some() {
+            state.last_access = Some(self.clock.now());
+        }
+    }
+
+    /// Demote the entries in `protected` that have been idle for longer than the timeout to `probation`.
+    ///
+    /// `protected` is ordered by the access time, so only the idle entries at the LRU end are visited.
+    fn demote_idle_protected(&mut self) {
+        let Some(timeout) = self.config.protected_idle_timeout else {
+            return;
+        };
+        let now = self.clock.now();
+
+        while let Some(record) = self.protected.front().get() {
+            let state = unsafe { &mut *record.state().get() };
+            match state.last_access {
+                Some(last_access) if now.saturating_duration_since(last_access) >= timeout => {}
+                Some(_) => break,
+                None => {
+                    // Entered `protected` before the idle demotion is enabled, start timing from now.
+                    state.last_access = Some(now);
+                    break;
+                }
+            }
+
+            let r = self.protected.pop_front().unwrap();
+            self.decrease_queue_weight(Queue::Protected, r.weight());
+            state.queue = Queue::Probation;
+            state.last_access = None;
+            self.increase_queue_weight(Queue::Probation, r.weight());
+            self.probation.push_back(r);
+        }
+    }
+
+    /// Use the given clock to time the idle demotion, for testing only.
+    #[cfg(test)]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Push a record to `window`.
+    ///
+    /// Overflow record from `window` to `probation` if needed.
+    fn push_window(&mut self, record: Arc<Record<Self>>) {
+        let state = unsafe { &mut *record.state().get() };
+
+        state.queue = Queue::Window;
+        self.increase_queue_weight(Queue::Window, record.weight());
+        self.window.push_back(record);
+
+        // If `window` weight exceeds the capacity, overflow entry from `window` to `probation`.
+        while self.window_weight > self.window_weight_capacity {
+            strict_assert!(!self.window.is_empty());
+            let r = self.window.pop_front().unwrap();
+            let s = unsafe { &mut *r.state().get() };
+            self.decrease_queue_weight(Queue::Window, r.weight());
+            s.queue = Queue::Probation;
+            self.increase_queue_weight(Queue::Probation, r.weight());
+            self.probation.push_back(r);
         }
     }
 }
@@ -196,25 +482,41 @@ where
             config.window_capacity_ratio + config.protected_capacity_ratio
         );
 
+        assert!(
+            config.decay_factor >= 2 && config.decay_factor.is_power_of_two(),
+            "decay_factor must be a power of 2 and at least 2, given: {}",
+            config.decay_factor
+        );
+
         let config = config.clone();
 
         let window_weight_capacity = (capacity as f64 * config.window_capacity_ratio) as usize;
         let protected_weight_capacity = (capacity as f64 * config.protected_capacity_ratio) as usize;
-        let frequencies = CMSketchU16::new(config.cmsketch_eps, config.cmsketch_confidence);
+        let frequencies = Frequencies::new(
+            config.frequency_backend,
+            Self::cmsketch_eps(&config, capacity),
+            config.cmsketch_confidence,
+        );
         let decay = frequencies.width();
 
         Self {
+            bypass: LinkedList::new(Adapter::new()),
             window: LinkedList::new(Adapter::new()),
             probation: LinkedList::new(Adapter::new()),
             protected: LinkedList::new(Adapter::new()),
+            bypass_weight: 0,
             window_weight: 0,
             probation_weight: 0,
             protected_weight: 0,
             window_weight_capacity,
             protected_weight_capacity,
             frequencies,
+            capacity,
             step: 0,
             decay,
+            admitted: 0,
+            rejected: 0,
+            clock: Arc::new(SystemClock),
             config,
         }
     }
@@ -250,6 +552,11 @@ where
 
         // TODO(MrCroxx): Raise a warn log the cmsketch args updates is not supported yet if it is modified.
 
+        if self.config.resize_sketch && capacity != self.capacity {
+            self.rebuild_sketch(capacity);
+        }
+        self.capacity = capacity;
+
         let window_weight_capacity = (capacity as f64 * self.config.window_capacity_ratio) as usize;
         let protected_weight_capacity = (capacity as f64 * self.config.protected_capacity_ratio) as usize;
 
@@ -259,7 +566,15 @@ where
         Ok(())
     }
 
-    /// Push a new record to `window`.
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
+    fn weight(&self) -> usize {
+        self.bypass_weight + self.window_weight + self.probation_weight + self.protected_weight
+    }
+
+    /// Push a new record to `window`, or to `bypass` if the bypass window is enabled.
     ///
     /// Overflow record from `window` to `probation` if needed.
     fn push(&mut self, record: Arc<Record<Self>>) {
@@ -269,45 +584,45 @@ where
         strict_assert!(!record.is_in_eviction());
         strict_assert_eq!(state.queue, Queue::None);
 
+        self.demote_idle_protected();
+
         record.set_in_eviction(true);
-        state.queue = Queue::Window;
-        self.increase_queue_weight(Queue::Window, record.weight());
-        self.update_frequencies(record.hash());
-        self.window.push_back(record);
+        if self.config.update_frequency_on_push {
+            self.update_frequencies(record.hash());
+        }
+        if self.config.initial_frequency > 0 {
+            let frequency = self.frequencies.estimate(record.hash());
+            if frequency < self.config.initial_frequency {
+                self.frequencies
+                    .inc_by(record.hash(), self.config.initial_frequency - frequency);
+            }
+        }
 
-        // If `window` weight exceeds the capacity, overflow entry from `window` to `probation`.
-        while self.window_weight > self.window_weight_capacity {
-            strict_assert!(!self.window.is_empty());
-            let r = self.window.pop_front().unwrap();
-            let s = unsafe { &mut *r.state().get() };
-            self.decrease_queue_weight(Queue::Window, r.weight());
-            s.queue = Queue::Probation;
-            self.increase_queue_weight(Queue::Probation, r.weight());
-            self.probation.push_back(r);
+        if self.config.bypass_window_size > 0 {
+            state.queue = Queue::Bypass;
+            self.increase_queue_weight(Queue::Bypass, record.weight());
+            self.bypass.push_back(record);
+        } else {
+            self.push_window(record);
         }
     }
 
     fn pop(&mut self) -> Option<Arc<Record<Self>>> {
-        // Compare the frequency of the front element of `window` and `probation` queue, and evict the lower one.
-        // If both `window` and `probation` are empty, try evict from `protected`.
-        let mut cw = self.window.front_mut();
-        let mut cp = self.probation.front_mut();
-        let record = match (cw.get(), cp.get()) {
-            (None, None) => None,
-            (None, Some(_)) => cp.remove(),
-            (Some(_), None) => cw.remove(),
-            (Some(w), Some(p)) => {
-                if self.frequencies.estimate(w.hash()) < self.frequencies.estimate(p.hash()) {
-                    cw.remove()
+        self.pop_with(|_| false)
+    }
 
-                    // TODO(MrCroxx): Rotate probation to prevent a high frequency but cold head holds back promotion
-                    // too long like CacheLib does?
-                } else {
-                    cp.remove()
-                }
-            }
-        }
-        .or_else(|| self.protected.pop_front())?;
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        // Evict the entries overflowed from `bypass` first.
+        // Then evict from `window` or `probation`, then from `protected`, and finally from `bypass`.
+        let overflowed = if self.bypass_weight > self.config.bypass_window_size {
+            unlink_first(&mut self.bypass, &skip)
+        } else {
+            None
+        };
+        let record = overflowed
+            .or_else(|| self.pop_window_or_probation(&skip))
+            .or_else(|| unlink_first(&mut self.protected, &skip))
+            .or_else(|| unlink_first(&mut self.bypass, &skip))?;
 
         let state = unsafe { &mut *record.state().get() };
 
@@ -322,6 +637,47 @@ where
         Some(record)
     }
 
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+
+        // Same order as `pop`: overflowed `bypass`, then `window` and `probation` interleaved by frequency, then
+        // `protected`, and finally the rest of `bypass`.
+        let mut cb = self.bypass.front();
+        let mut bypass_weight = self.bypass_weight;
+        while res.len() < n && bypass_weight > self.config.bypass_window_size {
+            let Some(record) = cb.clone_pointer() else { break };
+            bypass_weight -= record.weight();
+            res.push(record);
+            cb.move_next();
+        }
+
+        let mut cw = self.window.front();
+        let mut cp = self.probation.front();
+        while res.len() < n {
+            let pop_window = match (cw.get(), cp.get()) {
+                (None, None) => break,
+                (None, Some(_)) => false,
+                (Some(_), None) => true,
+                (Some(w), Some(p)) => {
+                    Self::effective_frequency(&self.frequencies, w) < Self::effective_frequency(&self.frequencies, p)
+                }
+            };
+            let cursor = if pop_window { &mut cw } else { &mut cp };
+            res.push(cursor.clone_pointer().unwrap());
+            cursor.move_next();
+        }
+
+        for mut cursor in [self.protected.front(), cb] {
+            while res.len() < n {
+                let Some(record) = cursor.clone_pointer() else { break };
+                res.push(record);
+                cursor.move_next();
+            }
+        }
+
+        res
+    }
+
     fn remove(&mut self, record: &Arc<Record<Self>>) {
         let state = unsafe { &mut *record.state().get() };
 
@@ -331,6 +687,7 @@ where
 
         match state.queue {
             Queue::None => unreachable!(),
+            Queue::Bypass => unsafe { self.bypass.remove_from_ptr(Arc::as_ptr(record)) },
             Queue::Window => unsafe { self.window.remove_from_ptr(Arc::as_ptr(record)) },
             Queue::Probation => unsafe { self.probation.remove_from_ptr(Arc::as_ptr(record)) },
             Queue::Protected => unsafe { self.protected.remove_from_ptr(Arc::as_ptr(record)) },
@@ -350,6 +707,54 @@ where
             strict_assert!(!state.link.is_linked());
             strict_assert_eq!(state.queue, Queue::None);
         }
+        // Draining the queues is not an admission decision.
+        self.admitted = 0;
+        self.rejected = 0;
+    }
+
+    fn queue_of(&self, record: &Arc<Record<Self>>) -> Option<QueueTag> {
+        let state = unsafe { &*record.state().get() };
+        match state.queue {
+            Queue::None => None,
+            Queue::Bypass => Some(QueueTag::Bypass),
+            Queue::Window => Some(QueueTag::Window),
+            Queue::Probation => Some(QueueTag::Probation),
+            Queue::Protected => Some(QueueTag::Protected),
+        }
+    }
+
+    fn frequency_of(&self, record: &Arc<Record<Self>>) -> Option<u32> {
+        record
+            .is_in_eviction()
+            .then(|| self.frequencies.estimate(record.hash()) as u32)
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.bypass.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.window.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.probation.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.protected.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+        res
     }
 
     fn acquire() -> Op<Self> {
@@ -367,6 +772,12 @@ where
 
             match state.queue {
                 Queue::None => unreachable!(),
+                Queue::Bypass => {
+                    // Accessed again within the bypass window, admit it to `window`.
+                    let r = unsafe { this.bypass.remove_from_ptr(Arc::as_ptr(record)) };
+                    this.decrease_queue_weight(Queue::Bypass, record.weight());
+                    this.push_window(r);
+                }
                 Queue::Window => {
                     // Move to MRU position of `window`.
                     let r = unsafe { this.window.remove_from_ptr(Arc::as_ptr(record)) };
@@ -377,6 +788,7 @@ where
                     let r = unsafe { this.probation.remove_from_ptr(Arc::as_ptr(record)) };
                     this.decrease_queue_weight(Queue::Probation, record.weight());
                     state.queue = Queue::Protected;
+                    this.touch_protected(state);
                     this.increase_queue_weight(Queue::Protected, record.weight());
                     this.protected.push_back(r);
 
@@ -394,6 +806,7 @@ where
                 Queue::Protected => {
                     // Move to MRU position of `protected`.
                     let r = unsafe { this.protected.remove_from_ptr(Arc::as_ptr(record)) };
+                    this.touch_protected(state);
                     this.protected.push_back(r);
                 }
             }
@@ -408,11 +821,15 @@ where
 #[cfg(test)]
 mod tests {
 
+    use foyer_common::clock::MockClock;
     use itertools::Itertools;
 
     use super::*;
     use crate::{
-        eviction::test_utils::{assert_ptr_eq, assert_ptr_vec_vec_eq, Dump, OpExt, TestProperties},
+        eviction::{
+            sketch::{decode_frequencies, encode_frequencies},
+            test_utils::{assert_ptr_eq, assert_ptr_vec_eq, assert_ptr_vec_vec_eq, Dump, OpExt, TestProperties},
+        },
         record::Data,
     };
 
@@ -481,6 +898,13 @@ mod tests {
             protected_capacity_ratio: 0.6,
             cmsketch_eps: 0.01,
             cmsketch_confidence: 0.95,
+            update_frequency_on_push: true,
+            bypass_window_size: 0,
+            initial_frequency: 0,
+            decay_factor: 2,
+            frequency_backend: FrequencyBackend::CMSketch,
+            resize_sketch: false,
+            protected_idle_timeout: None,
         };
         let mut lfu = TestLfu::new(10, &config);
 
@@ -648,4 +1072,448 @@ mod tests {
         lfu.clear();
         assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![], vec![], vec![]]);
     }
+
+    #[test]
+    fn test_lfu_config_serde_compat() {
+        // The config serialized before the new fields are introduced.
+        let config: LfuConfig = serde_json::from_str(
+            r#"{
+                "window_capacity_ratio": 0.1,
+                "protected_capacity_ratio": 0.8,
+                "cmsketch_eps": 0.001,
+                "cmsketch_confidence": 0.9
+            }"#,
+        )
+        .unwrap();
+        let default = LfuConfig::default();
+        assert_eq!(config.update_frequency_on_push, default.update_frequency_on_push);
+        assert_eq!(config.bypass_window_size, default.bypass_window_size);
+        assert_eq!(config.initial_frequency, default.initial_frequency);
+        assert_eq!(config.decay_factor, default.decay_factor);
+    }
+
+    #[test]
+    fn test_lfu_update_frequency_on_push() {
+        let rs = (0..100)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        let config = LfuConfig {
+            update_frequency_on_push: false,
+            ..Default::default()
+        };
+        let mut lfu = TestLfu::new(10, &config);
+
+        // Pure write workload.
+        for r in rs.iter() {
+            lfu.push(r.clone());
+            while lfu.window_weight + lfu.probation_weight + lfu.protected_weight > 10 {
+                lfu.pop();
+            }
+        }
+        for r in rs.iter() {
+            assert_eq!(lfu.frequencies.estimate(r.hash()), 0);
+        }
+
+        // Reads still update frequencies.
+        lfu.acquire_mutable(&rs[99]);
+        assert_eq!(lfu.frequencies.estimate(rs[99].hash()), 1);
+    }
+
+    #[test]
+    fn test_lfu_bypass_window() {
+        let rs = (0..100)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        let r = |i: usize| rs[i].clone();
+
+        let config = LfuConfig {
+            window_capacity_ratio: 0.2,
+            protected_capacity_ratio: 0.6,
+            bypass_window_size: 2,
+            ..Default::default()
+        };
+        let mut lfu = TestLfu::new(10, &config);
+        let evict = |lfu: &mut TestLfu| {
+            let mut evicted = vec![];
+            while lfu.bypass_weight + lfu.window_weight + lfu.probation_weight + lfu.protected_weight > 10 {
+                evicted.push(lfu.pop().unwrap());
+            }
+            evicted
+        };
+
+        // Keep 0 and 1 hot, they are admitted after the second access.
+        lfu.push(r(0));
+        lfu.push(r(1));
+        lfu.acquire_mutable(&rs[0]);
+        lfu.acquire_mutable(&rs[1]);
+        assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![r(0), r(1)], vec![], vec![]]);
+
+        // Scan with one-hit-wonders.
+        for i in 2..100 {
+            lfu.push(r(i));
+            for evicted in evict(&mut lfu) {
+                assert!(evicted.key() >= &2, "hot key evicted: {}", evicted.key());
+            }
+            assert_eq!(lfu.probation_weight, 0);
+            assert_eq!(lfu.protected_weight, 0);
+        }
+        assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![r(0), r(1)], vec![], vec![]]);
+        assert_ptr_vec_eq(
+            lfu.dump_order(),
+            vec![r(92), r(93), r(94), r(95), r(96), r(97), r(98), r(99), r(0), r(1)],
+        );
+
+        // Accessed again while in the bypass window, admit it to `window`, which overflows 0 to `probation`.
+        lfu.acquire_mutable(&rs[99]);
+        assert_eq!(lfu.bypass_weight, 7);
+        assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![r(1), r(99)], vec![r(0)], vec![]]);
+    }
+
+    #[test]
+    fn test_lfu_cost_aware() {
+        let record = |i: u64, cost: u32| {
+            Arc::new(Record::new(Data {
+                key: i,
+                value: i,
+                properties: TestProperties::default().with_cost(cost),
+                hash: i,
+                weight: 1,
+            }))
+        };
+        let config = LfuConfig {
+            window_capacity_ratio: 0.2,
+            protected_capacity_ratio: 0.6,
+            ..Default::default()
+        };
+        let pop_all = |lfu: &mut TestLfu| std::iter::from_fn(|| lfu.pop()).map(|r| *r.key()).collect_vec();
+
+        // All records are accessed once. With the same cost, `probation` head is evicted first on a tie.
+        let mut lfu = TestLfu::new(10, &config);
+        (0..4).for_each(|i| lfu.push(record(i, 1)));
+        assert_eq!(
+            lfu.dump()
+                .into_iter()
+                .map(|q| q.into_iter().map(|r| *r.key()).collect_vec())
+                .collect_vec(),
+            vec![vec![2, 3], vec![0, 1], vec![]]
+        );
+        assert_eq!(pop_all(&mut lfu), vec![0, 1, 2, 3]);
+
+        // The high-cost record survives the low-cost records with the same access frequency.
+        let mut lfu = TestLfu::new(10, &config);
+        lfu.push(record(0, 10));
+        (1..4).for_each(|i| lfu.push(record(i, 1)));
+        assert_eq!(pop_all(&mut lfu), vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "lfu Window weight underflow")]
+    fn test_lfu_queue_weight_underflow() {
+        let mut lfu = TestLfu::new(10, &LfuConfig::default());
+        lfu.push(Arc::new(Record::new(Data {
+            key: 0,
+            value: 0,
+            properties: TestProperties::default(),
+            hash: 0,
+            weight: 1,
+        })));
+        lfu.decrease_queue_weight(Queue::Window, 2);
+    }
+
+    #[test]
+    fn test_lfu_decay_factor() {
+        let case = |decay_factor: u16| {
+            let config = LfuConfig {
+                decay_factor,
+                ..Default::default()
+            };
+            let mut lfu = TestLfu::new(10, &config);
+            lfu.decay = 16;
+            for _ in 0..15 {
+                lfu.update_frequencies(0);
+            }
+            assert_eq!(lfu.frequencies.estimate(0), 15);
+            // The 16th access triggers the decay.
+            lfu.update_frequencies(0);
+            assert_eq!(lfu.step, 16 / decay_factor as usize);
+            lfu.frequencies.estimate(0)
+        };
+
+        assert_eq!(case(2), 8);
+        assert_eq!(case(4), 4);
+        assert_eq!(case(16), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "decay_factor must be a power of 2")]
+    fn test_lfu_decay_factor_not_power_of_2() {
+        let config = LfuConfig {
+            decay_factor: 3,
+            ..Default::default()
+        };
+        TestLfu::new(10, &config);
+    }
+
+    #[test]
+    fn test_lfu_initial_frequency() {
+        let rs = (0..2)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        let case = |initial_frequency: u16| {
+            // window: 1
+            let config = LfuConfig {
+                window_capacity_ratio: 0.1,
+                update_frequency_on_push: false,
+                initial_frequency,
+                ..Default::default()
+            };
+            let mut lfu = TestLfu::new(10, &config);
+            // The incumbent 0 is accessed twice and then aged, and finally overflowed to `probation` by the new key 1.
+            lfu.push(rs[0].clone());
+            lfu.acquire_mutable(&rs[0]);
+            lfu.acquire_mutable(&rs[0]);
+            lfu.frequencies.halve();
+            assert_eq!(lfu.frequencies.estimate(rs[0].hash()), (initial_frequency + 2) / 2);
+            lfu.push(rs[1].clone());
+            assert_eq!(lfu.frequencies.estimate(rs[1].hash()), initial_frequency);
+            assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![rs[1].clone()], vec![rs[0].clone()], vec![]]);
+            let evicted = lfu.pop().unwrap();
+            lfu.clear();
+            evicted
+        };
+
+        // Without the initial frequency, the new key is evicted immediately.
+        assert_ptr_eq(&case(0), &rs[1]);
+        // With the initial frequency, the new key survives the eviction.
+        assert_ptr_eq(&case(2), &rs[0]);
+    }
+
+    #[test]
+    fn test_lfu_estimated_hit_ratio() {
+        let rs = (0..10)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        // window: 1
+        let config = LfuConfig {
+            window_capacity_ratio: 0.1,
+            update_frequency_on_push: false,
+            ..Default::default()
+        };
+        let mut lfu = TestLfu::new(10, &config);
+        assert_eq!(lfu.estimated_hit_ratio(), 0.0);
+
+        // Only one queue is not empty, no admission decision is made.
+        lfu.push(rs[0].clone());
+        lfu.pop().unwrap();
+        assert_eq!(lfu.estimated_hit_ratio(), 0.0);
+
+        // Before each eviction, `window` holds the candidate and `probation` holds the victim.
+        // The candidate is admitted if it is not less frequent than the victim.
+        let decisions = [true, false, true, true, false, true, true, false];
+        lfu.push(rs[0].clone());
+        for (i, &admit) in decisions.iter().enumerate() {
+            lfu.push(rs[i + 1].clone());
+            let dump = lfu.dump();
+            let (candidate, victim) = (dump[0][0].clone(), dump[1][0].clone());
+            lfu.frequencies.clear();
+            if !admit {
+                lfu.frequencies.inc(victim.hash());
+            }
+            let evicted = lfu.pop().unwrap();
+            assert_ptr_eq(&evicted, if admit { &victim } else { &candidate });
+        }
+        assert_eq!(lfu.estimated_hit_ratio(), 5.0 / 8.0);
+
+        // The counters are reset on decay.
+        lfu.step = lfu.decay - 1;
+        lfu.update_frequencies(0);
+        assert_eq!(lfu.estimated_hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_lfu_resize_sketch() {
+        let rs = (0..10)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        let config = LfuConfig {
+            cmsketch_eps: 0.001,
+            resize_sketch: true,
+            ..Default::default()
+        };
+        let mut lfu = TestLfu::new(1000, &config);
+        // The width is bounded by the capacity.
+        let width = lfu.frequencies.width();
+        assert!((1000..2000).contains(&width), "width: {width}");
+
+        rs.iter().for_each(|r| lfu.push(r.clone()));
+        for (i, r) in rs.iter().enumerate().take(5) {
+            for _ in 0..i * 2 {
+                lfu.acquire_mutable(r);
+            }
+        }
+        let before = rs.iter().map(|r| lfu.frequencies.estimate(r.hash())).collect_vec();
+
+        // Shrink the capacity, the sketch is rebuilt with a smaller width.
+        lfu.update(20, None).unwrap();
+        let shrunk = lfu.frequencies.width();
+        assert!(shrunk < width && shrunk <= 20, "width: {width} -> {shrunk}");
+        assert_eq!(lfu.decay, shrunk);
+
+        // The resident records retain at least their frequencies, and the hotter records stay hotter.
+        let after = rs.iter().map(|r| lfu.frequencies.estimate(r.hash())).collect_vec();
+        assert!(before.iter().zip_eq(after.iter()).all(|(b, a)| a >= b));
+        assert!(after[4] >= after[0] + 8);
+
+        // The sketch is not rebuilt if disabled.
+        let mut lfu = TestLfu::new(1000, &LfuConfig::default());
+        let width = lfu.frequencies.width();
+        lfu.update(20, None).unwrap();
+        assert_eq!(lfu.frequencies.width(), width);
+
+        lfu.clear();
+    }
+
+    #[test]
+    fn test_lfu_frequency_snapshot() {
+        let rs = (0..10)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        let mut lfu = TestLfu::new(100, &LfuConfig::default());
+        rs.iter().for_each(|r| lfu.push(r.clone()));
+        for (i, r) in rs.iter().enumerate() {
+            for _ in 0..i {
+                lfu.acquire_mutable(r);
+            }
+        }
+        let estimates = |lfu: &TestLfu| rs.iter().map(|r| lfu.frequencies.estimate(r.hash())).collect_vec();
+        let before = estimates(&lfu);
+        let buf = encode_frequencies(&lfu.resident_frequencies());
+
+        // Resetting zeroes the sketch without touching the queues.
+        lfu.reset_frequencies();
+        assert!(estimates(&lfu).iter().all(|f| *f == 0));
+        assert_eq!(lfu.dump_order().len(), rs.len());
+
+        // A new sketch of a different width is rehydrated with the snapshot.
+        let mut restored = TestLfu::new(1000, &LfuConfig::default());
+        restored.restore_frequencies(decode_frequencies(&buf).unwrap());
+        assert_eq!(estimates(&restored), before);
+
+        lfu.clear();
+    }
+
+    #[test]
+    fn test_lfu_protected_idle_demotion() {
+        let rs = (0..10)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        let r = |i: usize| rs[i].clone();
+
+        // window: 1, protected: 8, probation: 1
+        let config = LfuConfig {
+            window_capacity_ratio: 0.1,
+            protected_capacity_ratio: 0.8,
+            protected_idle_timeout: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let mut lfu = TestLfu::new(10, &config);
+        let clock = MockClock::default();
+        lfu.set_clock(Arc::new(clock.clone()));
+
+        // t = 0s, r0 is promoted to `protected`.
+        lfu.push(r(0));
+        lfu.push(r(1));
+        lfu.acquire_mutable(&rs[0]);
+        assert_eq!(lfu.queue_of(&rs[0]), Some(QueueTag::Protected));
+
+        // t = 30s, r1 is promoted to `protected`.
+        clock.advance(Duration::from_secs(30));
+        lfu.push(r(2));
+        lfu.acquire_mutable(&rs[1]);
+        assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![r(2)], vec![], vec![r(0), r(1)]]);
+
+        // t = 70s, only r0 is idle for longer than the timeout.
+        clock.advance(Duration::from_secs(40));
+        lfu.push(r(3));
+        assert_eq!(lfu.queue_of(&rs[0]), Some(QueueTag::Probation));
+        assert_eq!(lfu.queue_of(&rs[1]), Some(QueueTag::Protected));
+        assert_ptr_vec_vec_eq(lfu.dump(), vec![vec![r(3)], vec![r(0), r(2)], vec![r(1)]]);
+
+        // t = 80s, an access resets the idle time.
+        clock.advance(Duration::from_secs(10));
+        lfu.acquire_mutable(&rs[1]);
+        clock.advance(Duration::from_secs(55));
+        lfu.push(r(4));
+        assert_eq!(lfu.queue_of(&rs[1]), Some(QueueTag::Protected));
+
+        // t = 145s, r1 is idle for longer than the timeout.
+        clock.advance(Duration::from_secs(10));
+        lfu.push(r(5));
+        assert_eq!(lfu.queue_of(&rs[1]), Some(QueueTag::Probation));
+        assert_eq!(lfu.protected_weight, 0);
+
+        lfu.clear();
+    }
 }
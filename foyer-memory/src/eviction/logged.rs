@@ -0,0 +1,418 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Config, Eviction, Op, QueueTag};
+use crate::{error::Result, record::Record};
+
+/// Config of the [`Logged`] eviction decorator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedConfig<C> {
+    /// Config of the decorated eviction algorithm.
+    pub inner: C,
+    /// Count of the most recent eviction decisions to keep.
+    pub capacity: usize,
+}
+
+impl<C> Default for LoggedConfig<C>
+where
+    C: Config,
+{
+    fn default() -> Self {
+        Self {
+            inner: C::default(),
+            capacity: 1024,
+        }
+    }
+}
+
+/// Reason of an eviction decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictReason {
+    /// The record is evicted to make room for the other records.
+    Evict,
+    /// The record is removed from the eviction algorithm explicitly.
+    Remove,
+}
+
+/// An eviction decision recorded by the [`Logged`] eviction decorator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvictRecord {
+    /// Hash of the record.
+    pub hash: u64,
+    /// Weight of the record.
+    pub weight: usize,
+    /// Queue that held the record right before the decision, see [`Eviction::queue_of`].
+    pub queue: Option<QueueTag>,
+    /// Frequency of the record right before the decision, see [`Eviction::frequency_of`].
+    pub frequency: Option<u32>,
+    /// Reason of the decision.
+    pub reason: EvictReason,
+}
+
+/// Eviction algorithm decorator that records the most recent eviction decisions of the decorated eviction algorithm
+/// for auditing.
+///
+/// The decorator behaves exactly the same as the decorated eviction algorithm, and keeps the last
+/// [`LoggedConfig::capacity`] decisions in a ring buffer, which can be inspected with [`Logged::recent_evictions`].
+///
+/// The decorator peeks the next victim before each eviction to record its queue and frequency, which may be expensive
+/// for some eviction algorithms. It is supposed to be used for debugging.
+///
+/// The records of the decorator are handed to the decorated eviction algorithm as is, which is why the decorator shares
+/// all associated types except `Config` with the decorated eviction algorithm.
+pub struct Logged<E>
+where
+    E: Eviction,
+{
+    inner: E,
+    records: VecDeque<EvictRecord>,
+    capacity: usize,
+}
+
+impl<E> Logged<E>
+where
+    E: Eviction,
+{
+    /// Get the most recent eviction decisions, from the oldest to the latest.
+    pub fn recent_evictions(&self) -> Vec<EvictRecord> {
+        self.records.iter().copied().collect()
+    }
+
+    fn log(&mut self, record: &Arc<Record<E>>, queue: Option<QueueTag>, frequency: Option<u32>, reason: EvictReason) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(EvictRecord {
+            hash: record.hash(),
+            weight: record.weight(),
+            queue,
+            frequency,
+            reason,
+        });
+    }
+
+    fn wrap(op: Op<E>) -> Op<Self> {
+        match op {
+            Op::Noop => Op::noop(),
+            Op::Immutable(f) => Op::immutable(move |this: &Self, record| f(&this.inner, &Record::cast_ref(record))),
+            Op::Mutable(mut f) => {
+                Op::mutable(move |this: &mut Self, record| f(&mut this.inner, &Record::cast_ref(record)))
+            }
+        }
+    }
+}
+
+impl<E> Eviction for Logged<E>
+where
+    E: Eviction,
+{
+    type Config = LoggedConfig<E::Config>;
+    type Key = E::Key;
+    type Value = E::Value;
+    type Properties = E::Properties;
+    type State = E::State;
+
+    fn new(capacity: usize, config: &Self::Config) -> Self {
+        Self {
+            inner: E::new(capacity, &config.inner),
+            records: VecDeque::with_capacity(config.capacity),
+            capacity: config.capacity,
+        }
+    }
+
+    fn update(&mut self, capacity: usize, config: Option<&Self::Config>) -> Result<()> {
+        self.inner.update(capacity, config.map(|config| &config.inner))?;
+        if let Some(config) = config {
+            self.capacity = config.capacity;
+            while self.records.len() > self.capacity {
+                self.records.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    fn weight(&self) -> usize {
+        self.inner.weight()
+    }
+
+    fn push(&mut self, record: Arc<Record<Self>>) {
+        self.inner.push(Record::cast(record));
+    }
+
+    fn on_admit(&mut self, record: &Arc<Record<Self>>) {
+        self.inner.on_admit(&Record::cast_ref(record));
+    }
+
+    fn pop(&mut self) -> Option<Arc<Record<Self>>> {
+        self.pop_with(|_| false)
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        let skip = |record: &Record<E>| skip(Record::cast_borrow(record));
+        let next = self.inner.peek_n(1).pop();
+        let (queue, frequency) = match next.as_ref() {
+            Some(next) => (self.inner.queue_of(next), self.inner.frequency_of(next)),
+            None => (None, None),
+        };
+        let record = self.inner.pop_with(skip)?;
+        // Only trust the peeked states if the peeked record is the victim.
+        let (queue, frequency) = match next {
+            Some(next) if Arc::ptr_eq(&next, &record) => (queue, frequency),
+            _ => (None, None),
+        };
+        self.log(&record, queue, frequency, EvictReason::Evict);
+        Some(Record::cast(record))
+    }
+
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        self.inner.peek_n(n).into_iter().map(Record::cast).collect()
+    }
+
+    fn peek_n_after(&self, record: &Arc<Record<Self>>, n: usize) -> Option<Vec<Arc<Record<Self>>>> {
+        self.inner
+            .peek_n_after(&Record::cast_ref(record), n)
+            .map(|records| records.into_iter().map(Record::cast).collect())
+    }
+
+    fn remove(&mut self, record: &Arc<Record<Self>>) {
+        let record = Record::cast_ref(record);
+        let queue = self.inner.queue_of(&record);
+        let frequency = self.inner.frequency_of(&record);
+        self.inner.remove(&record);
+        self.log(&record, queue, frequency, EvictReason::Remove);
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    fn queue_of(&self, record: &Arc<Record<Self>>) -> Option<QueueTag> {
+        self.inner.queue_of(&Record::cast_ref(record))
+    }
+
+    fn frequency_of(&self, record: &Arc<Record<Self>>) -> Option<u32> {
+        self.inner.frequency_of(&Record::cast_ref(record))
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        self.inner.dump_order().into_iter().map(Record::cast).collect()
+    }
+
+    fn acquire() -> Op<Self> {
+        Self::wrap(E::acquire())
+    }
+
+    fn release() -> Op<Self> {
+        Self::wrap(E::release())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        eviction::{
+            fifo::{Fifo, FifoConfig},
+            lru::{Lru, LruConfig},
+            s3fifo::{S3Fifo, S3FifoConfig},
+            test_utils::{assert_ptr_vec_eq, OpExt, TestProperties},
+        },
+        record::Data,
+    };
+
+    fn records<E>(n: u64) -> Vec<Arc<Record<E>>>
+    where
+        E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+    {
+        (0..n)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_logged_recent_evictions() {
+        type TestLogged = Logged<Fifo<u64, u64, TestProperties>>;
+
+        let rs = records::<TestLogged>(8);
+        let r = |i: usize| rs[i].clone();
+
+        let mut logged = TestLogged::new(
+            4,
+            &LoggedConfig {
+                inner: FifoConfig {},
+                capacity: 3,
+            },
+        );
+
+        let mut usage = 0;
+        for i in 0..8 {
+            usage -= logged.push_evict(r(i), 4, usage).len();
+            usage += 1;
+        }
+        assert_ptr_vec_eq(logged.dump_order(), vec![r(4), r(5), r(6), r(7)]);
+        assert!((0..4).all(|i| !r(i).is_in_eviction()));
+
+        let evict = |hash| EvictRecord {
+            hash,
+            weight: 1,
+            queue: Some(QueueTag::Main),
+            frequency: None,
+            reason: EvictReason::Evict,
+        };
+        // Only the most recent decisions are kept, from the oldest to the latest.
+        assert_eq!(logged.recent_evictions(), vec![evict(1), evict(2), evict(3)]);
+
+        logged.remove(&r(6));
+        assert!(!r(6).is_in_eviction());
+        assert_eq!(logged.pop().map(|r| r.hash()), Some(4));
+        assert_eq!(
+            logged.recent_evictions(),
+            vec![
+                evict(3),
+                EvictRecord {
+                    hash: 6,
+                    weight: 1,
+                    queue: Some(QueueTag::Main),
+                    frequency: None,
+                    reason: EvictReason::Remove,
+                },
+                evict(4)
+            ]
+        );
+
+        logged.clear();
+        assert!(logged.dump_order().is_empty());
+    }
+
+    #[test]
+    fn test_logged_transparent() {
+        type TestLru = Lru<u64, u64, TestProperties>;
+        type TestLogged = Logged<TestLru>;
+
+        let rs = records::<TestLogged>(8);
+        let bare = records::<TestLru>(8);
+
+        let mut logged = TestLogged::new(8, &LoggedConfig::default());
+        let mut lru = TestLru::new(8, &LruConfig::default());
+        for i in 0..8 {
+            logged.push(rs[i].clone());
+            lru.push(bare[i].clone());
+        }
+
+        // The decorated operations reorder the records the same as the decorated eviction algorithm.
+        for i in [0, 3, 5, 0] {
+            logged.acquire_mutable(&rs[i]);
+            logged.release_mutable(&rs[i]);
+            lru.acquire_mutable(&bare[i]);
+            lru.release_mutable(&bare[i]);
+        }
+        assert_eq!(
+            logged.dump_order().iter().map(|r| r.hash()).collect_vec(),
+            lru.dump_order().iter().map(|r| r.hash()).collect_vec()
+        );
+
+        logged
+            .update(
+                8,
+                Some(&LoggedConfig {
+                    inner: LruConfig::default(),
+                    capacity: 1,
+                }),
+            )
+            .unwrap();
+        let popped = std::iter::from_fn(|| logged.pop()).map(|r| r.hash()).collect_vec();
+        let expected = std::iter::from_fn(|| lru.pop()).map(|r| r.hash()).collect_vec();
+        assert_eq!(popped, expected);
+        assert_eq!(
+            logged.recent_evictions(),
+            vec![EvictRecord {
+                hash: *expected.last().unwrap(),
+                weight: 1,
+                queue: Some(QueueTag::HighPriority),
+                frequency: None,
+                reason: EvictReason::Evict,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_logged_queue_and_frequency() {
+        type TestLogged = Logged<S3Fifo<u64, u64, TestProperties>>;
+
+        let rs = records::<TestLogged>(4);
+
+        // capacity: 4, small: 1
+        let mut logged = TestLogged::new(
+            4,
+            &LoggedConfig {
+                inner: S3FifoConfig {
+                    small_queue_capacity_ratio: 0.25,
+                    ghost_queue_capacity_ratio: 1.0,
+                    small_to_main_freq_threshold: 2,
+                },
+                capacity: 8,
+            },
+        );
+        logged.push(rs[0].clone());
+        logged.push(rs[1].clone());
+        logged.acquire_immutable(&rs[0]);
+        logged.acquire_immutable(&rs[0]);
+        logged.acquire_immutable(&rs[1]);
+
+        // `0` is promoted to the main queue, `1` is evicted from the small queue with its frequency.
+        assert_eq!(logged.pop().map(|r| r.hash()), Some(1));
+        assert_eq!(logged.queue_of(&rs[0]), Some(QueueTag::Main));
+        logged.remove(&rs[0]);
+        assert_eq!(
+            logged.recent_evictions(),
+            vec![
+                EvictRecord {
+                    hash: 1,
+                    weight: 1,
+                    queue: Some(QueueTag::Small),
+                    frequency: Some(1),
+                    reason: EvictReason::Evict,
+                },
+                EvictRecord {
+                    hash: 0,
+                    weight: 1,
+                    queue: Some(QueueTag::Main),
+                    frequency: Some(2),
+                    reason: EvictReason::Remove,
+                }
+            ]
+        );
+    }
+}
@@ -22,7 +22,7 @@ use foyer_common::{
 use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListAtomicLink};
 use serde::{Deserialize, Serialize};
 
-use super::{Eviction, Op};
+use super::{unlink_first, Eviction, Op, QueueTag};
 use crate::{
     error::{Error, Result},
     record::Record,
@@ -40,12 +40,23 @@ pub struct LruConfig {
     ///
     /// Panics if the value is not in [0, 1.0].
     pub high_priority_pool_ratio: f64,
+
+    /// Enable scan resistance.
+    ///
+    /// If enabled, newly inserted entries with [`Hint::Normal`] are admitted into the probation segment (the low
+    /// priority pool) first, and are only promoted to the protected segment (the high priority pool) after they are
+    /// accessed again. `high_priority_pool_ratio` works as the protected ratio in this mode.
+    ///
+    /// A sequential scan that touches each entry only once can no longer flush the protected segment.
+    #[serde(default)]
+    pub scan_resistant: bool,
 }
 
 impl Default for LruConfig {
     fn default() -> Self {
         Self {
             high_priority_pool_ratio: 0.9,
+            scan_resistant: false,
         }
     }
 }
@@ -55,6 +66,7 @@ impl Default for LruConfig {
 pub struct LruState {
     link: LinkedListAtomicLink,
     in_high_priority_pool: bool,
+    in_probation: bool,
     is_pinned: bool,
 }
 
@@ -73,6 +85,8 @@ where
     high_priority_weight: usize,
     high_priority_weight_capacity: usize,
 
+    capacity: usize,
+
     config: LruConfig,
 }
 
@@ -129,6 +143,7 @@ where
             pin_list: LinkedList::new(Adapter::new()),
             high_priority_weight: 0,
             high_priority_weight_capacity,
+            capacity,
             config,
         }
     }
@@ -148,12 +163,17 @@ where
 
         let high_priority_weight_capacity = (capacity as f64 * self.config.high_priority_pool_ratio) as usize;
         self.high_priority_weight_capacity = high_priority_weight_capacity;
+        self.capacity = capacity;
 
         self.may_overflow_high_priority_pool();
 
         Ok(())
     }
 
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
     fn push(&mut self, record: Arc<Record<Self>>) {
         let state = unsafe { &mut *record.state().get() };
 
@@ -162,6 +182,10 @@ where
         record.set_in_eviction(true);
 
         match record.properties().hint().unwrap_or_default() {
+            Hint::Normal if self.config.scan_resistant => {
+                state.in_probation = true;
+                self.list.push_back(record);
+            }
             Hint::Normal => {
                 state.in_high_priority_pool = true;
                 self.high_priority_weight += record.weight();
@@ -177,7 +201,12 @@ where
     }
 
     fn pop(&mut self) -> Option<Arc<Record<Self>>> {
-        let record = self.list.pop_front().or_else(|| self.high_priority_list.pop_front())?;
+        self.pop_with(|_| false)
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        let record =
+            unlink_first(&mut self.list, &skip).or_else(|| unlink_first(&mut self.high_priority_list, &skip))?;
 
         let state = unsafe { &mut *record.state().get() };
 
@@ -187,12 +216,57 @@ where
             self.high_priority_weight -= record.weight();
             state.in_high_priority_pool = false;
         }
+        state.in_probation = false;
 
         record.set_in_eviction(false);
 
         Some(record)
     }
 
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        // Records in `pin_list` are never evicted.
+        for list in [&self.list, &self.high_priority_list] {
+            let mut cursor = list.front();
+            while res.len() < n {
+                let Some(record) = cursor.clone_pointer() else { break };
+                res.push(record);
+                cursor.move_next();
+            }
+        }
+        res
+    }
+
+    fn peek_n_after(&self, record: &Arc<Record<Self>>, n: usize) -> Option<Vec<Arc<Record<Self>>>> {
+        if !record.is_in_eviction() {
+            return None;
+        }
+        let state = unsafe { &*record.state().get() };
+        // Records in `pin_list` are never evicted, so they are never peeked.
+        if state.is_pinned {
+            return None;
+        }
+
+        let mut cursors = vec![];
+        if state.in_high_priority_pool {
+            cursors.push(unsafe { self.high_priority_list.cursor_from_ptr(Arc::as_ptr(record)) });
+        } else {
+            cursors.push(unsafe { self.list.cursor_from_ptr(Arc::as_ptr(record)) });
+            cursors.push(self.high_priority_list.front());
+        }
+        cursors[0].move_next();
+
+        let mut res = vec![];
+        for mut cursor in cursors {
+            while res.len() < n {
+                let Some(record) = cursor.clone_pointer() else { break };
+                res.push(record);
+                cursor.move_next();
+            }
+        }
+        Some(res)
+    }
+
     fn remove(&mut self, record: &Arc<Record<Self>>) {
         let state = unsafe { &mut *record.state().get() };
 
@@ -212,6 +286,7 @@ where
             }
             (false, false) => unsafe { self.list.remove_from_ptr(Arc::as_ptr(record)) },
         };
+        state.in_probation = false;
 
         strict_assert!(!state.link.is_linked());
 
@@ -230,6 +305,7 @@ where
                 self.high_priority_weight -= record.weight();
                 state.in_high_priority_pool = false;
             }
+            state.in_probation = false;
 
             record.set_in_eviction(false);
         }
@@ -240,6 +316,42 @@ where
         assert_eq!(self.high_priority_weight, 0);
     }
 
+    fn queue_of(&self, record: &Arc<Record<Self>>) -> Option<QueueTag> {
+        if !record.is_in_eviction() {
+            return None;
+        }
+        let state = unsafe { &*record.state().get() };
+        if state.in_probation {
+            Some(QueueTag::Probation)
+        } else if state.in_high_priority_pool {
+            Some(QueueTag::HighPriority)
+        } else {
+            Some(QueueTag::Main)
+        }
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.list.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.high_priority_list.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.pin_list.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+        res
+    }
+
     fn acquire() -> Op<Self> {
         Op::mutable(|this: &mut Self, record| {
             if !record.is_in_eviction() {
@@ -284,6 +396,13 @@ where
 
             unsafe { this.pin_list.remove_from_ptr(Arc::as_ptr(record)) };
 
+            // The record in the probation segment has survived until its next access, promote it.
+            if state.in_probation {
+                state.in_probation = false;
+                state.in_high_priority_pool = true;
+                this.high_priority_weight += record.weight();
+            }
+
             if state.in_high_priority_pool {
                 this.high_priority_list.push_back(record.clone());
             } else {
@@ -291,6 +410,8 @@ where
             }
 
             state.is_pinned = false;
+
+            this.may_overflow_high_priority_pool();
         })
     }
 }
@@ -298,6 +419,8 @@ where
 #[cfg(test)]
 pub mod tests {
 
+    use std::collections::HashMap;
+
     use itertools::Itertools;
 
     use super::*;
@@ -371,6 +494,7 @@ pub mod tests {
 
         let config = LruConfig {
             high_priority_pool_ratio: 0.5,
+            scan_resistant: false,
         };
         let mut lru = TestLru::new(8, &config);
 
@@ -447,6 +571,7 @@ pub mod tests {
 
         let config = LruConfig {
             high_priority_pool_ratio: 0.5,
+            scan_resistant: false,
         };
         let mut lru = TestLru::new(8, &config);
 
@@ -519,4 +644,136 @@ pub mod tests {
         lru.clear();
         assert_ptr_vec_vec_eq(lru.dump(), vec![vec![], vec![], vec![]]);
     }
+
+    #[test]
+    fn test_lru_scan_resistant() {
+        let rs = (0..10)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default().with_hint(Hint::Normal),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        let r = |i: usize| rs[i].clone();
+
+        let config = LruConfig {
+            high_priority_pool_ratio: 0.5,
+            scan_resistant: true,
+        };
+        let mut lru = TestLru::new(8, &config);
+
+        // probation: 0, 1, 2
+        lru.push(r(0));
+        lru.push(r(1));
+        lru.push(r(2));
+        assert_ptr_vec_vec_eq(lru.dump(), vec![vec![r(0), r(1), r(2)], vec![], vec![]]);
+
+        // Releasing an unpinned record must not promote it.
+        lru.release_mutable(&rs[0]);
+        assert_ptr_vec_vec_eq(lru.dump(), vec![vec![r(0), r(1), r(2)], vec![], vec![]]);
+
+        // probation: 0, 2
+        // pin: 1
+        lru.acquire_mutable(&rs[1]);
+        assert_ptr_vec_vec_eq(lru.dump(), vec![vec![r(0), r(2)], vec![], vec![r(1)]]);
+
+        // probation: 0, 2
+        // protected: [1]
+        lru.release_mutable(&rs[1]);
+        assert_ptr_vec_vec_eq(lru.dump(), vec![vec![r(0), r(2)], vec![r(1)], vec![]]);
+        assert_eq!(lru.high_priority_weight, 1);
+
+        // probation: 0, 2, 3, 4, 5, 6
+        // protected: [1]
+        for i in 3..7 {
+            lru.push(r(i));
+        }
+        // probation: 0, 2, 1, 3
+        // protected: [4, 5, 6, 0]
+        for i in [3, 4, 5, 6, 0] {
+            lru.acquire_mutable(&rs[i]);
+            lru.release_mutable(&rs[i]);
+        }
+        assert_ptr_vec_vec_eq(
+            lru.dump(),
+            vec![vec![r(2), r(1), r(3)], vec![r(4), r(5), r(6), r(0)], vec![]],
+        );
+        assert_eq!(lru.high_priority_weight, 4);
+
+        // Demoted records are no longer in probation, accessing them again doesn't promote.
+        lru.acquire_mutable(&rs[1]);
+        lru.release_mutable(&rs[1]);
+        assert_ptr_vec_vec_eq(
+            lru.dump(),
+            vec![vec![r(2), r(3), r(1)], vec![r(4), r(5), r(6), r(0)], vec![]],
+        );
+
+        lru.clear();
+        assert_ptr_vec_vec_eq(lru.dump(), vec![vec![], vec![], vec![]]);
+        assert_eq!(lru.high_priority_weight, 0);
+    }
+
+    /// Replay the trace and return the hit count of the accesses after `warmup` accesses.
+    fn replay(config: &LruConfig, capacity: usize, trace: &[u64], warmup: usize) -> usize {
+        let mut lru = TestLru::new(capacity, config);
+        let mut records: HashMap<u64, Arc<Record<TestLru>>> = HashMap::new();
+        let mut hits = 0;
+
+        for (i, &key) in trace.iter().enumerate() {
+            if let Some(record) = records.get(&key) {
+                lru.acquire_mutable(record);
+                lru.release_mutable(record);
+                if i >= warmup {
+                    hits += 1;
+                }
+                continue;
+            }
+            let record = Arc::new(Record::new(Data {
+                key,
+                value: key,
+                properties: TestProperties::default().with_hint(Hint::Normal),
+                hash: key,
+                weight: 1,
+            }));
+            lru.push(record.clone());
+            records.insert(key, record);
+            while records.len() > capacity {
+                let evicted = lru.pop().unwrap();
+                records.remove(evicted.key());
+            }
+        }
+
+        hits
+    }
+
+    #[test]
+    fn test_lru_scan_resistant_sequential_scan() {
+        const CAPACITY: usize = 16;
+        const HOT: u64 = 6;
+
+        // Warm up the hot set, then run a sequential scan that is larger than the cache, then access the hot set
+        // again.
+        let hot = (0..HOT).collect_vec();
+        let scan = (1000..1000 + 4 * CAPACITY as u64).collect_vec();
+        let trace = [hot.clone(), hot.clone(), scan, hot].concat();
+        let warmup = trace.len() - HOT as usize;
+
+        let plain = LruConfig {
+            high_priority_pool_ratio: 0.5,
+            scan_resistant: false,
+        };
+        let resistant = LruConfig {
+            high_priority_pool_ratio: 0.5,
+            scan_resistant: true,
+        };
+
+        // The scan flushes the whole plain lru.
+        assert_eq!(replay(&plain, CAPACITY, &trace, warmup), 0);
+        // The hot set is protected from the scan.
+        assert_eq!(replay(&resistant, CAPACITY, &trace, warmup), HOT as usize);
+    }
 }
@@ -18,6 +18,7 @@ use foyer_common::{
     code::{Key, Value},
     properties::Properties,
 };
+use intrusive_collections::{linked_list::LinkedListOps, Adapter, DefaultPointerOps, LinkedList};
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{error::Result, record::Record};
@@ -72,6 +73,27 @@ where
     }
 }
 
+/// Tag of the internal queue of a cache eviction algorithm that holds a record.
+///
+/// Used for debugging and explaining the behavior of the cache eviction algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueTag {
+    /// The only queue of the cache eviction algorithms with a single queue, or the main queue of S3FIFO and LRU.
+    Main,
+    /// The small queue of S3FIFO.
+    Small,
+    /// The high priority pool of LRU.
+    HighPriority,
+    /// The bypass window of LFU.
+    Bypass,
+    /// The window queue of LFU.
+    Window,
+    /// The probation queue of LFU, or the probation segment of the scan resistant LRU.
+    Probation,
+    /// The protected queue of LFU.
+    Protected,
+}
+
 /// Cache eviction algorithm abstraction.
 ///
 /// [`Eviction`] provides essential APIs for the plug-and-play algorithm abstraction.
@@ -102,6 +124,24 @@ pub trait Eviction: Send + Sync + 'static + Sized {
     /// Update the arguments of the ache eviction algorithm instance.
     fn update(&mut self, capacity: usize, config: Option<&Self::Config>) -> Result<()>;
 
+    /// Get the capacity of the cache eviction algorithm instance, given by `new` or the latest `update`.
+    ///
+    /// The cache can cross-check its capacity accounting against it. The default implementation returns `None` for
+    /// the cache eviction algorithms that do not track the capacity, the capacity of the cache is used then.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// Get the total weight of the records in the cache eviction algorithm instance.
+    ///
+    /// The cache can cross-check its weight accounting against it.
+    ///
+    /// The default implementation sums the weights of the dumped records, which is O(n). The cache eviction algorithm
+    /// that tracks the weights of its queues should override it.
+    fn weight(&self) -> usize {
+        self.dump_order().iter().map(|record| record.weight()).sum()
+    }
+
     /// Push a record into the cache eviction algorithm instance.
     ///
     /// The caller guarantees that the record is NOT in the cache eviction algorithm instance.
@@ -109,11 +149,94 @@ pub trait Eviction: Send + Sync + 'static + Sized {
     /// The cache eviction algorithm instance MUST hold the record and set its `IN_EVICTION` flag to true.
     fn push(&mut self, record: Arc<Record<Self>>);
 
+    /// Called by the cache before a new record is admitted and pushed.
+    ///
+    /// The record is NOT in the cache eviction algorithm instance yet. The cache eviction algorithm with a ghost queue
+    /// can prune the ghost entry of the re-admitted key here, to keep the ghost queue accurate, and carry what it
+    /// remembers to the following `push` with the record state.
+    ///
+    /// The default implementation does nothing.
+    fn on_admit(&mut self, record: &Arc<Record<Self>>) {
+        let _ = record;
+    }
+
+    /// Push a record into the cache eviction algorithm instance, and pop the records that must be evicted to fit the
+    /// capacity.
+    ///
+    /// `usage` is the total weight of the records in the cache, excluding the record to push.
+    ///
+    /// The default implementation pops unpinned records with `pop_with` until the record fits the capacity, then
+    /// pushes it. The cache eviction algorithm can override it to make a holistic decision with both the new record
+    /// and the victims, e.g. admission.
+    ///
+    /// The returned records MUST follow the same contract as `pop`, and MUST NOT include pinned records.
+    fn push_evict(&mut self, record: Arc<Record<Self>>, capacity: usize, usage: usize) -> Vec<Arc<Record<Self>>> {
+        let target = capacity.saturating_sub(record.weight());
+        let mut usage = usage;
+        let mut victims = vec![];
+        while usage > target {
+            let Some(victim) = self.pop_with(|record| record.is_pinned()) else {
+                break;
+            };
+            usage -= victim.weight();
+            victims.push(victim);
+        }
+        self.push(record);
+        victims
+    }
+
     /// Push a record from the cache eviction algorithm instance.
     ///
     /// The cache eviction algorithm instance MUST remove the record and set its `IN_EVICTION` flag to false.
     fn pop(&mut self) -> Option<Arc<Record<Self>>>;
 
+    /// Pop a record from the cache eviction algorithm instance like `pop`, but pass over the records that `skip`
+    /// returns `true` for, e.g. the pinned records.
+    ///
+    /// The passed over records are supposed to be left in place with their states untouched. `pop_with(|_| false)`
+    /// MUST be the same as `pop`.
+    ///
+    /// The default implementation pops the records and pushes the passed over ones back afterwards, which resets their
+    /// states and positions. The cache eviction algorithm is supposed to override it to pass over the records in place.
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        let mut skipped = vec![];
+        let res = loop {
+            match self.pop() {
+                Some(record) if skip(&record) => skipped.push(record),
+                res => break res,
+            }
+        };
+        for record in skipped {
+            self.push(record);
+        }
+        res
+    }
+
+    /// Peek the next `n` records to be evicted without removing them or touching their states.
+    ///
+    /// The returned records MUST be the same as the records returned by calling `pop` `n` times, in the same order,
+    /// as long as the cache eviction algorithm instance is not modified in between.
+    ///
+    /// The default implementation returns nothing, for the cache eviction algorithms that cannot tell the candidates
+    /// without modifying the states.
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let _ = n;
+        vec![]
+    }
+
+    /// Peek the next `n` records to be evicted after the given record, without removing them or touching their states.
+    ///
+    /// The returned records MUST follow the given record in the order of [`Eviction::peek_n`], so a caller can resume
+    /// peeking from the last record it got without peeking the records before it again.
+    ///
+    /// Returns `None` if the cache eviction algorithm cannot resume from the record, e.g. the record is no longer in the
+    /// cache eviction algorithm instance. The caller then falls back to [`Eviction::peek_n`] and skips the records it
+    /// has already got. The default implementation always returns `None`.
+    fn peek_n_after(&self, record: &Arc<Record<Self>>, n: usize) -> Option<Vec<Arc<Record<Self>>>> {
+        let _ = (record, n);
+        None
+    }
+
     /// Remove a record from the cache eviction algorithm instance.
     ///
     /// The caller guarantees that the record is in the cache eviction algorithm instance.
@@ -128,6 +251,34 @@ pub trait Eviction: Send + Sync + 'static + Sized {
         while self.pop().is_some() {}
     }
 
+    /// Dump all records in the cache eviction algorithm instance without removing them.
+    ///
+    /// The records are supposed to be ordered from the first to be evicted to the last to be evicted, so pushing them
+    /// back in the same order restores the eviction order as much as possible.
+    ///
+    /// The default implementation returns nothing, the cache then falls back to the indexed records held by the cache
+    /// eviction algorithm, in an arbitrary order.
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        vec![]
+    }
+
+    /// Get the tag of the internal queue that holds the record, or `None` if the record is not in the cache eviction
+    /// algorithm instance.
+    ///
+    /// The default implementation reports [`QueueTag::Main`] for the cache eviction algorithms with a single queue.
+    fn queue_of(&self, record: &Arc<Record<Self>>) -> Option<QueueTag> {
+        record.is_in_eviction().then_some(QueueTag::Main)
+    }
+
+    /// Get the access frequency of the record tracked by the cache eviction algorithm instance, or `None` if the
+    /// record is not in the cache eviction algorithm instance or the frequency is not tracked.
+    ///
+    /// The default implementation returns `None`.
+    fn frequency_of(&self, record: &Arc<Record<Self>>) -> Option<u32> {
+        let _ = record;
+        None
+    }
+
     /// `acquire` is called when an external caller acquire a cache entry from the cache.
     ///
     /// The entry can be EITHER in the cache eviction algorithm instance or not.
@@ -139,11 +290,426 @@ pub trait Eviction: Send + Sync + 'static + Sized {
     fn release() -> Op<Self>;
 }
 
+/// Unlink the first record from the front of the list that `skip` returns `false` for, the passed over records are
+/// left in place.
+pub(crate) fn unlink_first<E, A>(list: &mut LinkedList<A>, skip: impl Fn(&Record<E>) -> bool) -> Option<Arc<Record<E>>>
+where
+    E: Eviction,
+    A: Adapter<PointerOps = DefaultPointerOps<Arc<Record<E>>>>,
+    A::LinkOps: LinkedListOps,
+{
+    let mut cursor = list.front_mut();
+    while let Some(record) = cursor.get() {
+        if !skip(record) {
+            return cursor.remove();
+        }
+        cursor.move_next();
+    }
+    None
+}
+
+pub mod decay_lfu;
 pub mod fifo;
 pub mod lfu;
+pub mod logged;
 pub mod lru;
 pub mod s3fifo;
 pub mod sieve;
+pub mod sketch;
 
 #[cfg(any(test, feature = "test_utils"))]
 pub mod test_utils;
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        eviction::{
+            decay_lfu::{DecayLfu, DecayLfuConfig},
+            fifo::{Fifo, FifoConfig},
+            lfu::{Lfu, LfuConfig},
+            logged::{Logged, LoggedConfig},
+            lru::{Lru, LruConfig},
+            s3fifo::{S3Fifo, S3FifoConfig},
+            sieve::{Sieve, SieveConfig},
+            sketch::FrequencyBackend,
+            test_utils::TestProperties,
+        },
+        record::Data,
+    };
+
+    fn acquire<E: Eviction>(eviction: &mut E, record: &Arc<Record<E>>) {
+        match E::acquire() {
+            Op::Noop => {}
+            Op::Immutable(f) => f(eviction, record),
+            Op::Mutable(mut f) => f(eviction, record),
+        }
+    }
+
+    fn release<E: Eviction>(eviction: &mut E, record: &Arc<Record<E>>) {
+        match E::release() {
+            Op::Noop => {}
+            Op::Immutable(f) => f(eviction, record),
+            Op::Mutable(mut f) => f(eviction, record),
+        }
+    }
+
+    fn test_push_evict<E>(config: E::Config)
+    where
+        E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+    {
+        const CAPACITY: usize = 64;
+
+        let record = |i: u64| {
+            Arc::new(Record::new(Data {
+                key: i,
+                value: i,
+                properties: TestProperties::default(),
+                hash: i,
+                weight: i as usize % 4 + 1,
+            }))
+        };
+
+        let mut a = E::new(CAPACITY, &config);
+        let mut b = E::new(CAPACITY, &config);
+        let mut ra = vec![];
+        let mut rb = vec![];
+        let mut usage_a = 0;
+        let mut usage_b = 0;
+
+        for i in 0..1000 {
+            let (x, y) = (record(i), record(i));
+            let weight = x.weight();
+
+            let victims_a = a.push_evict(x.clone(), CAPACITY, usage_a);
+
+            let mut victims_b = vec![];
+            while usage_b > CAPACITY - weight {
+                let victim = b.pop().unwrap();
+                usage_b -= victim.weight();
+                victims_b.push(victim);
+            }
+            b.push(y.clone());
+
+            assert_eq!(
+                victims_a.iter().map(|r| *r.key()).collect_vec(),
+                victims_b.iter().map(|r| *r.key()).collect_vec()
+            );
+            assert!(victims_a.iter().all(|r| !r.is_in_eviction()));
+            usage_a = usage_a - victims_a.iter().map(|r| r.weight()).sum::<usize>() + weight;
+            usage_b += weight;
+            assert_eq!(usage_a, usage_b);
+            assert!(usage_a <= CAPACITY);
+
+            ra.push(x);
+            rb.push(y);
+            // Access some records to make the eviction algorithm reorder them.
+            let j = (i * 7 % (i + 1)) as usize;
+            if ra[j].is_in_eviction() {
+                acquire(&mut a, &ra[j]);
+                acquire(&mut b, &rb[j]);
+            }
+        }
+    }
+
+    fn test_peek_n<E>(config: E::Config)
+    where
+        E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+    {
+        const CAPACITY: usize = 64;
+
+        let build = || {
+            let mut eviction = E::new(CAPACITY, &config);
+            let records = (0..CAPACITY as u64)
+                .map(|i| {
+                    Arc::new(Record::new(Data {
+                        key: i,
+                        value: i,
+                        properties: TestProperties::default(),
+                        hash: i,
+                        weight: 1,
+                    }))
+                })
+                .collect_vec();
+            for (i, record) in records.iter().enumerate() {
+                eviction.push(record.clone());
+                // Access some records to make the eviction algorithm reorder them.
+                for j in (0..=i).filter(|j| j % 3 == 0 || j % 5 == 0) {
+                    acquire(&mut eviction, &records[j]);
+                    release(&mut eviction, &records[j]);
+                }
+            }
+            eviction
+        };
+
+        // `peek_n` with `n` larger than the record count returns all records in the `pop` order.
+        let mut eviction = build();
+        let peeked = eviction.peek_n(CAPACITY * 2).iter().map(|r| *r.key()).collect_vec();
+        let popped = std::iter::from_fn(|| eviction.pop()).map(|r| *r.key()).collect_vec();
+        assert_eq!(peeked.len(), CAPACITY);
+        assert_eq!(peeked, popped);
+
+        // `peek_n(k)` is always the prefix of the following `pop` sequence.
+        let mut eviction = build();
+        loop {
+            let peeked = eviction.peek_n(7).iter().map(|r| *r.key()).collect_vec();
+            let popped = (0..7).filter_map(|_| eviction.pop()).map(|r| *r.key()).collect_vec();
+            assert_eq!(peeked, popped);
+            if popped.is_empty() {
+                break;
+            }
+        }
+
+        // `peek_n_after` continues the order of `peek_n` after any record, if the algorithm can resume from it.
+        let mut eviction = build();
+        let peeked = eviction.peek_n(CAPACITY);
+        for (i, record) in peeked.iter().enumerate() {
+            if let Some(after) = eviction.peek_n_after(record, 7) {
+                let after = after.iter().map(|r| *r.key()).collect_vec();
+                let expected = peeked[i + 1..].iter().take(7).map(|r| *r.key()).collect_vec();
+                assert_eq!(after, expected);
+            }
+        }
+
+        // A record that has left the eviction algorithm cannot be resumed from.
+        let popped = eviction.pop().unwrap();
+        assert!(eviction.peek_n_after(&popped, 7).is_none());
+    }
+
+    fn test_peek_n_after<E>(config: E::Config)
+    where
+        E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+    {
+        const CAPACITY: usize = 64;
+
+        let mut eviction = E::new(CAPACITY, &config);
+        let records = (0..CAPACITY as u64)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        for (i, record) in records.iter().enumerate() {
+            eviction.push(record.clone());
+            for j in (0..=i).filter(|j| j % 3 == 0) {
+                acquire(&mut eviction, &records[j]);
+                release(&mut eviction, &records[j]);
+            }
+        }
+
+        // Resuming batch by batch visits all records in the order of `peek_n`.
+        let peeked = eviction.peek_n(CAPACITY).iter().map(|r| *r.key()).collect_vec();
+        let mut resumed = eviction.peek_n(7);
+        while let Some(last) = resumed.last().cloned() {
+            let batch = eviction.peek_n_after(&last, 7).unwrap();
+            if batch.is_empty() {
+                break;
+            }
+            resumed.extend(batch);
+        }
+        let resumed = resumed.iter().map(|r| *r.key()).collect_vec();
+        assert_eq!(resumed, peeked);
+    }
+
+    fn test_pop_with<E>(config: E::Config)
+    where
+        E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+    {
+        const CAPACITY: usize = 64;
+
+        let mut eviction = E::new(CAPACITY, &config);
+        let records = (0..CAPACITY as u64)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        for (i, record) in records.iter().enumerate() {
+            eviction.push(record.clone());
+            // Access some records to make the eviction algorithm reorder them.
+            for j in (0..=i).filter(|j| j % 3 == 0 || j % 5 == 0) {
+                acquire(&mut eviction, &records[j]);
+                release(&mut eviction, &records[j]);
+            }
+        }
+
+        let pinned = records.iter().filter(|r| r.key() % 4 == 0).cloned().collect_vec();
+        for record in pinned.iter() {
+            record.pin();
+        }
+        let states = pinned
+            .iter()
+            .map(|r| (eviction.queue_of(r), eviction.frequency_of(r)))
+            .collect_vec();
+
+        // The pinned records are passed over in place with their states untouched.
+        let popped = std::iter::from_fn(|| eviction.pop_with(|record| record.is_pinned())).collect_vec();
+        assert_eq!(popped.len(), CAPACITY - pinned.len());
+        assert!(popped.iter().all(|r| !r.is_pinned() && !r.is_in_eviction()));
+        assert!(pinned.iter().all(|r| r.is_in_eviction()));
+        assert_eq!(
+            pinned
+                .iter()
+                .map(|r| (eviction.queue_of(r), eviction.frequency_of(r)))
+                .collect_vec(),
+            states
+        );
+
+        // `pop_with(|_| false)` is the same as `pop`.
+        let popped = std::iter::from_fn(|| eviction.pop_with(|_| false)).collect_vec();
+        assert_eq!(popped.len(), pinned.len());
+        assert!(eviction.pop().is_none());
+    }
+
+    #[test]
+    fn test_fifo_pop_with() {
+        test_pop_with::<Fifo<u64, u64, TestProperties>>(FifoConfig::default());
+    }
+
+    #[test]
+    fn test_lru_pop_with() {
+        test_pop_with::<Lru<u64, u64, TestProperties>>(LruConfig::default());
+    }
+
+    #[test]
+    fn test_lfu_pop_with() {
+        test_pop_with::<Lfu<u64, u64, TestProperties>>(LfuConfig::default());
+        test_pop_with::<Lfu<u64, u64, TestProperties>>(LfuConfig {
+            bypass_window_size: 8,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_logged_pop_with() {
+        test_pop_with::<Logged<Lfu<u64, u64, TestProperties>>>(LoggedConfig::default());
+    }
+
+    #[test]
+    fn test_s3fifo_pop_with() {
+        test_pop_with::<S3Fifo<u64, u64, TestProperties>>(S3FifoConfig::default());
+    }
+
+    #[test]
+    fn test_sieve_pop_with() {
+        test_pop_with::<Sieve<u64, u64, TestProperties>>(SieveConfig {});
+    }
+
+    #[test]
+    fn test_decay_lfu_pop_with() {
+        test_pop_with::<DecayLfu<u64, u64, TestProperties>>(DecayLfuConfig::default());
+    }
+
+    #[test]
+    fn test_fifo_peek_n() {
+        test_peek_n::<Fifo<u64, u64, TestProperties>>(FifoConfig::default());
+    }
+
+    #[test]
+    fn test_lru_peek_n() {
+        test_peek_n::<Lru<u64, u64, TestProperties>>(LruConfig::default());
+    }
+
+    #[test]
+    fn test_lfu_peek_n() {
+        test_peek_n::<Lfu<u64, u64, TestProperties>>(LfuConfig::default());
+        test_peek_n::<Lfu<u64, u64, TestProperties>>(LfuConfig {
+            bypass_window_size: 8,
+            ..Default::default()
+        });
+        test_peek_n::<Lfu<u64, u64, TestProperties>>(LfuConfig {
+            frequency_backend: FrequencyBackend::Concurrent,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_logged_peek_n() {
+        test_peek_n::<Logged<Lfu<u64, u64, TestProperties>>>(LoggedConfig::default());
+    }
+
+    #[test]
+    fn test_s3fifo_peek_n() {
+        test_peek_n::<S3Fifo<u64, u64, TestProperties>>(S3FifoConfig::default());
+    }
+
+    #[test]
+    fn test_sieve_peek_n() {
+        test_peek_n::<Sieve<u64, u64, TestProperties>>(SieveConfig {});
+    }
+
+    #[test]
+    fn test_decay_lfu_peek_n() {
+        test_peek_n::<DecayLfu<u64, u64, TestProperties>>(DecayLfuConfig::default());
+    }
+
+    #[test]
+    fn test_fifo_peek_n_after() {
+        test_peek_n_after::<Fifo<u64, u64, TestProperties>>(FifoConfig::default());
+    }
+
+    #[test]
+    fn test_lru_peek_n_after() {
+        test_peek_n_after::<Lru<u64, u64, TestProperties>>(LruConfig::default());
+        test_peek_n_after::<Lru<u64, u64, TestProperties>>(LruConfig {
+            scan_resistant: true,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_decay_lfu_peek_n_after() {
+        test_peek_n_after::<DecayLfu<u64, u64, TestProperties>>(DecayLfuConfig::default());
+    }
+
+    #[test]
+    fn test_fifo_push_evict() {
+        test_push_evict::<Fifo<u64, u64, TestProperties>>(FifoConfig::default());
+    }
+
+    #[test]
+    fn test_lru_push_evict() {
+        test_push_evict::<Lru<u64, u64, TestProperties>>(LruConfig::default());
+    }
+
+    #[test]
+    fn test_lfu_push_evict() {
+        test_push_evict::<Lfu<u64, u64, TestProperties>>(LfuConfig::default());
+        test_push_evict::<Lfu<u64, u64, TestProperties>>(LfuConfig {
+            frequency_backend: FrequencyBackend::Concurrent,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_logged_push_evict() {
+        test_push_evict::<Logged<Lfu<u64, u64, TestProperties>>>(LoggedConfig::default());
+    }
+
+    #[test]
+    fn test_s3fifo_push_evict() {
+        test_push_evict::<S3Fifo<u64, u64, TestProperties>>(S3FifoConfig::default());
+    }
+
+    #[test]
+    fn test_sieve_push_evict() {
+        test_push_evict::<Sieve<u64, u64, TestProperties>>(SieveConfig {});
+    }
+
+    #[test]
+    fn test_decay_lfu_push_evict() {
+        test_push_evict::<DecayLfu<u64, u64, TestProperties>>(DecayLfuConfig::default());
+    }
+}
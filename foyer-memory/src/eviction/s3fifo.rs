@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, VecDeque},
     mem::offset_of,
     sync::{
         atomic::{AtomicU8, Ordering},
@@ -29,7 +29,7 @@ use foyer_common::{
 use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListAtomicLink};
 use serde::{Deserialize, Serialize};
 
-use super::{Eviction, Op};
+use super::{unlink_first, Eviction, Op, QueueTag};
 use crate::{
     error::{Error, Result},
     record::Record,
@@ -75,6 +75,8 @@ pub struct S3FifoState {
     link: LinkedListAtomicLink,
     frequency: AtomicU8,
     queue: Queue,
+    /// Frequency remembered by the ghost queue, set by `on_admit` and taken by the following `push`.
+    readmitted: Option<u8>,
 }
 
 impl S3FifoState {
@@ -116,6 +118,7 @@ where
     main_queue: LinkedList<Adapter<K, V, P>>,
 
     small_weight_capacity: usize,
+    capacity: usize,
 
     small_weight: usize,
     main_weight: usize,
@@ -131,33 +134,37 @@ where
     V: Value,
     P: Properties,
 {
-    fn evict(&mut self) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
+    /// Evict a record, the records that `skip` returns `true` for are passed over in place.
+    fn evict(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
         // TODO(MrCroxx): Use `let_chains` here after it is stable.
         if self.small_weight > self.small_weight_capacity {
-            if let Some(record) = self.evict_small() {
+            if let Some(record) = self.evict_small(&skip) {
                 return Some(record);
             }
         }
-        if let Some(record) = self.evict_main() {
+        if let Some(record) = self.evict_main(&skip) {
             return Some(record);
         }
-        self.evict_small_force()
+        self.evict_small_force(&skip)
     }
 
-    #[expect(clippy::never_loop)]
-    fn evict_small_force(&mut self) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
-        while let Some(record) = self.small_queue.pop_front() {
-            let state = unsafe { &mut *record.state().get() };
-            state.queue = Queue::None;
-            state.set_frequency(0);
-            self.small_weight -= record.weight();
-            return Some(record);
-        }
-        None
+    fn evict_small_force(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
+        let record = unlink_first(&mut self.small_queue, skip)?;
+        let state = unsafe { &mut *record.state().get() };
+        state.queue = Queue::None;
+        state.set_frequency(0);
+        self.small_weight -= record.weight();
+        Some(record)
     }
 
-    fn evict_small(&mut self) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
-        while let Some(record) = self.small_queue.pop_front() {
+    fn evict_small(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
+        let mut cursor = self.small_queue.front_mut();
+        while let Some(record) = cursor.get() {
+            if skip(record) {
+                cursor.move_next();
+                continue;
+            }
+            let record = cursor.remove().unwrap();
             let state = unsafe { &mut *record.state().get() };
             if state.frequency() >= self.small_to_main_freq_threshold {
                 state.queue = Queue::Main;
@@ -166,10 +173,11 @@ where
                 self.main_queue.push_back(record);
             } else {
                 state.queue = Queue::None;
+                let frequency = state.frequency();
                 state.set_frequency(0);
                 self.small_weight -= record.weight();
 
-                self.ghost_queue.push(record.hash(), record.weight());
+                self.ghost_queue.push(record.hash(), record.weight(), frequency);
 
                 return Some(record);
             }
@@ -177,18 +185,40 @@ where
         None
     }
 
-    fn evict_main(&mut self) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
-        while let Some(record) = self.main_queue.pop_front() {
-            let state = unsafe { &mut *record.state().get() };
-            if state.dec_frequency() > 0 {
+    fn evict_main(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<S3Fifo<K, V, P>>>> {
+        // The records with frequency are moved to the back after each pass, as if they were popped and pushed back one
+        // by one, while the passed over records stay in place.
+        loop {
+            let mut requeued = vec![];
+            let mut victim = None;
+            let mut cursor = self.main_queue.front_mut();
+            while let Some(record) = cursor.get() {
+                if skip(record) {
+                    cursor.move_next();
+                    continue;
+                }
+                let state = unsafe { &mut *record.state().get() };
+                if state.dec_frequency() > 0 {
+                    requeued.push(cursor.remove().unwrap());
+                } else {
+                    victim = cursor.remove();
+                    break;
+                }
+            }
+            let exhausted = requeued.is_empty();
+            for record in requeued {
                 self.main_queue.push_back(record);
-            } else {
+            }
+            if let Some(record) = victim {
+                let state = unsafe { &mut *record.state().get() };
                 state.queue = Queue::None;
                 self.main_weight -= record.weight();
                 return Some(record);
             }
+            if exhausted {
+                return None;
+            }
         }
-        None
     }
 }
 
@@ -225,6 +255,7 @@ where
             small_queue: LinkedList::new(Adapter::new()),
             main_queue: LinkedList::new(Adapter::new()),
             small_weight_capacity,
+            capacity,
             small_weight: 0,
             main_weight: 0,
             small_to_main_freq_threshold: config.small_to_main_freq_threshold.min(S3FifoState::MAX_FREQUENCY),
@@ -247,10 +278,19 @@ where
         let small_weight_capacity = (capacity as f64 * self.config.small_queue_capacity_ratio) as usize;
         self.ghost_queue.update(ghost_queue_capacity);
         self.small_weight_capacity = small_weight_capacity;
+        self.capacity = capacity;
 
         Ok(())
     }
 
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
+    fn weight(&self) -> usize {
+        self.small_weight + self.main_weight
+    }
+
     fn push(&mut self, record: Arc<Record<Self>>) {
         let state = unsafe { &mut *record.state().get() };
 
@@ -259,7 +299,13 @@ where
 
         record.set_in_eviction(true);
 
-        if self.ghost_queue.contains(record.hash()) {
+        let readmitted = state
+            .readmitted
+            .take()
+            .or_else(|| self.ghost_queue.frequency(record.hash()));
+        if let Some(frequency) = readmitted {
+            // Resume with the frequency remembered by the ghost queue.
+            state.set_frequency(frequency);
             state.queue = Queue::Main;
             self.main_weight += record.weight();
             self.main_queue.push_back(record);
@@ -270,16 +316,79 @@ where
         }
     }
 
+    fn on_admit(&mut self, record: &Arc<Record<Self>>) {
+        let state = unsafe { &mut *record.state().get() };
+        state.readmitted = self.ghost_queue.remove(record.hash());
+    }
+
     fn pop(&mut self) -> Option<Arc<Record<Self>>> {
-        if let Some(record) = self.evict() {
-            // `handle.queue` has already been set with `evict()`
-            record.set_in_eviction(false);
-            Some(record)
-        } else {
+        let record = self.pop_with(|_| false);
+        if record.is_none() {
             strict_assert!(self.small_queue.is_empty());
             strict_assert!(self.main_queue.is_empty());
-            None
         }
+        record
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
+        // `handle.queue` has already been set with `evict()`
+        self.evict(skip).inspect(|record| record.set_in_eviction(false))
+    }
+
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+
+        // Simulate `evict()` without touching the queues and the record states.
+        //
+        // Records are never pushed back to `small`, and records pushed back to `main` are buffered in `requeued` with
+        // their simulated frequencies.
+        let mut small = self.small_queue.front();
+        let mut main = self.main_queue.front();
+        let mut requeued = VecDeque::new();
+        let mut small_weight = self.small_weight;
+
+        let mut pop_main = |requeued: &mut VecDeque<(Arc<Record<Self>>, u8)>| match main.clone_pointer() {
+            Some(record) => {
+                main.move_next();
+                let frequency = unsafe { &*record.state().get() }.frequency();
+                Some((record, frequency))
+            }
+            None => requeued.pop_front(),
+        };
+
+        'outer: while res.len() < n {
+            if small_weight > self.small_weight_capacity {
+                while let Some(record) = small.clone_pointer() {
+                    small.move_next();
+                    small_weight -= record.weight();
+                    let frequency = unsafe { &*record.state().get() }.frequency();
+                    if frequency >= self.small_to_main_freq_threshold {
+                        requeued.push_back((record, frequency));
+                    } else {
+                        res.push(record);
+                        continue 'outer;
+                    }
+                }
+            }
+            while let Some((record, frequency)) = pop_main(&mut requeued) {
+                if frequency > 0 {
+                    requeued.push_back((record, frequency - 1));
+                } else {
+                    res.push(record);
+                    continue 'outer;
+                }
+            }
+            match small.clone_pointer() {
+                Some(record) => {
+                    small.move_next();
+                    small_weight -= record.weight();
+                    res.push(record);
+                }
+                None => break,
+            }
+        }
+
+        res
     }
 
     fn remove(&mut self, record: &Arc<Record<Self>>) {
@@ -308,6 +417,36 @@ where
         }
     }
 
+    fn queue_of(&self, record: &Arc<Record<Self>>) -> Option<QueueTag> {
+        let state = unsafe { &*record.state().get() };
+        match state.queue {
+            Queue::None => None,
+            Queue::Small => Some(QueueTag::Small),
+            Queue::Main => Some(QueueTag::Main),
+        }
+    }
+
+    fn frequency_of(&self, record: &Arc<Record<Self>>) -> Option<u32> {
+        let state = unsafe { &*record.state().get() };
+        (state.queue != Queue::None).then(|| state.frequency() as u32)
+    }
+
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.small_queue.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+
+        let mut cursor = self.main_queue.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+        res
+    }
+
     fn acquire() -> Op<Self> {
         Op::immutable(|_: &Self, record| {
             let state = unsafe { &mut *record.state().get() };
@@ -321,20 +460,32 @@ where
 }
 
 // TODO(MrCroxx): use ordered hash map?
+/// Ghost queue that remembers the hashes of the recently evicted keys and their frequencies at eviction time.
+///
+/// Each push is tagged with a sequence number. The queue slot of a removed or re-pushed hash is stale, it is skipped
+/// when popped and doesn't count toward the weight.
 struct GhostQueue {
-    counts: HashSet<u64>,
-    queue: VecDeque<(u64, usize)>,
+    entries: HashMap<u64, GhostEntry>,
+    queue: VecDeque<(u64, u64)>,
     capacity: usize,
     weight: usize,
+    sequence: u64,
+}
+
+struct GhostEntry {
+    frequency: u8,
+    weight: usize,
+    sequence: u64,
 }
 
 impl GhostQueue {
     fn new(capacity: usize) -> Self {
         Self {
-            counts: HashSet::default(),
+            entries: HashMap::default(),
             queue: VecDeque::new(),
             capacity,
             weight: 0,
+            sequence: 0,
         }
     }
 
@@ -348,27 +499,60 @@ impl GhostQueue {
         }
     }
 
-    fn push(&mut self, hash: u64, weight: usize) {
+    fn push(&mut self, hash: u64, weight: usize, frequency: u8) {
         if self.capacity == 0 {
             return;
         }
+        self.remove(hash);
         while self.weight + weight > self.capacity && self.weight > 0 {
             self.pop();
         }
-        self.queue.push_back((hash, weight));
-        self.counts.insert(hash);
+        let sequence = self.sequence;
+        self.sequence += 1;
+        self.queue.push_back((hash, sequence));
+        self.entries.insert(
+            hash,
+            GhostEntry {
+                frequency,
+                weight,
+                sequence,
+            },
+        );
         self.weight += weight;
     }
 
     fn pop(&mut self) {
-        if let Some((hash, weight)) = self.queue.pop_front() {
-            self.weight -= weight;
-            self.counts.remove(&hash);
+        if let Some((hash, sequence)) = self.queue.pop_front() {
+            if self.entries.get(&hash).is_some_and(|entry| entry.sequence == sequence) {
+                let entry = self.entries.remove(&hash).unwrap();
+                self.weight -= entry.weight;
+            }
         }
     }
 
+    #[cfg(test)]
     fn contains(&self, hash: u64) -> bool {
-        self.counts.contains(&hash)
+        self.entries.contains_key(&hash)
+    }
+
+    /// Get the remembered frequency of the hash, or `None` if it is not in the ghost queue.
+    fn frequency(&self, hash: u64) -> Option<u8> {
+        self.entries.get(&hash).map(|entry| entry.frequency)
+    }
+
+    /// Remove the hash from the ghost queue, return the remembered frequency if it was in the ghost queue.
+    ///
+    /// The weight is released immediately, but the queue slot is released lazily. The stale slots are compacted once
+    /// they outnumber the live ones, so the queue length stays proportional to the remembered hashes.
+    fn remove(&mut self, hash: u64) -> Option<u8> {
+        let entry = self.entries.remove(&hash)?;
+        self.weight -= entry.weight;
+        if self.queue.len() >= 2 * self.entries.len() + 16 {
+            let entries = &self.entries;
+            self.queue
+                .retain(|(hash, sequence)| entries.get(hash).is_some_and(|entry| entry.sequence == *sequence));
+        }
+        Some(entry.frequency)
     }
 }
 
@@ -484,4 +668,170 @@ mod tests {
         s3fifo.clear();
         assert_ptr_vec_vec_eq(s3fifo.dump(), vec![vec![], vec![]]);
     }
+
+    #[test]
+    fn test_s3fifo_one_hit_wonders() {
+        let rs = (0..100)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+
+        // capacity: 8, small: 2
+        let config = S3FifoConfig {
+            small_queue_capacity_ratio: 0.25,
+            ghost_queue_capacity_ratio: 1.0,
+            small_to_main_freq_threshold: 1,
+        };
+        let mut s3fifo = TestS3Fifo::new(8, &config);
+        let mut usage = 0;
+        let mut victims = vec![];
+
+        // The reused entries are accessed again while in the small queue.
+        for r in rs.iter().take(4) {
+            victims.extend(s3fifo.push_evict(r.clone(), 8, usage));
+            usage += 1;
+            s3fifo.acquire_immutable(r);
+        }
+
+        // A scan of one-hit-wonders.
+        for r in rs.iter().skip(4) {
+            let evicted = s3fifo.push_evict(r.clone(), 8, usage);
+            usage = usage + 1 - evicted.len();
+            victims.extend(evicted);
+        }
+
+        // The one-hit-wonders are evicted from the small queue without ever entering the main queue, while the reused
+        // entries are retained.
+        assert_eq!(victims.len(), 100 - 8);
+        assert!(victims.iter().all(|r| r.hash() >= 4));
+        assert!(rs.iter().take(4).all(|r| r.is_in_eviction()));
+        assert_ptr_vec_vec_eq(s3fifo.dump(), vec![rs[96..100].to_vec(), rs[0..4].to_vec()]);
+
+        s3fifo.clear();
+    }
+
+    #[test]
+    fn test_s3fifo_on_admit() {
+        let rs = (0..4)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        let r = |i: usize| rs[i].clone();
+
+        // capacity: 4, small: 1, ghost: 4
+        let config = S3FifoConfig {
+            small_queue_capacity_ratio: 0.25,
+            ghost_queue_capacity_ratio: 1.0,
+            small_to_main_freq_threshold: 1,
+        };
+        let mut s3fifo = TestS3Fifo::new(4, &config);
+
+        s3fifo.push(r(0));
+        s3fifo.push(r(1));
+        assert_ptr_eq(&s3fifo.pop().unwrap(), &rs[0]);
+        assert!(s3fifo.ghost_queue.contains(0));
+
+        // The ghost entry is pruned on re-admission, and the re-admitted key still goes to `main`.
+        s3fifo.on_admit(&rs[0]);
+        assert!(!s3fifo.ghost_queue.contains(0));
+        s3fifo.push(r(0));
+        assert_ptr_vec_vec_eq(s3fifo.dump(), vec![vec![r(1)], vec![r(0)]]);
+
+        // Keys that are not in the ghost queue are not affected.
+        s3fifo.on_admit(&rs[2]);
+        s3fifo.push(r(2));
+        assert_ptr_vec_vec_eq(s3fifo.dump(), vec![vec![r(1), r(2)], vec![r(0)]]);
+
+        s3fifo.clear();
+    }
+
+    #[test]
+    fn test_s3fifo_ghost_frequency() {
+        let rs = (0..4)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec();
+        let r = |i: usize| rs[i].clone();
+
+        // capacity: 4, small: 1, ghost: 4
+        let config = S3FifoConfig {
+            small_queue_capacity_ratio: 0.25,
+            ghost_queue_capacity_ratio: 1.0,
+            small_to_main_freq_threshold: 3,
+        };
+        let mut s3fifo = TestS3Fifo::new(4, &config);
+
+        s3fifo.push(r(0));
+        s3fifo.push(r(1));
+        s3fifo.acquire_immutable(&rs[0]);
+        s3fifo.acquire_immutable(&rs[0]);
+        assert_frequencies(&rs, 0..1, 2);
+
+        // `r(0)` is evicted with frequency 2, which is below the threshold to be promoted to `main`.
+        assert_ptr_eq(&s3fifo.pop().unwrap(), &rs[0]);
+        assert_frequencies(&rs, 0..1, 0);
+        assert_eq!(s3fifo.ghost_queue.frequency(0), Some(2));
+
+        // The re-admitted key resumes with the remembered frequency, even if another record is pushed in between.
+        s3fifo.on_admit(&rs[0]);
+        assert!(!s3fifo.ghost_queue.contains(0));
+        s3fifo.push(r(2));
+        s3fifo.push(r(0));
+        assert_ptr_vec_vec_eq(s3fifo.dump(), vec![vec![r(1), r(2)], vec![r(0)]]);
+        assert_frequencies(&rs, 0..1, 2);
+        assert_frequencies(&rs, 2..3, 0);
+
+        s3fifo.clear();
+    }
+
+    #[test]
+    fn test_ghost_queue() {
+        let mut ghost = GhostQueue::new(4);
+        for hash in 0..4 {
+            ghost.push(hash, 1, hash as u8);
+        }
+        assert_eq!(ghost.weight, 4);
+
+        // The removed hash releases its weight, so no live hash is popped for the next push.
+        assert_eq!(ghost.remove(1), Some(1));
+        assert_eq!(ghost.weight, 3);
+        ghost.push(4, 1, 4);
+        assert!((0..5).filter(|hash| *hash != 1).all(|hash| ghost.contains(hash)));
+
+        // Popping the stale slot of a re-pushed hash doesn't forget the newer push.
+        ghost.push(0, 1, 10);
+        ghost.push(5, 1, 5);
+        assert_eq!(ghost.frequency(0), Some(10));
+        assert!(!ghost.contains(2));
+        assert_eq!(ghost.weight, 4);
+
+        // The stale slots are compacted.
+        for _ in 0..100 {
+            ghost.push(6, 1, 6);
+            ghost.remove(6);
+        }
+        assert!(ghost.queue.len() < 2 * ghost.entries.len() + 16);
+        assert_eq!(ghost.weight, ghost.entries.len());
+    }
 }
@@ -79,6 +79,7 @@ where
     queue: LinkedList<Adapter<K, V, P>>,
     /// Hand pointer for eviction scanning, points to the next candidate to examine
     hand: Option<Arc<Record<Sieve<K, V, P>>>>,
+    capacity: usize,
 }
 
 impl<K, V, P> Eviction for Sieve<K, V, P>
@@ -93,48 +94,66 @@ where
     type Properties = P;
     type State = SieveState;
 
-    fn new(_capacity: usize, _config: &Self::Config) -> Self
+    fn new(capacity: usize, _config: &Self::Config) -> Self
     where
         Self: Sized,
     {
         Self {
             queue: LinkedList::new(Adapter::new()),
             hand: None,
+            capacity,
         }
     }
 
-    fn update(&mut self, _: usize, _: Option<&Self::Config>) -> Result<()> {
+    fn update(&mut self, capacity: usize, _: Option<&Self::Config>) -> Result<()> {
+        self.capacity = capacity;
         Ok(())
     }
 
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
     fn push(&mut self, record: Arc<Record<Self>>) {
         record.set_in_eviction(true);
         self.queue.push_back(record);
     }
 
     fn pop(&mut self) -> Option<Arc<Record<Self>>> {
+        self.pop_with(|_| false)
+    }
+
+    fn pop_with(&mut self, skip: impl Fn(&Record<Self>) -> bool) -> Option<Arc<Record<Self>>> {
         let mut candidate = if let Some(ref hand_ptr) = self.hand {
             unsafe { self.queue.cursor_mut_from_ptr(Arc::as_ptr(hand_ptr)) }
         } else {
             self.queue.front_mut()
         };
 
+        // Queue is empty, no record to evict
+        let start = candidate.get()? as *const Record<Self>;
+        // The visited flags of the records that are not passed over are all cleared in the first lap, so there is no
+        // record to evict if the second lap finds nothing.
+        let mut laps = 0;
         loop {
-            if let Some(record) = candidate.get() {
+            let record = candidate.get().unwrap();
+            if !skip(record) {
                 let state = unsafe { &*record.state().get() };
                 if !state.is_visited() {
                     break;
-                } else {
-                    state.set_visited(false);
-                    if candidate.peek_next().is_null() {
-                        candidate = self.queue.front_mut();
-                    } else {
-                        candidate.move_next();
-                    }
                 }
+                state.set_visited(false);
+            }
+            if candidate.peek_next().is_null() {
+                candidate = self.queue.front_mut();
             } else {
-                // Queue is empty, no record to evict
-                return None;
+                candidate.move_next();
+            }
+            if std::ptr::eq(candidate.get().unwrap(), start) {
+                laps += 1;
+                if laps == 2 {
+                    return None;
+                }
             }
         }
 
@@ -142,18 +161,70 @@ where
         candidate.remove().inspect(|record| record.set_in_eviction(false))
     }
 
-    fn remove(&mut self, record: &Arc<Record<Self>>) {
-        if let Some(ref hand_ptr) = self.hand {
-            if Arc::ptr_eq(hand_ptr, record) {
-                // Reset hand if we are removing the current hand pointer
-                self.hand = None;
+    fn peek_n(&self, n: usize) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+
+        // Simulate the hand without clearing the visited flags.
+        //
+        // The first round from the hand picks the unvisited records. The visited records are cleared during the first
+        // round, so they are picked in the same order in the second round.
+        let mut cursor = match self.hand {
+            Some(ref hand) => unsafe { self.queue.cursor_from_ptr(Arc::as_ptr(hand)) },
+            None => self.queue.front(),
+        };
+        let Some(start) = cursor.clone_pointer() else {
+            return res;
+        };
+        let mut visited = vec![];
+        loop {
+            let record = cursor.clone_pointer().unwrap();
+            let state = unsafe { &*record.state().get() };
+            if state.is_visited() {
+                visited.push(record);
+            } else {
+                res.push(record);
+                if res.len() >= n {
+                    return res;
+                }
+            }
+            cursor.move_next();
+            if cursor.is_null() {
+                cursor = self.queue.front();
             }
+            if cursor
+                .get()
+                .is_some_and(|record| std::ptr::eq(record, Arc::as_ptr(&start)))
+            {
+                break;
+            }
+        }
+        let rest = n - res.len();
+        res.extend(visited.into_iter().take(rest));
+        res
+    }
+
+    fn remove(&mut self, record: &Arc<Record<Self>>) {
+        let mut cursor = unsafe { self.queue.cursor_mut_from_ptr(Arc::as_ptr(record)) };
+        if self.hand.as_ref().is_some_and(|hand| Arc::ptr_eq(hand, record)) {
+            // Move the hand to the next candidate if we are removing the current hand pointer, `None` wraps around to
+            // the front.
+            self.hand = cursor.peek_next().clone_pointer();
         }
 
-        unsafe { self.queue.remove_from_ptr(Arc::as_ptr(record)) };
+        cursor.remove();
         record.set_in_eviction(false);
     }
 
+    fn dump_order(&self) -> Vec<Arc<Record<Self>>> {
+        let mut res = vec![];
+        let mut cursor = self.queue.front();
+        while let Some(record) = cursor.clone_pointer() {
+            res.push(record);
+            cursor.move_next();
+        }
+        res
+    }
+
     fn acquire() -> Op<Self> {
         Op::immutable(|_: &Self, record| {
             let state = unsafe { &*record.state().get() };
@@ -252,4 +323,78 @@ pub mod tests {
         sieve.clear();
         assert_ptr_vec_eq(sieve.dump(), vec![]);
     }
+
+    fn records(n: u64) -> Vec<Arc<Record<TestSieve>>> {
+        (0..n)
+            .map(|i| {
+                Arc::new(Record::new(Data {
+                    key: i,
+                    value: i,
+                    properties: TestProperties::default(),
+                    hash: i,
+                    weight: 1,
+                }))
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_sieve_hand_wrap_around() {
+        let rs = records(5);
+        let r = |i: usize| rs[i].clone();
+        let mut sieve = TestSieve::new(100, &SieveConfig {});
+
+        for i in 0..4 {
+            sieve.push(r(i));
+        }
+        sieve.acquire_immutable(&r(2));
+        sieve.acquire_immutable(&r(3));
+
+        assert_ptr_eq(&rs[0], &sieve.pop().unwrap());
+        assert_ptr_eq(&rs[1], &sieve.pop().unwrap());
+
+        // The hand clears 2 and 3, then wraps around to the front and evicts 2.
+        assert_ptr_eq(&rs[2], &sieve.pop().unwrap());
+        assert_ptr_eq(sieve.hand.as_ref().unwrap(), &rs[3]);
+
+        // The hand survives the push, clears 3 and evicts the new tail 4.
+        sieve.push(r(4));
+        sieve.acquire_immutable(&r(3));
+        assert_ptr_eq(&rs[4], &sieve.pop().unwrap());
+        assert!(sieve.hand.is_none());
+
+        // The hand wraps around to the front.
+        assert_ptr_eq(&rs[3], &sieve.pop().unwrap());
+        assert!(sieve.pop().is_none());
+    }
+
+    #[test]
+    fn test_sieve_remove_hand() {
+        let rs = records(5);
+        let r = |i: usize| rs[i].clone();
+        let mut sieve = TestSieve::new(100, &SieveConfig {});
+
+        for i in 0..5 {
+            sieve.push(r(i));
+        }
+        sieve.acquire_immutable(&r(0));
+
+        // The hand clears 0 and evicts 1, then points to 2.
+        assert_ptr_eq(&rs[1], &sieve.pop().unwrap());
+        assert_ptr_eq(sieve.hand.as_ref().unwrap(), &rs[2]);
+
+        // Removing the hand target moves the hand to the next record instead of restarting from the front.
+        sieve.remove(&r(2));
+        assert!(!r(2).is_in_eviction());
+        assert_ptr_eq(sieve.hand.as_ref().unwrap(), &rs[3]);
+        assert_ptr_eq(&rs[3], &sieve.pop().unwrap());
+        assert_ptr_eq(sieve.hand.as_ref().unwrap(), &rs[4]);
+
+        // Removing the hand target at the tail wraps the hand around to the front.
+        sieve.remove(&r(4));
+        assert!(sieve.hand.is_none());
+        assert_ptr_vec_eq(sieve.dump(), vec![r(0)]);
+        assert_ptr_eq(&rs[0], &sieve.pop().unwrap());
+        assert!(sieve.pop().is_none());
+    }
 }
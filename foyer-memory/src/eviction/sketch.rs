@@ -0,0 +1,339 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU16, Ordering};
+
+use cmsketch::CMSketchU16;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Magic byte that leads a frequency snapshot.
+const SNAPSHOT_MAGIC: u8 = 0xF5;
+/// Version of the frequency snapshot format, bumped on any format change.
+const SNAPSHOT_VERSION: u8 = 1;
+/// Size of the header of a frequency snapshot: magic, version and the count of the items.
+const SNAPSHOT_HEADER_SIZE: usize = 1 + 1 + 8;
+/// Size of an item of a frequency snapshot: hash and frequency.
+const SNAPSHOT_ITEM_SIZE: usize = 8 + 2;
+
+/// Encode the estimated frequencies of the hashes into a frequency snapshot.
+///
+/// Format: `magic (u8) | version (u8) | count (u64) | (hash (u64) | frequency (u16)) * count`, little endian.
+///
+/// The snapshot is keyed by hash instead of holding the raw counters, so it can be restored into a sketch of any
+/// width and depth, or into a cache with a different count of shards.
+pub fn encode_frequencies(frequencies: &[(u64, u16)]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(SNAPSHOT_HEADER_SIZE + frequencies.len() * SNAPSHOT_ITEM_SIZE);
+    buf.push(SNAPSHOT_MAGIC);
+    buf.push(SNAPSHOT_VERSION);
+    buf.extend_from_slice(&(frequencies.len() as u64).to_le_bytes());
+    for (hash, frequency) in frequencies {
+        buf.extend_from_slice(&hash.to_le_bytes());
+        buf.extend_from_slice(&frequency.to_le_bytes());
+    }
+    buf
+}
+
+/// Decode a frequency snapshot encoded by [`encode_frequencies`].
+pub fn decode_frequencies(buf: &[u8]) -> Result<Vec<(u64, u16)>> {
+    let invalid = |msg: String| Err(Error::InvalidSnapshot(msg));
+
+    if buf.len() < SNAPSHOT_HEADER_SIZE {
+        return invalid(format!("snapshot too short, size: {}", buf.len()));
+    }
+    if buf[0] != SNAPSHOT_MAGIC {
+        return invalid(format!(
+            "magic mismatch, expected: {SNAPSHOT_MAGIC:#x}, given: {:#x}",
+            buf[0]
+        ));
+    }
+    if buf[1] != SNAPSHOT_VERSION {
+        return invalid(format!(
+            "unsupported version, expected: {SNAPSHOT_VERSION}, given: {}",
+            buf[1]
+        ));
+    }
+    let count = u64::from_le_bytes(buf[2..SNAPSHOT_HEADER_SIZE].try_into().unwrap()) as usize;
+    let items = &buf[SNAPSHOT_HEADER_SIZE..];
+    if Some(items.len()) != count.checked_mul(SNAPSHOT_ITEM_SIZE) {
+        return invalid(format!("size mismatch, count: {count}, size: {}", buf.len()));
+    }
+
+    let frequencies = items
+        .chunks_exact(SNAPSHOT_ITEM_SIZE)
+        .map(|item| {
+            let hash = u64::from_le_bytes(item[..8].try_into().unwrap());
+            let frequency = u16::from_le_bytes(item[8..].try_into().unwrap());
+            (hash, frequency)
+        })
+        .collect();
+    Ok(frequencies)
+}
+
+/// Backend of the frequency sketch of the LFU eviction algorithm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrequencyBackend {
+    /// Count-min sketch with plain counters, which requires exclusive access to update.
+    #[default]
+    CMSketch,
+    /// Count-min sketch with atomic counters, which can be updated from multiple threads without a lock.
+    ///
+    /// See [`ConcurrentCMSketch`].
+    Concurrent,
+}
+
+/// Lock-free count-min sketch with atomic 16-bit counters.
+///
+/// All operations take `&self`, so the sketch can be shared and updated by multiple threads. The counters saturate at
+/// [`u16::MAX`] instead of wrapping around. The estimation of a hash never decreases unless the sketch is halved or
+/// cleared.
+#[derive(Debug)]
+pub struct ConcurrentCMSketch {
+    width: usize,
+    depth: usize,
+    table: Box<[AtomicU16]>,
+}
+
+impl ConcurrentCMSketch {
+    /// Create a count-min sketch with the given error and confidence.
+    ///
+    /// The estimation exceeds the real count by at most `eps * total` with the probability of `confidence`.
+    pub fn new(eps: f64, confidence: f64) -> Self {
+        assert!(eps > 0.0, "eps must be positive, given: {eps}");
+        assert!(
+            confidence > 0.0 && confidence < 1.0,
+            "confidence must be in (0, 1), given: {confidence}"
+        );
+
+        let width = (2.0 / eps).ceil() as usize;
+        let depth = (-(1.0 - confidence).log2()).ceil().max(1.0) as usize;
+        let table = (0..width * depth).map(|_| AtomicU16::new(0)).collect();
+        Self { width, depth, table }
+    }
+
+    fn index(&self, hash: u64, row: usize) -> usize {
+        let h = (hash ^ (row as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        row * self.width + (h >> 32) as usize % self.width
+    }
+
+    /// Increase the counters of the hash by `1`.
+    pub fn inc(&self, hash: u64) {
+        self.inc_by(hash, 1);
+    }
+
+    /// Increase the counters of the hash by `count`.
+    pub fn inc_by(&self, hash: u64, count: u16) {
+        for row in 0..self.depth {
+            let _ = self.table[self.index(hash, row)]
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(v.saturating_add(count)));
+        }
+    }
+
+    /// Estimate the count of the hash.
+    pub fn estimate(&self, hash: u64) -> u16 {
+        (0..self.depth)
+            .map(|row| self.table[self.index(hash, row)].load(Ordering::Relaxed))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Halve all counters.
+    ///
+    /// Each counter is halved atomically, but the counters are not halved as a whole, so a concurrent estimation may
+    /// observe a partially halved sketch.
+    pub fn halve(&self) {
+        for counter in self.table.iter() {
+            let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(v >> 1));
+        }
+    }
+
+    /// Reset all counters to `0`.
+    pub fn clear(&self) {
+        for counter in self.table.iter() {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Get the count of the counters of each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the count of the rows.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// Frequency sketch with the selected backend.
+#[derive(Debug)]
+pub enum Frequencies {
+    CMSketch(CMSketchU16),
+    Concurrent(ConcurrentCMSketch),
+}
+
+impl Frequencies {
+    pub fn new(backend: FrequencyBackend, eps: f64, confidence: f64) -> Self {
+        match backend {
+            FrequencyBackend::CMSketch => Self::CMSketch(CMSketchU16::new(eps, confidence)),
+            FrequencyBackend::Concurrent => Self::Concurrent(ConcurrentCMSketch::new(eps, confidence)),
+        }
+    }
+
+    pub fn inc(&mut self, hash: u64) {
+        match self {
+            Self::CMSketch(sketch) => sketch.inc(hash),
+            Self::Concurrent(sketch) => sketch.inc(hash),
+        }
+    }
+
+    pub fn inc_by(&mut self, hash: u64, count: u16) {
+        match self {
+            Self::CMSketch(sketch) => sketch.inc_by(hash, count),
+            Self::Concurrent(sketch) => sketch.inc_by(hash, count),
+        }
+    }
+
+    pub fn estimate(&self, hash: u64) -> u16 {
+        match self {
+            Self::CMSketch(sketch) => sketch.estimate(hash),
+            Self::Concurrent(sketch) => sketch.estimate(hash),
+        }
+    }
+
+    pub fn halve(&mut self) {
+        match self {
+            Self::CMSketch(sketch) => sketch.halve(),
+            Self::Concurrent(sketch) => sketch.halve(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::CMSketch(sketch) => sketch.clear(),
+            Self::Concurrent(sketch) => sketch.clear(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Self::CMSketch(sketch) => sketch.width(),
+            Self::Concurrent(sketch) => sketch.width(),
+        }
+    }
+
+    /// Raise the estimated frequency of the hash to at least the given frequency.
+    ///
+    /// Hash collisions may have raised the estimation already, so only the difference is added.
+    pub fn raise(&mut self, hash: u64, frequency: u16) {
+        let estimated = self.estimate(hash);
+        if frequency > estimated {
+            self.inc_by(hash, frequency - estimated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_frequency_snapshot() {
+        let frequencies = vec![(1, 3), (u64::MAX, u16::MAX), (42, 0)];
+        let buf = encode_frequencies(&frequencies);
+        assert_eq!(decode_frequencies(&buf).unwrap(), frequencies);
+        assert!(decode_frequencies(&encode_frequencies(&[])).unwrap().is_empty());
+
+        let assert_invalid = |buf: &[u8]| {
+            assert!(matches!(decode_frequencies(buf), Err(Error::InvalidSnapshot(_))));
+        };
+        // Truncated.
+        assert_invalid(&buf[..4]);
+        assert_invalid(&buf[..buf.len() - 1]);
+        // Magic mismatch.
+        let mut corrupted = buf.clone();
+        corrupted[0] = !SNAPSHOT_MAGIC;
+        assert_invalid(&corrupted);
+        // Unsupported version.
+        let mut corrupted = buf.clone();
+        corrupted[1] = SNAPSHOT_VERSION + 1;
+        assert_invalid(&corrupted);
+    }
+
+    #[test]
+    fn test_concurrent_cmsketch() {
+        let sketch = ConcurrentCMSketch::new(0.01, 0.9);
+        for hash in 0..100 {
+            for _ in 0..hash {
+                sketch.inc(hash);
+            }
+        }
+        for hash in 0..100 {
+            // A count-min sketch never underestimates.
+            assert!(sketch.estimate(hash) >= hash as u16);
+        }
+
+        sketch.inc_by(1000, u16::MAX);
+        sketch.inc(1000);
+        assert_eq!(sketch.estimate(1000), u16::MAX);
+
+        sketch.halve();
+        assert_eq!(sketch.estimate(1000), u16::MAX >> 1);
+
+        sketch.clear();
+        assert!((0..100).chain([1000]).all(|hash| sketch.estimate(hash) == 0));
+    }
+
+    #[test]
+    fn test_concurrent_cmsketch_concurrency() {
+        const THREADS: usize = 8;
+        const LOOPS: usize = 10000;
+        const HASHES: u64 = 16;
+
+        let sketch = Arc::new(ConcurrentCMSketch::new(0.01, 0.9));
+
+        let handles = (0..THREADS)
+            .map(|t| {
+                let sketch = sketch.clone();
+                std::thread::spawn(move || {
+                    let mut last = [0u16; HASHES as usize];
+                    for i in 0..LOOPS {
+                        let hash = (i as u64 + t as u64) % HASHES;
+                        sketch.inc(hash);
+                        let estimate = sketch.estimate(hash);
+                        assert!(
+                            estimate > last[hash as usize],
+                            "estimate must increase after inc: {} -> {}",
+                            last[hash as usize],
+                            estimate
+                        );
+                        last[hash as usize] = estimate;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // No increment is lost.
+        let per_hash = (THREADS * LOOPS) as u64 / HASHES;
+        for hash in 0..HASHES {
+            assert!(sketch.estimate(hash) as u64 >= per_hash);
+        }
+    }
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use foyer_common::properties::{Hint, Location, Properties, Source};
 use itertools::Itertools;
@@ -86,6 +86,8 @@ pub struct TestProperties {
     hint: Hint,
     location: Location,
     source: Source,
+    cost: u32,
+    ttl: Option<Duration>,
 }
 
 impl Properties for TestProperties {
@@ -133,4 +135,22 @@ impl Properties for TestProperties {
     fn source(&self) -> Option<Source> {
         None
     }
+
+    fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    fn cost(&self) -> Option<u32> {
+        Some(self.cost)
+    }
+
+    fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
 }
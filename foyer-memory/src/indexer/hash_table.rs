@@ -85,4 +85,20 @@ where
     fn drain(&mut self) -> impl Iterator<Item = Arc<Record<Self::Eviction>>> {
         self.table.drain()
     }
+
+    fn iter(&self) -> impl Iterator<Item = &Arc<Record<Self::Eviction>>> {
+        self.table.iter()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.table.reserve(additional, |r| r.hash())
+    }
+
+    fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.table.len()
+    }
 }
@@ -29,6 +29,23 @@ pub trait Indexer: Send + Sync + 'static + Default {
     where
         Q: Hash + Equivalent<<Self::Eviction as Eviction>::Key> + ?Sized;
     fn drain(&mut self) -> impl Iterator<Item = Arc<Record<Self::Eviction>>>;
+    /// Iterate over the records in the indexer in arbitrary order.
+    fn iter(&self) -> impl Iterator<Item = &Arc<Record<Self::Eviction>>>;
+    /// Reserve capacity for at least `additional` more records without reallocation.
+    fn reserve(&mut self, additional: usize);
+    /// Count of the records the indexer can hold without reallocation.
+    fn capacity(&self) -> usize;
+    /// Count of the records in the indexer.
+    ///
+    /// The default implementation counts the records with [`Indexer::iter`], which is O(n). The indexer that tracks
+    /// the count should override it.
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+    /// Check if the indexer holds no record.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub mod hash_table;
@@ -77,4 +77,20 @@ where
             r.set_in_indexer(false)
         })
     }
+
+    fn iter(&self) -> impl Iterator<Item = &Arc<Record<Self::Eviction>>> {
+        self.indexer.iter()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.indexer.reserve(additional)
+    }
+
+    fn capacity(&self) -> usize {
+        self.indexer.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.indexer.len()
+    }
 }
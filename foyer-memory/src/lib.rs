@@ -42,6 +42,8 @@ mod indexer;
 mod pipe;
 mod raw;
 mod record;
+mod simulator;
+mod stats;
 
 mod prelude;
 pub use prelude::*;
@@ -15,9 +15,28 @@
 #[cfg(any(test, feature = "test_utils"))]
 pub use crate::eviction::test_utils::TestProperties;
 pub use crate::{
-    cache::{Cache, CacheBuilder, CacheEntry, CacheProperties, EvictionConfig, Fetch},
+    cache::{
+        Cache, CacheBuilder, CacheEntry, CacheProperties, CacheView, Capacity, EvictionCandidates, EvictionConfig,
+        Fetch,
+    },
     error::{Error, Result},
-    eviction::{fifo::FifoConfig, lfu::LfuConfig, lru::LruConfig, s3fifo::S3FifoConfig, Eviction, Op},
+    eviction::{
+        decay_lfu::DecayLfuConfig,
+        fifo::FifoConfig,
+        lfu::LfuConfig,
+        logged::{EvictReason, EvictRecord, Logged, LoggedConfig},
+        lru::LruConfig,
+        s3fifo::S3FifoConfig,
+        sketch::{ConcurrentCMSketch, FrequencyBackend},
+        Eviction, Op, QueueTag,
+    },
     pipe::{Piece, Pipe},
-    raw::{FetchContext, FetchState, FetchTarget, Filter, Weighter},
+    raw::{FetchContext, FetchState, FetchTarget, Fetcher, Filter, Lookup, Weighter},
+    simulator::{simulate, SimulationStats, Simulator},
+    stats::CacheStats,
+};
+#[cfg(feature = "custom-eviction")]
+pub use crate::{
+    raw::{RawCache, RawCacheConfig, RawCacheEntry},
+    record::{Data, Record, StateOwner},
 };
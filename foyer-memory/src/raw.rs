@@ -13,33 +13,46 @@
 // limitations under the License.
 
 use std::{
-    collections::hash_map::{Entry as HashMapEntry, HashMap},
+    cell::{Cell, RefCell},
+    collections::{
+        hash_map::{Entry as HashMapEntry, HashMap},
+        BinaryHeap, VecDeque,
+    },
     fmt::Debug,
     future::Future,
     hash::Hash,
+    io::{Read, Write},
     ops::Deref,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use equivalent::Equivalent;
 #[cfg(feature = "tracing")]
 use fastrace::{
     future::{FutureExt, InSpan},
+    local::LocalSpan,
     Span,
 };
 use foyer_common::{
-    code::HashBuilder,
+    clock::{Clock, SystemClock},
+    code::{Code, CodeError, HashBuilder, Key, Value},
     event::{Event, EventListener},
     future::{Diversion, DiversionFuture},
+    hasher::hash_with_domain,
     metrics::Metrics,
-    properties::{Location, Properties, Source},
+    properties::{Hint, Location, Properties, Source},
     runtime::SingletonHandle,
     strict_assert,
     utils::scope::Scope,
 };
+use hashbrown::hash_table::{Entry as HashTableEntry, HashTable};
 use itertools::Itertools;
 use parking_lot::{Mutex, RwLock};
 use pin_project::pin_project;
@@ -47,10 +60,15 @@ use tokio::{sync::oneshot, task::JoinHandle};
 
 use crate::{
     error::{Error, Result},
-    eviction::{Eviction, Op},
+    eviction::{
+        lfu::Lfu,
+        sketch::{decode_frequencies, encode_frequencies},
+        Eviction, Op, QueueTag,
+    },
     indexer::{hash_table::HashTableIndexer, sentry::Sentry, Indexer},
     pipe::NoopPipe,
     record::{Data, Record},
+    stats::{CacheStats, ShardCounters},
     Piece, Pipe,
 };
 
@@ -73,21 +91,356 @@ impl<K, V, T> Weighter<K, V> for T where T: Fn(&K, &V) -> usize + Send + Sync +
 pub trait Filter<K, V>: Fn(&K, &V) -> bool + Send + Sync + 'static {}
 impl<K, V, T> Filter<K, V> for T where T: Fn(&K, &V) -> bool + Send + Sync + 'static {}
 
+type DeferredSettlement = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Whether the current thread is calling the event listener.
+    static IN_EVENT_LISTENER: Cell<bool> = const { Cell::new(false) };
+    /// Callbacks of the garbages made by the event listener or the pipe, processed after the outermost callback returns.
+    static DEFERRED_SETTLEMENTS: RefCell<VecDeque<DeferredSettlement>> = const { RefCell::new(VecDeque::new()) };
+    /// Whether the current thread is processing the deferred settlements.
+    static PROCESSING_DEFERRED_SETTLEMENTS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores the reentrancy flag even if the event listener panics.
+struct EventListenerScope {
+    prev: bool,
+}
+
+impl EventListenerScope {
+    fn enter() -> Self {
+        Self {
+            prev: IN_EVENT_LISTENER.replace(true),
+        }
+    }
+}
+
+impl Drop for EventListenerScope {
+    fn drop(&mut self) {
+        IN_EVENT_LISTENER.set(self.prev);
+        if !self.prev {
+            process_deferred_settlements();
+        }
+    }
+}
+
+/// Restores the processing flag even if a deferred settlement panics.
+struct ProcessingScope;
+
+impl Drop for ProcessingScope {
+    fn drop(&mut self) {
+        PROCESSING_DEFERRED_SETTLEMENTS.set(false);
+    }
+}
+
+/// Process the deferred settlements in order, including the ones deferred while processing.
+///
+/// Nested calls return immediately, so a settlement deferred by the callbacks of a deferred settlement is processed by
+/// the outermost loop instead of recursively.
+fn process_deferred_settlements() {
+    if std::thread::panicking() {
+        DEFERRED_SETTLEMENTS.with_borrow_mut(|settlements| settlements.clear());
+        return;
+    }
+    if PROCESSING_DEFERRED_SETTLEMENTS.replace(true) {
+        return;
+    }
+    let _scope = ProcessingScope;
+    while let Some(settle) = DEFERRED_SETTLEMENTS.with_borrow_mut(|settlements| settlements.pop_front()) {
+        settle();
+    }
+}
+
+/// Call the event listener with the reentrancy guard held.
+fn on_leave<K: Key, V: Value>(listener: &dyn EventListener<Key = K, Value = V>, event: Event, key: &K, value: &V) {
+    let _scope = EventListenerScope::enter();
+    listener.on_leave(event, key, value);
+}
+
+/// Send the piece to the pipe with the reentrancy guard held.
+fn spill<K: Key, V: Value, P: Properties>(pipe: &dyn Pipe<Key = K, Value = V, Properties = P>, piece: Piece<K, V, P>) {
+    let _scope = EventListenerScope::enter();
+    pipe.send(piece);
+}
+
+/// Detect cache-wide operations called from within the event listener or the pipe.
+///
+/// The callbacks are called while an operation is being finalized, resizing, flushing or draining the cache in the
+/// callbacks may lead to a deadlock or a double mutation. Only checked with debug assertions enabled.
+///
+/// Operations by key, e.g. insertions and removals, and [`RawCache::clear`] are allowed. They are made in place and
+/// the callbacks of the entries they evict, replace or remove are deferred until the outermost callback returns.
+fn assert_not_in_event_listener() {
+    debug_assert!(
+        !IN_EVENT_LISTENER.get(),
+        "modifying the in-memory cache from within the event listener is not allowed"
+    );
+}
+
+/// Config of the [`RawCache`].
 pub struct RawCacheConfig<E, S>
 where
     E: Eviction,
     S: HashBuilder,
 {
+    /// Total capacity of the cache, measured by the weighter.
     pub capacity: usize,
+    /// Count of the shards, each shard is guarded by its own lock.
     pub shards: usize,
+    /// Optional per-shard capacities. If set, it overrides the equal split of `capacity` among `shards`.
+    pub shard_capacities: Option<Vec<usize>>,
+    /// Enable the lock-free single-entry hot cache of each shard. Ignored if the eviction algorithm observes the
+    /// accesses.
+    pub hot_entry: bool,
+    /// Config of the eviction algorithm.
     pub eviction_config: E::Config,
+    /// Hash builder of the keys.
     pub hash_builder: S,
+    /// Hash domain mixed into the hash of every key, see [`hash_with_domain`].
+    pub hash_domain: u64,
+    /// Weighter that calculates the weight of an entry.
     pub weighter: Arc<dyn Weighter<E::Key, E::Value>>,
+    /// Measure the capacity with the count of the entries, so every entry weighs `1` regardless of the weighter or
+    /// the explicitly given weight.
+    pub count_entries: bool,
+    /// Filter that decides whether an entry is admitted.
     pub filter: Arc<dyn Filter<E::Key, E::Value>>,
+    /// Entries that weigh more than it are treated as uncacheable, like the entries rejected by the filter.
+    pub max_admit_weight: Option<usize>,
+    /// Defer the records that are still referenced by the cache entries when evicting, and only evict them as the
+    /// last resort.
+    pub skip_referenced: bool,
+    /// Keep the keys that the fetcher of `get_or_fetch` doesn't find as tombstones for the given duration, so the
+    /// following lookups of the keys are answered as known absent without fetching again. `None` disables it.
+    pub negative_ttl: Option<Duration>,
+    /// Optional listener of the entry events.
     pub event_listener: Option<Arc<dyn EventListener<Key = E::Key, Value = E::Value>>>,
+    /// Metrics of the cache.
     pub metrics: Arc<Metrics>,
 }
 
+/// A single-entry cache of the most recently accessed record of a shard, which can be read without the shard lock.
+///
+/// The hot entry is only updated with the shard read lock held, and invalidated with the shard write lock held when the
+/// record is removed from the indexer.
+struct HotEntry<E>
+where
+    E: Eviction,
+{
+    record: ArcSwapOption<Record<E>>,
+}
+
+impl<E> Default for HotEntry<E>
+where
+    E: Eviction,
+{
+    fn default() -> Self {
+        Self {
+            record: ArcSwapOption::empty(),
+        }
+    }
+}
+
+impl<E> HotEntry<E>
+where
+    E: Eviction,
+{
+    /// Get the hot record and increase its reference count if it matches the key.
+    fn get<Q>(&self, hash: u64, key: &Q) -> Option<Arc<Record<E>>>
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let guard = self.record.load();
+        let record = guard.as_ref()?;
+        if record.hash() != hash || !key.equivalent(record.key()) {
+            return None;
+        }
+        record.inc_refs(1);
+        Some(record.clone())
+    }
+
+    fn update(&self, record: &Arc<Record<E>>) {
+        if !self.is(record) {
+            self.record.store(Some(record.clone()));
+        }
+    }
+
+    fn invalidate(&self, record: &Arc<Record<E>>) {
+        if self.is(record) {
+            self.record.store(None);
+        }
+    }
+
+    fn is(&self, record: &Arc<Record<E>>) -> bool {
+        self.record.load().as_ref().is_some_and(|hot| Arc::ptr_eq(hot, record))
+    }
+}
+
+/// Tombstones of the keys known to be absent in the outer storage, for the negative cache.
+///
+/// The tombstones hold no value and don't count towards the usage. All tombstones share the same ttl, so they expire in
+/// the insertion order, and the expired ones are pruned from the front on insertion.
+///
+/// The count of the tombstones is bounded by the capacity of the shard, so a scan of missing keys cannot grow the memory
+/// without bound. The oldest tombstone is dropped first when the bound is reached.
+struct Tombstones<K> {
+    /// Hash, key and deadline of the tombstones.
+    table: HashTable<(u64, K, Instant)>,
+    /// Hash and deadline of the tombstones in the insertion order, may contain stale items of refreshed or removed
+    /// tombstones.
+    deadlines: VecDeque<(u64, Instant)>,
+    /// Max count of the tombstones.
+    capacity: usize,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K> Tombstones<K>
+where
+    K: Key,
+{
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            table: HashTable::new(),
+            deadlines: VecDeque::new(),
+            capacity,
+            ttl,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.table.len() > capacity {
+            self.pop();
+        }
+    }
+
+    fn insert(&mut self, hash: u64, key: K) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let now = self.clock.now();
+        self.prune(now);
+
+        let deadline = now + self.ttl;
+        match self
+            .table
+            .entry(hash, |(h, k, _)| *h == hash && *k == key, |(h, _, _)| *h)
+        {
+            HashTableEntry::Occupied(mut o) => o.get_mut().2 = deadline,
+            HashTableEntry::Vacant(v) => {
+                v.insert((hash, key, deadline));
+            }
+        }
+        self.deadlines.push_back((hash, deadline));
+
+        while self.table.len() > self.capacity {
+            self.pop();
+        }
+        // Compact the stale items of the refreshed or removed tombstones.
+        if self.deadlines.len() >= self.table.len() * 2 + 16 {
+            let table = &self.table;
+            self.deadlines
+                .retain(|&(hash, deadline)| table.find(hash, |(h, _, d)| *h == hash && *d == deadline).is_some());
+        }
+    }
+
+    /// Drop the oldest tombstone.
+    fn pop(&mut self) {
+        while let Some((hash, deadline)) = self.deadlines.pop_front() {
+            if let Ok(o) = self.table.find_entry(hash, |(h, _, d)| *h == hash && *d == deadline) {
+                o.remove();
+                return;
+            }
+        }
+    }
+
+    fn contains<Q>(&self, hash: u64, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let now = self.clock.now();
+        self.table
+            .find(hash, |(h, k, _)| *h == hash && key.equivalent(k))
+            .is_some_and(|(_, _, deadline)| *deadline > now)
+    }
+
+    fn remove<Q>(&mut self, hash: u64, key: &Q)
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        if let Ok(o) = self.table.find_entry(hash, |(h, k, _)| *h == hash && key.equivalent(k)) {
+            o.remove();
+        }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some(&(hash, deadline)) = self.deadlines.front() {
+            if deadline > now {
+                break;
+            }
+            self.deadlines.pop_front();
+            // Skip the stale item if the tombstone is refreshed or removed.
+            if let Ok(o) = self.table.find_entry(hash, |(h, _, d)| *h == hash && *d == deadline) {
+                o.remove();
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.table.clear();
+        self.deadlines.clear();
+    }
+}
+
+/// A record with ttl in the expiration queue of the shard, the one with the earliest deadline is popped first.
+///
+/// The record is held weakly, so the queue doesn't keep the removed records alive. The items of the removed or replaced
+/// records are skipped when popped.
+struct Expiration<E>
+where
+    E: Eviction,
+{
+    expire_at: Instant,
+    record: Weak<Record<E>>,
+}
+
+impl<E> PartialEq for Expiration<E>
+where
+    E: Eviction,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.expire_at == other.expire_at
+    }
+}
+
+impl<E> Eq for Expiration<E> where E: Eviction {}
+
+impl<E> PartialOrd for Expiration<E>
+where
+    E: Eviction,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E> Ord for Expiration<E>
+where
+    E: Eviction,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Reversed, so the max-heap pops the earliest deadline first.
+        other.expire_at.cmp(&self.expire_at)
+    }
+}
+
 struct RawCacheShard<E, S, I>
 where
     E: Eviction,
@@ -99,6 +452,18 @@ where
 
     usage: usize,
     capacity: usize,
+    skip_referenced: bool,
+
+    /// Tombstones of the negative cache, `None` if disabled.
+    tombstones: Option<Tombstones<E::Key>>,
+
+    hot_entry: Option<Arc<HotEntry<E>>>,
+
+    counters: Arc<ShardCounters>,
+
+    /// Records with ttl in the order of their deadlines.
+    expirations: BinaryHeap<Expiration<E>>,
+    clock: Arc<dyn Clock>,
 
     #[expect(clippy::type_complexity)]
     waiters: Mutex<HashMap<E::Key, Vec<oneshot::Sender<RawCacheEntry<E, S, I>>>>>,
@@ -114,25 +479,197 @@ where
     I: Indexer<Eviction = E>,
 {
     /// Evict entries to fit the target usage.
+    ///
+    /// Pinned records are passed over in place by the eviction algorithm, with their states untouched.
+    ///
+    /// If `skip_referenced` is enabled, the records that are still referenced by the cache entries are passed over as
+    /// well, and only evicted as the last resort if the unreferenced records are not enough to fit the target usage.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::shard::evict"))]
     fn evict(&mut self, target: usize, garbages: &mut Vec<(Event, Arc<Record<E>>)>) {
+        #[cfg(feature = "tracing")]
+        let (count, weight) = (garbages.len(), self.usage);
+
         // Evict overflow records.
+        let skip_referenced = self.skip_referenced;
         while self.usage > target {
-            let evicted = match self.eviction.pop() {
-                Some(evicted) => evicted,
-                None => break,
+            let Some(evicted) = self
+                .eviction
+                .pop_with(|record| record.is_pinned() || (skip_referenced && record.refs() > 0))
+            else {
+                break;
             };
-            self.metrics.memory_evict.increase(1);
+            self.reclaim_evicted(evicted, garbages);
+        }
+
+        // Evict the referenced records in the eviction order as the last resort.
+        while skip_referenced && self.usage > target {
+            let Some(evicted) = self.eviction.pop_with(|record| record.is_pinned()) else {
+                break;
+            };
+            self.reclaim_evicted(evicted, garbages);
+        }
+
+        #[cfg(feature = "tracing")]
+        LocalSpan::add_properties(|| {
+            [
+                ("evicted", (garbages.len() - count).to_string()),
+                ("weight", (weight - self.usage).to_string()),
+            ]
+        });
+    }
+
+    /// Verify the invariants among the indexer, the eviction algorithm and the usage of the shard, panic if violated.
+    ///
+    /// - Every indexed record has `IN_INDEXER` set, and the usage equals the total weight of the indexed records.
+    /// - Every record held by the eviction algorithm has `IN_EVICTION` set and is the indexed record of its key.
+    /// - Every indexed record with `IN_EVICTION` set is held by the eviction algorithm.
+    ///
+    /// The records are reference counted instead of allocated from a slab, so the removed records that are still held
+    /// by the cache entries are not tracked by the shard and are not verified.
+    #[cfg(debug_assertions)]
+    fn audit(&self) {
+        let mut usage = 0;
+        let mut in_eviction = 0;
+        for record in self.indexer.iter() {
+            assert!(record.is_in_indexer(), "indexed record without IN_INDEXER: {record:?}");
+            usage += record.weight();
+            if record.is_in_eviction() {
+                in_eviction += 1;
+            }
+        }
+        assert_eq!(usage, self.usage, "usage mismatch with the indexed records");
+
+        let records = self.eviction.dump_order();
+        assert_eq!(
+            self.eviction.weight(),
+            records.iter().map(|record| record.weight()).sum::<usize>(),
+            "weight mismatch with the records held by eviction"
+        );
+        let records = if records.is_empty() { self.dump_order() } else { records };
+        if let Some(capacity) = self.eviction.capacity() {
+            assert_eq!(capacity, self.capacity, "capacity mismatch with eviction");
+        }
+        for record in records.iter() {
+            assert!(
+                record.is_in_eviction(),
+                "record held by eviction without IN_EVICTION: {record:?}"
+            );
+            let indexed = self.indexer.get(record.hash(), record.key());
+            assert!(
+                indexed.is_some_and(|indexed| Arc::ptr_eq(indexed, record)),
+                "record held by eviction is not indexed: {record:?}"
+            );
+        }
+        assert_eq!(
+            in_eviction,
+            records.len(),
+            "indexed records with IN_EVICTION are not all held by eviction"
+        );
+    }
+
+    /// Remove the record evicted by the eviction algorithm from the indexer.
+    fn reclaim_evicted(&mut self, evicted: Arc<Record<E>>, garbages: &mut Vec<(Event, Arc<Record<E>>)>) {
+        self.metrics.memory_evict.increase(1);
+        self.counters.evict();
+
+        let e = self.indexer.remove(evicted.hash(), evicted.key()).unwrap();
+        assert_eq!(Arc::as_ptr(&evicted), Arc::as_ptr(&e));
+        self.invalidate_hot_entry(&evicted);
+
+        strict_assert!(!evicted.as_ref().is_in_indexer());
+        strict_assert!(!evicted.as_ref().is_in_eviction());
+
+        self.usage -= evicted.weight();
+
+        garbages.push((Event::Evict, evicted));
+    }
+
+    /// Check if the record is expired. The clock is only read if the record has a ttl.
+    fn is_expired(&self, record: &Record<E>) -> bool {
+        record
+            .expire_at()
+            .is_some_and(|expire_at| expire_at <= self.clock.now())
+    }
+
+    /// Check if the indexed record of the given key is expired.
+    fn contains_expired<Q>(&self, hash: u64, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        self.indexer
+            .get(hash, key)
+            .is_some_and(|record| self.is_expired(record))
+    }
+
+    /// Remove all expired records in the order of their deadlines.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::shard::expire"))]
+    fn expire(&mut self, garbages: &mut Vec<(Event, Arc<Record<E>>)>) {
+        if self.expirations.is_empty() {
+            return;
+        }
+        let now = self.clock.now();
+        while self
+            .expirations
+            .peek()
+            .is_some_and(|expiration| expiration.expire_at <= now)
+        {
+            let expiration = self.expirations.pop().unwrap();
+            // Skip the removed or replaced records.
+            if let Some(record) = expiration.record.upgrade().filter(|record| record.is_in_indexer()) {
+                self.reclaim_expired(record, garbages);
+            }
+        }
+    }
+
+    /// Remove the indexed record of the given key if it is expired.
+    fn expire_one<Q>(&mut self, hash: u64, key: &Q, garbages: &mut Vec<(Event, Arc<Record<E>>)>)
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        if let Some(record) = self
+            .indexer
+            .get(hash, key)
+            .filter(|record| self.is_expired(record))
+            .cloned()
+        {
+            self.reclaim_expired(record, garbages);
+        }
+    }
+
+    /// Remove the expired record from the indexer and the eviction algorithm.
+    fn reclaim_expired(&mut self, record: Arc<Record<E>>, garbages: &mut Vec<(Event, Arc<Record<E>>)>) {
+        self.metrics.memory_expire.increase(1);
+        self.counters.expire();
+
+        let r = self.indexer.remove(record.hash(), record.key()).unwrap();
+        assert_eq!(Arc::as_ptr(&record), Arc::as_ptr(&r));
+        self.invalidate_hot_entry(&record);
 
-            let e = self.indexer.remove(evicted.hash(), evicted.key()).unwrap();
-            assert_eq!(Arc::as_ptr(&evicted), Arc::as_ptr(&e));
+        if record.is_in_eviction() {
+            self.eviction.remove(&record);
+        }
+        strict_assert!(!record.is_in_indexer());
+        strict_assert!(!record.is_in_eviction());
+
+        self.usage -= record.weight();
+        self.metrics.memory_usage.decrease(record.weight() as _);
 
-            strict_assert!(!evicted.as_ref().is_in_indexer());
-            strict_assert!(!evicted.as_ref().is_in_eviction());
+        garbages.push((Event::Expire, record));
+    }
 
-            self.usage -= evicted.weight();
+    /// Track the deadline of the record, if it has a ttl.
+    fn track_expiration(&mut self, record: &Arc<Record<E>>) {
+        let Some(expire_at) = record.expire_at() else { return };
 
-            garbages.push((Event::Evict, evicted));
+        // Drop the items of the removed or replaced records if they pile up.
+        if self.expirations.len() >= 2 * self.indexer.len() + 16 {
+            self.expirations
+                .retain(|expiration| expiration.record.upgrade().is_some_and(|record| record.is_in_indexer()));
         }
+        self.expirations.push(Expiration {
+            expire_at,
+            record: Arc::downgrade(record),
+        });
     }
 
     fn emplace(
@@ -143,17 +680,22 @@ where
     ) -> Arc<Record<E>> {
         *waiters = self.waiters.lock().remove(record.key()).unwrap_or_default();
 
+        if let Some(tombstones) = self.tombstones.as_mut() {
+            tombstones.remove(record.hash(), record.key());
+        }
+
+        // Remove the expired records before evicting the others.
+        self.expire(garbages);
+
         let weight = record.weight();
         let old_usage = self.usage;
 
-        // Evict overflow records.
-        self.evict(self.capacity.saturating_sub(weight), garbages);
-
         // Insert new record
         if let Some(old) = self.indexer.insert(record.clone()) {
             self.metrics.memory_replace.increase(1);
 
             strict_assert!(!old.is_in_indexer());
+            self.invalidate_hot_entry(&old);
 
             if old.is_in_eviction() {
                 self.eviction.remove(&old);
@@ -166,16 +708,34 @@ where
         } else {
             self.metrics.memory_insert.increase(1);
         }
+        self.counters.insert();
         strict_assert!(record.is_in_indexer());
+        self.track_expiration(&record);
 
         let ephemeral = record.properties().ephemeral().unwrap_or_default();
         record.set_ephemeral(ephemeral);
-        if !ephemeral {
-            self.eviction.push(record.clone());
-            strict_assert!(record.is_in_eviction());
+        if ephemeral {
+            // Evict overflow records.
+            self.evict(self.capacity.saturating_sub(weight), garbages);
+        } else {
+            // Push the new record and evict overflow records.
+            self.eviction.on_admit(&record);
+            if self.skip_referenced {
+                self.evict(self.capacity.saturating_sub(weight), garbages);
+                self.eviction.push(record.clone());
+            } else {
+                let victims = self.eviction.push_evict(record.clone(), self.capacity, self.usage);
+                for victim in victims {
+                    self.reclaim_evicted(victim, garbages);
+                }
+            }
         }
 
         self.usage += weight;
+        if self.usage > self.capacity {
+            // Nothing evictable is left, e.g. all records are pinned.
+            self.metrics.memory_overflow.increase(1);
+        }
         // Increase the reference count within the lock section.
         // The reference count of the new record must be at the moment.
         record.inc_refs(waiters.len() + 1);
@@ -195,6 +755,7 @@ where
         Q: Hash + Equivalent<E::Key> + ?Sized,
     {
         let record = self.indexer.remove(hash, key)?;
+        self.invalidate_hot_entry(&record);
 
         if record.is_in_eviction() {
             self.eviction.remove(&record);
@@ -248,13 +809,15 @@ where
     where
         Q: Hash + Equivalent<E::Key> + ?Sized,
     {
-        let record = match self.indexer.get(hash, key).cloned() {
-            Some(record) => {
+        let record = match self.indexer.get(hash, key) {
+            Some(record) if !self.is_expired(record) => {
                 self.metrics.memory_hit.increase(1);
-                record
+                self.counters.hit();
+                record.clone()
             }
-            None => {
+            _ => {
                 self.metrics.memory_miss.increase(1);
+                self.counters.miss();
                 return None;
             }
         };
@@ -272,6 +835,13 @@ where
     fn clear(&mut self, garbages: &mut Vec<Arc<Record<E>>>) {
         let records = self.indexer.drain().collect_vec();
         self.eviction.clear();
+        self.expirations.clear();
+        if let Some(hot_entry) = self.hot_entry.as_ref() {
+            hot_entry.record.store(None);
+        }
+        if let Some(tombstones) = self.tombstones.as_mut() {
+            tombstones.clear();
+        }
 
         let mut count = 0;
 
@@ -282,10 +852,113 @@ where
 
             garbages.push(record);
         }
+        self.metrics.memory_usage.decrease(self.usage as _);
+        self.usage = 0;
 
         self.metrics.memory_remove.increase(count);
     }
 
+    /// Remove at most `budget` records from the shard, return `true` if the shard is empty afterwards.
+    ///
+    /// The records held by the eviction container are removed first. The records that are indexed but not held by the
+    /// eviction container (e.g. ephemeral records) are removed once the eviction container is empty.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::raw::shard::clear_partial")
+    )]
+    fn clear_partial(&mut self, budget: usize, garbages: &mut Vec<Arc<Record<E>>>) -> bool {
+        let mut count = 0;
+        let mut weight = 0;
+
+        while count < budget {
+            let Some(record) = self.eviction.pop() else { break };
+            let removed = self.indexer.remove(record.hash(), record.key());
+            strict_assert!(removed.is_some());
+            self.invalidate_hot_entry(&record);
+            strict_assert!(!record.is_in_indexer());
+            strict_assert!(!record.is_in_eviction());
+
+            count += 1;
+            weight += record.weight();
+            garbages.push(record);
+        }
+
+        let empty = count < budget;
+        if empty {
+            self.expirations.clear();
+            if let Some(hot_entry) = self.hot_entry.as_ref() {
+                hot_entry.record.store(None);
+            }
+            for record in self.indexer.drain().collect_vec() {
+                strict_assert!(!record.is_in_indexer());
+                // Some eviction algorithms never pop some of their records (e.g. the pinned records of LRU), unlink them
+                // explicitly.
+                if record.is_in_eviction() {
+                    self.eviction.remove(&record);
+                }
+                strict_assert!(!record.is_in_eviction());
+
+                count += 1;
+                weight += record.weight();
+                garbages.push(record);
+            }
+        }
+
+        self.usage -= weight;
+
+        self.metrics.memory_remove.increase(count as _);
+        self.metrics.memory_usage.decrease(weight as _);
+
+        empty
+    }
+
+    /// Get the records held by the eviction algorithm, in the eviction order if the eviction algorithm dumps them.
+    ///
+    /// Falls back to the indexed records held by the eviction algorithm in an arbitrary order, if the eviction algorithm
+    /// doesn't implement [`Eviction::dump_order`].
+    fn dump_order(&self) -> Vec<Arc<Record<E>>> {
+        let records = self.eviction.dump_order();
+        if records.is_empty() && self.indexer.iter().any(|record| record.is_in_eviction()) {
+            return self
+                .indexer
+                .iter()
+                .filter(|record| record.is_in_eviction())
+                .cloned()
+                .collect();
+        }
+        records
+    }
+
+    /// Rebuild the indexer with the records held by the eviction container.
+    ///
+    /// The records that are indexed but not held by the eviction container (e.g. ephemeral records) are kept.
+    fn rebuild_index(&mut self) {
+        let managed = self.dump_order();
+        let unmanaged = self
+            .indexer
+            .drain()
+            .filter(|record| !record.is_in_eviction())
+            .collect_vec();
+        for record in managed.into_iter().chain(unmanaged) {
+            let old = self.indexer.insert(record);
+            strict_assert!(old.is_none());
+        }
+    }
+
+    /// Update the hot entry with the record just accessed.
+    fn update_hot_entry(&self, record: &Arc<Record<E>>) {
+        if let Some(hot_entry) = self.hot_entry.as_ref() {
+            hot_entry.update(record);
+        }
+    }
+
+    /// Invalidate the hot entry if it holds the record removed from the indexer.
+    fn invalidate_hot_entry(&self, record: &Arc<Record<E>>) {
+        if let Some(hot_entry) = self.hot_entry.as_ref() {
+            hot_entry.invalidate(record);
+        }
+    }
+
     #[cfg_attr(
         feature = "tracing",
         fastrace::trace(name = "foyer::memory::raw::shard::acquire_immutable")
@@ -407,16 +1080,30 @@ where
     I: Indexer<Eviction = E>,
 {
     shards: Vec<RwLock<RawCacheShard<E, S, I>>>,
-
-    capacity: usize,
+    /// Hot entries of the shards, empty if disabled.
+    hot_entries: Vec<Arc<HotEntry<E>>>,
+    /// Counters of the shards, shared with the shards so that they can be read and updated without the shard locks.
+    counters: Vec<Arc<ShardCounters>>,
+    /// Total capacity of the shards, so that it can be read without the shard locks.
+    capacity: AtomicUsize,
 
     hash_builder: Arc<S>,
+    hash_domain: u64,
     weighter: Arc<dyn Weighter<E::Key, E::Value>>,
+    count_entries: bool,
     filter: Arc<dyn Filter<E::Key, E::Value>>,
+    max_admit_weight: Option<usize>,
+    negative_ttl: Option<Duration>,
 
     metrics: Arc<Metrics>,
     event_listener: Option<Arc<dyn EventListener<Key = E::Key, Value = E::Value>>>,
     pipe: ArcSwap<Box<dyn Pipe<Key = E::Key, Value = E::Value, Properties = E::Properties>>>,
+    /// Clock to set the deadlines of the records with ttl.
+    clock: ArcSwap<Arc<dyn Clock>>,
+
+    /// Route all keys to the given shard, for testing only.
+    #[cfg(any(test, feature = "test_utils"))]
+    force_shard: Option<usize>,
 }
 
 impl<E, S, I> RawCacheInner<E, S, I>
@@ -425,8 +1112,37 @@ where
     S: HashBuilder,
     I: Indexer<Eviction = E>,
 {
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::inner::clear"))]
-    fn clear(&self) {
+    fn shard(&self, hash: u64) -> usize {
+        #[cfg(any(test, feature = "test_utils"))]
+        if let Some(shard) = self.force_shard {
+            return shard;
+        }
+        hash as usize % self.shards.len()
+    }
+
+    /// Update the capacity of the shard and evict the overflowed records, with the shard write lock held.
+    fn set_shard_capacity(
+        &self,
+        shard: &mut RawCacheShard<E, S, I>,
+        capacity: usize,
+        garbages: &mut Vec<(Event, Arc<Record<E>>)>,
+    ) -> Result<()> {
+        shard.eviction.update(capacity, None)?;
+        if capacity >= shard.capacity {
+            self.capacity.fetch_add(capacity - shard.capacity, Ordering::Relaxed);
+        } else {
+            self.capacity.fetch_sub(shard.capacity - capacity, Ordering::Relaxed);
+        }
+        shard.capacity = capacity;
+        shard.evict(capacity, garbages);
+        if let Some(tombstones) = shard.tombstones.as_mut() {
+            tombstones.set_capacity(capacity);
+        }
+        Ok(())
+    }
+
+    /// Remove all entries from the shards, the returned garbages must be deallocated out of the lock critical section.
+    fn clear_shards(&self) -> Vec<Arc<Record<E>>> {
         let mut garbages = vec![];
 
         self.shards
@@ -434,15 +1150,26 @@ where
             .map(|shard| shard.write())
             .for_each(|mut shard| shard.clear(&mut garbages));
 
+        garbages
+    }
+
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::inner::clear"))]
+    fn clear(&self) {
+        let garbages = self.clear_shards();
+
         // Do not deallocate data within the lock section.
         if let Some(listener) = self.event_listener.as_ref() {
             for record in garbages {
-                listener.on_leave(Event::Clear, record.key(), record.value());
+                on_leave(listener.as_ref(), Event::Clear, record.key(), record.value());
             }
         }
     }
 }
 
+/// In-memory cache with the eviction algorithm `E`.
+///
+/// [`Cache`](crate::Cache) dispatches to it with the built-in eviction algorithms. With the `custom-eviction` feature
+/// enabled, it can be built with a custom eviction algorithm.
 pub struct RawCache<E, S, I = HashTableIndexer<E>>
 where
     E: Eviction,
@@ -482,15 +1209,58 @@ where
     S: HashBuilder,
     I: Indexer<Eviction = E>,
 {
+    /// Create a cache with the given config.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the config is invalid, see [`RawCache::try_new`].
     pub fn new(config: RawCacheConfig<E, S>) -> Self {
-        let shard_capacity = config.capacity / config.shards;
+        Self::try_new(config).unwrap()
+    }
+
+    /// Create a cache with the given config, or return an error if the config is invalid.
+    ///
+    /// The count of the shard capacities must be equal to the count of the shards if given.
+    pub fn try_new(config: RawCacheConfig<E, S>) -> Result<Self> {
+        let shard_capacities = match config.shard_capacities {
+            Some(shard_capacities) if shard_capacities.len() != config.shards => {
+                return Err(Error::ConfigError(format!(
+                    "the count of shard capacities ({}) must be equal to the count of shards ({})",
+                    shard_capacities.len(),
+                    config.shards
+                )));
+            }
+            Some(shard_capacities) => shard_capacities,
+            None => split_capacity(config.capacity, config.shards),
+        };
+        let capacity = shard_capacities.iter().sum();
+
+        // The hot entry skips the acquire op, only enable it if the eviction algorithm doesn't observe the accesses.
+        // Even an immutable acquire op (e.g. the frequency of S3FIFO, the visited bit of SIEVE) would be undercounted.
+        let hot_entries = match E::acquire() {
+            Op::Noop if config.hot_entry => (0..shard_capacities.len())
+                .map(|_| Arc::new(HotEntry::default()))
+                .collect_vec(),
+            _ => vec![],
+        };
+        let counters = (0..shard_capacities.len())
+            .map(|_| Arc::new(ShardCounters::default()))
+            .collect_vec();
 
-        let shards = (0..config.shards)
-            .map(|_| RawCacheShard {
+        let shards = shard_capacities
+            .into_iter()
+            .enumerate()
+            .map(|(i, shard_capacity)| RawCacheShard {
                 eviction: E::new(shard_capacity, &config.eviction_config),
                 indexer: Sentry::default(),
                 usage: 0,
                 capacity: shard_capacity,
+                skip_referenced: config.skip_referenced,
+                tombstones: config.negative_ttl.map(|ttl| Tombstones::new(ttl, shard_capacity)),
+                hot_entry: hot_entries.get(i).cloned(),
+                counters: counters[i].clone(),
+                expirations: BinaryHeap::new(),
+                clock: Arc::new(SystemClock),
                 waiters: Mutex::default(),
                 metrics: config.metrics.clone(),
                 _event_listener: config.event_listener.clone(),
@@ -503,44 +1273,58 @@ where
 
         let inner = RawCacheInner {
             shards,
-            capacity: config.capacity,
+            hot_entries,
+            counters,
+            capacity: AtomicUsize::new(capacity),
             hash_builder: Arc::new(config.hash_builder),
+            hash_domain: config.hash_domain,
             weighter: config.weighter,
+            count_entries: config.count_entries,
             filter: config.filter,
+            max_admit_weight: config.max_admit_weight,
+            negative_ttl: config.negative_ttl,
             metrics: config.metrics,
             event_listener: config.event_listener,
             pipe: ArcSwap::new(Arc::new(pipe)),
+            clock: ArcSwap::new(Arc::new(Arc::new(SystemClock))),
+            #[cfg(any(test, feature = "test_utils"))]
+            force_shard: None,
         };
 
-        Self { inner: Arc::new(inner) }
+        Ok(Self { inner: Arc::new(inner) })
     }
 
+    /// Resize the cache capacity.
+    ///
+    /// The capacity is split among the shards in proportion to their current capacities, or equally if the current
+    /// capacity is `0`.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::resize"))]
     pub fn resize(&self, capacity: usize) -> Result<()> {
+        assert_not_in_event_listener();
+
         let shards = self.inner.shards.len();
-        let shard_capacity = capacity / shards;
+        let current = (0..shards).map(|i| self.shard_capacity(i)).collect_vec();
+        let shard_capacities = scale_capacities(&current, capacity);
 
         let handles = (0..shards)
             .map(|i| {
                 let inner = self.inner.clone();
+                let shard_capacity = shard_capacities[i];
                 std::thread::spawn(move || {
                     let mut garbages = vec![];
-                    let res = inner.shards[i].write().with(|mut shard| {
-                        shard.eviction.update(shard_capacity, None).inspect(|_| {
-                            shard.capacity = shard_capacity;
-                            shard.evict(shard_capacity, &mut garbages)
-                        })
-                    });
+                    let res = inner.shards[i]
+                        .write()
+                        .with(|mut shard| inner.set_shard_capacity(&mut shard, shard_capacity, &mut garbages));
                     // Deallocate data out of the lock critical section.
                     let pipe = inner.pipe.load();
                     let piped = pipe.is_enabled();
                     if inner.event_listener.is_some() || piped {
                         for (event, record) in garbages {
                             if let Some(listener) = inner.event_listener.as_ref() {
-                                listener.on_leave(event, record.key(), record.value())
+                                on_leave(listener.as_ref(), event, record.key(), record.value())
                             }
                             if piped && event == Event::Evict {
-                                pipe.send(Piece::new(record));
+                                spill(&***pipe, Piece::new(record));
                             }
                         }
                     }
@@ -562,6 +1346,46 @@ where
         Ok(())
     }
 
+    /// Update the capacity of the given shard and evict overflowed entries of the shard.
+    ///
+    /// The capacities of other shards are not affected. Returns an error if the shard is out of range.
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::raw::set_shard_capacity")
+    )]
+    pub fn set_shard_capacity(&self, shard: usize, capacity: usize) -> Result<()> {
+        assert_not_in_event_listener();
+
+        if shard >= self.inner.shards.len() {
+            return Err(Error::ConfigError(format!(
+                "shard {shard} is out of range, the count of shards is {}",
+                self.inner.shards.len()
+            )));
+        }
+
+        let mut garbages = vec![];
+        let res = self.inner.shards[shard]
+            .write()
+            .with(|mut shard| self.inner.set_shard_capacity(&mut shard, capacity, &mut garbages));
+
+        // Deallocate data out of the lock critical section.
+        let pipe = self.inner.pipe.load();
+        let piped = pipe.is_enabled();
+        if self.inner.event_listener.is_some() || piped {
+            for (event, record) in garbages {
+                if let Some(listener) = self.inner.event_listener.as_ref() {
+                    on_leave(listener.as_ref(), event, record.key(), record.value())
+                }
+                if piped && event == Event::Evict {
+                    spill(&***pipe, Piece::new(record));
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Insert an entry with the default properties.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::insert"))]
     pub fn insert(&self, key: E::Key, value: E::Value) -> RawCacheEntry<E, S, I> {
         self.insert_with_properties(key, value, Default::default())
@@ -571,25 +1395,108 @@ where
         feature = "tracing",
         fastrace::trace(name = "foyer::memory::raw::insert_with_properties")
     )]
+    /// Insert an entry with the given properties.
     pub fn insert_with_properties(
         &self,
         key: E::Key,
         value: E::Value,
-        mut properties: E::Properties,
+        properties: E::Properties,
     ) -> RawCacheEntry<E, S, I> {
-        let hash = self.inner.hash_builder.hash_one(&key);
         let weight = (self.inner.weighter)(&key, &value);
-        if !(self.inner.filter)(&key, &value) {
+        self.insert_weighted(key, value, properties, weight)
+    }
+
+    /// Insert the entry with the given weight instead of the weight computed by the weighter.
+    ///
+    /// The explicit weight is used for the capacity accounting and the admission check of the max admission weight,
+    /// unless the cache counts the entries, see [`RawCacheConfig::count_entries`].
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::raw::insert_with_weight")
+    )]
+    pub fn insert_with_weight(&self, key: E::Key, value: E::Value, weight: usize) -> RawCacheEntry<E, S, I> {
+        self.insert_weighted(key, value, Default::default(), weight)
+    }
+
+    fn insert_weighted(
+        &self,
+        key: E::Key,
+        value: E::Value,
+        properties: E::Properties,
+        weight: usize,
+    ) -> RawCacheEntry<E, S, I> {
+        let record = self.record(key, value, properties, weight);
+        self.insert_inner(record)
+    }
+
+    /// Create the record to insert, the record is disposable if it is rejected by the filter or too heavy to admit.
+    fn record(&self, key: E::Key, value: E::Value, mut properties: E::Properties, weight: usize) -> Arc<Record<E>> {
+        let hash = self.hash(&key);
+        let weight = if self.inner.count_entries { 1 } else { weight };
+        if self.inner.max_admit_weight.is_some_and(|max| weight > max) || !(self.inner.filter)(&key, &value) {
             properties = properties.with_disposable(true);
         }
-        let record = Arc::new(Record::new(Data {
-            key,
-            value,
-            properties,
-            hash,
-            weight,
-        }));
-        self.insert_inner(record)
+        let expire_at = properties.ttl().map(|ttl| self.inner.clock.load().now() + ttl);
+        Arc::new(
+            Record::new(Data {
+                key,
+                value,
+                properties,
+                hash,
+                weight,
+            })
+            .with_expire_at(expire_at),
+        )
+    }
+
+    /// Insert the entries with the default properties, the returned entries are aligned with the items.
+    ///
+    /// The entries are grouped by shard, so each shard is locked at most once, which is cheaper than calling
+    /// [`RawCache::insert`] for each entry. The entries with the same key are inserted in order, so the last one is
+    /// resident.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::insert_many"))]
+    pub fn insert_many(&self, items: Vec<(E::Key, E::Value)>) -> Vec<RawCacheEntry<E, S, I>> {
+        let records = items.into_iter().map(|(key, value)| {
+            let weight = (self.inner.weighter)(&key, &value);
+            self.record(key, value, Default::default(), weight)
+        });
+
+        let mut groups = vec![vec![]; self.inner.shards.len()];
+        for (i, record) in records.enumerate() {
+            groups[self.shard(record.hash())].push((i, record));
+        }
+
+        let mut garbages = vec![];
+        let mut settled = vec![];
+        for (shard, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+            self.inner.shards[shard].write().with(|mut shard| {
+                for (i, record) in group {
+                    if record.properties().disposable().unwrap_or_default() {
+                        // Remove the stale record and skip the disposable record, the same as `insert`.
+                        if let Some(old) = shard.remove(record.hash(), record.key()) {
+                            garbages.push((Event::Replace, old));
+                        }
+                        record.inc_refs(1);
+                        settled.push((i, record, vec![]));
+                    } else {
+                        let mut waiters = vec![];
+                        let record = shard.emplace(record, &mut garbages, &mut waiters);
+                        settled.push((i, record, waiters));
+                    }
+                }
+            });
+        }
+
+        self.settle_garbages(garbages);
+
+        settled.sort_by_key(|(i, _, _)| *i);
+        settled
+            .into_iter()
+            .map(|(_, record, waiters)| self.settle_insert(record, vec![], waiters))
+            .collect()
     }
 
     #[doc(hidden)]
@@ -600,17 +1507,23 @@ where
 
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::insert_inner"))]
     fn insert_inner(&self, record: Arc<Record<E>>) -> RawCacheEntry<E, S, I> {
+        #[cfg(feature = "tracing")]
+        LocalSpan::add_properties(|| {
+            [
+                ("hash", record.hash().to_string()),
+                ("shard", self.shard(record.hash()).to_string()),
+                ("weight", record.weight().to_string()),
+            ]
+        });
+
         if record.properties().disposable().unwrap_or_default() {
-            // Remove the stale record if it exists.
-            self.inner.shards[self.shard(record.hash())]
+            // Remove the stale record if it exists, and deallocate it out of the lock critical section.
+            let old = self.inner.shards[self.shard(record.hash())]
                 .write()
-                .remove(record.hash(), record.key())
-                .inspect(|r| {
-                    // Deallocate data out of the lock critical section.
-                    if let Some(listener) = self.inner.event_listener.as_ref() {
-                        listener.on_leave(Event::Replace, r.key(), r.value());
-                    }
-                });
+                .remove(record.hash(), record.key());
+            if let Some(old) = old {
+                self.settle_garbages(vec![(Event::Replace, old)]);
+            }
 
             // If the record is disposable, we do not insert it into the cache.
             // Instead, we just return it and let it be dropped immediately after the last reference drops.
@@ -628,6 +1541,79 @@ where
             .write()
             .with(|mut shard| shard.emplace(record, &mut garbages, &mut waiters));
 
+        self.settle_insert(record, garbages, waiters)
+    }
+
+    /// Insert the entry only if there is no entry with the same key in the cache.
+    ///
+    /// Returns the resident entry and `true` if this call inserted it, or the existing entry and `false` if an entry
+    /// with the same key is found. The lookup and the insertion are atomic under the shard lock, so only one of the
+    /// racing calls with the same key reports `true`.
+    ///
+    /// If the entry is rejected by the filter or exceeds the max admission weight, it is returned with `true` without
+    /// being inserted, the same as [`RawCache::insert`].
+    ///
+    /// The lookup doesn't count as a hit or a miss.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::insert_if_absent"))]
+    pub fn insert_if_absent(&self, key: E::Key, value: E::Value) -> (RawCacheEntry<E, S, I>, bool) {
+        self.insert_if_absent_with_properties(key, value, Default::default())
+    }
+
+    /// Insert the entry with the given properties only if there is no entry with the same key in the cache.
+    ///
+    /// See [`RawCache::insert_if_absent`].
+    #[cfg_attr(
+        feature = "tracing",
+        fastrace::trace(name = "foyer::memory::raw::insert_if_absent_with_properties")
+    )]
+    pub fn insert_if_absent_with_properties(
+        &self,
+        key: E::Key,
+        value: E::Value,
+        properties: E::Properties,
+    ) -> (RawCacheEntry<E, S, I>, bool) {
+        let weight = (self.inner.weighter)(&key, &value);
+        let record = self.record(key, value, properties, weight);
+        let hash = record.hash();
+
+        let mut garbages = vec![];
+        let mut waiters = vec![];
+
+        let res = self.inner.shards[self.shard(hash)].write().with(|mut shard| {
+            if let Some(existing) = shard.indexer.get(hash, record.key()) {
+                if !shard.is_expired(existing) {
+                    let existing = existing.clone();
+                    existing.set_ephemeral(false);
+                    existing.inc_refs(1);
+                    return Err(existing);
+                }
+            }
+            if record.properties().disposable().unwrap_or_default() {
+                record.inc_refs(1);
+                return Ok(record);
+            }
+            Ok(shard.emplace(record, &mut garbages, &mut waiters))
+        });
+
+        match res {
+            Ok(record) => (self.settle_insert(record, garbages, waiters), true),
+            Err(record) => (
+                RawCacheEntry {
+                    record,
+                    inner: self.inner.clone(),
+                },
+                false,
+            ),
+        }
+    }
+
+    /// Notify the waiters and deallocate the garbages of an insertion out of the lock critical section.
+    fn settle_insert(
+        &self,
+        record: Arc<Record<E>>,
+        garbages: Vec<(Event, Arc<Record<E>>)>,
+        waiters: Vec<oneshot::Sender<RawCacheEntry<E, S, I>>>,
+    ) -> RawCacheEntry<E, S, I> {
         // Notify waiters out of the lock critical section.
         for waiter in waiters {
             let _ = waiter.send(RawCacheEntry {
@@ -636,29 +1622,48 @@ where
             });
         }
 
-        // Deallocate data out of the lock critical section.
+        self.settle_garbages(garbages);
+
+        RawCacheEntry {
+            record,
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Deallocate the garbages out of the lock critical section, and offload the evicted ones via the pipe if needed.
+    ///
+    /// Within the event listener or the pipe, the callbacks are deferred until the outermost callback returns. Calling
+    /// them in place could recurse without bound, e.g. an insertion made by the pipe evicts and spills another entry.
+    fn settle_garbages(&self, garbages: Vec<(Event, Arc<Record<E>>)>) {
+        if garbages.is_empty() {
+            return;
+        }
+        if IN_EVENT_LISTENER.get() {
+            let cache = self.clone();
+            DEFERRED_SETTLEMENTS.with_borrow_mut(|settlements| {
+                settlements.push_back(Box::new(move || cache.settle_garbages(garbages)))
+            });
+            return;
+        }
         let pipe = self.inner.pipe.load();
         let piped = pipe.is_enabled();
         if self.inner.event_listener.is_some() || piped {
             for (event, record) in garbages {
                 if let Some(listener) = self.inner.event_listener.as_ref() {
-                    listener.on_leave(event, record.key(), record.value())
+                    on_leave(listener.as_ref(), event, record.key(), record.value())
                 }
                 if piped && event == Event::Evict {
-                    pipe.send(Piece::new(record));
+                    spill(&***pipe, Piece::new(record));
                 }
             }
         }
-
-        RawCacheEntry {
-            record,
-            inner: self.inner.clone(),
-        }
     }
 
     /// Evict all entries in the cache and offload them into the disk cache via the pipe if needed.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::evict_all"))]
     pub fn evict_all(&self) {
+        assert_not_in_event_listener();
+
         let mut garbages = vec![];
         for shard in self.inner.shards.iter() {
             shard.write().evict(0, &mut garbages);
@@ -670,10 +1675,10 @@ where
         if self.inner.event_listener.is_some() || piped {
             for (event, record) in garbages {
                 if let Some(listener) = self.inner.event_listener.as_ref() {
-                    listener.on_leave(event, record.key(), record.value())
+                    on_leave(listener.as_ref(), event, record.key(), record.value())
                 }
                 if piped && event == Event::Evict {
-                    pipe.send(Piece::new(record));
+                    spill(&***pipe, Piece::new(record));
                 }
             }
         }
@@ -685,6 +1690,8 @@ where
     /// Therefore, this function is asynchronous.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::flush"))]
     pub async fn flush(&self) {
+        assert_not_in_event_listener();
+
         let mut garbages = vec![];
         for shard in self.inner.shards.iter() {
             shard.write().evict(0, &mut garbages);
@@ -696,7 +1703,7 @@ where
 
         if let Some(listener) = self.inner.event_listener.as_ref() {
             for (event, record) in garbages.iter() {
-                listener.on_leave(*event, record.key(), record.value());
+                on_leave(listener.as_ref(), *event, record.key(), record.value());
             }
         }
         if piped {
@@ -705,12 +1712,43 @@ where
         }
     }
 
+    /// Shut down the cache.
+    ///
+    /// All entries are removed from the cache, notified to the event listener with [`Event::Shutdown`], and offloaded
+    /// into the disk cache via the pipe if needed. The function returns after the pipe finishes the offloading, and
+    /// the records are freed then.
+    ///
+    /// The cache handle is consumed. The clones of the handle can still access the emptied cache.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::shutdown"))]
+    pub async fn shutdown(self) {
+        assert_not_in_event_listener();
+
+        let mut garbages = vec![];
+        for shard in self.inner.shards.iter() {
+            shard.write().clear(&mut garbages);
+        }
+
+        // Deallocate data out of the lock critical section.
+        let pipe = self.inner.pipe.load();
+
+        if let Some(listener) = self.inner.event_listener.as_ref() {
+            for record in garbages.iter() {
+                on_leave(listener.as_ref(), Event::Shutdown, record.key(), record.value());
+            }
+        }
+        if pipe.is_enabled() {
+            let pieces = garbages.into_iter().map(Piece::new).collect_vec();
+            pipe.flush(pieces).await;
+        }
+    }
+
+    /// Remove the entry with the given key, and return it if found.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::remove"))]
     pub fn remove<Q>(&self, key: &Q) -> Option<RawCacheEntry<E, S, I>>
     where
         Q: Hash + Equivalent<E::Key> + ?Sized,
     {
-        let hash = self.inner.hash_builder.hash_one(key);
+        let hash = self.hash(key);
 
         self.inner.shards[self.shard(hash)]
             .write()
@@ -720,102 +1758,576 @@ where
                     record,
                 })
             })
-            .inspect(|record| {
+            .inspect(|entry| {
                 // Deallocate data out of the lock critical section.
-                if let Some(listener) = self.inner.event_listener.as_ref() {
-                    listener.on_leave(Event::Remove, record.key(), record.value());
-                }
+                self.settle_garbages(vec![(Event::Remove, entry.record.clone())]);
             })
     }
 
+    /// Remove the entries with the given keys, and return the count of the removed entries.
+    ///
+    /// The keys are grouped by shard, so each shard is locked at most once.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::remove_many"))]
+    pub fn remove_many<Q>(&self, keys: &[Q]) -> usize
+    where
+        Q: Hash + Equivalent<E::Key>,
+    {
+        let mut groups = vec![vec![]; self.inner.shards.len()];
+        for key in keys {
+            let hash = self.hash(key);
+            groups[self.shard(hash)].push((hash, key));
+        }
+
+        let mut removed = vec![];
+        for (shard, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+            self.inner.shards[shard].write().with(|mut shard| {
+                removed.extend(group.into_iter().filter_map(|(hash, key)| {
+                    shard.remove(hash, key).map(|record| RawCacheEntry {
+                        inner: self.inner.clone(),
+                        record,
+                    })
+                }))
+            });
+        }
+
+        // Deallocate data out of the lock critical section.
+        self.settle_garbages(
+            removed
+                .iter()
+                .map(|entry| (Event::Remove, entry.record.clone()))
+                .collect(),
+        );
+
+        removed.len()
+    }
+
+    /// Get the entry with the given key, and apply the acquire op of the eviction algorithm.
     #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::get"))]
     pub fn get<Q>(&self, key: &Q) -> Option<RawCacheEntry<E, S, I>>
     where
         Q: Hash + Equivalent<E::Key> + ?Sized,
     {
-        let hash = self.inner.hash_builder.hash_one(key);
+        let hash = self.hash(key);
+        let shard = self.shard(hash);
 
-        let record = match E::acquire() {
-            Op::Noop => self.inner.shards[self.shard(hash)].read().get_noop(hash, key),
-            Op::Immutable(_) => self.inner.shards[self.shard(hash)]
-                .read()
-                .with(|shard| shard.get_immutable(hash, key)),
-            Op::Mutable(_) => self.inner.shards[self.shard(hash)]
-                .write()
-                .with(|mut shard| shard.get_mutable(hash, key)),
-        }?;
+        #[cfg(feature = "tracing")]
+        LocalSpan::add_properties(|| [("hash", hash.to_string()), ("shard", shard.to_string())]);
 
-        Some(RawCacheEntry {
-            inner: self.inner.clone(),
-            record,
-        })
+        let entry = self
+            .get_hot(shard, hash, key)
+            .or_else(|| self.get_locked(shard, hash, key));
+
+        #[cfg(feature = "tracing")]
+        LocalSpan::add_property(|| ("outcome", if entry.is_some() { "hit" } else { "miss" }));
+
+        entry
     }
 
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::contains"))]
-    pub fn contains<Q>(&self, key: &Q) -> bool
+    /// Look up the key in the cache, and tell the key known to be absent by the negative cache from a plain miss.
+    ///
+    /// A hit applies the acquire op of the eviction algorithm, the same as [`RawCache::get`]. Always returns
+    /// [`Lookup::Hit`] or [`Lookup::Miss`] if the negative cache is disabled.
+    pub fn lookup<Q>(&self, key: &Q) -> Lookup<RawCacheEntry<E, S, I>>
     where
         Q: Hash + Equivalent<E::Key> + ?Sized,
     {
-        let hash = self.inner.hash_builder.hash_one(key);
-
-        self.inner.shards[self.shard(hash)]
+        if let Some(entry) = self.get(key) {
+            return Lookup::Hit(entry);
+        }
+        if self.inner.negative_ttl.is_none() {
+            return Lookup::Miss;
+        }
+        let hash = self.hash(key);
+        let absent = self.inner.shards[self.shard(hash)]
             .read()
-            .with(|shard| shard.indexer.get(hash, key).is_some())
+            .tombstones
+            .as_ref()
+            .is_some_and(|tombstones| tombstones.contains(hash, key));
+        if absent {
+            Lookup::Absent
+        } else {
+            Lookup::Miss
+        }
     }
 
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::touch"))]
-    pub fn touch<Q>(&self, key: &Q) -> bool
+    /// Get the entries with the given keys, the results are aligned with the keys.
+    ///
+    /// Each shard is locked at most once, which is cheaper than calling [`RawCache::get`] for each key. The acquire op
+    /// of the eviction algorithm is applied to each hit, and the expired entries are removed, the same as
+    /// [`RawCache::get`].
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::get_many"))]
+    pub fn get_many<Q>(&self, keys: &[Q]) -> Vec<Option<RawCacheEntry<E, S, I>>>
     where
-        Q: Hash + Equivalent<E::Key> + ?Sized,
+        Q: Hash + Equivalent<E::Key>,
     {
-        let hash = self.inner.hash_builder.hash_one(key);
+        let mut groups = vec![vec![]; self.inner.shards.len()];
+        for (i, key) in keys.iter().enumerate() {
+            let hash = self.hash(key);
+            groups[self.shard(hash)].push((i, hash, key));
+        }
 
-        match E::acquire() {
-            Op::Noop => self.inner.shards[self.shard(hash)].read().get_noop(hash, key),
-            Op::Immutable(_) => self.inner.shards[self.shard(hash)]
-                .read()
-                .with(|shard| shard.get_immutable(hash, key)),
-            Op::Mutable(_) => self.inner.shards[self.shard(hash)]
-                .write()
-                .with(|mut shard| shard.get_mutable(hash, key)),
+        let mut records = vec![None; keys.len()];
+        for (s, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+            // Expired entries are misses, and are removed with the write lock afterwards.
+            let mut expired = vec![];
+            match E::acquire() {
+                Op::Noop => self.inner.shards[s].read().with(|shard| {
+                    for (i, hash, key) in group {
+                        records[i] = shard
+                            .get_noop(hash, key)
+                            .inspect(|record| shard.update_hot_entry(record));
+                        if records[i].is_none() && shard.contains_expired(hash, key) {
+                            expired.push((hash, key));
+                        }
+                    }
+                }),
+                Op::Immutable(_) => self.inner.shards[s].read().with(|shard| {
+                    for (i, hash, key) in group {
+                        records[i] = shard.get_immutable(hash, key);
+                        if records[i].is_none() && shard.contains_expired(hash, key) {
+                            expired.push((hash, key));
+                        }
+                    }
+                }),
+                Op::Mutable(_) => self.inner.shards[s].write().with(|mut shard| {
+                    for (i, hash, key) in group {
+                        records[i] = shard.get_mutable(hash, key);
+                        if records[i].is_none() && shard.contains_expired(hash, key) {
+                            expired.push((hash, key));
+                        }
+                    }
+                }),
+            }
+            if !expired.is_empty() {
+                self.remove_expired(s, &expired);
+            }
         }
-        .is_some()
-    }
 
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::clear"))]
-    pub fn clear(&self) {
-        self.inner.clear();
+        records
+            .into_iter()
+            .map(|record| {
+                record.map(|record| RawCacheEntry {
+                    inner: self.inner.clone(),
+                    record,
+                })
+            })
+            .collect()
     }
 
-    pub fn capacity(&self) -> usize {
-        self.inner.capacity
-    }
+    fn get_locked<Q>(&self, shard: usize, hash: u64, key: &Q) -> Option<RawCacheEntry<E, S, I>>
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        // An expired entry is a miss, and is removed with the write lock afterwards.
+        let (record, expired) = match E::acquire() {
+            Op::Noop => self.inner.shards[shard].read().with(|shard| {
+                let record = shard
+                    .get_noop(hash, key)
+                    .inspect(|record| shard.update_hot_entry(record));
+                let expired = record.is_none() && shard.contains_expired(hash, key);
+                (record, expired)
+            }),
+            Op::Immutable(_) => self.inner.shards[shard].read().with(|shard| {
+                let record = shard.get_immutable(hash, key);
+                let expired = record.is_none() && shard.contains_expired(hash, key);
+                (record, expired)
+            }),
+            Op::Mutable(_) => self.inner.shards[shard].write().with(|mut shard| {
+                let record = shard.get_mutable(hash, key);
+                let expired = record.is_none() && shard.contains_expired(hash, key);
+                (record, expired)
+            }),
+        };
 
-    pub fn usage(&self) -> usize {
-        self.inner.shards.iter().map(|shard| shard.read().usage).sum()
-    }
+        if expired {
+            self.remove_expired(shard, &[(hash, key)]);
+        }
 
-    pub fn metrics(&self) -> &Metrics {
-        &self.inner.metrics
-    }
+        let record = record?;
 
-    pub fn hash_builder(&self) -> &Arc<S> {
-        &self.inner.hash_builder
+        Some(RawCacheEntry {
+            inner: self.inner.clone(),
+            record,
+        })
     }
 
-    pub fn shards(&self) -> usize {
+    /// Remove the entries with the given keys of the shard if they are expired.
+    ///
+    /// Expired entries are not offloaded to the disk cache via the pipe.
+    fn remove_expired<Q>(&self, shard: usize, keys: &[(u64, &Q)])
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let mut garbages = vec![];
+        self.inner.shards[shard].write().with(|mut shard| {
+            for (hash, key) in keys {
+                shard.expire_one(*hash, *key, &mut garbages);
+            }
+        });
+
+        // Deallocate data out of the lock critical section.
+        self.settle_garbages(garbages);
+    }
+
+    /// Get the entry with the given key without updating the eviction state, the hot entry or the metrics.
+    ///
+    /// Expired entries are treated as absent, but are not removed.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::peek"))]
+    pub fn peek<Q>(&self, key: &Q) -> Option<RawCacheEntry<E, S, I>>
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        let record = self.inner.shards[self.shard(hash)].read().with(|shard| {
+            shard
+                .indexer
+                .get(hash, key)
+                .filter(|record| !shard.is_expired(record))
+                .cloned()
+                .inspect(|record| {
+                    record.inc_refs(1);
+                })
+        })?;
+
+        Some(RawCacheEntry {
+            inner: self.inner.clone(),
+            record,
+        })
+    }
+
+    /// Get the entry from the hot entry of the shard without taking the shard lock.
+    fn get_hot<Q>(&self, shard: usize, hash: u64, key: &Q) -> Option<RawCacheEntry<E, S, I>>
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let record = self.inner.hot_entries.get(shard)?.get(hash, key)?;
+        let entry = RawCacheEntry {
+            inner: self.inner.clone(),
+            record,
+        };
+        // The record may be removed right before its reference count is increased. Fall back to the locked path in
+        // that case, dropping the entry releases the record as usual.
+        //
+        // Records with ttl also take the locked path, so that the expired ones are removed there.
+        if !entry.record.is_in_indexer() || entry.record.expire_at().is_some() {
+            return None;
+        }
+        // Same as the locked path, an accessed record is no longer ephemeral.
+        entry.record.set_ephemeral(false);
+        self.inner.metrics.memory_hit.increase(1);
+        self.inner.counters[shard].hit();
+        Some(entry)
+    }
+
+    /// Check if the cache contains an entry with the given key, without applying the acquire op.
+    ///
+    /// Expired entries are treated as absent.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::contains"))]
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        self.inner.shards[self.shard(hash)].read().with(|shard| {
+            shard
+                .indexer
+                .get(hash, key)
+                .is_some_and(|record| !shard.is_expired(record))
+        })
+    }
+
+    /// Get the tag of the eviction queue that holds the entry with the given key, for debugging.
+    ///
+    /// Returns `None` if the key is not in the cache, or the entry is ephemeral and not held by the eviction algorithm.
+    pub fn queue_of<Q>(&self, key: &Q) -> Option<QueueTag>
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        self.inner.shards[self.shard(hash)].read().with(|shard| {
+            shard
+                .indexer
+                .get(hash, key)
+                .and_then(|record| shard.eviction.queue_of(record))
+        })
+    }
+
+    /// Apply the acquire op of the eviction algorithm to the entry with the given key without returning it.
+    ///
+    /// Returns `true` if the entry is found.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::touch"))]
+    pub fn touch<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<E::Key> + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        match E::acquire() {
+            Op::Noop => self.inner.shards[self.shard(hash)].read().get_noop(hash, key),
+            Op::Immutable(_) => self.inner.shards[self.shard(hash)]
+                .read()
+                .with(|shard| shard.get_immutable(hash, key)),
+            Op::Mutable(_) => self.inner.shards[self.shard(hash)]
+                .write()
+                .with(|mut shard| shard.get_mutable(hash, key)),
+        }
+        .is_some()
+    }
+
+    /// Remove all entries from the cache.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::clear"))]
+    pub fn clear(&self) {
+        let garbages = self.inner.clear_shards();
+
+        // Do not deallocate data within the lock section.
+        self.settle_garbages(garbages.into_iter().map(|record| (Event::Clear, record)).collect());
+    }
+
+    /// Invalidate all entries in the cache, alias of [`RawCache::clear`].
+    pub fn invalidate_all(&self) {
+        self.clear();
+    }
+
+    /// Clear the cache by removing at most `budget` entries per lock critical section, and yield to the runtime
+    /// between the critical sections.
+    ///
+    /// Clearing a huge cache with [`RawCache::clear`] holds the shard locks for a long time and starves the runtime.
+    /// The entries inserted concurrently may or may not be cleared.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::clear_cooperative"))]
+    pub async fn clear_cooperative(&self, budget: usize) {
+        let budget = budget.max(1);
+        for shard in self.inner.shards.iter() {
+            loop {
+                let mut garbages = vec![];
+                let empty = shard.write().clear_partial(budget, &mut garbages);
+
+                // Deallocate data out of the lock critical section.
+                self.settle_garbages(garbages.into_iter().map(|record| (Event::Clear, record)).collect());
+
+                if empty {
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+        }
+    }
+
+    /// Rebuild the indexer of each shard with the records held by the eviction algorithm.
+    ///
+    /// This is used for recovery when only the eviction state is reliable. Each shard is locked once.
+    pub fn rebuild_index(&self) {
+        for shard in self.inner.shards.iter() {
+            shard.write().rebuild_index();
+        }
+    }
+
+    /// Reserve the indexer capacity for at least `additional` more entries, evenly split among the shards.
+    ///
+    /// Reserving before a bulk load avoids repeated rehashing of the indexers while inserting.
+    pub fn reserve(&self, additional: usize) {
+        let additional = additional.div_ceil(self.inner.shards.len());
+        for shard in self.inner.shards.iter() {
+            shard.write().indexer.reserve(additional);
+        }
+    }
+
+    /// Get the total capacity of the cache.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Get the capacity of the given shard.
+    pub fn shard_capacity(&self, shard: usize) -> usize {
+        self.inner.shards[shard].read().capacity
+    }
+
+    /// Get the total weight of the entries in the cache.
+    pub fn usage(&self) -> usize {
+        self.inner.shards.iter().map(|shard| shard.read().usage).sum()
+    }
+
+    /// Verify the invariants of all shards, panic if violated.
+    ///
+    /// For catching state corruption in debug builds and tests.
+    #[cfg(debug_assertions)]
+    pub fn audit(&self) {
+        self.inner.shards.iter().for_each(|shard| shard.read().audit());
+    }
+
+    /// Get the count of the entries in the cache, including the ephemeral entries.
+    pub fn len(&self) -> usize {
+        self.inner.shards.iter().map(|shard| shard.read().indexer.len()).sum()
+    }
+
+    /// Check if the cache holds no entry.
+    pub fn is_empty(&self) -> bool {
+        self.inner.shards.iter().all(|shard| shard.read().indexer.is_empty())
+    }
+
+    /// Get the usage of each shard as a fraction of its capacity.
+    ///
+    /// A shard with zero capacity is reported as `0.0`.
+    pub fn shard_balance(&self) -> Vec<f64> {
+        self.inner
+            .shards
+            .iter()
+            .map(|shard| {
+                let shard = shard.read();
+                if shard.capacity == 0 {
+                    0.0
+                } else {
+                    shard.usage as f64 / shard.capacity as f64
+                }
+            })
+            .collect()
+    }
+
+    /// Get the difference between the most and the least occupied shards, as fractions of their capacities.
+    ///
+    /// A large skew means the keys are not evenly distributed among the shards, and the hot shards may need a larger
+    /// capacity.
+    pub fn max_shard_skew(&self) -> f64 {
+        let balance = self.shard_balance();
+        let max = balance.iter().copied().fold(f64::MIN, f64::max);
+        let min = balance.iter().copied().fold(f64::MAX, f64::min);
+        max - min
+    }
+
+    /// Get the statistics of each shard.
+    pub fn shard_stats(&self) -> Vec<CacheStats> {
+        self.inner
+            .shards
+            .iter()
+            .zip(self.inner.counters.iter())
+            .map(|(shard, counters)| {
+                let (usage, capacity, entries, tombstones) = shard.read().with(|shard| {
+                    let tombstones = shard.tombstones.as_ref().map(|tombstones| tombstones.len());
+                    (
+                        shard.usage,
+                        shard.capacity,
+                        shard.indexer.len(),
+                        tombstones.unwrap_or_default(),
+                    )
+                });
+                counters.snapshot(usage, capacity, entries, tombstones)
+            })
+            .collect()
+    }
+
+    /// Get the statistics of the cache, aggregated from all shards.
+    pub fn stats(&self) -> CacheStats {
+        self.shard_stats()
+            .into_iter()
+            .fold(CacheStats::default(), |acc, stats| acc + stats)
+    }
+
+    /// Get the metrics of the cache.
+    pub fn metrics(&self) -> &Metrics {
+        &self.inner.metrics
+    }
+
+    /// Get the hash builder of the cache.
+    pub fn hash_builder(&self) -> &Arc<S> {
+        &self.inner.hash_builder
+    }
+
+    /// Get the hash domain of the cache.
+    pub fn hash_domain(&self) -> u64 {
+        self.inner.hash_domain
+    }
+
+    /// Hash the given key with the hash builder and the hash domain of the cache.
+    pub fn hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        hash_with_domain(self.inner.hash_builder.hash_one(key), self.inner.hash_domain)
+    }
+
+    /// Get the count of the shards.
+    pub fn shards(&self) -> usize {
         self.inner.shards.len()
     }
 
+    /// Set the pipe that receives the evicted entries, e.g. to offload them to the disk cache.
     pub fn set_pipe(&self, pipe: Box<dyn Pipe<Key = E::Key, Value = E::Value, Properties = E::Properties>>) {
         self.inner.pipe.store(Arc::new(pipe));
     }
 
+    /// Route all keys to the given shard if `Some`, or restore the shard selection by hash if `None`.
+    ///
+    /// For testing only, e.g. to put multiple keys into the same shard to exercise intra-shard contention. Must be
+    /// called before the cache is shared or any entry is inserted.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn force_shard(&mut self, shard: Option<usize>) {
+        if let Some(shard) = shard {
+            assert!(
+                shard < self.inner.shards.len(),
+                "forced shard {shard} out of range, shards: {}",
+                self.inner.shards.len()
+            );
+        }
+        Arc::get_mut(&mut self.inner)
+            .expect("the shard selection can only be overridden before the cache is shared")
+            .force_shard = shard;
+    }
+
+    /// Use the given clock to expire the entries with ttl and the tombstones of the negative cache.
+    ///
+    /// The entries and the tombstones inserted before are kept with their deadlines.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        self.inner.clock.store(Arc::new(clock.clone()));
+        for shard in self.inner.shards.iter() {
+            let mut shard = shard.write();
+            shard.clock = clock.clone();
+            if let Some(tombstones) = shard.tombstones.as_mut() {
+                tombstones.clock = clock.clone();
+            }
+        }
+    }
+
     fn shard(&self, hash: u64) -> usize {
-        hash as usize % self.inner.shards.len()
+        self.inner.shard(hash)
+    }
+}
+
+/// Split the capacity among shards as equal as possible.
+///
+/// The remainder is distributed to the leading shards, so the sum of the shard capacities equals to `capacity`.
+fn split_capacity(capacity: usize, shards: usize) -> Vec<usize> {
+    (0..shards)
+        .map(|i| capacity / shards + usize::from(i < capacity % shards))
+        .collect_vec()
+}
+
+/// Scale the shard capacities to the given total capacity, keeping their proportions.
+fn scale_capacities(shard_capacities: &[usize], capacity: usize) -> Vec<usize> {
+    let total = shard_capacities.iter().sum::<usize>();
+    if total == 0 {
+        return split_capacity(capacity, shard_capacities.len());
     }
+    let mut scaled = shard_capacities
+        .iter()
+        .map(|c| (*c as u128 * capacity as u128 / total as u128) as usize)
+        .collect_vec();
+    // Hand out the remainder of the rounding one by one.
+    let remainder = capacity - scaled.iter().sum::<usize>();
+    for c in scaled.iter_mut().take(remainder) {
+        *c += 1;
+    }
+    scaled
 }
 
+/// Entry of the [`RawCache`], holds a reference of the cached record.
 pub struct RawCacheEntry<E, S, I = HashTableIndexer<E>>
 where
     E: Eviction,
@@ -845,7 +2357,7 @@ where
 {
     fn drop(&mut self) {
         let hash = self.record.hash();
-        let shard = &self.inner.shards[hash as usize % self.inner.shards.len()];
+        let shard = &self.inner.shards[self.inner.shard(hash)];
 
         if self.record.dec_refs(1) == 0 {
             if self.record.properties().disposable().unwrap_or_default() {
@@ -870,11 +2382,11 @@ where
                         let event = Event::Evict;
                         if self.inner.event_listener.is_some() || piped {
                             if let Some(listener) = self.inner.event_listener.as_ref() {
-                                listener.on_leave(Event::Evict, record.key(), record.value());
+                                on_leave(listener.as_ref(), Event::Evict, record.key(), record.value());
                             }
                         }
                         if piped && event == Event::Evict {
-                            pipe.send(self.piece());
+                            spill(&***pipe, self.piece());
                         }
                     });
             }
@@ -932,37 +2444,235 @@ where
     S: HashBuilder,
     I: Indexer<Eviction = E>,
 {
+    /// Get the hash of the entry key.
     pub fn hash(&self) -> u64 {
         self.record.hash()
     }
 
+    /// Get the key of the entry.
     pub fn key(&self) -> &E::Key {
         self.record.key()
     }
 
+    /// Get the value of the entry.
     pub fn value(&self) -> &E::Value {
         self.record.value()
     }
 
+    /// Get the properties of the entry.
     pub fn properties(&self) -> &E::Properties {
         self.record.properties()
     }
 
+    /// Get the weight of the entry.
     pub fn weight(&self) -> usize {
         self.record.weight()
     }
 
+    /// Get the reference count of the entry.
     pub fn refs(&self) -> usize {
         self.record.refs()
     }
 
+    /// Pin the entry against eviction.
+    ///
+    /// Unlike holding the entry, the pin outlives the entry handle and lasts until [`RawCacheEntry::unpin`] is called.
+    pub fn pin(&self) {
+        self.record.pin();
+    }
+
+    /// Unpin the entry pinned by [`RawCacheEntry::pin`].
+    pub fn unpin(&self) {
+        self.record.unpin();
+    }
+
+    /// Check if the entry is no longer held by the cache, e.g. removed or replaced.
     pub fn is_outdated(&self) -> bool {
         !self.record.is_in_indexer()
     }
 
+    /// Build a piece of the entry to send to the pipe.
     pub fn piece(&self) -> Piece<E::Key, E::Value, E::Properties> {
         Piece::new(self.record.clone())
     }
+
+    /// Release the entry without blocking the async runtime on the shard lock.
+    ///
+    /// Dropping the last reference of an entry may take the shard lock to release or remove the record. If the
+    /// release needs the lock, it is offloaded to the blocking thread pool of the current runtime. Otherwise, or if
+    /// there is no runtime, the entry is dropped inline.
+    pub async fn release(self) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => self.release_with_runtime(&handle.into()).await,
+            Err(_) => drop(self),
+        }
+    }
+
+    /// Release the entry without blocking the async runtime on the shard lock, with specified runtime.
+    ///
+    /// See [`RawCacheEntry::release`].
+    pub async fn release_with_runtime(self, runtime: &SingletonHandle) {
+        // Only dropping the last reference may take the shard lock.
+        let locked = self.record.refs() == 1 && (!matches!(E::release(), Op::Noop) || self.record.is_ephemeral());
+        if !locked {
+            drop(self);
+            return;
+        }
+        if let Err(e) = runtime.spawn_blocking(move || drop(self)).await {
+            tracing::error!("[raw]: release cache entry error: {e}");
+        }
+    }
+}
+
+impl<K, V, P, S, I> RawCache<Lfu<K, V, P>, S, I>
+where
+    K: Key,
+    V: Value,
+    P: Properties,
+    S: HashBuilder,
+    I: Indexer<Eviction = Lfu<K, V, P>>,
+{
+    /// Snapshot the estimated frequencies of the resident entries of all shards, for warm restarts.
+    ///
+    /// The snapshot is versioned and can be restored with [`RawCache::restore_frequencies`], even with a different
+    /// count of shards or a different sketch size.
+    pub fn snapshot_frequencies(&self) -> Vec<u8> {
+        let frequencies = self
+            .inner
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read().eviction.resident_frequencies())
+            .collect_vec();
+        encode_frequencies(&frequencies)
+    }
+
+    /// Restore the estimated frequencies from a snapshot taken by [`RawCache::snapshot_frequencies`].
+    ///
+    /// The frequencies only raise the current estimations. The snapshot is validated before any shard is touched.
+    pub fn restore_frequencies(&self, snapshot: &[u8]) -> Result<()> {
+        let mut frequencies = vec![vec![]; self.inner.shards.len()];
+        for (hash, frequency) in decode_frequencies(snapshot)? {
+            frequencies[self.inner.shard(hash)].push((hash, frequency));
+        }
+        for (shard, frequencies) in self.inner.shards.iter().zip_eq(frequencies) {
+            if !frequencies.is_empty() {
+                shard.write().eviction.restore_frequencies(frequencies);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset the estimated frequencies of all shards to `0` without evicting any entry.
+    pub fn reset_frequencies(&self) {
+        for shard in self.inner.shards.iter() {
+            shard.write().eviction.reset_frequencies();
+        }
+    }
+}
+
+const DUMP_MAGIC: &[u8; 8] = b"FOYERMEM";
+/// Version 2 adds the pinned flag to the per-entry flags, which is the hint in version 1.
+const DUMP_VERSION: u32 = 2;
+const DUMP_FLAG_HINT_LOW: u8 = 0b01;
+const DUMP_FLAG_PINNED: u8 = 0b10;
+
+fn code_error_to_io_error(e: CodeError) -> std::io::Error {
+    match e {
+        CodeError::Io(e) => e,
+        e => std::io::Error::other(e),
+    }
+}
+
+fn invalid_data(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+impl<E, S, I> RawCache<E, S, I>
+where
+    E: Eviction,
+    S: HashBuilder,
+    I: Indexer<Eviction = E>,
+    E::Key: Code,
+    E::Value: Code,
+{
+    /// Dump all entries of the cache into the writer in a compact binary format.
+    ///
+    /// Keys, values and hints are dumped shard by shard in the eviction order, so [`RawCache::load_from`] can restore
+    /// the eviction order as much as possible. Ephemeral entries are not dumped.
+    pub fn dump_to<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        w.write_all(DUMP_MAGIC)?;
+        w.write_all(&DUMP_VERSION.to_le_bytes())?;
+        w.write_all(&(self.inner.shards.len() as u64).to_le_bytes())?;
+
+        for shard in self.inner.shards.iter() {
+            // Collect records within the lock section and serialize them out of the lock critical section.
+            let records = shard.read().dump_order();
+            w.write_all(&(records.len() as u64).to_le_bytes())?;
+            for record in records {
+                let mut flags = 0u8;
+                if record.properties().hint().unwrap_or_default() == Hint::Low {
+                    flags |= DUMP_FLAG_HINT_LOW;
+                }
+                if record.is_pinned() {
+                    flags |= DUMP_FLAG_PINNED;
+                }
+                w.write_all(&[flags])?;
+                record.key().encode(&mut w).map_err(code_error_to_io_error)?;
+                record.value().encode(&mut w).map_err(code_error_to_io_error)?;
+            }
+        }
+
+        w.flush()
+    }
+
+    /// Load entries dumped by [`RawCache::dump_to`] into the cache.
+    ///
+    /// The entries are inserted in the dumped order, the usage is recalculated with the weighter of the cache. The
+    /// pinned entries are loaded pinned once, see [`RawCacheEntry::pin`].
+    pub fn load_from<R: Read>(&self, mut r: R) -> std::io::Result<()> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != DUMP_MAGIC {
+            return Err(invalid_data(format!("invalid dump magic: {magic:?}")));
+        }
+        let mut buf = [0u8; 4];
+        r.read_exact(&mut buf)?;
+        let version = u32::from_le_bytes(buf);
+        if !(1..=DUMP_VERSION).contains(&version) {
+            return Err(invalid_data(format!(
+                "unsupported dump version: {version}, expected: {DUMP_VERSION}"
+            )));
+        }
+
+        let mut buf = [0u8; 8];
+        r.read_exact(&mut buf)?;
+        let shards = u64::from_le_bytes(buf);
+        for _ in 0..shards {
+            r.read_exact(&mut buf)?;
+            let count = u64::from_le_bytes(buf);
+            for _ in 0..count {
+                let mut flags = [0u8; 1];
+                r.read_exact(&mut flags)?;
+                let flags = flags[0];
+                if flags & !(DUMP_FLAG_HINT_LOW | DUMP_FLAG_PINNED) != 0 {
+                    return Err(invalid_data(format!("invalid dumped entry flags: {flags}")));
+                }
+                let hint = if flags & DUMP_FLAG_HINT_LOW != 0 {
+                    Hint::Low
+                } else {
+                    Hint::Normal
+                };
+                let key = E::Key::decode(&mut r).map_err(code_error_to_io_error)?;
+                let value = E::Value::decode(&mut r).map_err(code_error_to_io_error)?;
+                let entry = self.insert_with_properties(key, value, E::Properties::default().with_hint(hint));
+                if flags & DUMP_FLAG_PINNED != 0 {
+                    entry.pin();
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// The state of `fetch`.
@@ -1032,6 +2742,54 @@ impl<K, V, P> From<Piece<K, V, P>> for FetchTarget<K, V, P> {
     }
 }
 
+/// Fetcher that populates the in-memory cache from the outer storage on cache miss, e.g. the disk cache.
+///
+/// Used by `get_or_fetch`.
+pub trait Fetcher<K, V>: Send + Sync + 'static {
+    /// Fetch the value of the given key. Returns `None` if the key does not exist.
+    fn fetch(&self, key: &K) -> impl Future<Output = Option<V>> + Send;
+}
+
+/// Result of looking up a key in the in-memory cache with the negative cache.
+#[derive(Debug)]
+pub enum Lookup<T> {
+    /// The key is cached.
+    Hit(T),
+    /// The key is known to be absent in the outer storage by a tombstone of the negative cache.
+    Absent,
+    /// The key is neither cached nor known to be absent.
+    Miss,
+}
+
+impl<T> Lookup<T> {
+    /// Map the cached entry of a hit.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Lookup<U> {
+        match self {
+            Lookup::Hit(entry) => Lookup::Hit(f(entry)),
+            Lookup::Absent => Lookup::Absent,
+            Lookup::Miss => Lookup::Miss,
+        }
+    }
+
+    /// Get the cached entry of a hit.
+    pub fn hit(self) -> Option<T> {
+        match self {
+            Lookup::Hit(entry) => Some(entry),
+            Lookup::Absent | Lookup::Miss => None,
+        }
+    }
+}
+
+/// The fetcher didn't find the key, or the concurrent fetch that the caller waits for didn't.
+#[derive(Debug)]
+struct FetcherMiss;
+
+impl From<Error> for FetcherMiss {
+    fn from(_: Error) -> Self {
+        Self
+    }
+}
+
 #[pin_project(project = RawFetchInnerProj)]
 pub enum RawFetchInner<E, ER, S, I>
 where
@@ -1083,9 +2841,127 @@ where
     S: HashBuilder,
     I: Indexer<Eviction = E>,
     E::Key: Clone,
+    E::Value: Clone,
 {
-    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::fetch"))]
-    pub fn fetch<F, FU, ER>(&self, key: E::Key, fetch: F) -> RawFetch<E, ER, S, I>
+    /// Remove all entries from the cache and return them, draining the shards in parallel.
+    ///
+    /// Each shard is drained by its own blocking task that only locks that shard, so no task ever holds more than one
+    /// shard lock and there is no lock ordering across shards. The entries are notified to the event listener with
+    /// [`Event::Shutdown`] out of the lock critical section, but not offloaded via the pipe, because they are handed
+    /// over to the caller. The returned entries are grouped by shard in the shard order.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::drain_parallel"))]
+    pub async fn drain_parallel(self) -> Vec<(E::Key, E::Value)> {
+        assert_not_in_event_listener();
+
+        let handles = (0..self.inner.shards.len())
+            .map(|i| {
+                let inner = self.inner.clone();
+                tokio::task::spawn_blocking(move || {
+                    let mut garbages = vec![];
+                    inner.shards[i].write().clear(&mut garbages);
+
+                    // Deallocate data out of the lock critical section.
+                    if let Some(listener) = inner.event_listener.as_ref() {
+                        for record in garbages.iter() {
+                            on_leave(listener.as_ref(), Event::Shutdown, record.key(), record.value());
+                        }
+                    }
+                    garbages
+                        .into_iter()
+                        .map(|record| (record.key().clone(), record.value().clone()))
+                        .collect_vec()
+                })
+            })
+            .collect_vec();
+
+        let mut entries = vec![];
+        for handle in handles {
+            entries.extend(handle.await.unwrap());
+        }
+        entries
+    }
+
+    /// Get a snapshot of the entries in the given shard.
+    ///
+    /// The snapshot is taken under the shard lock, so it is consistent within the shard, but not across shards.
+    /// Ephemeral entries are not included. Keys and values are cloned out of the lock critical section.
+    pub fn shard_entries(&self, shard: usize) -> Vec<(E::Key, E::Value)> {
+        let records = self.inner.shards[shard].read().dump_order();
+        records
+            .into_iter()
+            .map(|record| (record.key().clone(), record.value().clone()))
+            .collect()
+    }
+
+    /// Get the next `n` eviction candidates of the given shard after skipping the first `skip` ones.
+    ///
+    /// Only the given shard is locked while peeking. Keys and values are cloned out of the lock critical section.
+    ///
+    /// Returns nothing if the eviction algorithm does not support peeking, see [`Eviction::peek_n`].
+    pub fn shard_eviction_candidates(&self, shard: usize, skip: usize, n: usize) -> Vec<(E::Key, E::Value)> {
+        let records = self.inner.shards[shard].read().eviction.peek_n(skip + n);
+        records
+            .into_iter()
+            .skip(skip)
+            .map(|record| (record.key().clone(), record.value().clone()))
+            .collect()
+    }
+
+    /// Get the eviction candidates of the given shard in eviction order, at most `batch` of them at a time.
+    ///
+    /// The given shard is locked once per batch. Each batch resumes after the last record of the previous batch with
+    /// [`Eviction::peek_n_after`], and falls back to [`Eviction::peek_n`] skipping the candidates already yielded if the
+    /// eviction algorithm cannot resume from the record. Only the last record is kept between the batches.
+    ///
+    /// Yields nothing if the eviction algorithm does not support peeking, see [`Eviction::peek_n`].
+    pub(crate) fn shard_eviction_batches(
+        &self,
+        shard: usize,
+        batch: usize,
+    ) -> impl Iterator<Item = Vec<(E::Key, E::Value)>> + Send {
+        let cache = self.clone();
+        let mut last: Option<Arc<Record<E>>> = None;
+        let mut yielded = 0;
+        std::iter::from_fn(move || {
+            let records = {
+                let shard = cache.inner.shards[shard].read();
+                match last
+                    .as_ref()
+                    .and_then(|record| shard.eviction.peek_n_after(record, batch))
+                {
+                    Some(records) => records,
+                    None => {
+                        let mut records = shard.eviction.peek_n(yielded + batch);
+                        records.drain(..yielded.min(records.len()));
+                        records
+                    }
+                }
+            };
+            if records.is_empty() {
+                return None;
+            }
+            last = records.last().cloned();
+            yielded += records.len();
+            Some(
+                records
+                    .into_iter()
+                    .map(|record| (record.key().clone(), record.value().clone()))
+                    .collect(),
+            )
+        })
+    }
+}
+
+impl<E, S, I> RawCache<E, S, I>
+where
+    E: Eviction,
+    S: HashBuilder,
+    I: Indexer<Eviction = E>,
+    E::Key: Clone,
+{
+    /// Get the entry with the given key, or fetch it with the given future on cache miss.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::fetch"))]
+    pub fn fetch<F, FU, ER>(&self, key: E::Key, fetch: F) -> RawFetch<E, ER, S, I>
     where
         F: FnOnce() -> FU,
         FU: Future<Output = std::result::Result<E::Value, ER>> + Send + 'static,
@@ -1103,6 +2979,7 @@ where
         feature = "tracing",
         fastrace::trace(name = "foyer::memory::raw::fetch_with_properties")
     )]
+    /// Get the entry with the given key, or fetch it with the given future and properties on cache miss.
     pub fn fetch_with_properties<F, FU, ER, ID>(
         &self,
         key: E::Key,
@@ -1137,7 +3014,7 @@ where
         ID: Into<Diversion<std::result::Result<IT, ER>, FetchContext>>,
         IT: Into<FetchTarget<E::Key, E::Value, E::Properties>>,
     {
-        let hash = self.inner.hash_builder.hash_one(&key);
+        let hash = self.hash(&key);
 
         let raw = match E::acquire() {
             Op::Noop => self.inner.shards[self.shard(hash)].read().fetch_noop(hash, &key),
@@ -1202,11 +3079,57 @@ where
 
         RawFetch::new(RawFetchInner::Miss(join))
     }
+
+    /// Get the cached entry with the given key, or fetch it with the fetcher and insert it on cache miss.
+    ///
+    /// The concurrent misses of the same key are deduplicated, so the fetcher is called only once.
+    ///
+    /// Returns `None` if the fetcher doesn't find the key.
+    ///
+    /// If the negative cache is enabled, the key that the fetcher doesn't find is kept as a tombstone, and the
+    /// following calls return `None` without calling the fetcher until the tombstone expires.
+    #[cfg_attr(feature = "tracing", fastrace::trace(name = "foyer::memory::raw::get_or_fetch"))]
+    pub async fn get_or_fetch<F>(&self, key: &E::Key, fetcher: Arc<F>) -> Option<RawCacheEntry<E, S, I>>
+    where
+        F: Fetcher<E::Key, E::Value>,
+    {
+        if self.inner.negative_ttl.is_some() {
+            match self.lookup(key) {
+                Lookup::Hit(entry) => return Some(entry),
+                Lookup::Absent => return None,
+                Lookup::Miss => {}
+            }
+        }
+
+        let k = key.clone();
+        let cache = self.clone();
+        self.fetch(key.clone(), move || async move {
+            let value = fetcher.fetch(&k).await;
+            if value.is_none() {
+                cache.insert_tombstone(k);
+            }
+            value.ok_or(FetcherMiss)
+        })
+        .await
+        .ok()
+    }
+
+    /// Keep the key as a tombstone of the negative cache, unless it is inserted in the meantime.
+    fn insert_tombstone(&self, key: E::Key) {
+        let hash = self.hash(&key);
+        let mut shard = self.inner.shards[self.shard(hash)].write();
+        if shard.indexer.get(hash, &key).is_some() {
+            return;
+        }
+        if let Some(tombstones) = shard.tombstones.as_mut() {
+            tombstones.insert(hash, key);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use foyer_common::hasher::ModHasher;
+    use foyer_common::{code::DefaultHasher, hasher::ModHasher};
     use rand::{rngs::SmallRng, seq::IndexedRandom, RngCore, SeedableRng};
 
     use super::*;
@@ -1233,18 +3156,38 @@ mod tests {
         is_send_sync_static::<RawCache<Sieve<(), (), TestProperties>, ModHasher>>();
     }
 
-    #[expect(clippy::type_complexity)]
-    fn fifo_cache_for_test(
-    ) -> RawCache<Fifo<u64, u64, TestProperties>, ModHasher, HashTableIndexer<Fifo<u64, u64, TestProperties>>> {
-        RawCache::new(RawCacheConfig {
-            capacity: 256,
-            shards: 4,
-            eviction_config: FifoConfig::default(),
+    /// Config of a single-shard cache with the given capacity and eviction config, in which every entry weighs `1`.
+    /// Override the other fields with the struct update syntax.
+    fn config_for_test<E, S>(capacity: usize, eviction_config: E::Config) -> RawCacheConfig<E, S>
+    where
+        E: Eviction,
+        S: HashBuilder + Default,
+    {
+        RawCacheConfig {
+            capacity,
+            shards: 1,
+            shard_capacities: None,
+            hot_entry: false,
+            eviction_config,
             hash_builder: Default::default(),
+            hash_domain: 0,
             weighter: Arc::new(|_, _| 1),
+            count_entries: false,
             filter: Arc::new(|_, _| true),
+            max_admit_weight: None,
+            skip_referenced: false,
+            negative_ttl: None,
             event_listener: None,
             metrics: Arc::new(Metrics::noop()),
+        }
+    }
+
+    #[expect(clippy::type_complexity)]
+    fn fifo_cache_for_test(
+    ) -> RawCache<Fifo<u64, u64, TestProperties>, ModHasher, HashTableIndexer<Fifo<u64, u64, TestProperties>>> {
+        RawCache::new(RawCacheConfig {
+            shards: 4,
+            ..config_for_test(256, FifoConfig::default())
         })
     }
 
@@ -1252,14 +3195,8 @@ mod tests {
     fn s3fifo_cache_for_test(
     ) -> RawCache<S3Fifo<u64, u64, TestProperties>, ModHasher, HashTableIndexer<S3Fifo<u64, u64, TestProperties>>> {
         RawCache::new(RawCacheConfig {
-            capacity: 256,
             shards: 4,
-            eviction_config: S3FifoConfig::default(),
-            hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
-            filter: Arc::new(|_, _| true),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
+            ..config_for_test(256, S3FifoConfig::default())
         })
     }
 
@@ -1267,14 +3204,8 @@ mod tests {
     fn lru_cache_for_test(
     ) -> RawCache<Lru<u64, u64, TestProperties>, ModHasher, HashTableIndexer<Lru<u64, u64, TestProperties>>> {
         RawCache::new(RawCacheConfig {
-            capacity: 256,
             shards: 4,
-            eviction_config: LruConfig::default(),
-            hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
-            filter: Arc::new(|_, _| true),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
+            ..config_for_test(256, LruConfig::default())
         })
     }
 
@@ -1282,14 +3213,8 @@ mod tests {
     fn lfu_cache_for_test(
     ) -> RawCache<Lfu<u64, u64, TestProperties>, ModHasher, HashTableIndexer<Lfu<u64, u64, TestProperties>>> {
         RawCache::new(RawCacheConfig {
-            capacity: 256,
             shards: 4,
-            eviction_config: LfuConfig::default(),
-            hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
-            filter: Arc::new(|_, _| true),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
+            ..config_for_test(256, LfuConfig::default())
         })
     }
 
@@ -1297,17 +3222,27 @@ mod tests {
     fn sieve_cache_for_test(
     ) -> RawCache<Sieve<u64, u64, TestProperties>, ModHasher, HashTableIndexer<Sieve<u64, u64, TestProperties>>> {
         RawCache::new(RawCacheConfig {
-            capacity: 256,
             shards: 4,
-            eviction_config: SieveConfig {},
-            hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
-            filter: Arc::new(|_, _| true),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
+            ..config_for_test(256, SieveConfig {})
         })
     }
 
+    /// Config of a single-shard LRU cache without the high priority pool, so the entries are evicted in the recency
+    /// order. Override the other fields with the struct update syntax.
+    fn plain_lru_config_for_test<K, S>(capacity: usize) -> RawCacheConfig<Lru<K, u64, TestProperties>, S>
+    where
+        K: Key,
+        S: HashBuilder + Default,
+    {
+        config_for_test(
+            capacity,
+            LruConfig {
+                high_priority_pool_ratio: 0.0,
+                ..Default::default()
+            },
+        )
+    }
+
     #[test_log::test]
     fn test_insert_ephemeral() {
         let fifo = fifo_cache_for_test();
@@ -1361,14 +3296,9 @@ mod tests {
             ModHasher,
             HashTableIndexer<Fifo<u64, u64, TestProperties>>,
         > = RawCache::new(RawCacheConfig {
-            capacity: 256,
             shards: 4,
-            eviction_config: FifoConfig::default(),
-            hash_builder: Default::default(),
-            weighter: Arc::new(|_, _| 1),
             filter: Arc::new(|k, _| !matches!(*k, 42)),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
+            ..config_for_test(256, FifoConfig::default())
         });
 
         fifo.insert(1, 1);
@@ -1380,158 +3310,1064 @@ mod tests {
         assert!(fifo.get(&42).is_none());
     }
 
+    #[cfg(debug_assertions)]
     #[test]
-    fn test_evict_all() {
-        let pipe = Box::new(PiecePipe::default());
-
-        let fifo = fifo_cache_for_test();
-        fifo.set_pipe(pipe.clone());
-        for i in 0..fifo.capacity() as _ {
-            fifo.insert(i, i);
+    fn test_audit() {
+        fn case<E>(cache: RawCache<E, ModHasher, HashTableIndexer<E>>)
+        where
+            E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+        {
+            cache.audit();
+            for i in 0..512 {
+                cache.insert(i, i);
+                if i % 3 == 0 {
+                    drop(cache.get(&(i / 2)));
+                }
+                if i % 7 == 0 {
+                    cache.remove(&(i / 3));
+                }
+            }
+            cache.audit();
+
+            // Ephemeral entries are indexed but not held by the eviction algorithm.
+            let e = cache.insert_with_properties(1024, 1024, TestProperties::default().with_ephemeral(true));
+            cache.audit();
+            drop(e);
+            cache.audit();
+
+            let held = cache.get(&511);
+            cache.remove(&511);
+            cache.audit();
+            drop(held);
+
+            cache.resize(64).unwrap();
+            cache.audit();
+            cache.evict_all();
+            cache.audit();
+            cache.clear();
+            cache.audit();
+            assert!(cache.is_empty());
         }
-        assert_eq!(fifo.usage(), fifo.capacity());
 
-        fifo.evict_all();
-        let mut pieces = pipe
-            .pieces()
-            .iter()
-            .map(|p| (p.hash(), *p.key(), *p.value()))
-            .collect_vec();
-        pieces.sort_by_key(|t| t.0);
-        let expected = (0..fifo.capacity() as u64).map(|i| (i, i, i)).collect_vec();
-        assert_eq!(pieces, expected);
+        case(fifo_cache_for_test());
+        case(s3fifo_cache_for_test());
+        case(lru_cache_for_test());
+        case(lfu_cache_for_test());
+        case(sieve_cache_for_test());
     }
 
     #[test]
-    fn test_insert_size_over_capacity() {
-        let cache: RawCache<Fifo<Vec<u8>, Vec<u8>, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-            capacity: 4 * 1024, // 4KB
-            shards: 1,
-            eviction_config: FifoConfig::default(),
-            hash_builder: Default::default(),
-            weighter: Arc::new(|k, v| k.len() + v.len()),
-            filter: Arc::new(|_, _| true),
-            event_listener: None,
-            metrics: Arc::new(Metrics::noop()),
-        });
-
-        let key = vec![b'k'; 1024]; // 1KB
-        let value = vec![b'v'; 5 * 1024]; // 5KB
-
-        cache.insert(key.clone(), value.clone());
-        assert_eq!(cache.usage(), 6 * 1024);
-        assert_eq!(cache.get(&key).unwrap().value(), &value);
-    }
+    fn test_lfu_frequency_snapshot() {
+        let lfu = |shards| -> RawCache<Lfu<u64, u64, TestProperties>, ModHasher> {
+            RawCache::new(RawCacheConfig {
+                shards,
+                ..config_for_test(256, LfuConfig::default())
+            })
+        };
+        let frequencies = |cache: &RawCache<Lfu<u64, u64, TestProperties>, ModHasher>| {
+            decode_frequencies(&cache.snapshot_frequencies())
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect_vec()
+        };
 
-    fn test_resize<E>(cache: &RawCache<E, ModHasher, HashTableIndexer<E>>)
-    where
-        E: Eviction<Key = u64, Value = u64>,
-    {
-        let capacity = cache.capacity();
-        for i in 0..capacity as u64 * 2 {
+        let cache = lfu(4);
+        for i in 0..16 {
             cache.insert(i, i);
+            for _ in 0..i {
+                drop(cache.get(&i));
+            }
         }
-        assert_eq!(cache.usage(), capacity);
-        cache.resize(capacity / 2).unwrap();
-        assert_eq!(cache.usage(), capacity / 2);
-        for i in 0..capacity as u64 * 2 {
-            cache.insert(i, i);
+        let snapshot = cache.snapshot_frequencies();
+
+        // Restore into a cache with a different count of shards.
+        let restored = lfu(1);
+        for i in 0..16 {
+            restored.insert(i, i);
         }
-        assert_eq!(cache.usage(), capacity / 2);
+        restored.restore_frequencies(&snapshot).unwrap();
+        assert_eq!(frequencies(&restored), frequencies(&cache));
+
+        // An invalid snapshot is rejected without touching the frequencies.
+        let mut corrupted = snapshot.clone();
+        corrupted[0] ^= 0xff;
+        assert!(matches!(
+            restored.restore_frequencies(&corrupted),
+            Err(Error::InvalidSnapshot(_))
+        ));
+        assert_eq!(frequencies(&restored), frequencies(&cache));
+
+        restored.reset_frequencies();
+        assert!(frequencies(&restored).iter().all(|(_, f)| *f == 0));
+        assert_eq!(restored.usage(), 16);
     }
 
     #[test]
-    fn test_fifo_cache_resize() {
-        let cache = fifo_cache_for_test();
-        test_resize(&cache);
-    }
+    fn test_eviction_weight() {
+        fn case<E>(cache: RawCache<E, ModHasher, HashTableIndexer<E>>)
+        where
+            E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+        {
+            let check = |cache: &RawCache<E, ModHasher, HashTableIndexer<E>>| {
+                for shard in cache.inner.shards.iter() {
+                    let shard = shard.read();
+                    assert_eq!(shard.eviction.weight(), shard.usage);
+                    assert_eq!(shard.eviction.capacity(), Some(shard.capacity));
+                }
+            };
 
-    #[test]
-    fn test_s3fifo_cache_resize() {
-        let cache = s3fifo_cache_for_test();
-        test_resize(&cache);
+            for i in 0..512 {
+                cache.insert(i, i);
+                if i % 3 == 0 {
+                    drop(cache.get(&(i / 2)));
+                }
+                if i % 7 == 0 {
+                    cache.remove(&(i / 3));
+                }
+            }
+            check(&cache);
+
+            cache.resize(64).unwrap();
+            check(&cache);
+            for i in 0..64 {
+                cache.insert(i, i);
+            }
+            check(&cache);
+            cache.clear();
+            check(&cache);
+            assert_eq!(cache.usage(), 0);
+        }
+
+        case(fifo_cache_for_test());
+        case(s3fifo_cache_for_test());
+        case(lru_cache_for_test());
+        case(lfu_cache_for_test());
+        case(sieve_cache_for_test());
     }
 
     #[test]
-    fn test_lru_cache_resize() {
-        let cache = lru_cache_for_test();
-        test_resize(&cache);
+    fn test_get_peek_contains() {
+        type TestLru = Lru<String, u64, TestProperties>;
+
+        let lru: RawCache<TestLru, DefaultHasher, HashTableIndexer<TestLru>> =
+            RawCache::new(plain_lru_config_for_test(4));
+
+        for (i, key) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            lru.insert(key.to_string(), i as u64);
+        }
+
+        // Hit and miss with borrowed keys.
+        let e = lru.get("b").unwrap();
+        assert_eq!((e.key().as_str(), *e.value()), ("b", 1));
+        assert_eq!(e.refs(), 1);
+        drop(e);
+        assert!(lru.get("x").is_none());
+        assert!(lru.peek("x").is_none());
+        assert!(lru.contains("a"));
+        assert!(!lru.contains("x"));
+
+        // `peek` and `contains` do not promote "a", which is still the LRU tail.
+        let e = lru.peek("a").unwrap();
+        assert_eq!(*e.value(), 0);
+        drop(e);
+        lru.insert("e".to_string(), 4);
+        assert!(!lru.contains("a"));
+
+        // `get` promotes "c", so "d" is evicted instead.
+        drop(lru.get("c"));
+        lru.insert("f".to_string(), 5);
+        assert!(!lru.contains("d"));
+        assert!(["b", "c", "e", "f"].iter().all(|key| lru.contains(*key)));
     }
 
     #[test]
-    fn test_lfu_cache_resize() {
-        let cache = lfu_cache_for_test();
-        test_resize(&cache);
+    fn test_cache_stats() {
+        let mut fifo = fifo_cache_for_test();
+        fifo.force_shard(Some(0));
+        assert_eq!(
+            fifo.stats(),
+            CacheStats {
+                capacity: 256,
+                ..Default::default()
+            }
+        );
+
+        // 64 is the capacity of each shard.
+        for i in 0..64 {
+            fifo.insert(i, i);
+        }
+        for i in 0..32 {
+            assert!(fifo.get(&i).is_some());
+        }
+        for i in 64..80 {
+            assert!(fifo.get(&i).is_none());
+        }
+        // Replace an entry, and evict 16 entries.
+        fifo.insert(63, 63);
+        for i in 64..80 {
+            fifo.insert(i, i);
+        }
+        // `peek` and `contains` are not counted.
+        assert!(fifo.peek(&63).is_some());
+        assert!(fifo.contains(&63));
+
+        let stats = fifo.stats();
+        assert_eq!(
+            stats,
+            CacheStats {
+                usage: 64,
+                capacity: 256,
+                entries: 64,
+                hits: 32,
+                misses: 16,
+                insertions: 81,
+                evictions: 16,
+                expirations: 0,
+                tombstones: 0,
+            }
+        );
+        assert_eq!(stats.hit_ratio(), 32.0 / 48.0);
+
+        let shard_stats = fifo.shard_stats();
+        assert_eq!(shard_stats.len(), 4);
+        assert_eq!(shard_stats[0], CacheStats { capacity: 64, ..stats });
+        assert!(shard_stats[1..].iter().all(|s| *s
+            == CacheStats {
+                capacity: 64,
+                ..Default::default()
+            }));
     }
 
     #[test]
-    fn test_sieve_cache_resize() {
-        let cache = sieve_cache_for_test();
-        test_resize(&cache);
-    }
+    fn test_remove_and_clear() {
+        let fifo = fifo_cache_for_test();
+        for i in 0..16 {
+            fifo.insert(i, i);
+        }
 
-    mod fuzzy {
-        use foyer_common::properties::Hint;
+        // Remove a resident key that is still held by a live entry.
+        let e1 = fifo.get(&1).unwrap();
+        let removed = fifo.remove(&1).unwrap();
+        assert_eq!((*removed.key(), *removed.value()), (1, 1));
+        assert!(!fifo.contains(&1));
+        assert!(fifo.get(&1).is_none());
+        assert_eq!(*e1.value(), 1);
+        assert_eq!(fifo.usage(), 15);
+        drop(removed);
+        drop(e1);
+        assert_eq!(fifo.usage(), 15);
 
-        use super::*;
+        // Remove a missing key.
+        assert!(fifo.remove(&1).is_none());
+        assert!(fifo.remove(&42).is_none());
+        assert_eq!(fifo.len(), 15);
 
-        fn fuzzy<E, S>(cache: RawCache<E, S>, hints: Vec<Hint>)
-        where
-            E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
-            S: HashBuilder,
-        {
-            let handles = (0..8)
-                .map(|i| {
-                    let c = cache.clone();
-                    let hints = hints.clone();
-                    std::thread::spawn(move || {
-                        let mut rng = SmallRng::seed_from_u64(i);
-                        for _ in 0..100000 {
-                            let key = rng.next_u64();
-                            if let Some(entry) = c.get(&key) {
-                                assert_eq!(key, *entry);
-                                drop(entry);
-                                continue;
-                            }
-                            let hint = hints.choose(&mut rng).cloned().unwrap();
-                            c.insert_with_properties(key, key, TestProperties::default().with_hint(hint));
-                        }
-                    })
-                })
-                .collect_vec();
+        // Remove an ephemeral entry, which is not held by the eviction algorithm.
+        let e2 = fifo.insert_with_properties(100, 100, TestProperties::default().with_ephemeral(true));
+        assert!(fifo.remove(&100).is_some());
+        assert!(!fifo.contains(&100));
+        drop(e2);
+        assert_eq!(fifo.usage(), 15);
 
-            handles.into_iter().for_each(|handle| handle.join().unwrap());
+        // Clear a populated cache while an entry is still held.
+        let e3 = fifo.get(&3).unwrap();
+        fifo.clear();
+        assert!(fifo.is_empty());
+        assert_eq!(fifo.usage(), 0);
+        assert!((0..16).all(|i| !fifo.contains(&i)));
+        assert_eq!(*e3.value(), 3);
+        drop(e3);
 
-            assert_eq!(cache.usage(), cache.capacity());
+        for i in 0..16 {
+            fifo.insert(i, i);
         }
+        fifo.invalidate_all();
+        assert!(fifo.is_empty());
+        assert_eq!(fifo.usage(), 0);
+    }
 
-        #[test_log::test]
-        fn test_fifo_cache_fuzzy() {
-            let cache: RawCache<Fifo<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-                capacity: 256,
-                shards: 4,
-                eviction_config: FifoConfig::default(),
-                hash_builder: Default::default(),
-                weighter: Arc::new(|_, _| 1),
-                filter: Arc::new(|_, _| true),
-                event_listener: None,
-                metrics: Arc::new(Metrics::noop()),
-            });
-            let hints = vec![Hint::Normal];
+    #[test]
+    fn test_evict_skip_referenced() {
+        type TestLru = Lru<u64, u64, TestProperties>;
+
+        let lru: RawCache<TestLru, ModHasher, HashTableIndexer<TestLru>> = RawCache::new(RawCacheConfig {
+            skip_referenced: true,
+            ..plain_lru_config_for_test(4)
+        });
+
+        // Hold the reference of the LRU tail.
+        let e0 = lru.insert(0, 0);
+        for i in 1..4 {
+            lru.insert(i, i);
+        }
+
+        // The unreferenced entry next to the tail is evicted instead.
+        lru.insert(4, 4);
+        assert!(lru.contains(&0));
+        assert!(!lru.contains(&1));
+        assert_eq!(lru.usage(), 4);
+
+        // The referenced entry is only evicted as the last resort.
+        let es = [2, 3, 4].map(|i| lru.get(&i).unwrap());
+        lru.insert(5, 5);
+        assert!(!lru.contains(&0));
+        assert!([2, 3, 4, 5].iter().all(|i| lru.contains(i)));
+        assert_eq!(lru.usage(), 4);
+
+        drop(e0);
+        drop(es);
+    }
+
+    #[test]
+    fn test_insert_max_admit_weight() {
+        let fifo: FifoRawCache = RawCache::new(RawCacheConfig {
+            shards: 4,
+            weighter: Arc::new(|_, v| *v as usize),
+            max_admit_weight: Some(16),
+            ..config_for_test(256, FifoConfig::default())
+        });
+
+        for i in 0..4 {
+            fifo.insert(i, 1);
+        }
+        fifo.insert(4, 16);
+        assert_eq!(fifo.usage(), 20);
+
+        // The large entry fits in the shard capacity, but is still rejected.
+        let entry = fifo.insert(5, 17);
+        assert_eq!(entry.value(), &17);
+        drop(entry);
+        assert_eq!(fifo.usage(), 20);
+        assert!(fifo.get(&5).is_none());
+        for i in 0..5 {
+            assert!(fifo.get(&i).is_some());
+        }
+
+        // Replacing an entry with a too large value removes the stale one.
+        fifo.insert(0, 32);
+        assert!(fifo.get(&0).is_none());
+        assert_eq!(fifo.usage(), 19);
+    }
+
+    type FifoRawCache = RawCache<Fifo<u64, u64, TestProperties>, ModHasher>;
+
+    /// An event listener that calls back into the cache.
+    #[derive(Default)]
+    struct ReentrantEventListener {
+        cache: Mutex<Option<FifoRawCache>>,
+        modify: bool,
+        resize: bool,
+        removed: Mutex<Vec<u64>>,
+    }
+
+    impl EventListener for ReentrantEventListener {
+        type Key = u64;
+        type Value = u64;
+
+        fn on_leave(&self, event: Event, key: &u64, _: &u64) {
+            let Some(cache) = self.cache.lock().clone() else {
+                return;
+            };
+            if self.resize {
+                let _ = cache.resize(8);
+            } else if self.modify {
+                match event {
+                    // Remove the next entry along with the evicted one.
+                    Event::Evict => assert!(cache.remove(&(key + 1)).is_some()),
+                    Event::Remove => self.removed.lock().push(*key),
+                    _ => {}
+                }
+            } else {
+                assert!(cache.get(key).is_none());
+            }
+        }
+    }
+
+    fn reentrant_cache_for_test(listener: Arc<ReentrantEventListener>) -> FifoRawCache {
+        let cache: FifoRawCache = RawCache::new(RawCacheConfig {
+            event_listener: Some(listener.clone()),
+            ..config_for_test(4, FifoConfig::default())
+        });
+        *listener.cache.lock() = Some(cache.clone());
+        cache
+    }
+
+    #[test]
+    fn test_event_listener_reentrant_read() {
+        let listener = Arc::new(ReentrantEventListener::default());
+        let cache = reentrant_cache_for_test(listener.clone());
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.usage(), 4);
+        listener.cache.lock().take();
+    }
+
+    #[test]
+    fn test_event_listener_reentrant_modify() {
+        let listener = Arc::new(ReentrantEventListener {
+            modify: true,
+            ..Default::default()
+        });
+        let cache = reentrant_cache_for_test(listener.clone());
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+
+        // Evicting 0 and 2 removes 1 and 3, the removals are notified after the eviction callbacks return.
+        assert_eq!(cache.usage(), 4);
+        assert!((4..8).all(|i| cache.contains(&i)));
+        assert_eq!(*listener.removed.lock(), vec![1, 3]);
+
+        cache.remove(&4);
+        assert_eq!(*listener.removed.lock(), vec![1, 3, 4]);
+        listener.cache.lock().take();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "modifying the in-memory cache from within the event listener is not allowed")]
+    fn test_event_listener_reentrant_resize() {
+        let listener = Arc::new(ReentrantEventListener {
+            resize: true,
+            ..Default::default()
+        });
+        let cache = reentrant_cache_for_test(listener);
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+    }
+
+    const META: u64 = 10000;
+
+    /// A pipe that inserts a metadata entry for each spilled entry into the same cache.
+    #[derive(Default)]
+    struct MetaPipe {
+        cache: Mutex<Option<FifoRawCache>>,
+        spilled: Mutex<Vec<u64>>,
+    }
+
+    impl Debug for MetaPipe {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("MetaPipe").finish()
+        }
+    }
+
+    impl Pipe for Arc<MetaPipe> {
+        type Key = u64;
+        type Value = u64;
+        type Properties = TestProperties;
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn send(&self, piece: Piece<u64, u64, TestProperties>) {
+            let key = *piece.key();
+            self.spilled.lock().push(key);
+            let Some(cache) = self.cache.lock().clone() else {
+                return;
+            };
+            if key < META {
+                let entry = cache.insert(key + META, key);
+                assert_eq!(*entry.value(), key);
+                // The insertion is made in place, only the callbacks of the entries it evicts are deferred.
+                assert!(!entry.is_outdated());
+                assert!(cache.contains(&(key + META)));
+            }
+        }
+
+        fn flush(&self, pieces: Vec<Piece<u64, u64, TestProperties>>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            for piece in pieces {
+                self.send(piece);
+            }
+            Box::pin(async {})
+        }
+    }
+
+    #[test]
+    fn test_spill_reentrant_insert() {
+        let pipe = Arc::new(MetaPipe::default());
+        let cache = reentrant_cache_for_test(Arc::new(ReentrantEventListener::default()));
+        cache.set_pipe(Box::new(pipe.clone()));
+        *pipe.cache.lock() = Some(cache.clone());
+
+        // Each spilled entry inserts a metadata entry, which evicts and spills another entry within the spill callback.
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+
+        assert_eq!(cache.usage(), 4);
+        let spilled = pipe.spilled.lock().clone();
+        for i in 0..8 {
+            assert!(cache.get(&i).is_some() || spilled.contains(&i));
+        }
+        for i in spilled.iter().filter(|i| **i < META) {
+            assert!(cache.get(&(i + META)).is_some() || spilled.contains(&(i + META)));
+        }
+        // The evictions made by the metadata entries are spilled after the spill callback returns, in order.
+        assert_eq!(
+            spilled.iter().filter(|i| **i >= META).copied().collect_vec(),
+            spilled
+                .iter()
+                .filter(|i| **i < META)
+                .map(|i| i + META)
+                .take_while(|i| spilled.contains(i))
+                .collect_vec()
+        );
+
+        pipe.cache.lock().take();
+    }
+
+    fn dump_keys<E>(cache: &RawCache<E, ModHasher>) -> Vec<Vec<u64>>
+    where
+        E: Eviction<Key = u64, Value = u64>,
+    {
+        cache
+            .inner
+            .shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .read()
+                    .eviction
+                    .dump_order()
+                    .iter()
+                    .map(|r| *r.key())
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_dump_and_load() {
+        let fifo = fifo_cache_for_test();
+        for i in 0..300 {
+            fifo.insert(i, i);
+        }
+        let mut buf = vec![];
+        fifo.dump_to(&mut buf).unwrap();
+
+        let restored = fifo_cache_for_test();
+        restored.load_from(&buf[..]).unwrap();
+        assert_eq!(restored.usage(), fifo.usage());
+        assert_eq!(dump_keys(&restored), dump_keys(&fifo));
+
+        let lru = lru_cache_for_test();
+        for i in 0..200 {
+            let hint = if i % 3 == 0 { Hint::Low } else { Hint::Normal };
+            lru.insert_with_properties(i, i, TestProperties::default().with_hint(hint));
+        }
+        for i in (0..200).step_by(7) {
+            lru.get(&i);
+        }
+        let mut buf = vec![];
+        lru.dump_to(&mut buf).unwrap();
+
+        let restored = lru_cache_for_test();
+        restored.load_from(&buf[..]).unwrap();
+        assert_eq!(restored.usage(), lru.usage());
+        assert_eq!(dump_keys(&restored), dump_keys(&lru));
+
+        let mut corrupted = buf.clone();
+        corrupted[0] = b'X';
+        let err = lru_cache_for_test().load_from(&corrupted[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // The pinned entries are loaded pinned, no matter they are in the pin list of LRU or not.
+        let lru = lru_cache_for_test();
+        for i in 0..8 {
+            lru.insert(i, i);
+        }
+        lru.get(&1).unwrap().pin();
+        let held = lru.get(&2).unwrap();
+        held.pin();
+        let mut buf = vec![];
+        lru.dump_to(&mut buf).unwrap();
+        drop(held);
+
+        let restored = lru_cache_for_test();
+        restored.load_from(&buf[..]).unwrap();
+        for i in 0..8 {
+            assert_eq!(restored.peek(&i).unwrap().record.is_pinned(), i == 1 || i == 2);
+        }
+    }
+
+    #[test]
+    fn test_evict_all() {
+        let pipe = Box::new(PiecePipe::default());
+
+        let fifo = fifo_cache_for_test();
+        fifo.set_pipe(pipe.clone());
+        for i in 0..fifo.capacity() as _ {
+            fifo.insert(i, i);
+        }
+        assert_eq!(fifo.usage(), fifo.capacity());
+
+        fifo.evict_all();
+        let mut pieces = pipe
+            .pieces()
+            .iter()
+            .map(|p| (p.hash(), *p.key(), *p.value()))
+            .collect_vec();
+        pieces.sort_by_key(|t| t.0);
+        let expected = (0..fifo.capacity() as u64).map(|i| (i, i, i)).collect_vec();
+        assert_eq!(pieces, expected);
+    }
+
+    #[test]
+    fn test_shard_eviction_batches() {
+        let cache: FifoRawCache = RawCache::new(config_for_test(16, FifoConfig::default()));
+        for i in 0..16 {
+            cache.insert(i, i);
+        }
+        let keys = |batch: Option<Vec<(u64, u64)>>| batch.unwrap().into_iter().map(|(k, _)| k).collect_vec();
+
+        let mut batches = cache.shard_eviction_batches(0, 4);
+        assert_eq!(keys(batches.next()), [0, 1, 2, 3]);
+        // Resume after the last yielded record.
+        cache.remove(&0);
+        assert_eq!(keys(batches.next()), [4, 5, 6, 7]);
+        // The last yielded record has left the cache, fall back to skip the count of the yielded candidates. Both removed
+        // records shift the eviction order, so two candidates are skipped.
+        cache.remove(&7);
+        assert_eq!(keys(batches.next()), [10, 11, 12, 13]);
+        assert_eq!(keys(batches.next()), [14, 15]);
+        assert!(batches.next().is_none());
+    }
+
+    #[test]
+    fn test_insert_size_over_capacity() {
+        let cache: RawCache<Fifo<Vec<u8>, Vec<u8>, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+            weighter: Arc::new(|k, v| k.len() + v.len()),
+            ..config_for_test(4 * 1024 /* 4KB */, FifoConfig::default())
+        });
+
+        let key = vec![b'k'; 1024]; // 1KB
+        let value = vec![b'v'; 5 * 1024]; // 5KB
+
+        cache.insert(key.clone(), value.clone());
+        assert_eq!(cache.usage(), 6 * 1024);
+        assert_eq!(cache.get(&key).unwrap().value(), &value);
+    }
+
+    fn test_resize<E>(cache: &RawCache<E, ModHasher, HashTableIndexer<E>>)
+    where
+        E: Eviction<Key = u64, Value = u64>,
+    {
+        let capacity = cache.capacity();
+        for i in 0..capacity as u64 * 2 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.usage(), capacity);
+        cache.resize(capacity / 2).unwrap();
+        assert_eq!(cache.usage(), capacity / 2);
+        for i in 0..capacity as u64 * 2 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.usage(), capacity / 2);
+    }
+
+    #[test]
+    fn test_fifo_cache_resize() {
+        let cache = fifo_cache_for_test();
+        test_resize(&cache);
+    }
+
+    #[test]
+    fn test_s3fifo_cache_resize() {
+        let cache = s3fifo_cache_for_test();
+        test_resize(&cache);
+    }
+
+    #[test]
+    fn test_lru_cache_resize() {
+        let cache = lru_cache_for_test();
+        test_resize(&cache);
+    }
+
+    #[test]
+    fn test_lfu_cache_resize() {
+        let cache = lfu_cache_for_test();
+        test_resize(&cache);
+    }
+
+    #[test]
+    fn test_sieve_cache_resize() {
+        let cache = sieve_cache_for_test();
+        test_resize(&cache);
+    }
+
+    #[test]
+    fn test_shard_capacities() {
+        let cache: RawCache<Fifo<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+            shards: 2,
+            shard_capacities: Some(vec![64, 16]),
+            ..config_for_test(0, FifoConfig::default())
+        });
+        assert_eq!(cache.capacity(), 80);
+
+        // With `ModHasher`, even keys go to shard 0 and odd keys go to shard 1.
+        for i in 0..64 {
+            cache.insert(i * 2, i * 2);
+            cache.insert(i * 2 + 1, i * 2 + 1);
+        }
+        assert_eq!(cache.usage(), 80);
+        // The undersized shard evicts earlier than the oversized one under the same load.
+        for i in 0..64 {
+            assert!(cache.contains(&(i * 2)));
+            assert_eq!(cache.contains(&(i * 2 + 1)), i >= 48);
+        }
+
+        cache.set_shard_capacity(0, 8).unwrap();
+        assert_eq!(cache.shard_capacity(0), 8);
+        assert_eq!(cache.capacity(), 24);
+        assert_eq!(cache.usage(), 24);
+        assert!(!cache.contains(&0));
+        assert!(cache.contains(&126));
+
+        assert!(matches!(cache.set_shard_capacity(2, 8), Err(Error::ConfigError(_))));
+        assert_eq!(cache.capacity(), 24);
+
+        // Resizing keeps the proportions of the shard capacities.
+        cache.resize(48).unwrap();
+        assert_eq!(cache.shard_capacity(0), 16);
+        assert_eq!(cache.shard_capacity(1), 32);
+        assert_eq!(cache.capacity(), 48);
+    }
+
+    #[test]
+    fn test_shard_capacities_mismatch() {
+        let res: Result<RawCache<Fifo<u64, u64, TestProperties>, ModHasher>> = RawCache::try_new(RawCacheConfig {
+            shards: 4,
+            shard_capacities: Some(vec![64, 16]),
+            ..config_for_test(0, FifoConfig::default())
+        });
+        assert!(matches!(res, Err(Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_split_capacity() {
+        assert_eq!(split_capacity(256, 4), vec![64; 4]);
+        assert_eq!(split_capacity(10, 4), vec![3, 3, 2, 2]);
+        assert_eq!(split_capacity(10, 4).into_iter().sum::<usize>(), 10);
+    }
+
+    #[test]
+    fn test_scale_capacities() {
+        assert_eq!(scale_capacities(&[64, 16], 40), vec![32, 8]);
+        assert_eq!(scale_capacities(&[1, 1, 1], 10), vec![4, 3, 3]);
+        assert_eq!(scale_capacities(&[0, 0], 10), vec![5, 5]);
+        assert_eq!(scale_capacities(&[3, 0, 7], 0), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rebuild_index() {
+        let cache = fifo_cache_for_test();
+        for i in 0..16 {
+            cache.insert(i, i);
+        }
+
+        for shard in cache.inner.shards.iter() {
+            shard.write().indexer.drain().for_each(drop);
+        }
+        assert!((0..16).all(|i| cache.get(&i).is_none()));
+
+        cache.rebuild_index();
+        for i in 0..16 {
+            assert_eq!(cache.get(&i).unwrap().value(), &i);
+        }
+        assert_eq!(cache.usage(), 16);
+    }
+
+    #[test]
+    fn test_pinned_record_survives_eviction() {
+        let cache = fifo_cache_for_test();
+
+        // With `ModHasher`, keys `4 * n` go to shard 0 and keys `4 * n + 1` go to shard 1.
+        cache.insert(0, 0).pin();
+        drop(cache.insert(1, 1));
+        assert_eq!(cache.get(&0).unwrap().refs(), 1);
+
+        for i in 1..=64 {
+            cache.insert(i * 4, i * 4);
+            cache.insert(i * 4 + 1, i * 4 + 1);
+        }
+        assert!(cache.contains(&0));
+        assert!(!cache.contains(&1));
+
+        cache.get(&0).unwrap().unpin();
+        for i in 65..=128 {
+            cache.insert(i * 4, i * 4);
+        }
+        assert!(!cache.contains(&0));
+    }
+
+    #[test]
+    fn test_pinned_record_keeps_eviction_state() {
+        let cache = s3fifo_cache_for_test();
+
+        // With `ModHasher`, keys `4 * n` go to shard 0.
+        let entry = cache.insert(0, 0);
+        entry.pin();
+        drop(entry);
+        cache.get(&0).unwrap();
+        cache.get(&0).unwrap();
+
+        let frequency = |cache: &RawCache<S3Fifo<u64, u64, TestProperties>, ModHasher>| {
+            let shard = cache.inner.shards[0].read();
+            let record = shard.indexer.get(0, &0).unwrap().clone();
+            shard.eviction.frequency_of(&record)
+        };
+        assert_eq!(frequency(&cache), Some(2));
+
+        // The pinned record is passed over in place instead of being popped and pushed back.
+        for i in 1..=256 {
+            cache.insert(i * 4, i * 4);
+        }
+        assert!(cache.contains(&0));
+        assert_eq!(frequency(&cache), Some(2));
+        assert_eq!(cache.usage(), 64);
+    }
+
+    #[test]
+    fn test_hot_entry() {
+        let cache: RawCache<Fifo<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+            shards: 4,
+            hot_entry: true,
+            ..config_for_test(256, FifoConfig::default())
+        });
+        // With `ModHasher`, key 1 goes to shard 1.
+        let shard = 1;
+
+        cache.insert(1, 1);
+        // The first `get` takes the locked path and fills the hot entry. Hold the entry, so dropping the entries in
+        // the threads below doesn't need to release the record with the shard lock.
+        let entry = cache.get(&1).unwrap();
+        assert!(cache.inner.hot_entries[shard].is(&entry.record));
+
+        // Gets of the hot key complete without the shard lock, even if the shard is exclusively locked.
+        {
+            let _guard = cache.inner.shards[shard].write();
+            std::thread::scope(|s| {
+                for _ in 0..4 {
+                    s.spawn(|| {
+                        for _ in 0..8 {
+                            assert_eq!(cache.get(&1).unwrap().value(), &1);
+                        }
+                    });
+                }
+            });
+        }
+        assert_eq!(entry.refs(), 1);
+
+        // The hot entry is invalidated on removal.
+        cache.remove(&1);
+        assert!(cache.inner.hot_entries[shard].record.load().is_none());
+        assert!(cache.get(&1).is_none());
+        drop(entry);
+
+        // Replaced records are invalidated as well.
+        cache.insert(1, 1);
+        let old = cache.get(&1).unwrap();
+        cache.insert(1, 2);
+        assert!(!cache.inner.hot_entries[shard].is(&old.record));
+        let entry = cache.get(&1).unwrap();
+        assert_eq!(entry.value(), &2);
+
+        // The hot path clears the ephemeral flag as the locked path does.
+        entry.record.set_ephemeral(true);
+        assert!(cache.inner.hot_entries[shard].is(&entry.record));
+        drop(cache.get(&1).unwrap());
+        assert!(!entry.record.is_ephemeral());
+
+        // The hot entry is disabled for the eviction algorithms that observe the accesses.
+        fn hot_entries<E>(eviction_config: E::Config) -> usize
+        where
+            E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+        {
+            let cache: RawCache<E, ModHasher> = RawCache::new(RawCacheConfig {
+                shards: 4,
+                hot_entry: true,
+                ..config_for_test(256, eviction_config)
+            });
+            cache.inner.hot_entries.len()
+        }
+        assert_eq!(hot_entries::<Lru<_, _, _>>(LruConfig::default()), 0);
+        assert_eq!(hot_entries::<S3Fifo<_, _, _>>(S3FifoConfig::default()), 0);
+        assert_eq!(hot_entries::<Sieve<_, _, _>>(SieveConfig), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch() {
+        struct MockFetcher {
+            fetched: std::sync::atomic::AtomicUsize,
+        }
+
+        impl Fetcher<u64, u64> for MockFetcher {
+            async fn fetch(&self, key: &u64) -> Option<u64> {
+                self.fetched.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                (*key < 100).then_some(*key)
+            }
+        }
+
+        let cache = fifo_cache_for_test();
+        let fetcher = Arc::new(MockFetcher {
+            fetched: Default::default(),
+        });
+
+        for key in [1, 1000] {
+            let handles = (0..16)
+                .map(|_| {
+                    let cache = cache.clone();
+                    let fetcher = fetcher.clone();
+                    tokio::spawn(async move { cache.get_or_fetch(&key, fetcher).await.map(|e| *e) })
+                })
+                .collect_vec();
+            for handle in handles {
+                let res = handle.await.unwrap();
+                assert_eq!(res, (key < 100).then_some(key));
+            }
+        }
+        assert_eq!(fetcher.fetched.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // Hit without fetching.
+        assert_eq!(cache.get_or_fetch(&1, fetcher.clone()).await.map(|e| *e), Some(1));
+        assert_eq!(fetcher.fetched.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_negative() {
+        use foyer_common::clock::MockClock;
+
+        type TestFifo = Fifo<u64, u64, TestProperties>;
+
+        struct MockFetcher {
+            fetched: AtomicUsize,
+        }
+
+        impl Fetcher<u64, u64> for MockFetcher {
+            async fn fetch(&self, key: &u64) -> Option<u64> {
+                self.fetched.fetch_add(1, Ordering::Relaxed);
+                (*key < 100).then_some(*key)
+            }
+        }
+
+        let cache: RawCache<TestFifo, ModHasher, HashTableIndexer<TestFifo>> = RawCache::new(RawCacheConfig {
+            shards: 4,
+            negative_ttl: Some(Duration::from_secs(60)),
+            ..config_for_test(256, FifoConfig::default())
+        });
+        let clock = MockClock::default();
+        cache.set_clock(Arc::new(clock.clone()));
+        let fetcher = Arc::new(MockFetcher {
+            fetched: AtomicUsize::new(0),
+        });
+        let fetched = || fetcher.fetched.load(Ordering::Relaxed);
+
+        // The missing key is tombstoned without taking any capacity.
+        assert!(matches!(cache.lookup(&1000), Lookup::Miss));
+        assert!(cache.get_or_fetch(&1000, fetcher.clone()).await.is_none());
+        assert_eq!(fetched(), 1);
+        assert!(matches!(cache.lookup(&1000), Lookup::Absent));
+        assert!(cache.get(&1000).is_none());
+        assert_eq!(cache.usage(), 0);
+
+        // The tombstone short-circuits the fetcher until it expires.
+        clock.advance(Duration::from_secs(30));
+        assert!(cache.get_or_fetch(&1000, fetcher.clone()).await.is_none());
+        assert_eq!(fetched(), 1);
+        clock.advance(Duration::from_secs(30));
+        assert!(matches!(cache.lookup(&1000), Lookup::Miss));
+        assert!(cache.get_or_fetch(&1000, fetcher.clone()).await.is_none());
+        assert_eq!(fetched(), 2);
+
+        // The found key is cached as usual.
+        assert_eq!(cache.get_or_fetch(&1, fetcher.clone()).await.map(|e| *e), Some(1));
+        assert_eq!(cache.lookup(&1).hit().map(|e| *e), Some(1));
+        assert_eq!(fetched(), 3);
+
+        // Inserting the key drops its tombstone.
+        cache.insert(1000, 1000);
+        cache.remove(&1000);
+        assert!(matches!(cache.lookup(&1000), Lookup::Miss));
+
+        // Clearing the cache drops the tombstones.
+        assert!(cache.get_or_fetch(&2000, fetcher.clone()).await.is_none());
+        assert!(matches!(cache.lookup(&2000), Lookup::Absent));
+        cache.clear();
+        assert!(matches!(cache.lookup(&2000), Lookup::Miss));
+
+        // The tombstones are bounded by the shard capacity, the oldest ones are dropped first.
+        // With `ModHasher`, keys `4 * n` go to shard 0, whose capacity is 64.
+        for i in 0..128 {
+            assert!(cache.get_or_fetch(&(4000 + i * 4), fetcher.clone()).await.is_none());
+        }
+        assert_eq!(cache.stats().tombstones, 64);
+        assert!(matches!(cache.lookup(&4000), Lookup::Miss));
+        assert!(matches!(cache.lookup(&(4000 + 127 * 4)), Lookup::Absent));
+
+        // Shrinking the shard drops the oldest tombstones.
+        cache.set_shard_capacity(0, 16).unwrap();
+        assert_eq!(cache.stats().tombstones, 16);
+        assert!(matches!(cache.lookup(&(4000 + 111 * 4)), Lookup::Miss));
+        assert!(matches!(cache.lookup(&(4000 + 112 * 4)), Lookup::Absent));
+    }
+
+    mod fuzzy {
+        use foyer_common::properties::Hint;
+
+        use super::*;
+
+        fn fuzzy<E, S>(cache: RawCache<E, S>, hints: Vec<Hint>)
+        where
+            E: Eviction<Key = u64, Value = u64, Properties = TestProperties>,
+            S: HashBuilder,
+        {
+            let handles = (0..8)
+                .map(|i| {
+                    let c = cache.clone();
+                    let hints = hints.clone();
+                    std::thread::spawn(move || {
+                        let mut rng = SmallRng::seed_from_u64(i);
+                        for _ in 0..100000 {
+                            let key = rng.next_u64();
+                            if let Some(entry) = c.get(&key) {
+                                assert_eq!(key, *entry);
+                                drop(entry);
+                                continue;
+                            }
+                            let hint = hints.choose(&mut rng).cloned().unwrap();
+                            c.insert_with_properties(key, key, TestProperties::default().with_hint(hint));
+                        }
+                    })
+                })
+                .collect_vec();
+
+            handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+            #[cfg(debug_assertions)]
+            cache.audit();
+            assert_eq!(cache.usage(), cache.capacity());
+        }
+
+        #[test_log::test]
+        fn test_fifo_cache_fuzzy() {
+            let cache: RawCache<Fifo<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+                shards: 4,
+                ..config_for_test(256, FifoConfig::default())
+            });
+            let hints = vec![Hint::Normal];
             fuzzy(cache, hints);
         }
 
         #[test_log::test]
         fn test_s3fifo_cache_fuzzy() {
             let cache: RawCache<S3Fifo<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-                capacity: 256,
                 shards: 4,
-                eviction_config: S3FifoConfig::default(),
-                hash_builder: Default::default(),
-                weighter: Arc::new(|_, _| 1),
-                filter: Arc::new(|_, _| true),
-                event_listener: None,
-                metrics: Arc::new(Metrics::noop()),
+                ..config_for_test(256, S3FifoConfig::default())
             });
             let hints = vec![Hint::Normal];
             fuzzy(cache, hints);
@@ -1540,14 +4376,8 @@ mod tests {
         #[test_log::test]
         fn test_lru_cache_fuzzy() {
             let cache: RawCache<Lru<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-                capacity: 256,
                 shards: 4,
-                eviction_config: LruConfig::default(),
-                hash_builder: Default::default(),
-                weighter: Arc::new(|_, _| 1),
-                filter: Arc::new(|_, _| true),
-                event_listener: None,
-                metrics: Arc::new(Metrics::noop()),
+                ..config_for_test(256, LruConfig::default())
             });
             let hints = vec![Hint::Normal, Hint::Low];
             fuzzy(cache, hints);
@@ -1556,14 +4386,8 @@ mod tests {
         #[test_log::test]
         fn test_lfu_cache_fuzzy() {
             let cache: RawCache<Lfu<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-                capacity: 256,
                 shards: 4,
-                eviction_config: LfuConfig::default(),
-                hash_builder: Default::default(),
-                weighter: Arc::new(|_, _| 1),
-                filter: Arc::new(|_, _| true),
-                event_listener: None,
-                metrics: Arc::new(Metrics::noop()),
+                ..config_for_test(256, LfuConfig::default())
             });
             let hints = vec![Hint::Normal];
             fuzzy(cache, hints);
@@ -1572,17 +4396,544 @@ mod tests {
         #[test_log::test]
         fn test_sieve_cache_fuzzy() {
             let cache: RawCache<Sieve<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
-                capacity: 256,
                 shards: 4,
-                eviction_config: SieveConfig {},
-                hash_builder: Default::default(),
-                weighter: Arc::new(|_, _| 1),
-                filter: Arc::new(|_, _| true),
-                event_listener: None,
-                metrics: Arc::new(Metrics::noop()),
+                ..config_for_test(256, SieveConfig {})
             });
             let hints = vec![Hint::Normal];
             fuzzy(cache, hints);
         }
     }
+
+    #[cfg(feature = "tracing_test")]
+    #[test]
+    fn test_get_span_properties() {
+        use fastrace::{
+            collector::{Config, Reporter, SpanRecord},
+            prelude::*,
+        };
+
+        #[derive(Debug, Clone, Default)]
+        struct TestReporter(Arc<Mutex<Vec<SpanRecord>>>);
+
+        impl Reporter for TestReporter {
+            fn report(&mut self, spans: Vec<SpanRecord>) {
+                self.0.lock().extend(spans);
+            }
+        }
+
+        let reporter = TestReporter::default();
+        fastrace::set_reporter(reporter.clone(), Config::default());
+
+        let cache = fifo_cache_for_test();
+        cache.insert(1, 1);
+        {
+            let root = Span::root("test_get_span_properties", SpanContext::random());
+            let _guard = root.set_local_parent();
+            assert!(cache.get(&1).is_some());
+            assert!(cache.get(&2).is_none());
+        }
+        fastrace::flush();
+
+        let mut gets = reporter
+            .0
+            .lock()
+            .iter()
+            .filter(|span| span.name == "foyer::memory::raw::get")
+            .map(|span| {
+                span.properties
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect_vec()
+            })
+            .collect_vec();
+        gets.sort();
+        let expected = [
+            [("hash", "1"), ("shard", "1"), ("outcome", "hit")],
+            [("hash", "2"), ("shard", "2"), ("outcome", "miss")],
+        ]
+        .map(|properties| properties.map(|(k, v)| (k.to_string(), v.to_string())).to_vec())
+        .to_vec();
+        assert_eq!(gets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_release_does_not_block_executor() {
+        let cache = Arc::new(lru_cache_for_test());
+        // Lru releases the record with the shard lock.
+        drop(cache.insert(0, 0));
+        let entry = cache.get(&0).unwrap();
+        assert_eq!(entry.refs(), 1);
+
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let (alive_tx, alive_rx) = std::sync::mpsc::channel();
+        let c = cache.clone();
+        let holder = std::thread::spawn(move || {
+            let _guard = c.inner.shards[0].write();
+            locked_tx.send(()).unwrap();
+            // Hold the lock until the executor proves it is not blocked.
+            alive_rx.recv_timeout(std::time::Duration::from_secs(3)).is_ok()
+        });
+        locked_rx.recv().unwrap();
+
+        // The test runs on a current thread runtime, the release task would stall the only worker if it took the
+        // shard lock on the executor.
+        let release = tokio::spawn(entry.release());
+        tokio::task::yield_now().await;
+        alive_tx.send(()).unwrap();
+
+        assert!(holder.join().unwrap());
+        release.await.unwrap();
+        assert_eq!(cache.get(&0).unwrap().refs(), 1);
+    }
+
+    #[test]
+    fn test_release_inline() {
+        let cache = Arc::new(lru_cache_for_test());
+        drop(cache.insert(0, 0));
+        let entry = cache.get(&0).unwrap();
+        let other = entry.clone();
+
+        // Releasing a reference that is not the last one never takes the shard lock, even without a runtime.
+        let guard = cache.inner.shards[0].write();
+        assert!(futures_util::FutureExt::now_or_never(other.release()).is_some());
+        drop(guard);
+        assert_eq!(entry.refs(), 1);
+
+        // Without a runtime, the last reference is dropped inline.
+        assert!(futures_util::FutureExt::now_or_never(entry.release()).is_some());
+        assert_eq!(cache.get(&0).unwrap().refs(), 1);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let cache = fifo_cache_for_test();
+        cache.reserve(200);
+        let capacities = cache
+            .inner
+            .shards
+            .iter()
+            .map(|shard| shard.read().indexer.capacity())
+            .collect_vec();
+        assert!(capacities.iter().all(|&capacity| capacity >= 50));
+
+        // Keys are distributed evenly among the shards, the indexers don't grow.
+        for i in 0..200 {
+            cache.insert(i, i);
+        }
+        for (shard, capacity) in cache.inner.shards.iter().zip(capacities) {
+            assert_eq!(shard.read().indexer.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn test_shard_balance() {
+        let cache = fifo_cache_for_test();
+        assert_eq!(cache.shard_balance(), vec![0.0; 4]);
+        assert_eq!(cache.max_shard_skew(), 0.0);
+
+        // Keys are distributed evenly among the shards.
+        for i in 0..128 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.shard_balance(), vec![0.5; 4]);
+        assert_eq!(cache.max_shard_skew(), 0.0);
+
+        // Most of the keys land in shard 0.
+        let cache = fifo_cache_for_test();
+        for i in 0..64 {
+            cache.insert(i * 4, i);
+        }
+        for i in 0..8 {
+            cache.insert(i * 4 + 1, i);
+        }
+        assert_eq!(cache.shard_balance(), vec![1.0, 0.125, 0.0, 0.0]);
+        assert!(cache.max_shard_skew() > 0.9);
+    }
+
+    /// A pipe that spills the flushed pieces into a channel asynchronously.
+    #[derive(Debug)]
+    struct SpillPipe {
+        tx: tokio::sync::mpsc::UnboundedSender<u64>,
+    }
+
+    impl Pipe for SpillPipe {
+        type Key = u64;
+        type Value = u64;
+        type Properties = TestProperties;
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn send(&self, piece: Piece<u64, u64, TestProperties>) {
+            self.tx.send(*piece.key()).unwrap();
+        }
+
+        fn flush(&self, pieces: Vec<Piece<u64, u64, TestProperties>>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            let tx = self.tx.clone();
+            Box::pin(async move {
+                for piece in pieces {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    tx.send(*piece.key()).unwrap();
+                }
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct ExpireEventListener {
+        keys: Mutex<Vec<u64>>,
+    }
+
+    impl EventListener for ExpireEventListener {
+        type Key = u64;
+        type Value = u64;
+
+        fn on_leave(&self, event: Event, key: &u64, _: &u64) {
+            if event == Event::Expire {
+                self.keys.lock().push(*key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ttl() {
+        use foyer_common::clock::MockClock;
+
+        let listener = Arc::new(ExpireEventListener::default());
+        let lru: RawCache<Lru<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+            event_listener: Some(listener.clone()),
+            ..plain_lru_config_for_test(4)
+        });
+        let clock = MockClock::default();
+        lru.set_clock(Arc::new(clock.clone()));
+        let ttl = |secs| TestProperties::default().with_ttl(Duration::from_secs(secs));
+
+        lru.insert_with_properties(1, 1, ttl(10));
+        lru.insert_with_properties(2, 2, ttl(20));
+        lru.insert(3, 3);
+
+        // `peek` and `contains` treat the expired entry as absent without removing it.
+        clock.advance(Duration::from_secs(10));
+        assert!(!lru.contains(&1));
+        assert!(lru.peek(&1).is_none());
+        assert_eq!(lru.usage(), 3);
+        assert!(listener.keys.lock().is_empty());
+
+        // `get` treats the expired entry as a miss and removes it.
+        assert!(lru.get(&1).is_none());
+        assert_eq!(lru.usage(), 2);
+        assert_eq!(*listener.keys.lock(), vec![1]);
+
+        // Promote 2, so 3 is the LRU tail. The replaced entry of 5 leaves a stale deadline behind.
+        assert_eq!(lru.get(&2).map(|e| *e), Some(2));
+        lru.insert(4, 4);
+        lru.insert_with_properties(5, 5, ttl(5));
+        lru.insert(5, 5);
+        assert_eq!(lru.usage(), 4);
+
+        // The expired entry is removed before the LRU tail is evicted.
+        clock.advance(Duration::from_secs(10));
+        lru.insert(6, 6);
+        assert!(!lru.contains(&2));
+        assert!([3, 4, 5, 6].iter().all(|key| lru.contains(key)));
+        assert_eq!(*listener.keys.lock(), vec![1, 2]);
+        assert_eq!(lru.usage(), 4);
+        assert_eq!(lru.stats().evictions, 0);
+        assert_eq!(lru.stats().expirations, 2);
+
+        // Entries without ttl never expire.
+        clock.advance(Duration::from_secs(3600));
+        assert!([3, 4, 5, 6].iter().all(|key| lru.get(key).is_some()));
+        assert_eq!(*listener.keys.lock(), vec![1, 2]);
+
+        // `get_many` removes the expired entries as well.
+        lru.insert_with_properties(7, 7, ttl(10));
+        clock.advance(Duration::from_secs(10));
+        let entries = lru.get_many(&[4, 7]);
+        assert!(entries[0].is_some());
+        assert!(entries[1].is_none());
+        assert_eq!(*listener.keys.lock(), vec![1, 2, 7]);
+        assert_eq!(lru.usage(), 3);
+    }
+
+    type LruRawCache = RawCache<Lru<u64, u64, TestProperties>, ModHasher>;
+
+    /// An event listener that gets the next key on removal, and records the events with the depth of the calls.
+    #[derive(Default)]
+    struct ReentrantExpireEventListener {
+        cache: Mutex<Option<LruRawCache>>,
+        depth: AtomicUsize,
+        events: Mutex<Vec<(Event, u64, usize)>>,
+    }
+
+    impl EventListener for ReentrantExpireEventListener {
+        type Key = u64;
+        type Value = u64;
+
+        fn on_leave(&self, event: Event, key: &u64, _: &u64) {
+            let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+            self.events.lock().push((event, *key, depth));
+            let cache = self.cache.lock().clone();
+            if let (Event::Remove, Some(cache)) = (event, cache) {
+                assert!(cache.get(&(key + 1)).is_none());
+            }
+            self.depth.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_ttl_reentrant_get() {
+        use foyer_common::clock::MockClock;
+
+        let listener = Arc::new(ReentrantExpireEventListener::default());
+        let lru: LruRawCache = RawCache::new(RawCacheConfig {
+            event_listener: Some(listener.clone()),
+            ..plain_lru_config_for_test(4)
+        });
+        *listener.cache.lock() = Some(lru.clone());
+        let clock = MockClock::default();
+        lru.set_clock(Arc::new(clock.clone()));
+
+        lru.insert(1, 1);
+        lru.insert_with_properties(2, 2, TestProperties::default().with_ttl(Duration::from_secs(10)));
+        clock.advance(Duration::from_secs(10));
+
+        // The expiration found by `get` within the listener is notified after the outer call returns.
+        lru.remove(&1);
+        assert_eq!(
+            *listener.events.lock(),
+            vec![(Event::Remove, 1, 1), (Event::Expire, 2, 1)]
+        );
+        assert_eq!(lru.usage(), 0);
+
+        listener.cache.lock().take();
+    }
+
+    #[derive(Default)]
+    struct ShutdownEventListener {
+        keys: Mutex<Vec<u64>>,
+    }
+
+    impl EventListener for ShutdownEventListener {
+        type Key = u64;
+        type Value = u64;
+
+        fn on_leave(&self, event: Event, key: &u64, _: &u64) {
+            if event == Event::Shutdown {
+                self.keys.lock().push(*key);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let listener = Arc::new(ShutdownEventListener::default());
+        let cache: FifoRawCache = RawCache::new(RawCacheConfig {
+            shards: 4,
+            event_listener: Some(listener.clone()),
+            ..config_for_test(256, FifoConfig::default())
+        });
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        cache.set_pipe(Box::new(SpillPipe { tx }));
+
+        for i in 0..100 {
+            cache.insert(i, i);
+        }
+        let held = cache.get(&0).unwrap();
+
+        cache.clone().shutdown().await;
+
+        // Every entry is delivered before `shutdown` returns.
+        let mut spilled = vec![];
+        while let Ok(key) = rx.try_recv() {
+            spilled.push(key);
+        }
+        spilled.sort();
+        assert_eq!(spilled, (0..100).collect_vec());
+        let mut notified = listener.keys.lock().clone();
+        notified.sort();
+        assert_eq!(notified, (0..100).collect_vec());
+
+        assert_eq!(cache.usage(), 0);
+        assert!(cache.get(&0).is_none());
+        // The held entry outlives the shutdown.
+        assert_eq!(held.value(), &0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_drain_parallel() {
+        let listener = Arc::new(ShutdownEventListener::default());
+        let cache: FifoRawCache = RawCache::new(RawCacheConfig {
+            shards: 8,
+            event_listener: Some(listener.clone()),
+            ..config_for_test(1024, FifoConfig::default())
+        });
+
+        for i in 0..1000 {
+            cache.insert(i, i * 2);
+        }
+        let held = cache.get(&0).unwrap();
+
+        let mut drained = cache.clone().drain_parallel().await;
+        drained.sort();
+        // Every entry is returned exactly once.
+        assert_eq!(drained, (0..1000).map(|i| (i, i * 2)).collect_vec());
+        let mut notified = listener.keys.lock().clone();
+        notified.sort();
+        assert_eq!(notified, (0..1000).collect_vec());
+
+        assert_eq!(cache.usage(), 0);
+        assert!(cache.get(&0).is_none());
+        assert_eq!(held.value(), &0);
+    }
+
+    #[test]
+    fn test_insert_with_weight() {
+        let cache: FifoRawCache = RawCache::new(RawCacheConfig {
+            max_admit_weight: Some(128),
+            ..config_for_test(256, FifoConfig::default())
+        });
+
+        let e1 = cache.insert_with_weight(1, 1, 100);
+        assert_eq!(e1.weight(), 100);
+        cache.insert(2, 2);
+        assert_eq!(cache.usage(), 101);
+
+        // Replacing the entry accounts the explicit weight of the old entry.
+        cache.insert_with_weight(1, 10, 50);
+        assert_eq!(cache.usage(), 51);
+
+        // The explicit weight evicts the other entries.
+        cache.insert_with_weight(3, 3, 128);
+        cache.insert_with_weight(4, 4, 128);
+        assert!(cache.usage() <= 256);
+        assert!(cache.get(&1).is_none());
+
+        // The explicit weight is subject to the max admission weight.
+        drop(e1);
+        let e5 = cache.insert_with_weight(5, 5, 129);
+        assert!(e5.properties().disposable().unwrap_or_default());
+        assert!(cache.get(&5).is_none());
+    }
+
+    #[test]
+    fn test_insert_if_absent() {
+        let fifo = fifo_cache_for_test();
+
+        let (e1, inserted) = fifo.insert_if_absent(1, 1);
+        assert!(inserted);
+        assert_eq!(e1.value(), &1);
+
+        let (e2, inserted) = fifo.insert_if_absent(1, 2);
+        assert!(!inserted);
+        assert_eq!(e2.value(), &1);
+        assert!(Arc::ptr_eq(&e1.record, &e2.record));
+        assert_eq!(fifo.usage(), 1);
+
+        // The lookup is not counted.
+        let stats = fifo.stats();
+        assert_eq!((stats.hits, stats.misses), (0, 0));
+    }
+
+    #[test]
+    fn test_insert_if_absent_ttl() {
+        use foyer_common::clock::MockClock;
+
+        let cache: RawCache<Lru<u64, u64, TestProperties>, ModHasher> = RawCache::new(plain_lru_config_for_test(4));
+        let clock = MockClock::default();
+        cache.set_clock(Arc::new(clock.clone()));
+
+        let (_, inserted) =
+            cache.insert_if_absent_with_properties(1, 1, TestProperties::default().with_ttl(Duration::from_secs(1)));
+        assert!(inserted);
+        let (_, inserted) = cache.insert_if_absent(1, 2);
+        assert!(!inserted);
+
+        clock.advance(Duration::from_secs(1));
+        let (entry, inserted) = cache.insert_if_absent(1, 3);
+        assert!(inserted);
+        assert_eq!(entry.value(), &3);
+    }
+
+    #[test]
+    fn test_insert_if_absent_race() {
+        const THREADS: u64 = 16;
+
+        for key in 0..16 {
+            let fifo = Arc::new(fifo_cache_for_test());
+            let barrier = Arc::new(std::sync::Barrier::new(THREADS as usize));
+            let handles = (0..THREADS)
+                .map(|i| {
+                    let fifo = fifo.clone();
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        let (entry, inserted) = fifo.insert_if_absent(key, i);
+                        (*entry.value(), inserted)
+                    })
+                })
+                .collect_vec();
+            let results = handles.into_iter().map(|handle| handle.join().unwrap()).collect_vec();
+
+            // Exactly one call wins, and all calls observe the winner's value.
+            let winners = results.iter().filter(|(_, inserted)| *inserted).collect_vec();
+            assert_eq!(winners.len(), 1);
+            let value = winners[0].0;
+            assert!(results.iter().all(|(v, _)| *v == value));
+            assert_eq!(fifo.get(&key).unwrap().value(), &value);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clear_cooperative() {
+        let cache: RawCache<Lfu<u64, u64, TestProperties>, ModHasher> = RawCache::new(RawCacheConfig {
+            shards: 4,
+            hot_entry: true,
+            ..config_for_test(4096, LfuConfig::default())
+        });
+        for i in 0..4096 {
+            cache.insert(i, i);
+        }
+        let ephemeral = cache.insert_with_properties(4096, 4096, TestProperties::default().with_ephemeral(true));
+        let usage = cache.usage();
+
+        // The concurrent task observes the cache between the bounded critical sections.
+        let observer = {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.usage() })
+        };
+        cache.clear_cooperative(64).await;
+        let observed = observer.await.unwrap();
+        assert!(observed > 0 && observed < usage, "observed: {observed}");
+
+        assert_eq!(cache.usage(), 0);
+        for i in 0..=4096 {
+            assert!(cache.get(&i).is_none());
+        }
+        assert_eq!(ephemeral.value(), &4096);
+        for shard in cache.inner.shards.iter() {
+            let mut shard = shard.write();
+            assert_eq!(shard.indexer.drain().count(), 0);
+            assert!(shard.eviction.dump_order().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clear_cooperative_pinned_lru() {
+        let cache = lru_cache_for_test();
+        cache.insert(0, 0);
+        // The held entry is pinned by LRU and never popped.
+        let entry = cache.get(&0).unwrap();
+
+        cache.clear_cooperative(1).await;
+        assert_eq!(cache.usage(), 0);
+        drop(entry);
+
+        // Insert past the capacity of shard 0 to trigger the eviction.
+        for i in 0..256 {
+            cache.insert(i * 4, i * 4);
+        }
+        assert_eq!(cache.usage(), 64);
+    }
 }
@@ -15,7 +15,13 @@
 use std::{
     cell::UnsafeCell,
     fmt::Debug,
-    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
 };
 
 use bitflags::bitflags;
@@ -31,27 +37,103 @@ bitflags! {
     }
 }
 
+/// Owner of the states of the records held by an eviction algorithm instance.
+///
+/// An eviction algorithm instance keeps its own owner, binds a record to it with [`Record::bind`] when the record is
+/// pushed, and unbinds it with [`Record::unbind`] when the record is popped or removed. The state of a record can only
+/// be accessed with the owner it is bound to, see [`Record::state_ref`] and [`Record::state_mut`].
+///
+/// Each owner has a unique id and can't be cloned, so a mutable borrow of the owner guarantees that no other reference
+/// of the states bound to it is alive.
+#[cfg(feature = "custom-eviction")]
+#[derive(Debug)]
+pub struct StateOwner {
+    id: u64,
+}
+
+#[cfg(feature = "custom-eviction")]
+impl Default for StateOwner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "custom-eviction")]
+impl StateOwner {
+    /// Create an owner with a unique id.
+    pub fn new() -> Self {
+        // `0` is reserved for the records that are not bound to any owner.
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Data of the cached entry.
+///
+/// When the [`Record`] that holds the data is dropped, the value is dropped before the key, so a value that logically
+/// refers to its key can still rely on the key during its drop. The properties are dropped last.
 pub struct Data<E>
 where
     E: Eviction,
 {
+    /// Key of the entry.
     pub key: E::Key,
+    /// Value of the entry.
     pub value: E::Value,
+    /// Properties of the entry.
     pub properties: E::Properties,
+    /// Hash of the key.
     pub hash: u64,
+    /// Weight of the entry.
     pub weight: usize,
 }
 
+/// Fields of the [`Record`], which only depend on the associated types of the eviction algorithm.
+struct Slot<K, V, P, S> {
+    /// Dropped field by field in a guaranteed order, see [`Data`].
+    key: ManuallyDrop<K>,
+    value: ManuallyDrop<V>,
+    properties: ManuallyDrop<P>,
+    hash: u64,
+    weight: usize,
+    state: UnsafeCell<S>,
+    /// Id of the [`StateOwner`] the state is bound to, `0` if it is not bound.
+    #[cfg(feature = "custom-eviction")]
+    owner: AtomicU64,
+    /// Reference count used in the in-memory cache.
+    refs: AtomicUsize,
+    /// Pin count that prevents the record from being evicted, independent of `refs`.
+    pins: AtomicUsize,
+    flags: AtomicU64,
+    /// Deadline after which the record expires, `None` if it never expires.
+    expire_at: Option<Instant>,
+}
+
+impl<K, V, P, S> Drop for Slot<K, V, P, S> {
+    fn drop(&mut self) {
+        // SAFETY: The fields are never accessed after the slot is dropped, and each field is dropped exactly once.
+        unsafe {
+            ManuallyDrop::drop(&mut self.value);
+            ManuallyDrop::drop(&mut self.key);
+            ManuallyDrop::drop(&mut self.properties);
+        }
+    }
+}
+
 /// [`Record`] holds the information of the cached entry.
+///
+/// [`Record`] is a transparent wrapper of its fields, which only depend on the associated types of the eviction
+/// algorithm. So the records of two eviction algorithms with the same associated types share the same layout, see
+/// [`Record::cast`].
+#[repr(transparent)]
 pub struct Record<E>
 where
     E: Eviction,
 {
-    data: Data<E>,
-    state: UnsafeCell<E::State>,
-    /// Reference count used in the in-memory cache.
-    refs: AtomicUsize,
-    flags: AtomicU64,
+    slot: Slot<E::Key, E::Value, E::Properties, E::State>,
+    _marker: PhantomData<fn() -> E>,
 }
 
 unsafe impl<E> Send for Record<E> where E: Eviction {}
@@ -62,7 +144,7 @@ where
     E: Eviction,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Record").field("hash", &self.data.hash).finish()
+        f.debug_struct("Record").field("hash", &self.slot.hash).finish()
     }
 }
 
@@ -71,48 +153,162 @@ where
     E: Eviction,
 {
     /// `state` field memory layout offset of the [`Record`].
-    pub const STATE_OFFSET: usize = std::mem::offset_of!(Self, state);
+    pub const STATE_OFFSET: usize =
+        std::mem::offset_of!(Self, slot) + std::mem::offset_of!(Slot<E::Key, E::Value, E::Properties, E::State>, state);
 
     /// Create a record with data.
     pub fn new(data: Data<E>) -> Self {
         Record {
-            data,
-            state: Default::default(),
-            refs: AtomicUsize::new(0),
-            flags: AtomicU64::new(0),
+            slot: Slot {
+                key: ManuallyDrop::new(data.key),
+                value: ManuallyDrop::new(data.value),
+                properties: ManuallyDrop::new(data.properties),
+                hash: data.hash,
+                weight: data.weight,
+                state: Default::default(),
+                #[cfg(feature = "custom-eviction")]
+                owner: AtomicU64::new(0),
+                refs: AtomicUsize::new(0),
+                pins: AtomicUsize::new(0),
+                flags: AtomicU64::new(0),
+                expire_at: None,
+            },
+            _marker: PhantomData,
         }
     }
 
+    /// Cast the record into the record of another eviction algorithm with the same associated types, e.g. to hand the
+    /// records of an eviction decorator to the decorated eviction algorithm.
+    ///
+    /// The reference count and the state are kept.
+    pub(crate) fn cast<F>(record: Arc<Self>) -> Arc<Record<F>>
+    where
+        F: Eviction<Key = E::Key, Value = E::Value, Properties = E::Properties, State = E::State>,
+    {
+        // SAFETY: `Record<E>` and `Record<F>` are both `repr(transparent)` wrappers of the same `Slot` type, so they
+        // have the same size and alignment, which is what `Arc::from_raw` requires.
+        unsafe { Arc::from_raw(Arc::into_raw(record) as *const Record<F>) }
+    }
+
+    /// Borrow the record as the record of another eviction algorithm with the same associated types, see
+    /// [`Record::cast`].
+    ///
+    /// The returned [`Arc`] MUST NOT be dropped, so the reference count is balanced.
+    pub(crate) fn cast_ref<F>(record: &Arc<Self>) -> ManuallyDrop<Arc<Record<F>>>
+    where
+        F: Eviction<Key = E::Key, Value = E::Value, Properties = E::Properties, State = E::State>,
+    {
+        // SAFETY: See `cast`.
+        ManuallyDrop::new(unsafe { Arc::from_raw(Arc::as_ptr(record) as *const Record<F>) })
+    }
+
+    /// Borrow the record as the record of another eviction algorithm with the same associated types, see
+    /// [`Record::cast`].
+    pub(crate) fn cast_borrow<F>(record: &Self) -> &Record<F>
+    where
+        F: Eviction<Key = E::Key, Value = E::Value, Properties = E::Properties, State = E::State>,
+    {
+        // SAFETY: See `cast`.
+        unsafe { &*(record as *const Self as *const Record<F>) }
+    }
+
+    /// Set the deadline after which the record expires.
+    pub(crate) fn with_expire_at(mut self, expire_at: Option<Instant>) -> Self {
+        self.slot.expire_at = expire_at;
+        self
+    }
+
+    /// Get the deadline after which the record expires, `None` if it never expires.
+    pub fn expire_at(&self) -> Option<Instant> {
+        self.slot.expire_at
+    }
+
+    /// Check if the record is expired at the given time.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.slot.expire_at.is_some_and(|expire_at| expire_at <= now)
+    }
+
     /// Get the immutable reference of the record key.
     pub fn key(&self) -> &E::Key {
-        &self.data.key
+        &self.slot.key
     }
 
     /// Get the immutable reference of the record value.
     pub fn value(&self) -> &E::Value {
-        &self.data.value
+        &self.slot.value
     }
 
     /// Get the immutable reference of the record properties.
     pub fn properties(&self) -> &E::Properties {
-        &self.data.properties
+        &self.slot.properties
     }
 
     /// Get the record hash.
     pub fn hash(&self) -> u64 {
-        self.data.hash
+        self.slot.hash
     }
 
     /// Get the record weight.
     pub fn weight(&self) -> usize {
-        self.data.weight
+        self.slot.weight
     }
 
     /// Get the record state wrapped with [`UnsafeCell`].
     ///
     /// # Safety
     pub fn state(&self) -> &UnsafeCell<E::State> {
-        &self.state
+        &self.slot.state
+    }
+
+    /// Bind the record state to the owner.
+    ///
+    /// Returns `false` if the record state is already bound to an owner.
+    #[cfg(feature = "custom-eviction")]
+    pub fn bind(&self, owner: &StateOwner) -> bool {
+        self.slot
+            .owner
+            .compare_exchange(0, owner.id, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Unbind the record state from the owner, so it can be bound to another owner.
+    ///
+    /// The owner is borrowed mutably, so no reference of the state resolved with it is alive.
+    ///
+    /// Returns `false` if the record state is not bound to the owner.
+    #[cfg(feature = "custom-eviction")]
+    pub fn unbind(&self, owner: &mut StateOwner) -> bool {
+        self.slot
+            .owner
+            .compare_exchange(owner.id, 0, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Get the immutable reference of the record state with the owner it is bound to.
+    ///
+    /// Returns `None` if the record state is not bound to the owner.
+    #[cfg(feature = "custom-eviction")]
+    pub fn state_ref<'a>(&'a self, owner: &'a StateOwner) -> Option<&'a E::State> {
+        if self.slot.owner.load(Ordering::Acquire) != owner.id {
+            return None;
+        }
+        // SAFETY: The state is only accessed through the owner it is bound to, and it can't be rebound while the owner
+        // is borrowed. Mutable references require a mutable borrow of the owner, which can't coexist with this one.
+        Some(unsafe { &*self.slot.state.get() })
+    }
+
+    /// Get the mutable reference of the record state with the owner it is bound to.
+    ///
+    /// Returns `None` if the record state is not bound to the owner.
+    #[cfg(feature = "custom-eviction")]
+    pub fn state_mut<'a>(&'a self, owner: &'a mut StateOwner) -> Option<&'a mut E::State> {
+        if self.slot.owner.load(Ordering::Acquire) != owner.id {
+            return None;
+        }
+        // SAFETY: The state is only accessed through the owner it is bound to, and it can't be rebound while the owner
+        // is borrowed. The owner is borrowed mutably as long as the returned reference, so no other reference of the
+        // state is alive.
+        Some(unsafe { &mut *self.slot.state.get() })
     }
 
     /// Set in eviction flag with relaxed memory order.
@@ -148,26 +344,26 @@ where
     /// Set the record atomic flags.
     pub fn set_flags(&self, flags: Flags, val: bool, order: Ordering) {
         match val {
-            true => self.flags.fetch_or(flags.bits(), order),
-            false => self.flags.fetch_and(!flags.bits(), order),
+            true => self.slot.flags.fetch_or(flags.bits(), order),
+            false => self.slot.flags.fetch_and(!flags.bits(), order),
         };
     }
 
     /// Get the record atomic flags.
     pub fn get_flags(&self, flags: Flags, order: Ordering) -> bool {
-        self.flags.load(order) & flags.bits() == flags.bits()
+        self.slot.flags.load(order) & flags.bits() == flags.bits()
     }
 
     /// Get the atomic reference count.
     pub fn refs(&self) -> usize {
-        self.refs.load(Ordering::Acquire)
+        self.slot.refs.load(Ordering::Acquire)
     }
 
     /// Increase the atomic reference count.
     ///
     /// This function returns the new reference count after the op.
     pub fn inc_refs(&self, val: usize) -> usize {
-        let old = self.refs.fetch_add(val, Ordering::SeqCst);
+        let old = self.slot.refs.fetch_add(val, Ordering::SeqCst);
         tracing::trace!(
             "[record]: inc record (hash: {}) refs: {} => {}",
             self.hash(),
@@ -181,7 +377,7 @@ where
     ///
     /// This function returns the new reference count after the op.
     pub fn dec_refs(&self, val: usize) -> usize {
-        let old = self.refs.fetch_sub(val, Ordering::SeqCst);
+        let old = self.slot.refs.fetch_sub(val, Ordering::SeqCst);
         tracing::trace!(
             "[record]: dec record (hash: {}) refs: {} => {}",
             self.hash(),
@@ -190,4 +386,172 @@ where
         );
         old - val
     }
+
+    /// Get the atomic pin count.
+    pub fn pins(&self) -> usize {
+        self.slot.pins.load(Ordering::Acquire)
+    }
+
+    /// Check if the record is pinned against eviction.
+    pub fn is_pinned(&self) -> bool {
+        self.pins() > 0
+    }
+
+    /// Pin the record, so it will be skipped by the eviction until unpinned.
+    ///
+    /// This function returns the new pin count after the op.
+    pub fn pin(&self) -> usize {
+        self.slot.pins.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Unpin the record.
+    ///
+    /// This function returns the new pin count after the op. Unpinning a record that is not pinned is a no-op, so the
+    /// pin count saturates at 0 and never wraps around.
+    pub fn unpin(&self) -> usize {
+        match self
+            .slot
+            .pins
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pins| pins.checked_sub(1))
+        {
+            Ok(old) => old - 1,
+            Err(_) => {
+                tracing::warn!("[record]: unpin record (hash: {}) that is not pinned", self.hash());
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{hash::Hash, sync::Arc};
+
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::eviction::{fifo::Fifo, test_utils::TestProperties};
+
+    /// A key or value that logs its name when dropped.
+    struct Tracked {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl PartialEq for Tracked {
+        fn eq(&self, other: &Self) -> bool {
+            self.name == other.name
+        }
+    }
+
+    impl Eq for Tracked {}
+
+    impl Hash for Tracked {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.name.hash(state);
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.log.lock().push(self.name);
+        }
+    }
+
+    #[test]
+    fn test_record_flags_read_without_side_effect() {
+        let record = Record::<Fifo<u64, u64, TestProperties>>::new(Data {
+            key: 1,
+            value: 1,
+            properties: TestProperties::default(),
+            hash: 1,
+            weight: 1,
+        });
+        record.set_in_indexer(true);
+        record.set_in_eviction(true);
+
+        // Reading a flag must not clear the others.
+        assert!(record.is_in_indexer());
+        assert!(record.is_in_eviction());
+        assert!(!record.is_ephemeral());
+        assert!(record.is_in_indexer());
+        assert!(record.get_flags(Flags::IN_INDEXER | Flags::IN_EVICTION, Ordering::Acquire));
+
+        record.set_in_indexer(false);
+        assert!(!record.is_in_indexer());
+        assert!(record.is_in_eviction());
+    }
+
+    #[test]
+    fn test_record_unpin_saturate() {
+        let record = Record::<Fifo<u64, u64, TestProperties>>::new(Data {
+            key: 1,
+            value: 1,
+            properties: TestProperties::default(),
+            hash: 1,
+            weight: 1,
+        });
+
+        assert_eq!(record.pin(), 1);
+        assert_eq!(record.unpin(), 0);
+        // Excess unpin doesn't underflow the pin count.
+        assert_eq!(record.unpin(), 0);
+        assert!(!record.is_pinned());
+        assert_eq!(record.pin(), 1);
+        assert!(record.is_pinned());
+    }
+
+    #[cfg(feature = "custom-eviction")]
+    #[test]
+    fn test_record_state_owner() {
+        let record = Record::<Fifo<u64, u64, TestProperties>>::new(Data {
+            key: 1,
+            value: 1,
+            properties: TestProperties::default(),
+            hash: 1,
+            weight: 1,
+        });
+        let mut owner = StateOwner::new();
+        let mut other = StateOwner::new();
+
+        // An unbound state can't be accessed by any owner.
+        assert!(record.state_ref(&owner).is_none());
+        assert!(record.state_mut(&mut owner).is_none());
+
+        assert!(record.bind(&owner));
+        assert!(!record.bind(&other));
+        assert!(record.state_ref(&owner).is_some());
+        assert!(record.state_mut(&mut owner).is_some());
+        assert!(record.state_ref(&other).is_none());
+        assert!(record.state_mut(&mut other).is_none());
+
+        // Only the bound owner can unbind the state.
+        assert!(!record.unbind(&mut other));
+        assert!(record.unbind(&mut owner));
+        assert!(record.state_ref(&owner).is_none());
+
+        assert!(record.bind(&other));
+        assert!(record.state_mut(&mut other).is_some());
+        assert!(record.state_ref(&owner).is_none());
+    }
+
+    #[test]
+    fn test_record_drop_order() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let tracked = |name| Tracked { name, log: log.clone() };
+
+        let record = Arc::new(Record::<Fifo<Tracked, Tracked, TestProperties>>::new(Data {
+            key: tracked("key"),
+            value: tracked("value"),
+            properties: TestProperties::default(),
+            hash: 0,
+            weight: 1,
+        }));
+        let cloned = record.clone();
+        drop(record);
+        assert!(log.lock().is_empty());
+
+        drop(cloned);
+        assert_eq!(*log.lock(), vec!["value", "key"]);
+    }
 }
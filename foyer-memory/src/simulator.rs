@@ -0,0 +1,187 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    cache::{CacheProperties, EvictionConfig},
+    eviction::{decay_lfu::DecayLfu, fifo::Fifo, lfu::Lfu, lru::Lru, s3fifo::S3Fifo, sieve::Sieve, Eviction, Op},
+    record::{Data, Record},
+};
+
+/// Hit and miss counts of a simulation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    /// Count of the accesses that hit the cache.
+    pub hits: usize,
+    /// Count of the accesses that miss the cache.
+    pub misses: usize,
+}
+
+impl SimulationStats {
+    /// Get the hit ratio of the simulation, `0.0` if there is no access.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+/// Replays a trace of key hashes through an eviction algorithm without real values.
+///
+/// Every entry weighs `1`, so the capacity is the count of the entries that the simulated cache can hold. A missed
+/// hash is inserted into the simulated cache, and the entries are evicted in the order of the eviction algorithm until
+/// the usage fits the capacity.
+pub struct Simulator<E>
+where
+    E: Eviction<Key = u64, Value = ()>,
+{
+    eviction: E,
+    capacity: usize,
+    records: HashMap<u64, Arc<Record<E>>>,
+    stats: SimulationStats,
+}
+
+impl<E> Simulator<E>
+where
+    E: Eviction<Key = u64, Value = ()>,
+    E::Properties: Default,
+{
+    /// Create a simulator with the given capacity and eviction algorithm config.
+    pub fn new(capacity: usize, config: &E::Config) -> Self {
+        Self {
+            eviction: E::new(capacity, config),
+            capacity,
+            records: HashMap::new(),
+            stats: SimulationStats::default(),
+        }
+    }
+
+    /// Access the given hash, return `true` if it hits the simulated cache.
+    pub fn access(&mut self, hash: u64) -> bool {
+        if let Some(record) = self.records.get(&hash) {
+            match E::acquire() {
+                Op::Noop => {}
+                Op::Immutable(f) => f(&self.eviction, record),
+                Op::Mutable(mut f) => f(&mut self.eviction, record),
+            }
+            match E::release() {
+                Op::Noop => {}
+                Op::Immutable(f) => f(&self.eviction, record),
+                Op::Mutable(mut f) => f(&mut self.eviction, record),
+            }
+            self.stats.hits += 1;
+            return true;
+        }
+
+        self.stats.misses += 1;
+        let record = Arc::new(Record::new(Data {
+            key: hash,
+            value: (),
+            properties: E::Properties::default(),
+            hash,
+            weight: 1,
+        }));
+        self.eviction.push(record.clone());
+        self.records.insert(hash, record);
+        while self.records.len() > self.capacity {
+            let Some(record) = self.eviction.pop() else { break };
+            self.records.remove(&record.hash());
+        }
+        false
+    }
+
+    /// Replay the trace and return the accumulated stats.
+    pub fn replay(&mut self, trace: &[u64]) -> SimulationStats {
+        for &hash in trace {
+            self.access(hash);
+        }
+        self.stats
+    }
+
+    /// Get the accumulated stats.
+    pub fn stats(&self) -> SimulationStats {
+        self.stats
+    }
+}
+
+impl<E> Drop for Simulator<E>
+where
+    E: Eviction<Key = u64, Value = ()>,
+{
+    fn drop(&mut self) {
+        self.eviction.clear();
+    }
+}
+
+/// Replay the trace of key hashes through the eviction algorithm of the given config.
+///
+/// See [`Simulator`].
+pub fn simulate(eviction_config: &EvictionConfig, capacity: usize, trace: &[u64]) -> SimulationStats {
+    match eviction_config {
+        EvictionConfig::Fifo(config) => {
+            Simulator::<Fifo<u64, (), CacheProperties>>::new(capacity, config).replay(trace)
+        }
+        EvictionConfig::S3Fifo(config) => {
+            Simulator::<S3Fifo<u64, (), CacheProperties>>::new(capacity, config).replay(trace)
+        }
+        EvictionConfig::Lru(config) => Simulator::<Lru<u64, (), CacheProperties>>::new(capacity, config).replay(trace),
+        EvictionConfig::Lfu(config) => Simulator::<Lfu<u64, (), CacheProperties>>::new(capacity, config).replay(trace),
+        EvictionConfig::Sieve(config) => {
+            Simulator::<Sieve<u64, (), CacheProperties>>::new(capacity, config).replay(trace)
+        }
+        EvictionConfig::DecayLfu(config) => {
+            Simulator::<DecayLfu<u64, (), CacheProperties>>::new(capacity, config).replay(trace)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eviction::{fifo::FifoConfig, lru::LruConfig, test_utils::TestProperties};
+
+    #[test]
+    fn test_simulator() {
+        let mut simulator = Simulator::<Fifo<u64, (), TestProperties>>::new(2, &FifoConfig {});
+        assert!(!simulator.access(1));
+        assert!(!simulator.access(2));
+        assert!(simulator.access(1));
+        // Evicts 1.
+        assert!(!simulator.access(3));
+        assert!(!simulator.access(1));
+        assert_eq!(simulator.stats(), SimulationStats { hits: 1, misses: 4 });
+        assert_eq!(simulator.stats().hit_ratio(), 0.2);
+    }
+
+    #[test]
+    fn test_simulate_recency_friendly_trace() {
+        // A few hot keys are accessed between a stream of one-hit keys.
+        let trace = (0..1000u64).flat_map(|i| [i % 4, 1000 + i]).collect::<Vec<_>>();
+
+        let fifo = simulate(&FifoConfig {}.into(), 8, &trace);
+        let lru = simulate(&LruConfig::default().into(), 8, &trace);
+
+        assert_eq!(fifo.hits + fifo.misses, trace.len());
+        assert_eq!(lru.hits + lru.misses, trace.len());
+        assert!(
+            lru.hit_ratio() > fifo.hit_ratio(),
+            "lru: {}, fifo: {}",
+            lru.hit_ratio(),
+            fifo.hit_ratio()
+        );
+    }
+}
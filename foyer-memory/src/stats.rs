@@ -0,0 +1,122 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    ops::Add,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A snapshot of the statistics of the in-memory cache or one of its shards.
+///
+/// The counters are cumulative since the cache is created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Total weight of the entries.
+    pub usage: usize,
+    /// Capacity.
+    pub capacity: usize,
+    /// Count of the entries.
+    pub entries: usize,
+    /// Count of the lookups that hit.
+    pub hits: u64,
+    /// Count of the lookups that miss.
+    pub misses: u64,
+    /// Count of the inserted entries, including the ones that replace an existing entry.
+    pub insertions: u64,
+    /// Count of the entries evicted by the eviction algorithm.
+    pub evictions: u64,
+    /// Count of the entries removed for their ttl expire.
+    pub expirations: u64,
+    /// Count of the tombstones of the negative cache.
+    pub tombstones: usize,
+}
+
+impl CacheStats {
+    /// Get the hit ratio of the lookups, `0.0` if there is no lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+impl Add for CacheStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            usage: self.usage + rhs.usage,
+            capacity: self.capacity + rhs.capacity,
+            entries: self.entries + rhs.entries,
+            hits: self.hits + rhs.hits,
+            misses: self.misses + rhs.misses,
+            insertions: self.insertions + rhs.insertions,
+            evictions: self.evictions + rhs.evictions,
+            expirations: self.expirations + rhs.expirations,
+            tombstones: self.tombstones + rhs.tombstones,
+        }
+    }
+}
+
+/// Cumulative counters of a shard.
+///
+/// The counters are atomics so that they can be updated with the shard read lock or without the shard lock (e.g. the
+/// hot entry).
+#[derive(Debug, Default)]
+pub(crate) struct ShardCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+impl ShardCounters {
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn insert(&self) {
+        self.insertions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn evict(&self) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn expire(&self) {
+        self.expirations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the counters with the given usage, capacity, entry count and tombstone count of the shard.
+    pub fn snapshot(&self, usage: usize, capacity: usize, entries: usize, tombstones: usize) -> CacheStats {
+        CacheStats {
+            usage,
+            capacity,
+            entries,
+            tombstones,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            insertions: self.insertions.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            expirations: self.expirations.load(Ordering::Relaxed),
+        }
+    }
+}
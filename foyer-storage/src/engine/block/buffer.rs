@@ -27,9 +27,9 @@ use crate::{
     error::Error,
     io::{
         bytes::{IoSlice, IoSliceMut},
-        PAGE,
+        IoAlignment,
     },
-    serde::{Checksummer, EntrySerializer},
+    serde::{ChecksumAlgorithm, Checksummer, EntrySerializer},
 };
 
 /// [`EntryIndex`] index entry in a blob, which can be used to speed up recovery.
@@ -70,8 +70,8 @@ impl BlobEntryIndex {
         }
     }
 
-    pub fn aligned(&self) -> usize {
-        bits::align_up(PAGE, self.len as _)
+    pub fn aligned(&self, align: usize) -> usize {
+        bits::align_up(align, self.len as _)
     }
 }
 
@@ -160,8 +160,8 @@ pub struct BufferEntryInfo {
 }
 
 impl BufferEntryInfo {
-    pub fn aligned(&self) -> usize {
-        bits::align_up(PAGE, self.len)
+    pub fn aligned(&self, align: usize) -> usize {
+        bits::align_up(align, self.len)
     }
 }
 
@@ -191,13 +191,22 @@ impl Buffer {
         self.entry_infos.is_empty()
     }
 
-    pub fn push<K, V>(&mut self, key: &K, value: &V, hash: u64, compression: Compression, sequence: Sequence) -> bool
+    pub fn push<K, V>(
+        &mut self,
+        key: &K,
+        value: &V,
+        hash: u64,
+        compression: Compression,
+        checksum_algorithm: ChecksumAlgorithm,
+        sequence: Sequence,
+    ) -> bool
     where
         K: StorageKey,
         V: StorageValue,
     {
         tracing::trace!(hash, "[blob writer]: push");
 
+        let align = self.bytes.alignment();
         let offset = self.written;
         let buf = &mut self.bytes[offset..];
 
@@ -217,7 +226,8 @@ impl Buffer {
                 return false;
             }
         };
-        let checksum = Checksummer::checksum64(
+        let checksum = Checksummer::checksum(
+            checksum_algorithm,
             &buf[EntryHeader::serialized_len()
                 ..EntryHeader::serialized_len() + info.key_len as usize + info.value_len as usize],
         );
@@ -227,6 +237,7 @@ impl Buffer {
             hash,
             sequence,
             checksum,
+            checksum_algorithm,
             compression,
         };
         header.write(&mut buf[..EntryHeader::serialized_len()]);
@@ -236,7 +247,7 @@ impl Buffer {
             .record(ser.elapsed().as_secs_f64());
 
         let len = EntryHeader::serialized_len() + info.key_len as usize + info.value_len as usize;
-        let aligned = bits::align_up(PAGE, len);
+        let aligned = bits::align_up(align, len);
 
         if aligned > self.max_entry_size {
             return false;
@@ -260,11 +271,12 @@ impl Buffer {
     pub fn push_slice(&mut self, slice: &[u8], hash: u64, sequence: Sequence) -> bool {
         tracing::trace!(hash, "[blob writer]: push slice");
 
+        let align = self.bytes.alignment();
         let offset = self.written;
         let buf = &mut self.bytes[offset..];
 
         let len = slice.len();
-        let aligned = bits::align_up(PAGE, slice.len());
+        let aligned = bits::align_up(align, slice.len());
 
         if aligned > self.max_entry_size || aligned > buf.len() {
             return false;
@@ -301,16 +313,18 @@ pub struct SplitCtx {
 
     block_size: usize,
     blob_index_size: usize,
+    align: usize,
 }
 
 impl SplitCtx {
-    pub fn new(block_size: usize, blob_index_size: usize) -> Self {
+    pub fn new(block_size: usize, blob_index_size: usize, alignment: IoAlignment) -> Self {
         Self {
             current_part_blob_offset: blob_index_size,
-            current_blob_index: BlobIndex::new(IoSliceMut::new(blob_index_size)),
+            current_blob_index: BlobIndex::new(IoSliceMut::with_alignment(blob_index_size, alignment)),
             current_blob_block_offset: 0,
             block_size,
             blob_index_size,
+            align: alignment.get(),
         }
     }
 }
@@ -346,7 +360,7 @@ impl Splitter {
                 }
 
                 // Split blob and block if block is full.
-                if ctx.current_blob_block_offset + ctx.current_part_blob_offset + part_size + info.aligned()
+                if ctx.current_blob_block_offset + ctx.current_part_blob_offset + part_size + info.aligned(ctx.align)
                     > ctx.block_size
                 {
                     if let Some(part) = Self::split_blob(ctx, &mut indices, &mut part_size, &mut bytes) {
@@ -367,7 +381,7 @@ impl Splitter {
 
                 ctx.current_blob_index.write(&index);
                 indices.push(index);
-                part_size += info.aligned();
+                part_size += info.aligned(ctx.align);
 
                 break 'handle;
             }
@@ -491,6 +505,7 @@ mod tests {
     use itertools::Itertools;
 
     use super::*;
+    use crate::io::PAGE;
 
     const KB: usize = 1024;
 
@@ -524,20 +539,41 @@ mod tests {
         const MAX_ENTRY_SIZE: usize = BLOCK_SIZE - BLOB_INDEX_SIZE;
         const BATCH_SIZE: usize = 64 * KB;
 
-        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE);
+        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE, IoAlignment::default());
 
         // 1. Test write single blob part.
 
         let mut buffer = Buffer::new(IoSliceMut::new(BATCH_SIZE), MAX_ENTRY_SIZE, Arc::new(Metrics::noop()));
 
         // 4K
-        assert!(buffer.push(&1u64, &vec![1u8; 3 * KB], 1, Compression::None, 1));
+        assert!(buffer.push(
+            &1u64,
+            &vec![1u8; 3 * KB],
+            1,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            1
+        ));
 
         // 16K (deny)
-        assert!(!buffer.push(&2u64, &vec![2u8; 13 * KB], 2, Compression::None, 2));
+        assert!(!buffer.push(
+            &2u64,
+            &vec![2u8; 13 * KB],
+            2,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            2
+        ));
 
         // 4K
-        assert!(buffer.push(&3u64, &vec![3u8; 3 * KB], 3, Compression::None, 3));
+        assert!(buffer.push(
+            &3u64,
+            &vec![3u8; 3 * KB],
+            3,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            3
+        ));
 
         let (buf, infos) = buffer.finish();
         let buf = buf.into_io_slice();
@@ -577,13 +613,34 @@ mod tests {
         let mut buffer = Buffer::new(IoSliceMut::new(BATCH_SIZE), MAX_ENTRY_SIZE, Arc::new(Metrics::noop()));
 
         // 4K, block split
-        assert!(buffer.push(&4u64, &vec![4u8; 3 * KB], 4, Compression::None, 4));
+        assert!(buffer.push(
+            &4u64,
+            &vec![4u8; 3 * KB],
+            4,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            4
+        ));
 
         // 8K
-        assert!(buffer.push(&5u64, &vec![5u8; 7 * KB], 5, Compression::None, 5));
+        assert!(buffer.push(
+            &5u64,
+            &vec![5u8; 7 * KB],
+            5,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            5
+        ));
 
         // 8K, block early split
-        assert!(buffer.push(&6u64, &vec![6u8; 7 * KB], 6, Compression::None, 6));
+        assert!(buffer.push(
+            &6u64,
+            &vec![6u8; 7 * KB],
+            6,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            6
+        ));
 
         let (buf, infos) = buffer.finish();
         let buf = buf.into_io_slice();
@@ -645,7 +702,14 @@ mod tests {
         let mut buffer = Buffer::new(IoSliceMut::new(BATCH_SIZE), MAX_ENTRY_SIZE, Arc::new(Metrics::noop()));
 
         // 8K, block split
-        assert!(buffer.push(&7u64, &vec![7u8; 7 * KB], 7, Compression::None, 7));
+        assert!(buffer.push(
+            &7u64,
+            &vec![7u8; 7 * KB],
+            7,
+            Compression::None,
+            ChecksumAlgorithm::default(),
+            7
+        ));
 
         let (buf, infos) = buffer.finish();
         let buf = buf.into_io_slice();
@@ -685,7 +749,7 @@ mod tests {
         const BATCH_SIZE: usize = 128 * KB;
 
         // Remain 4 KB in size and 1 entry in count.
-        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE);
+        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE, IoAlignment::default());
         ctx.current_blob_block_offset = 40 * KB;
         ctx.current_part_blob_offset = 16 * KB;
         ctx.current_blob_index.count = ctx.current_blob_index.capacity() - 1;
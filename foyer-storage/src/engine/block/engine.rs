@@ -37,6 +37,7 @@ use futures_util::{
     FutureExt,
 };
 use itertools::Itertools;
+use tokio::sync::Semaphore;
 
 use super::{
     flusher::{Flusher, InvalidStats, Submission},
@@ -59,10 +60,10 @@ use crate::{
     },
     error::{Error, Result},
     filter::conditions::IoThrottle,
-    io::{bytes::IoSliceMut, PAGE},
+    io::{bytes::IoSliceMut, device::validate_alignment, IoAlignment, PAGE},
     keeper::PieceRef,
     runtime::Runtime,
-    serde::EntryDeserializer,
+    serde::{ChecksumAlgorithm, EntryDeserializer},
     Device, Load, RejectAll, StorageFilter, StorageFilterResult,
 };
 
@@ -83,9 +84,11 @@ where
     device: Arc<dyn Device>,
     block_size: usize,
     compression: Compression,
+    checksum_algorithm: ChecksumAlgorithm,
     indexer_shards: usize,
     recover_concurrency: usize,
     flushers: usize,
+    max_concurrent_writes: Option<usize>,
     reclaimers: usize,
     buffer_pool_size: usize,
     blob_index_size: usize,
@@ -109,9 +112,11 @@ where
             .field("device", &self.device)
             .field("block_size", &self.block_size)
             .field("compression", &self.compression)
+            .field("checksum_algorithm", &self.checksum_algorithm)
             .field("indexer_shards", &self.indexer_shards)
             .field("recover_concurrency", &self.recover_concurrency)
             .field("flushers", &self.flushers)
+            .field("max_concurrent_writes", &self.max_concurrent_writes)
             .field("reclaimers", &self.reclaimers)
             .field("buffer_pool_size", &self.buffer_pool_size)
             .field("blob_index_size", &self.blob_index_size)
@@ -137,9 +142,11 @@ where
             device,
             block_size: 16 * 1024 * 1024, // 16 MiB
             compression: Compression::default(),
+            checksum_algorithm: ChecksumAlgorithm::default(),
             indexer_shards: 64,
             recover_concurrency: 8,
             flushers: 1,
+            max_concurrent_writes: None,
             reclaimers: 1,
             buffer_pool_size: 16 * 1024 * 1024,            // 16 MiB
             blob_index_size: 4 * 1024,                     // 4 KiB
@@ -159,6 +166,7 @@ where
     /// its size also limits the max cacheable entry size.
     ///
     /// The block size must be 4K-aligned. the given value is not 4K-aligned, it will be automatically aligned up.
+    /// If the IO alignment of the device is larger than 4K, the block size is aligned up to it when building.
     ///
     /// Default: `16 MiB`.
     pub fn with_block_size(mut self, block_size: usize) -> Self {
@@ -166,6 +174,17 @@ where
         self
     }
 
+    /// Set the checksum algorithm of the entries.
+    ///
+    /// The checksum of an entry is verified on load, the entry is treated as a miss if it mismatches. Entries written
+    /// with other checksum algorithms can still be verified after the configuration changes.
+    ///
+    /// Default: [`ChecksumAlgorithm::Crc32c`].
+    pub fn with_checksum_algorithm(mut self, checksum_algorithm: ChecksumAlgorithm) -> Self {
+        self.checksum_algorithm = checksum_algorithm;
+        self
+    }
+
     /// Set the shard num of the indexer. Each shard has its own lock.
     ///
     /// Default: `64`.
@@ -192,6 +211,23 @@ where
         self
     }
 
+    /// Set the max concurrent block writes of all flushers for the disk cache store.
+    ///
+    /// Flushers wait for a free slot before writing, which limits the io pressure on the device when entries are
+    /// spilled in bursts.
+    ///
+    /// NOTE: The limit does not apply backpressure to the eviction of the in-memory cache, enqueuing an entry never
+    /// waits for a free slot. A flusher doesn't start the next batch before the writes of the current batch finish, so
+    /// while it waits, the new entries are buffered, and the entries that don't fit into the flusher buffer or exceed
+    /// the submit queue size threshold are dropped instead of written.
+    ///
+    /// Default: unlimited.
+    pub fn with_max_concurrent_writes(mut self, max_concurrent_writes: usize) -> Self {
+        assert!(max_concurrent_writes > 0, "max concurrent writes must be positive");
+        self.max_concurrent_writes = Some(max_concurrent_writes);
+        self
+    }
+
     /// Set the admission filter for th disk cache store.
     ///
     /// The admission filter is used to pick the entries that can be inserted into the disk cache store.
@@ -229,7 +265,7 @@ where
     /// A larger blob index size can hold more blob entries, but it will also increase the io size of each blob part
     /// write.
     ///
-    /// NOTE: The size will be aligned up to a multiplier of 4K.
+    /// NOTE: The size will be aligned up to a multiplier of 4K, or of the IO alignment of the device if it is larger.
     ///
     /// Default: 4 KiB
     pub fn with_blob_index_size(mut self, blob_index_size: usize) -> Self {
@@ -308,7 +344,13 @@ where
         }: EngineBuildContext,
     ) -> Result<Arc<BlockEngine<K, V, P>>> {
         let device = self.device;
-        let block_size = self.block_size;
+
+        validate_alignment(device.as_ref())?;
+
+        // The entries are laid out on 4K boundaries, or on the IO alignment of the device if it is larger.
+        let alignment = device.alignment().max(IoAlignment::default());
+        let block_size = bits::align_up(alignment.get(), self.block_size);
+        let blob_index_size = bits::align_up(alignment.get(), self.blob_index_size);
 
         let mut tombstones = vec![];
 
@@ -316,17 +358,13 @@ where
             // TODO(MrCroxx): The tombstone log support multiples partitions for multiple device support.
             let mut partitions = vec![];
 
-            let max_entries = device.capacity() / PAGE;
-            let pages = max_entries / TombstoneLog::SLOTS_PER_PAGE
-                + if max_entries % TombstoneLog::SLOTS_PER_PAGE > 0 {
-                    1
-                } else {
-                    0
-                };
-            let partition = device.create_partition(pages * PAGE)?;
+            let max_entries = device.capacity() / alignment.get();
+            let slots_per_page = TombstoneLog::slots_per_page(alignment.get());
+            let pages = max_entries / slots_per_page + if max_entries % slots_per_page > 0 { 1 } else { 0 };
+            let partition = device.create_partition(pages * alignment.get())?;
             partitions.push(partition);
 
-            let tombstone_log = TombstoneLog::open(partitions, io_engine.clone(), &mut tombstones).await?;
+            let tombstone_log = TombstoneLog::open(partitions, io_engine.clone(), alignment, &mut tombstones).await?;
             Some(tombstone_log)
         } else {
             None
@@ -344,7 +382,7 @@ where
             indexer.clone(),
             flushers.clone(),
             Arc::new(self.reinsertion_filter),
-            self.blob_index_size,
+            blob_index_size,
             device.statistics().clone(),
             runtime.clone(),
         );
@@ -354,6 +392,7 @@ where
             device.clone(),
             io_engine,
             block_size,
+            alignment,
             self.eviction_pickers,
             reclaimer,
             self.reclaimers,
@@ -375,7 +414,7 @@ where
         RecoverRunner::run(
             self.recover_concurrency,
             recover_mode,
-            self.blob_index_size,
+            blob_index_size,
             &(0..blocks as BlockId).collect_vec(),
             &sequence,
             &indexer,
@@ -389,17 +428,21 @@ where
         #[cfg(test)]
         let flush_holder = FlushHolder::default();
 
+        let write_semaphore = self.max_concurrent_writes.map(|n| Arc::new(Semaphore::new(n)));
         let io_buffer_size = self.buffer_pool_size / self.flushers;
         for (flusher, rx) in flushers.iter().zip(rxs.into_iter()) {
             flusher.run(
                 rx,
                 block_size,
                 io_buffer_size,
-                self.blob_index_size,
+                blob_index_size,
+                alignment,
                 self.compression,
+                self.checksum_algorithm,
                 indexer.clone(),
                 block_manager.clone(),
                 tombstone_log.clone(),
+                write_semaphore.clone(),
                 metrics.clone(),
                 &runtime,
                 #[cfg(test)]
@@ -412,6 +455,7 @@ where
         let inner = BlockEngineInner {
             admission_filter,
             device,
+            alignment,
             indexer,
             block_manager,
             flushers,
@@ -483,6 +527,7 @@ where
     admission_filter: StorageFilter,
 
     device: Arc<dyn Device>,
+    alignment: IoAlignment,
 
     indexer: Indexer,
     block_manager: BlockManager,
@@ -605,7 +650,7 @@ where
                 return Ok(Load::Throttled);
             }
 
-            let buf = IoSliceMut::new(bits::align_up(PAGE, addr.len as _));
+            let buf = IoSliceMut::with_alignment(addr.len as _, block.alignment());
             let (buf, res) = block.read(Box::new(buf), addr.offset as _).await;
             match res {
                 Ok(_) => {}
@@ -625,6 +670,7 @@ where
                 Err(e @ Error::MagicMismatch { .. })
                 | Err(e @ Error::ChecksumMismatch { .. })
                 | Err(e @ Error::CompressionAlgorithmNotSupported(_))
+                | Err(e @ Error::ChecksumAlgorithmNotSupported(_))
                 | Err(e @ Error::OutOfRange { .. })
                 | Err(e @ Error::InvalidIoRange { .. }) => {
                     tracing::warn!(
@@ -649,7 +695,7 @@ where
                     header.key_len as _,
                     header.value_len as _,
                     header.compression,
-                    Some(header.checksum),
+                    Some((header.checksum_algorithm, header.checksum)),
                 ) {
                     Ok(res) => res,
                     Err(e @ Error::MagicMismatch { .. })
@@ -708,7 +754,7 @@ where
             .insert_tombstone(hash, sequence)
             .map(|addr| InvalidStats {
                 block: addr.block,
-                size: bits::align_up(PAGE, addr.len as usize),
+                size: bits::align_up(self.inner.alignment.get(), addr.len as usize),
             });
 
         let this = self.clone();
@@ -823,7 +869,7 @@ where
 #[cfg(test)]
 mod tests {
 
-    use std::{fs::File, path::Path};
+    use std::{fs::File, path::Path, time::Duration};
 
     use bytesize::ByteSize;
     use foyer_common::hasher::ModHasher;
@@ -836,8 +882,12 @@ mod tests {
         engine::RecoverMode,
         io::{
             self,
-            device::{combined::CombinedDeviceBuilder, fs::FsDeviceBuilder, DeviceBuilder},
-            engine::{IoEngine, IoEngineBuilder},
+            bytes::{IoBuf, IoBufMut},
+            device::{
+                combined::CombinedDeviceBuilder, fs::FsDeviceBuilder, memory::MemoryDeviceBuilder, DeviceBuilder,
+                Partition,
+            },
+            engine::{memory::MemoryIoEngineBuilder, IoEngine, IoEngineBuilder, IoHandle},
         },
         serde::EntrySerializer,
         test_utils::Biased,
@@ -879,9 +929,11 @@ mod tests {
             device,
             block_size: 16 * 1024,
             compression: Compression::None,
+            checksum_algorithm: ChecksumAlgorithm::default(),
             indexer_shards: 4,
             recover_concurrency: 2,
             flushers: 1,
+            max_concurrent_writes: None,
             reclaimers: 1,
             clean_block_threshold: 1,
             admission_filter: StorageFilter::new(),
@@ -920,9 +972,11 @@ mod tests {
             device,
             block_size: 16 * 1024,
             compression: Compression::None,
+            checksum_algorithm: ChecksumAlgorithm::default(),
             indexer_shards: 4,
             recover_concurrency: 2,
             flushers: 1,
+            max_concurrent_writes: None,
             reclaimers: 1,
             clean_block_threshold: 1,
             eviction_pickers: vec![Box::<FifoPicker>::default()],
@@ -1374,4 +1428,245 @@ mod tests {
             .unwrap();
         assert_eq!(engine.inner.block_manager.blocks(), (1 + 2 + 4) * MB / (64 * KB));
     }
+
+    /// An io engine that records the max count of concurrent writes.
+    #[derive(Debug)]
+    struct ConcurrencyIoEngine {
+        io_engine: Arc<dyn IoEngine>,
+        writes: Arc<AtomicUsize>,
+        max_writes: Arc<AtomicUsize>,
+    }
+
+    impl IoEngine for ConcurrencyIoEngine {
+        fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            self.io_engine.read(buf, partition, offset)
+        }
+
+        fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            let writes = self.writes.clone();
+            let current = writes.fetch_add(1, Ordering::Relaxed) + 1;
+            self.max_writes.fetch_max(current, Ordering::Relaxed);
+            let handle = self.io_engine.write(buf, partition, offset);
+            async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let res = handle.await;
+                writes.fetch_sub(1, Ordering::Relaxed);
+                res
+            }
+            .boxed()
+            .into()
+        }
+    }
+
+    async fn max_concurrent_writes_for_test(max_concurrent_writes: Option<usize>) -> usize {
+        let dir = tempfile::tempdir().unwrap();
+
+        let device = FsDeviceBuilder::new(dir.path())
+            .with_capacity(ByteSize::kib(64).as_u64() as _)
+            .build()
+            .unwrap();
+        let max_writes = Arc::<AtomicUsize>::default();
+        let io_engine = Arc::new(ConcurrencyIoEngine {
+            io_engine: io_engine_for_test().await,
+            writes: Arc::default(),
+            max_writes: max_writes.clone(),
+        });
+        let mut builder = BlockEngineBuilder::<u64, Vec<u8>, TestProperties>::new(device)
+            .with_block_size(16 * KB)
+            .with_eviction_pickers(vec![Box::<FifoPicker>::default()]);
+        if let Some(max_concurrent_writes) = max_concurrent_writes {
+            builder = builder.with_max_concurrent_writes(max_concurrent_writes);
+        }
+        let store = builder
+            .boxed()
+            .build(EngineBuildContext {
+                io_engine,
+                metrics: Arc::new(Metrics::noop()),
+                runtime: Runtime::current(),
+                recover_mode: RecoverMode::None,
+            })
+            .await
+            .unwrap();
+
+        // Each entry takes a whole block, so the burst is flushed as one batch with 3 concurrent block writes.
+        let memory = cache_for_test();
+        store.hold_flush();
+        for i in 0..3 {
+            enqueue(&store, memory.insert(i, vec![i as u8; 10 * KB]));
+        }
+        store.unhold_flush();
+        store.wait().await;
+
+        for i in 0..3 {
+            let kv = store.load(memory.hash(&i)).await.unwrap().kv().unwrap();
+            assert_eq!(kv, (i, vec![i as u8; 10 * KB]));
+        }
+
+        max_writes.load(Ordering::Relaxed)
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_max_concurrent_writes() {
+        assert!(max_concurrent_writes_for_test(None).await > 1);
+        assert_eq!(max_concurrent_writes_for_test(Some(1)).await, 1);
+    }
+
+    /// An io engine that holds the completion of the writes until the gate is opened.
+    #[derive(Debug)]
+    struct GatedIoEngine {
+        io_engine: Arc<dyn IoEngine>,
+        writes: Arc<AtomicUsize>,
+        gate: Arc<Semaphore>,
+    }
+
+    impl IoEngine for GatedIoEngine {
+        fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            self.io_engine.read(buf, partition, offset)
+        }
+
+        fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            let gate = self.gate.clone();
+            let handle = self.io_engine.write(buf, partition, offset);
+            async move {
+                let _permit = gate.acquire().await.unwrap();
+                handle.await
+            }
+            .boxed()
+            .into()
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_max_concurrent_writes_saturated() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let device = FsDeviceBuilder::new(dir.path())
+            .with_capacity(ByteSize::kib(256).as_u64() as _)
+            .build()
+            .unwrap();
+        let writes = Arc::<AtomicUsize>::default();
+        let gate = Arc::new(Semaphore::new(0));
+        let io_engine = Arc::new(GatedIoEngine {
+            io_engine: io_engine_for_test().await,
+            writes: writes.clone(),
+            gate: gate.clone(),
+        });
+        let store = BlockEngineBuilder::<u64, Vec<u8>, TestProperties>::new(device)
+            .with_block_size(16 * KB)
+            .with_buffer_pool_size(32 * KB)
+            .with_max_concurrent_writes(1)
+            .with_eviction_pickers(vec![Box::<FifoPicker>::default()])
+            .boxed()
+            .build(EngineBuildContext {
+                io_engine,
+                metrics: Arc::new(Metrics::noop()),
+                runtime: Runtime::current(),
+                recover_mode: RecoverMode::None,
+            })
+            .await
+            .unwrap();
+
+        // Flush 2 entries as one batch, each takes a whole block. The write of the first block takes the only slot and
+        // stalls, the write of the second block waits for the slot.
+        let memory = cache_for_test();
+        store.hold_flush();
+        for i in 0..2 {
+            enqueue(&store, memory.insert(i, vec![i as u8; 10 * KB]));
+        }
+        store.unhold_flush();
+        let waiter = tokio::spawn(store.wait());
+        while writes.load(Ordering::Relaxed) == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(writes.load(Ordering::Relaxed), 1);
+
+        // Enqueuing doesn't wait for the slot. The entries beyond the flusher buffer are dropped.
+        for i in 2..8 {
+            enqueue(&store, memory.insert(i, vec![i as u8; 10 * KB]));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        gate.add_permits(Semaphore::MAX_PERMITS);
+        waiter.await.unwrap();
+        store.wait().await;
+
+        for i in 0..2 {
+            let kv = store.load(memory.hash(&i)).await.unwrap().kv().unwrap();
+            assert_eq!(kv, (i, vec![i as u8; 10 * KB]));
+        }
+        let mut written = 0;
+        for i in 2..8 {
+            if let Some(kv) = store.load(memory.hash(&i)).await.unwrap().kv() {
+                assert_eq!(kv, (i, vec![i as u8; 10 * KB]));
+                written += 1;
+            }
+        }
+        assert!(written > 0 && written < 6, "written: {written}");
+    }
+
+    /// An io engine that checks the buffers of the ios are aligned to the given alignment.
+    #[derive(Debug)]
+    struct AlignedIoEngine {
+        io_engine: Arc<dyn IoEngine>,
+        align: usize,
+    }
+
+    impl IoEngine for AlignedIoEngine {
+        fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            assert!(bits::is_aligned(self.align, buf.as_raw_parts().0 as usize));
+            self.io_engine.read(buf, partition, offset)
+        }
+
+        fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            assert!(bits::is_aligned(self.align, buf.as_raw_parts().0 as usize));
+            self.io_engine.write(buf, partition, offset)
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_device_alignment() {
+        for align in [512, 8192] {
+            let device = MemoryDeviceBuilder::new(256 * KB)
+                .with_alignment(IoAlignment::new(align).unwrap())
+                .build()
+                .unwrap();
+            let io_engine = Arc::new(AlignedIoEngine {
+                io_engine: MemoryIoEngineBuilder.build().await.unwrap(),
+                align,
+            });
+            let store = BlockEngineBuilder::<u64, Vec<u8>, TestProperties>::new(device)
+                .with_block_size(64 * KB)
+                .with_eviction_pickers(vec![Box::<FifoPicker>::default()])
+                .with_tombstone_log(true)
+                .boxed()
+                .build(EngineBuildContext {
+                    io_engine,
+                    metrics: Arc::new(Metrics::noop()),
+                    runtime: Runtime::current(),
+                    recover_mode: RecoverMode::None,
+                })
+                .await
+                .unwrap();
+
+            let memory = cache_for_test();
+            for i in 0..8 {
+                enqueue(&store, memory.insert(i, vec![i as u8; (i as usize + 1) * KB]));
+            }
+            store.wait().await;
+
+            for i in 0..8 {
+                // The entries are laid out on the larger one of 4K and the device alignment.
+                let addr = store.inner.indexer.get(memory.hash(&i)).unwrap();
+                assert!(bits::is_aligned(align.max(PAGE), addr.offset as usize));
+                let kv = store.load(memory.hash(&i)).await.unwrap().kv().unwrap();
+                assert_eq!(kv, (i, vec![i as u8; (i as usize + 1) * KB]));
+            }
+
+            store.delete(memory.hash(&0));
+            store.wait().await;
+            assert!(store.load(memory.hash(&0)).await.unwrap().kv().is_none());
+        }
+    }
 }
@@ -36,7 +36,7 @@ use futures_util::{
     FutureExt,
 };
 use itertools::Itertools;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Semaphore};
 
 #[cfg(test)]
 use crate::engine::block::test_utils::*;
@@ -52,10 +52,11 @@ use crate::{
     error::{Error, Result},
     io::{
         bytes::{IoSlice, IoSliceMut},
-        PAGE,
+        IoAlignment,
     },
     keeper::PieceRef,
     runtime::Runtime,
+    serde::ChecksumAlgorithm,
     Compression,
 };
 
@@ -171,32 +172,35 @@ where
         block_size: usize,
         io_buffer_size: usize,
         blob_index_size: usize,
+        alignment: IoAlignment,
         compression: Compression,
+        checksum_algorithm: ChecksumAlgorithm,
         indexer: Indexer,
         block_manager: BlockManager,
         tombstone_log: Option<TombstoneLog>,
+        write_semaphore: Option<Arc<Semaphore>>,
         metrics: Arc<Metrics>,
         runtime: &Runtime,
         #[cfg(test)] flush_holder: FlushHolder,
     ) -> Result<()> {
         let id = self.id;
-        let io_buffer_size = bits::align_down(PAGE, io_buffer_size);
+        let io_buffer_size = bits::align_down(alignment.get(), io_buffer_size);
         assert!(io_buffer_size > 0);
 
-        bits::assert_aligned(PAGE, io_buffer_size);
-        bits::assert_aligned(PAGE, blob_index_size);
+        bits::assert_aligned(alignment.get(), io_buffer_size);
+        bits::assert_aligned(alignment.get(), blob_index_size);
 
         let max_entry_size = block_size - blob_index_size;
 
-        let bytes = IoSliceMut::new(io_buffer_size);
-        let rotate_buffer = Some(IoSliceMut::new(io_buffer_size));
+        let bytes = IoSliceMut::with_alignment(io_buffer_size, alignment);
+        let rotate_buffer = Some(IoSliceMut::with_alignment(io_buffer_size, alignment));
 
         let buffer = Buffer::new(bytes, max_entry_size, metrics.clone());
         let buffer = Some(buffer);
 
         let current_block_handle = block_manager.get_clean_block();
 
-        let ctx = SplitCtx::new(block_size, blob_index_size);
+        let ctx = SplitCtx::new(block_size, blob_index_size, alignment);
 
         let runner = Runner {
             id,
@@ -212,7 +216,9 @@ where
             block_manager,
             indexer,
             tombstone_log,
+            write_semaphore,
             compression,
+            checksum_algorithm,
             runtime: runtime.clone(),
             metrics: metrics.clone(),
             io_tasks: VecDeque::with_capacity(1),
@@ -310,7 +316,11 @@ where
     indexer: Indexer,
     tombstone_log: Option<TombstoneLog>,
 
+    /// Limits the concurrent block writes of all flushers if set.
+    write_semaphore: Option<Arc<Semaphore>>,
+
     compression: Compression,
+    checksum_algorithm: ChecksumAlgorithm,
 
     runtime: Runtime,
 
@@ -427,6 +437,7 @@ where
                     piece.value(),
                     piece.hash(),
                     self.compression,
+                    self.checksum_algorithm,
                     sequence,
                 );
                 if enqueued {
@@ -489,10 +500,12 @@ where
                 let indexer = self.indexer.clone();
                 let block_manager = self.block_manager.clone();
                 let metrics = self.metrics.clone();
+                let write_semaphore = self.write_semaphore.clone();
 
                 async move {
                     // Wait for block is clean.
                     let block = block_handle.clone().await;
+                    let align = block.alignment().get();
 
                     let tasks = blob_parts.into_iter().map(
                         |BlobPart {
@@ -505,12 +518,19 @@ where
                             let offset = blob_block_offset + part_blob_offset;
                             let len = data.len();
 
-                            bits::assert_aligned(PAGE, offset);
-                            bits::assert_aligned(PAGE, len);
+                            bits::assert_aligned(align, offset);
+                            bits::assert_aligned(align, len);
 
                             let block = block.clone();
+                            let write_semaphore = write_semaphore.clone();
                             async move {
                                 if len > 0 {
+                                    // Hold the permit for both the data and the index write of the blob part.
+                                    let _permit = match write_semaphore {
+                                        Some(semaphore) => Some(semaphore.acquire_owned().await.unwrap()),
+                                        None => None,
+                                    };
+
                                     tracing::trace!(id, block = block.id(), offset, len, "[flusher]: write blob data");
 
                                     let (_, res) = block.write(Box::new(data), offset as _).await;
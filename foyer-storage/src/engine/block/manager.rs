@@ -42,6 +42,7 @@ use crate::{
         bytes::{IoB, IoBuf, IoBufMut},
         device::Partition,
         engine::IoEngine,
+        IoAlignment,
     },
     Device, IoError, Runtime,
 };
@@ -73,6 +74,7 @@ struct BlockInner {
     id: BlockId,
     partition: Arc<dyn Partition>,
     io_engine: Arc<dyn IoEngine>,
+    alignment: IoAlignment,
     statistics: Arc<BlockStatistics>,
 }
 
@@ -98,7 +100,14 @@ impl Block {
         self.inner.partition.size()
     }
 
+    /// Get the alignment of the IOs and the entries on the block.
+    pub fn alignment(&self) -> IoAlignment {
+        self.inner.alignment
+    }
+
     pub(crate) async fn write(&self, buf: Box<dyn IoBuf>, offset: u64) -> (Box<dyn IoB>, Result<()>) {
+        let statistics = self.inner.partition.statistics();
+        statistics.write_rate_limiter().acquire(buf.len()).await;
         let (buf, res) = self
             .inner
             .io_engine
@@ -108,6 +117,8 @@ impl Block {
     }
 
     pub(crate) async fn read(&self, buf: Box<dyn IoBufMut>, offset: u64) -> (Box<dyn IoB>, Result<()>) {
+        let statistics = self.inner.partition.statistics();
+        statistics.read_rate_limiter().acquire(buf.len()).await;
         let (buf, res) = self
             .inner
             .io_engine
@@ -128,6 +139,7 @@ impl Block {
             id,
             partition,
             io_engine,
+            alignment: IoAlignment::default(),
             statistics: Arc::<BlockStatistics>::default(),
         };
         let inner = Arc::new(inner);
@@ -173,6 +185,7 @@ impl BlockManager {
         device: Arc<dyn Device>,
         io_engine: Arc<dyn IoEngine>,
         block_size: usize,
+        alignment: IoAlignment,
         mut eviction_pickers: Vec<Box<dyn EvictionPicker>>,
         reclaimer: Arc<dyn ReclaimerTrait>,
         reclaim_concurrency: usize,
@@ -194,6 +207,7 @@ impl BlockManager {
                     id,
                     partition,
                     io_engine: io_engine.clone(),
+                    alignment,
                     statistics: Arc::<BlockStatistics>::default(),
                 }),
             };
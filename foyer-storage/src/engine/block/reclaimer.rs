@@ -32,10 +32,7 @@ use crate::{
         serde::Sequence,
     },
     error::Result,
-    io::{
-        bytes::{IoSlice, IoSliceMut},
-        PAGE,
-    },
+    io::bytes::{IoSlice, IoSliceMut},
     runtime::Runtime,
     Statistics, StorageFilter,
 };
@@ -136,7 +133,8 @@ where
                 };
                 for info in infos {
                     if reinsertion_picker.filter(&statistics, info.hash, info.addr.len as _).is_admitted() {
-                        let buf = IoSliceMut::new(bits::align_up(PAGE, info.addr.len as _));
+                        let alignment = block.alignment();
+                        let buf = IoSliceMut::with_alignment(info.addr.len as _, alignment);
                         let (buf, res) = block.read(Box::new(buf), info.addr.offset as _).await;
                         if let Err(e) = res {
                             tracing::warn!(
@@ -146,7 +144,7 @@ where
                             break 'reinsert;
                         }
                         let buf = buf.try_into_io_slice_mut().unwrap().into_io_slice();
-                        let slice = buf.slice(..bits::align_up(PAGE, info.addr.len as usize));
+                        let slice = buf.slice(..bits::align_up(alignment.get(), info.addr.len as usize));
                         let flusher = flushers[picked_count % flushers.len()].clone();
                         flusher.submit(Submission::Reinsertion {
                             reinsertion: Reinsertion {
@@ -191,7 +189,8 @@ pub struct BlockCleaner;
 
 impl BlockCleaner {
     pub async fn clean(block: &Block) -> Result<()> {
-        let mut page = IoSliceMut::new(PAGE);
+        let alignment = block.alignment();
+        let mut page = IoSliceMut::with_alignment(alignment.get(), alignment);
         page.fill(0);
         let (_, res) = block.write(Box::new(page), 0).await;
         res?;
@@ -49,7 +49,7 @@ impl BlockScanner {
             return Ok(None);
         }
 
-        let bytes = Box::new(IoSliceMut::new(self.blob_index_size));
+        let bytes = Box::new(IoSliceMut::with_alignment(self.blob_index_size, self.block.alignment()));
         let (bytes, res) = self.block.read(bytes, self.offset).await;
         res?;
         let indices = match BlobIndexReader::read(&bytes) {
@@ -65,7 +65,7 @@ impl BlockScanner {
 
         let step = indices
             .last()
-            .map(|index| index.offset as u64 + index.aligned() as u64)
+            .map(|index| index.offset as u64 + index.aligned(self.block.alignment().get()) as u64)
             .unwrap_or(self.block.size() as u64);
 
         tracing::trace!(
@@ -112,8 +112,9 @@ mod tests {
         io::{
             device::{fs::FsDeviceBuilder, Device, DeviceBuilder, Partition},
             engine::{psync::PsyncIoEngineBuilder, IoEngine, IoEngineBuilder},
-            PAGE,
+            IoAlignment, PAGE,
         },
+        serde::ChecksumAlgorithm,
         Compression,
     };
 
@@ -146,7 +147,7 @@ mod tests {
             .collect_vec();
         let engine = io_engine_for_test().await;
 
-        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE);
+        let mut ctx = SplitCtx::new(BLOCK_SIZE, BLOB_INDEX_SIZE, IoAlignment::default());
         let mut buffer = Buffer::new(IoSliceMut::new(BATCH_SIZE), MAX_ENTRY_SIZE, Arc::new(Metrics::noop()));
 
         for i in 0..BLOB_INDEX_CAPACITY * 5 {
@@ -155,6 +156,7 @@ mod tests {
                 &vec![i as u8; 3 * 1024],
                 i as u64,
                 Compression::None,
+                ChecksumAlgorithm::default(),
                 i as Sequence,
             );
         }
@@ -19,10 +19,13 @@ use bytes::{Buf, BufMut};
 use crate::{
     compress::Compression,
     error::{Error, Result},
+    serde::ChecksumAlgorithm,
 };
 
 const ENTRY_MAGIC: u32 = 0x97_03_27_00;
 const ENTRY_MAGIC_MASK: u32 = 0xFF_FF_FF_00;
+const COMPRESSION_MASK: u8 = 0x0F;
+const CHECKSUM_ALGORITHM_SHIFT: u8 = 4;
 
 pub type Sequence = u64;
 pub type AtomicSequence = AtomicU64;
@@ -34,12 +37,13 @@ pub struct EntryHeader {
     pub hash: u64,
     pub sequence: Sequence,
     pub checksum: u64,
+    pub checksum_algorithm: ChecksumAlgorithm,
     pub compression: Compression,
 }
 
 impl EntryHeader {
     pub const fn serialized_len() -> usize {
-        4 + 4 + 8 + 8 + 8 + 4 /* magic & compression */
+        4 + 4 + 8 + 8 + 8 + 4 /* magic & checksum algorithm & compression */
     }
 
     pub fn write(&self, mut buf: impl BufMut) {
@@ -49,7 +53,10 @@ impl EntryHeader {
         buf.put_u64(self.sequence);
         buf.put_u64(self.checksum);
 
-        let v = ENTRY_MAGIC | self.compression.to_u8() as u32;
+        // The checksum algorithm takes the high 4 bits of the lowest byte, so the entries written before the checksum
+        // algorithm is configurable are read as xxhash64 checksummed.
+        let v = ENTRY_MAGIC
+            | ((self.checksum_algorithm.to_u8() << CHECKSUM_ALGORITHM_SHIFT) | self.compression.to_u8()) as u32;
         buf.put_u32(v);
     }
 
@@ -71,7 +78,8 @@ impl EntryHeader {
                 get: magic,
             });
         }
-        let compression = Compression::try_from(v as u8)?;
+        let checksum_algorithm = ChecksumAlgorithm::try_from(v as u8 >> CHECKSUM_ALGORITHM_SHIFT)?;
+        let compression = Compression::try_from(v as u8 & COMPRESSION_MASK)?;
 
         Ok(Self {
             key_len,
@@ -79,7 +87,48 @@ impl EntryHeader {
             hash,
             sequence,
             checksum,
+            checksum_algorithm,
             compression,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_header_serde() {
+        for checksum_algorithm in [ChecksumAlgorithm::Crc32c, ChecksumAlgorithm::XxHash64] {
+            for compression in [Compression::None, Compression::Zstd, Compression::Lz4] {
+                let header = EntryHeader {
+                    key_len: 8,
+                    value_len: 1024,
+                    hash: 42,
+                    sequence: 7,
+                    checksum: 0xdead_beef,
+                    checksum_algorithm,
+                    compression,
+                };
+                let mut buf = vec![];
+                header.write(&mut buf);
+                assert_eq!(buf.len(), EntryHeader::serialized_len());
+                assert_eq!(EntryHeader::read(&buf[..]).unwrap(), header);
+            }
+        }
+
+        // Entries written before the checksum algorithm is configurable are checksummed by xxhash64.
+        let mut buf = vec![0; EntryHeader::serialized_len() - 4];
+        buf.put_u32(ENTRY_MAGIC | Compression::Lz4.to_u8() as u32);
+        let header = EntryHeader::read(&buf[..]).unwrap();
+        assert_eq!(header.checksum_algorithm, ChecksumAlgorithm::XxHash64);
+        assert_eq!(header.compression, Compression::Lz4);
+
+        let mut buf = vec![0; EntryHeader::serialized_len() - 4];
+        buf.put_u32(ENTRY_MAGIC | 0xF0);
+        assert!(matches!(
+            EntryHeader::read(&buf[..]),
+            Err(Error::ChecksumAlgorithmNotSupported(0x0F))
+        ));
+    }
+}
@@ -19,7 +19,7 @@ use tokio::sync::Mutex;
 
 use crate::{
     error::Result,
-    io::{bytes::IoSliceMut, device::Partition, PAGE},
+    io::{bytes::IoSliceMut, device::Partition, IoAlignment},
     IoEngine,
 };
 
@@ -50,6 +50,7 @@ impl Tombstone {
 pub struct TombstoneLog {
     inner: Arc<Mutex<TombstoneLogInner>>,
     pages: usize,
+    slots_per_page: usize,
 }
 
 #[derive(Debug)]
@@ -59,22 +60,28 @@ struct TombstoneLogInner {
 }
 
 impl TombstoneLog {
-    pub const SLOTS_PER_PAGE: usize = PAGE / Tombstone::serialized_len();
+    /// Get the count of the tombstone slots in a page of the given size.
+    pub const fn slots_per_page(page_size: usize) -> usize {
+        page_size / Tombstone::serialized_len()
+    }
 
     /// Open the tombstone log with given a dedicated device.
     ///
-    /// The tombstone log will
+    /// The tombstone log is read and written in pages, the page size is the given alignment.
     pub async fn open(
         partitions: Vec<Arc<dyn Partition>>,
         io_engine: Arc<dyn IoEngine>,
+        alignment: IoAlignment,
         tombstones: &mut Vec<Tombstone>,
     ) -> Result<Self> {
+        let page_size = alignment.get();
+        let slots_per_page = Self::slots_per_page(page_size);
         let mut recovered = vec![];
 
         for partition in partitions.iter() {
-            for offset in (0..partition.size()).step_by(PAGE) {
+            for offset in (0..partition.size()).step_by(page_size) {
                 tracing::trace!(offset, "[tombstone log]: recover at");
-                let buf = IoSliceMut::new(PAGE);
+                let buf = IoSliceMut::with_alignment(page_size, alignment);
                 let (buffer, res) = io_engine.read(Box::new(buf), partition.as_ref(), offset as u64).await;
                 res?;
 
@@ -108,35 +115,36 @@ impl TombstoneLog {
 
         tombstones.extend(recovered.into_iter().map(|(tombstone, _)| tombstone));
 
-        let latest_tombstone_page = latest_tombstone_offset / PAGE;
+        let latest_tombstone_page = latest_tombstone_offset / page_size;
         let latest_tombstone_slot = if latest_tombstone_page == 0 {
             latest_tombstone_offset / Tombstone::serialized_len()
         } else {
             let pages_before_latest_tombstone = latest_tombstone_page - 1;
-            Self::SLOTS_PER_PAGE * pages_before_latest_tombstone
-                + (latest_tombstone_offset - pages_before_latest_tombstone * PAGE) / Tombstone::serialized_len()
+            slots_per_page * pages_before_latest_tombstone
+                + (latest_tombstone_offset - pages_before_latest_tombstone * page_size) / Tombstone::serialized_len()
         };
 
-        let pages = partitions.iter().map(|p| p.size()).sum::<usize>() / PAGE;
+        let pages = partitions.iter().map(|p| p.size()).sum::<usize>() / page_size;
         let slot = latest_tombstone_slot + 1;
-        let (page, _) = Self::calculate_slot_addr(pages, slot);
-        let buffer = PageBuffer::open(io_engine, partitions, page as _).await?;
+        let (page, _) = Self::calculate_slot_addr(pages, slots_per_page, slot);
+        let buffer = PageBuffer::open(io_engine, partitions, alignment, page as _).await?;
 
         Ok(Self {
             inner: Arc::new(Mutex::new(TombstoneLogInner { buffer, slot })),
             pages,
+            slots_per_page,
         })
     }
 
-    fn calculate_slot_addr(pages: usize, slot: usize) -> (u32, usize) {
-        let page = slot / Self::SLOTS_PER_PAGE;
+    fn calculate_slot_addr(pages: usize, slots_per_page: usize, slot: usize) -> (u32, usize) {
+        let page = slot / slots_per_page;
         let page = page % pages;
-        let offset = (slot % Self::SLOTS_PER_PAGE) * Tombstone::serialized_len();
+        let offset = (slot % slots_per_page) * Tombstone::serialized_len();
         (page as u32, offset)
     }
 
     fn slot_addr(&self, slot: usize) -> (u32, usize) {
-        Self::calculate_slot_addr(self.pages, slot)
+        Self::calculate_slot_addr(self.pages, self.slots_per_page, slot)
     }
 
     pub async fn append(&self, tombstones: impl Iterator<Item = &Tombstone>) -> Result<()> {
@@ -168,6 +176,7 @@ pub struct PageBuffer {
 
     io_engine: Arc<dyn IoEngine>,
     partitions: Vec<Arc<dyn Partition>>,
+    page_size: usize,
     page: u32,
 }
 
@@ -184,11 +193,17 @@ impl AsMut<[u8]> for PageBuffer {
 }
 
 impl PageBuffer {
-    pub async fn open(io_engine: Arc<dyn IoEngine>, partitions: Vec<Arc<dyn Partition>>, page: u32) -> Result<Self> {
+    pub async fn open(
+        io_engine: Arc<dyn IoEngine>,
+        partitions: Vec<Arc<dyn Partition>>,
+        alignment: IoAlignment,
+        page: u32,
+    ) -> Result<Self> {
         let mut this = Self {
-            buffer: Some(IoSliceMut::new(PAGE)),
+            buffer: Some(IoSliceMut::with_alignment(alignment.get(), alignment)),
             io_engine,
             partitions,
+            page_size: alignment.get(),
             page,
         };
 
@@ -234,9 +249,9 @@ impl PageBuffer {
         let mut partition = 0;
 
         loop {
-            let partition_pages = self.partitions[partition].size() as u32 / PAGE as u32;
+            let partition_pages = self.partitions[partition].size() as u32 / self.page_size as u32;
             if page < partition_pages {
-                break (partition, PAGE as u64 * page as u64);
+                break (partition, self.page_size as u64 * page as u64);
             }
             page -= partition_pages;
             partition += 1;
@@ -251,7 +266,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        io::device::{fs::FsDeviceBuilder, DeviceBuilder},
+        io::{
+            device::{fs::FsDeviceBuilder, DeviceBuilder},
+            PAGE,
+        },
         IoEngineBuilder, PsyncIoEngineBuilder,
     };
 
@@ -268,9 +286,14 @@ mod tests {
         let p1 = device.create_partition(8 * 1024).unwrap();
         let io_engine = PsyncIoEngineBuilder::new().build().await.unwrap();
 
-        let log = TombstoneLog::open(vec![p0.clone(), p1.clone()], io_engine.clone(), &mut vec![])
-            .await
-            .unwrap();
+        let log = TombstoneLog::open(
+            vec![p0.clone(), p1.clone()],
+            io_engine.clone(),
+            IoAlignment::default(),
+            &mut vec![],
+        )
+        .await
+        .unwrap();
 
         log.append(
             (0..3 * 1024 + 42)
@@ -293,13 +316,21 @@ mod tests {
 
         drop(log);
 
-        let log = TombstoneLog::open(vec![p0.clone(), p1.clone()], io_engine.clone(), &mut vec![])
-            .await
-            .unwrap();
+        let log = TombstoneLog::open(
+            vec![p0.clone(), p1.clone()],
+            io_engine.clone(),
+            IoAlignment::default(),
+            &mut vec![],
+        )
+        .await
+        .unwrap();
 
         {
             let inner = log.inner.lock().await;
-            assert_eq!(inner.slot, (3 * 1024 + 42 + 1) % (TombstoneLog::SLOTS_PER_PAGE * 4));
+            assert_eq!(
+                inner.slot,
+                (3 * 1024 + 42 + 1) % (TombstoneLog::slots_per_page(PAGE) * 4)
+            );
             let (page, _) = log.slot_addr(inner.slot);
             assert_eq!(inner.buffer.page, page);
         }
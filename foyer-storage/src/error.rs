@@ -73,6 +73,9 @@ pub enum Error {
     /// Compression algorithm not supported.
     #[error("compression algorithm not supported: {0}")]
     CompressionAlgorithmNotSupported(u8),
+    /// Checksum algorithm not supported.
+    #[error("checksum algorithm not supported: {0}")]
+    ChecksumAlgorithmNotSupported(u8),
     /// Other error.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -24,7 +24,7 @@ use std::{
 use allocator_api2::alloc::{handle_alloc_error, Allocator, Global, Layout};
 use foyer_common::bits;
 
-use super::PAGE;
+use super::IoAlignment;
 
 pub trait IoB: Deref<Target = [u8]> + Send + Sync + 'static + Debug + Any {
     fn as_raw_parts(&self) -> (*mut u8, usize);
@@ -52,11 +52,12 @@ pub trait IoBufMut: DerefMut<Target = [u8]> + IoB {
     fn into_iob(self: Box<Self>) -> Box<dyn IoB>;
 }
 
-/// 4K-aligned raw bytes.
+/// Aligned raw bytes, 4K-aligned by default.
 #[derive(Debug)]
 pub struct Raw {
     ptr: *mut u8,
     cap: usize,
+    align: usize,
 }
 
 unsafe impl Send for Raw {}
@@ -65,8 +66,16 @@ unsafe impl Sync for Raw {}
 impl Raw {
     /// Allocate an 4K-aligned [`Raw`] with **AT LEAST** `capacity` bytes.
     pub fn new(capacity: usize) -> Self {
-        let capacity = bits::align_up(PAGE, capacity);
-        let layout = unsafe { Layout::from_size_align_unchecked(capacity, PAGE) };
+        Self::with_alignment(capacity, IoAlignment::default())
+    }
+
+    /// Allocate a [`Raw`] with **AT LEAST** `capacity` bytes, aligned to the given alignment.
+    ///
+    /// Both the address and the capacity are aligned.
+    pub fn with_alignment(capacity: usize, align: IoAlignment) -> Self {
+        let align = align.get();
+        let capacity = bits::align_up(align, capacity);
+        let layout = unsafe { Layout::from_size_align_unchecked(capacity, align) };
         let mut nonnull = match Global.allocate(layout) {
             Ok(nonnull) => nonnull,
             Err(_) => handle_alloc_error(layout),
@@ -74,7 +83,12 @@ impl Raw {
         let slice = unsafe { nonnull.as_mut() };
         let ptr = slice.as_mut_ptr();
         let cap = slice.len();
-        Self { ptr, cap }
+        Self { ptr, cap, align }
+    }
+
+    /// Get the alignment of the [`Raw`].
+    pub fn alignment(&self) -> usize {
+        self.align
     }
 
     /// Consume [`Raw`] and get the raw pointer and the capacity.
@@ -82,25 +96,58 @@ impl Raw {
     /// # Safety
     ///
     /// [`Raw::from_raw_parts`] must be called later. Otherwise the buffer memory will leak.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`Raw`] is not 4K-aligned, use [`Raw::into_raw_parts_with_alignment`] instead.
     pub fn into_raw_parts(self) -> (*mut u8, usize) {
+        assert_eq!(
+            self.align,
+            IoAlignment::default().get(),
+            "use `into_raw_parts_with_alignment` for non-4K-aligned raw bytes"
+        );
         let res = (self.ptr, self.cap);
         std::mem::forget(self);
         res
     }
 
-    /// Construct [`Raw`] with the raw pointer and the capacity.
+    /// Construct a 4K-aligned [`Raw`] with the raw pointer and the capacity.
     ///
     /// # Safety
     ///
     /// The `ptr` and `cap` must be returned by [`Raw::into_raw_parts`].
     pub unsafe fn from_raw_parts(ptr: *mut u8, cap: usize) -> Self {
-        Self { ptr, cap }
+        Self {
+            ptr,
+            cap,
+            align: IoAlignment::default().get(),
+        }
+    }
+
+    /// Consume [`Raw`] and get the raw pointer, the capacity and the alignment.
+    ///
+    /// # Safety
+    ///
+    /// [`Raw::from_raw_parts_with_alignment`] must be called later. Otherwise the buffer memory will leak.
+    pub fn into_raw_parts_with_alignment(self) -> (*mut u8, usize, usize) {
+        let res = (self.ptr, self.cap, self.align);
+        std::mem::forget(self);
+        res
+    }
+
+    /// Construct [`Raw`] with the raw pointer, the capacity and the alignment.
+    ///
+    /// # Safety
+    ///
+    /// The `ptr`, `cap` and `align` must be returned by [`Raw::into_raw_parts_with_alignment`].
+    pub unsafe fn from_raw_parts_with_alignment(ptr: *mut u8, cap: usize, align: usize) -> Self {
+        Self { ptr, cap, align }
     }
 }
 
 impl Clone for Raw {
     fn clone(&self) -> Self {
-        let mut buf = Raw::new(self.cap);
+        let mut buf = Raw::with_alignment(self.cap, IoAlignment(self.align));
         assert_eq!(buf.cap, self.cap);
         buf.copy_from_slice(self);
         buf
@@ -109,7 +156,7 @@ impl Clone for Raw {
 
 impl Drop for Raw {
     fn drop(&mut self) {
-        let layout = unsafe { Layout::from_size_align_unchecked(self.cap, PAGE) };
+        let layout = unsafe { Layout::from_size_align_unchecked(self.cap, self.align) };
         unsafe { Global.deallocate(NonNull::new_unchecked(self.ptr), layout) };
     }
 }
@@ -258,8 +305,8 @@ impl IoSlice {
             panic!("slice index starts at {s} but ends at {e}");
         }
 
-        bits::assert_aligned(PAGE, s);
-        bits::assert_aligned(PAGE, e);
+        bits::assert_aligned(self.raw.align, s);
+        bits::assert_aligned(self.raw.align, e);
 
         Self {
             raw: self.raw.clone(),
@@ -287,9 +334,20 @@ impl IoSliceMut {
         Self { raw }
     }
 
+    /// Allocate an [`IoSliceMut`] with **AT LEAST** `capacity` bytes, aligned to the given alignment.
+    pub fn with_alignment(capacity: usize, align: IoAlignment) -> Self {
+        let raw = Raw::with_alignment(capacity, align);
+        Self { raw }
+    }
+
     pub fn len(&self) -> usize {
         self.raw.cap
     }
+
+    /// Get the alignment of the [`IoSliceMut`].
+    pub fn alignment(&self) -> usize {
+        self.raw.align
+    }
 }
 
 impl Deref for IoSliceMut {
@@ -369,6 +427,7 @@ impl dyn IoB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::io::PAGE;
 
     #[test]
     fn test_dyn() {
@@ -376,4 +435,62 @@ mod tests {
         let _: Box<dyn IoBuf> = Box::new(raw.clone());
         let _: Box<dyn IoBufMut> = Box::new(raw.clone());
     }
+
+    fn assert_io_aligned(buf: &dyn IoB) {
+        let (ptr, len) = buf.as_raw_parts();
+        assert!(bits::is_aligned(PAGE, ptr as usize));
+        assert!(bits::is_aligned(PAGE, len));
+    }
+
+    #[test]
+    fn test_aligned() {
+        assert_io_aligned(&Raw::new(1));
+        assert_io_aligned(&Raw::new(PAGE * 3 + 1));
+        assert_io_aligned(&IoSliceMut::new(PAGE + 1));
+
+        let slice = IoSliceMut::new(PAGE * 4).into_io_slice();
+        assert_io_aligned(&slice);
+        assert_io_aligned(&slice.slice(PAGE..PAGE * 3));
+        assert_io_aligned(&slice.slice(PAGE * 2..).slice(PAGE..));
+    }
+
+    #[test]
+    fn test_custom_alignment() {
+        for align in [512, 8192] {
+            let align = IoAlignment::new(align).unwrap();
+            let assert_aligned = |buf: &dyn IoB| {
+                let (ptr, len) = buf.as_raw_parts();
+                assert!(bits::is_aligned(align.get(), ptr as usize));
+                assert!(bits::is_aligned(align.get(), len));
+            };
+
+            let raw = Raw::with_alignment(align.get() + 1, align);
+            assert_eq!(raw.len(), 2 * align.get());
+            assert_eq!(raw.alignment(), align.get());
+            assert_aligned(&raw.clone());
+
+            let (ptr, cap, a) = raw.into_raw_parts_with_alignment();
+            let raw = unsafe { Raw::from_raw_parts_with_alignment(ptr, cap, a) };
+            assert_eq!(raw.alignment(), align.get());
+
+            let slice = IoSliceMut::with_alignment(align.get() * 4, align).into_io_slice();
+            assert_aligned(&slice.slice(align.get()..align.get() * 3));
+        }
+
+        assert!(IoAlignment::new(0).is_err());
+        assert!(IoAlignment::new(256).is_err());
+        assert!(IoAlignment::new(3000).is_err());
+        assert_eq!(IoAlignment::default().get(), PAGE);
+
+        let (ptr, cap) = Raw::new(PAGE).into_raw_parts();
+        let raw = unsafe { Raw::from_raw_parts(ptr, cap) };
+        assert_eq!(raw.alignment(), PAGE);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unaligned_slice() {
+        let slice = IoSliceMut::new(PAGE * 2).into_io_slice();
+        let _ = slice.slice(1..PAGE);
+    }
 }
@@ -16,8 +16,9 @@ use std::sync::{Arc, RwLock};
 
 use crate::{
     io::{
-        device::{Device, DeviceBuilder, Partition, PartitionId},
+        device::{Device, DeviceBuilder, Partition, PartitionId, DEFAULT_LOGICAL_BLOCK_SIZE},
         error::{IoError, IoResult},
+        IoAlignment,
     },
     RawFile, Statistics, Throttle,
 };
@@ -151,6 +152,24 @@ impl Device for CombinedDevice {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn logical_block_size(&self) -> usize {
+        // Logical block sizes are powers of 2, the largest one satisfies all devices.
+        self.devices
+            .iter()
+            .map(|d| d.logical_block_size())
+            .max()
+            .unwrap_or(DEFAULT_LOGICAL_BLOCK_SIZE)
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        // Alignments are powers of 2, the largest one satisfies all devices.
+        self.devices
+            .iter()
+            .map(|d| d.alignment())
+            .max_by_key(|a| a.get())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug)]
@@ -176,4 +195,8 @@ impl Partition for CombinedPartition {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn alignment(&self) -> IoAlignment {
+        self.inner.alignment()
+    }
 }
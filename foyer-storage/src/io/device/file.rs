@@ -22,9 +22,12 @@ use fs4::free_space;
 
 use crate::{
     io::{
-        device::{statistics::Statistics, throttle::Throttle, Device, DeviceBuilder, Partition, PartitionId},
+        device::{
+            query_logical_block_size, statistics::Statistics, throttle::Throttle, Device, DeviceBuilder, Partition,
+            PartitionId,
+        },
         error::IoResult,
-        PAGE,
+        IoAlignment,
     },
     IoError, RawFile,
 };
@@ -35,7 +38,7 @@ pub struct FileDeviceBuilder {
     path: PathBuf,
     capacity: Option<usize>,
     throttle: Throttle,
-    #[cfg(target_os = "linux")]
+    alignment: IoAlignment,
     direct: bool,
 }
 
@@ -46,7 +49,7 @@ impl FileDeviceBuilder {
             path: path.as_ref().into(),
             capacity: None,
             throttle: Throttle::default(),
-            #[cfg(target_os = "linux")]
+            alignment: IoAlignment::default(),
             direct: false,
         }
     }
@@ -67,8 +70,23 @@ impl FileDeviceBuilder {
         self
     }
 
-    /// Set whether the file device should use direct I/O.
-    #[cfg(target_os = "linux")]
+    /// Set the IO alignment of the file device.
+    ///
+    /// The alignment must be a multiple of the logical block size of the device, which is queried from the raw block
+    /// device or the file system on build. The capacity is aligned to it on build.
+    ///
+    /// Default: [`IoAlignment::default`], which is 4K.
+    pub fn with_alignment(mut self, alignment: IoAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Set whether the file device should use direct I/O, which opens the file(s) with `O_DIRECT` and bypasses the page
+    /// cache.
+    ///
+    /// With direct I/O, the offsets, the lengths and the buffers of the I/Os must be aligned to the IO alignment.
+    ///
+    /// Direct I/O is only supported on Linux. On the other platforms, the option is ignored with a warning.
     pub fn with_direct(mut self, direct: bool) -> Self {
         self.direct = direct;
         self
@@ -87,10 +105,15 @@ impl DeviceBuilder for FileDeviceBuilder {
             create_dir_all(&dir).unwrap();
             free_space(&dir).unwrap() as usize / 10 * 8
         });
-        let capacity = align_v(capacity, PAGE);
+        let capacity = align_v(capacity, self.alignment.get());
 
         // Build device.
 
+        #[cfg(not(target_os = "linux"))]
+        if self.direct {
+            tracing::warn!("[file device]: Direct I/O is only supported on Linux, fall back to buffered I/O.");
+        }
+
         let mut opts = OpenOptions::new();
         opts.create(true).write(true).read(true);
         #[cfg(target_os = "linux")]
@@ -110,6 +133,7 @@ impl DeviceBuilder for FileDeviceBuilder {
             );
             file.set_len(capacity as _)?;
         }
+        let logical_block_size = query_logical_block_size(&file);
         let file = Arc::new(file);
 
         let statistics = Arc::new(Statistics::new(self.throttle));
@@ -117,9 +141,12 @@ impl DeviceBuilder for FileDeviceBuilder {
         let device = FileDevice {
             file,
             capacity,
+            alignment: self.alignment,
+            logical_block_size,
             statistics,
             partitions: RwLock::new(vec![]),
         };
+        device.alignment.validate_block_size(device.logical_block_size())?;
         let device: Arc<dyn Device> = Arc::new(device);
         Ok(device)
     }
@@ -130,6 +157,8 @@ impl DeviceBuilder for FileDeviceBuilder {
 pub struct FileDevice {
     file: Arc<File>,
     capacity: usize,
+    alignment: IoAlignment,
+    logical_block_size: usize,
     partitions: RwLock<Vec<Arc<FilePartition>>>,
     statistics: Arc<Statistics>,
 }
@@ -160,6 +189,7 @@ impl Device for FileDevice {
             id,
             size,
             offset,
+            alignment: self.alignment,
             statistics: self.statistics.clone(),
         });
         partitions.push(partition.clone());
@@ -177,6 +207,14 @@ impl Device for FileDevice {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn logical_block_size(&self) -> usize {
+        self.logical_block_size
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
 }
 
 #[derive(Debug)]
@@ -185,6 +223,7 @@ pub struct FilePartition {
     id: PartitionId,
     size: usize,
     offset: u64,
+    alignment: IoAlignment,
     statistics: Arc<Statistics>,
 }
 
@@ -217,4 +256,45 @@ impl Partition for FilePartition {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test_log::test(tokio::test)]
+    async fn test_direct_file_device() {
+        use crate::io::{
+            bytes::IoSliceMut,
+            engine::{psync::PsyncIoEngineBuilder, IoEngineBuilder},
+            PAGE,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let device = FileDeviceBuilder::new(dir.path().join("direct"))
+            .with_capacity(4 * PAGE)
+            .with_direct(true)
+            .build()
+            .unwrap();
+        let partition = device.create_partition(4 * PAGE).unwrap();
+        let engine = PsyncIoEngineBuilder::new().build().await.unwrap();
+
+        let mut buf = IoSliceMut::new(2 * PAGE);
+        buf.fill(42);
+        let (_, res) = engine
+            .write(Box::new(buf.into_io_slice()), partition.as_ref(), PAGE as u64)
+            .await;
+        res.unwrap();
+
+        let (buf, res) = engine
+            .read(Box::new(IoSliceMut::new(2 * PAGE)), partition.as_ref(), PAGE as u64)
+            .await;
+        res.unwrap();
+        assert!(buf.iter().all(|b| *b == 42));
+    }
 }
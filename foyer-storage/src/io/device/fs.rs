@@ -22,9 +22,12 @@ use fs4::free_space;
 
 use crate::{
     io::{
-        device::{statistics::Statistics, throttle::Throttle, Device, DeviceBuilder, Partition, PartitionId},
+        device::{
+            query_logical_block_size, statistics::Statistics, throttle::Throttle, Device, DeviceBuilder, Partition,
+            PartitionId,
+        },
         error::IoResult,
-        PAGE,
+        IoAlignment,
     },
     IoError, RawFile,
 };
@@ -35,7 +38,7 @@ pub struct FsDeviceBuilder {
     dir: PathBuf,
     capacity: Option<usize>,
     throttle: Throttle,
-    #[cfg(target_os = "linux")]
+    alignment: IoAlignment,
     direct: bool,
 }
 
@@ -46,7 +49,7 @@ impl FsDeviceBuilder {
             dir: dir.as_ref().into(),
             capacity: None,
             throttle: Throttle::default(),
-            #[cfg(target_os = "linux")]
+            alignment: IoAlignment::default(),
             direct: false,
         }
     }
@@ -67,8 +70,23 @@ impl FsDeviceBuilder {
         self
     }
 
-    /// Set whether the file device should use direct I/O.
-    #[cfg(target_os = "linux")]
+    /// Set the IO alignment of the file device.
+    ///
+    /// The alignment must be a multiple of the logical block size of the device, which is queried from the file system
+    /// on build. The capacity is aligned to it on build.
+    ///
+    /// Default: [`IoAlignment::default`], which is 4K.
+    pub fn with_alignment(mut self, alignment: IoAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Set whether the file device should use direct I/O, which opens the file(s) with `O_DIRECT` and bypasses the page
+    /// cache.
+    ///
+    /// With direct I/O, the offsets, the lengths and the buffers of the I/Os must be aligned to the IO alignment.
+    ///
+    /// Direct I/O is only supported on Linux. On the other platforms, the option is ignored with a warning.
     pub fn with_direct(mut self, direct: bool) -> Self {
         self.direct = direct;
         self
@@ -86,24 +104,42 @@ impl DeviceBuilder for FsDeviceBuilder {
             create_dir_all(&self.dir).unwrap();
             free_space(&self.dir).unwrap() as usize / 10 * 8
         });
-        let capacity = align_v(capacity, PAGE);
+        let capacity = align_v(capacity, self.alignment.get());
 
         let statistics = Arc::new(Statistics::new(self.throttle));
 
         // Build device.
 
+        #[cfg(not(target_os = "linux"))]
+        if self.direct {
+            tracing::warn!("[fs device]: Direct I/O is only supported on Linux, fall back to buffered I/O.");
+        }
+
         if !self.dir.exists() {
             create_dir_all(&self.dir)?;
         }
 
+        // The partition files are created on demand, probe the logical block size with a temporary file.
+        let logical_block_size = {
+            let path = self.dir.join(FsDevice::PROBE);
+            let file = OpenOptions::new().create(true).write(true).truncate(true).open(&path)?;
+            let logical_block_size = query_logical_block_size(&file);
+            drop(file);
+            std::fs::remove_file(&path)?;
+            logical_block_size
+        };
+
         let device = FsDevice {
             capacity,
+            alignment: self.alignment,
+            logical_block_size,
             statistics,
             dir: self.dir,
             #[cfg(target_os = "linux")]
             direct: self.direct,
             partitions: RwLock::new(vec![]),
         };
+        device.alignment.validate_block_size(device.logical_block_size())?;
         let device: Arc<dyn Device> = Arc::new(device);
         Ok(device)
     }
@@ -113,6 +149,8 @@ impl DeviceBuilder for FsDeviceBuilder {
 #[derive(Debug)]
 pub struct FsDevice {
     capacity: usize,
+    alignment: IoAlignment,
+    logical_block_size: usize,
     statistics: Arc<Statistics>,
     dir: PathBuf,
     #[cfg(target_os = "linux")]
@@ -122,6 +160,7 @@ pub struct FsDevice {
 
 impl FsDevice {
     const PREFIX: &str = "foyer-storage-direct-fs-";
+    const PROBE: &str = "foyer-storage-direct-fs-probe";
     fn filename(partition: PartitionId) -> String {
         format!("{prefix}{partition:08}", prefix = Self::PREFIX,)
     }
@@ -161,6 +200,7 @@ impl Device for FsDevice {
         let partition = Arc::new(FsPartition {
             id,
             size,
+            alignment: self.alignment,
             file,
             statistics: self.statistics.clone(),
         });
@@ -179,12 +219,21 @@ impl Device for FsDevice {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn logical_block_size(&self) -> usize {
+        self.logical_block_size
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
 }
 
 #[derive(Debug)]
 pub struct FsPartition {
     id: PartitionId,
     size: usize,
+    alignment: IoAlignment,
     statistics: Arc<Statistics>,
     file: File,
 }
@@ -217,4 +266,8 @@ impl Partition for FsPartition {
     fn statistics(&self) -> &Arc<Statistics> {
         &self.statistics
     }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
 }
@@ -0,0 +1,292 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::{Any, TypeId},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+
+use crate::{
+    io::{
+        bytes::Raw,
+        device::{statistics::Statistics, throttle::Throttle, Device, DeviceBuilder, Partition, PartitionId},
+        error::{IoError, IoResult},
+        IoAlignment,
+    },
+    RawFile,
+};
+
+/// Builder for a memory-backed device, for tests and CI.
+///
+/// The memory device must be used with [`MemoryIoEngine`](crate::io::engine::memory::MemoryIoEngine).
+#[derive(Debug)]
+pub struct MemoryDeviceBuilder {
+    capacity: usize,
+    throttle: Throttle,
+    alignment: IoAlignment,
+}
+
+impl MemoryDeviceBuilder {
+    /// Create a new memory device builder with the specified capacity.
+    ///
+    /// The given capacity may be modified on build for alignment.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            throttle: Throttle::default(),
+            alignment: IoAlignment::default(),
+        }
+    }
+
+    /// Set the throttle of the memory device.
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Set the IO alignment of the memory device.
+    ///
+    /// Default: [`IoAlignment::default`], which is 4K.
+    pub fn with_alignment(mut self, alignment: IoAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+impl DeviceBuilder for MemoryDeviceBuilder {
+    fn build(self) -> IoResult<Arc<dyn Device>> {
+        let capacity = self.capacity - self.capacity % self.alignment.get();
+        let statistics = Arc::new(Statistics::new(self.throttle));
+        let device: Arc<dyn Device> = Arc::new(MemoryDevice {
+            capacity,
+            alignment: self.alignment,
+            partitions: RwLock::new(vec![]),
+            statistics,
+        });
+        Ok(device)
+    }
+}
+
+/// A device upon aligned memory regions.
+///
+/// Each partition owns its own region, which is allocated on partition creation.
+#[derive(Debug)]
+pub struct MemoryDevice {
+    capacity: usize,
+    alignment: IoAlignment,
+    partitions: RwLock<Vec<Arc<MemoryPartition>>>,
+    statistics: Arc<Statistics>,
+}
+
+impl Device for MemoryDevice {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn allocated(&self) -> usize {
+        self.partitions.read().iter().map(|p| p.size).sum()
+    }
+
+    fn create_partition(&self, size: usize) -> IoResult<Arc<dyn Partition>> {
+        let mut partitions = self.partitions.write();
+        let allocated = partitions.iter().map(|p| p.size).sum::<usize>();
+        if allocated + size > self.capacity {
+            return Err(IoError::NoSpace {
+                capacity: self.capacity,
+                allocated,
+                required: allocated + size,
+            });
+        }
+        let mut region = Raw::with_alignment(size, self.alignment);
+        region.fill(0);
+        let id = partitions.len() as PartitionId;
+        let partition = Arc::new(MemoryPartition {
+            id,
+            size,
+            alignment: self.alignment,
+            region: RwLock::new(region),
+            statistics: self.statistics.clone(),
+        });
+        partitions.push(partition.clone());
+        Ok(partition)
+    }
+
+    fn partitions(&self) -> usize {
+        self.partitions.read().len()
+    }
+
+    fn partition(&self, id: PartitionId) -> Arc<dyn Partition> {
+        self.partitions.read()[id as usize].clone()
+    }
+
+    fn statistics(&self) -> &Arc<Statistics> {
+        &self.statistics
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
+}
+
+/// A partition of [`MemoryDevice`].
+#[derive(Debug)]
+pub struct MemoryPartition {
+    id: PartitionId,
+    size: usize,
+    alignment: IoAlignment,
+    region: RwLock<Raw>,
+    statistics: Arc<Statistics>,
+}
+
+impl MemoryPartition {
+    /// Downcast the partition to a memory partition.
+    ///
+    /// Returns `None` if the partition is not a memory partition.
+    pub fn downcast(partition: &dyn Partition) -> Option<&Self> {
+        if Any::type_id(partition) != TypeId::of::<Self>() {
+            return None;
+        }
+        // SAFETY: The concrete type of the partition is checked above.
+        Some(unsafe { &*(partition as *const dyn Partition as *const Self) })
+    }
+
+    /// Check that the I/O is aligned and within the partition.
+    fn check(&self, offset: u64, len: usize) -> IoResult<()> {
+        let align = self.alignment.get();
+        if offset % align as u64 != 0 || len % align != 0 {
+            return Err(IoError::Unaligned { offset, len, align });
+        }
+        if offset + len as u64 > self.size as u64 {
+            return Err(IoError::OutOfRange {
+                offset,
+                len,
+                size: self.size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Read data into the buffer from the given offset of the partition.
+    ///
+    /// Both the offset and the length of the buffer must be aligned to the alignment of the device.
+    pub fn read(&self, buf: &mut [u8], offset: u64) -> IoResult<()> {
+        self.check(offset, buf.len())?;
+        let offset = offset as usize;
+        buf.copy_from_slice(&self.region.read()[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    /// Write data from the buffer to the given offset of the partition.
+    ///
+    /// Both the offset and the length of the buffer must be aligned to the alignment of the device.
+    pub fn write(&self, buf: &[u8], offset: u64) -> IoResult<()> {
+        self.check(offset, buf.len())?;
+        let offset = offset as usize;
+        self.region.write()[offset..offset + buf.len()].copy_from_slice(buf);
+        Ok(())
+    }
+}
+
+impl Partition for MemoryPartition {
+    fn id(&self) -> PartitionId {
+        self.id
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The memory partition is not backed by any file, an invalid raw file is returned so that a file-based I/O engine
+    /// fails instead of touching an unrelated file.
+    fn translate(&self, address: u64) -> (RawFile, u64) {
+        (RawFile(usize::MAX as _), address)
+    }
+
+    fn statistics(&self) -> &Arc<Statistics> {
+        &self.statistics
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        self.alignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{device::noop::NoopPartition, PAGE};
+
+    #[test]
+    fn test_memory_device() {
+        let device = MemoryDeviceBuilder::new(4 * PAGE + 1).build().unwrap();
+        assert_eq!(device.capacity(), 4 * PAGE);
+
+        let p0 = device.create_partition(2 * PAGE).unwrap();
+        let p1 = device.create_partition(2 * PAGE).unwrap();
+        assert_eq!(device.allocated(), 4 * PAGE);
+        assert!(matches!(device.create_partition(PAGE), Err(IoError::NoSpace { .. })));
+
+        let p0 = MemoryPartition::downcast(p0.as_ref()).unwrap();
+        let p1 = MemoryPartition::downcast(p1.as_ref()).unwrap();
+        assert!(MemoryPartition::downcast(&NoopPartition::default()).is_none());
+
+        // Partitions are isolated from each other, and unwritten regions are zeroed.
+        p0.write(&[1; PAGE], PAGE as u64).unwrap();
+        let mut buf = vec![0xff; 2 * PAGE];
+        p0.read(&mut buf, 0).unwrap();
+        assert!(buf[..PAGE].iter().all(|b| *b == 0));
+        assert!(buf[PAGE..].iter().all(|b| *b == 1));
+        p1.read(&mut buf, 0).unwrap();
+        assert!(buf.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_memory_device_unaligned() {
+        let device = MemoryDeviceBuilder::new(4 * PAGE).build().unwrap();
+        let partition = device.create_partition(2 * PAGE).unwrap();
+        let partition = MemoryPartition::downcast(partition.as_ref()).unwrap();
+
+        let mut buf = vec![0; PAGE];
+        assert!(matches!(
+            partition.read(&mut buf, 512),
+            Err(IoError::Unaligned {
+                offset: 512,
+                len: PAGE,
+                align: PAGE
+            })
+        ));
+        assert!(matches!(
+            partition.write(&buf[..512], 0),
+            Err(IoError::Unaligned { len: 512, .. })
+        ));
+        assert!(matches!(
+            partition.write(&buf, 2 * PAGE as u64),
+            Err(IoError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_memory_partition_alignment() {
+        let alignment = IoAlignment::new(2 * PAGE).unwrap();
+        let device = MemoryDeviceBuilder::new(4 * PAGE)
+            .with_alignment(alignment)
+            .build()
+            .unwrap();
+        let partition = device.create_partition(2 * PAGE).unwrap();
+        assert_eq!(partition.alignment(), alignment);
+        assert_eq!(NoopPartition::default().alignment(), IoAlignment::default());
+    }
+}
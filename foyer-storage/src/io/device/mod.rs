@@ -15,9 +15,13 @@
 pub mod statistics;
 pub mod throttle;
 
-use std::{any::Any, fmt::Debug, sync::Arc};
+use std::{any::Any, fmt::Debug, fs::File, sync::Arc};
 
-use crate::io::{device::statistics::Statistics, error::IoResult};
+use crate::io::{
+    device::statistics::{DeviceHealth, Statistics},
+    error::IoResult,
+    IoAlignment,
+};
 
 pub type PartitionId = u32;
 
@@ -57,6 +61,13 @@ pub trait Partition: Send + Sync + 'static + Debug + Any {
 
     /// Get the statistics of the device this partition belongs to.
     fn statistics(&self) -> &Arc<Statistics>;
+
+    /// Get the IO alignment of the device this partition belongs to.
+    ///
+    /// The default implementation returns the default [`IoAlignment`], which is 4K.
+    fn alignment(&self) -> IoAlignment {
+        IoAlignment::default()
+    }
 }
 
 /// Device trait.
@@ -90,11 +101,189 @@ pub trait Device: Send + Sync + 'static + Debug + Any {
 
     /// Get the statistics of the device this partition belongs to.
     fn statistics(&self) -> &Arc<Statistics>;
+
+    /// Get the health of the device, tracked from the results of recent IOs.
+    fn health(&self) -> DeviceHealth {
+        self.statistics().health()
+    }
+
+    /// Get the logical block size of the device, which the offset and the length of the direct IOs must be aligned
+    /// to.
+    ///
+    /// The default implementation returns [`DEFAULT_LOGICAL_BLOCK_SIZE`], the logical block size of most block devices.
+    fn logical_block_size(&self) -> usize {
+        DEFAULT_LOGICAL_BLOCK_SIZE
+    }
+
+    /// Get the IO alignment of the device, which the offset, the length and the buffer of the IOs must be aligned to.
+    ///
+    /// The default implementation returns the default [`IoAlignment`], which is 4K.
+    fn alignment(&self) -> IoAlignment {
+        IoAlignment::default()
+    }
+}
+
+/// The logical block size of most block devices, used if the device doesn't report it.
+pub const DEFAULT_LOGICAL_BLOCK_SIZE: usize = 512;
+
+/// Query the logical block size of the opened raw block device or regular file, which the direct IOs on it must be
+/// aligned to.
+///
+/// The logical block size of a raw block device is queried with `BLKSSZGET`. The direct IO alignment of a regular file
+/// is queried with `statx` (`STATX_DIOALIGN`, since Linux 6.1).
+///
+/// Falls back to [`DEFAULT_LOGICAL_BLOCK_SIZE`] if the platform or the file system doesn't report it.
+pub(crate) fn query_logical_block_size(file: &File) -> usize {
+    #[cfg(all(target_os = "linux", any(target_env = "gnu", target_env = "musl")))]
+    {
+        use std::os::{fd::AsRawFd, unix::fs::FileTypeExt};
+
+        let fd = file.as_raw_fd();
+        let is_block_device = file
+            .metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_block_device());
+        if is_block_device {
+            let mut size: libc::c_int = 0;
+            // SAFETY: `BLKSSZGET` writes the logical block size of the block device to the given `c_int`.
+            if unsafe { libc::ioctl(fd, libc::BLKSSZGET, &mut size) } == 0 && size > 0 {
+                return size as usize;
+            }
+        } else {
+            // SAFETY: `statx` with `AT_EMPTY_PATH` and an empty path queries the opened file and writes to the buffer.
+            let mut statx: libc::statx = unsafe { std::mem::zeroed() };
+            let res = unsafe { libc::statx(fd, c"".as_ptr(), libc::AT_EMPTY_PATH, libc::STATX_DIOALIGN, &mut statx) };
+            if res == 0 && statx.stx_mask & libc::STATX_DIOALIGN != 0 && statx.stx_dio_offset_align > 0 {
+                return statx.stx_dio_offset_align as usize;
+            }
+        }
+    }
+    #[cfg(not(all(target_os = "linux", any(target_env = "gnu", target_env = "musl"))))]
+    let _ = file;
+
+    DEFAULT_LOGICAL_BLOCK_SIZE
+}
+
+/// Validate that the IO alignment of the device satisfies the logical block size of the device.
+///
+/// The IO alignment must be a multiple of the logical block size, otherwise the direct IOs on the device fail.
+pub fn validate_alignment(device: &dyn Device) -> IoResult<()> {
+    device.alignment().validate_block_size(device.logical_block_size())
 }
 
 pub mod file;
 pub mod fs;
+pub mod memory;
 pub mod noop;
 
 pub mod combined;
 pub mod partial;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{device::noop::NoopDeviceBuilder, error::IoError, PAGE};
+
+    /// Mock device that reports the given logical block size.
+    #[derive(Debug)]
+    struct BlockSizeDevice {
+        inner: Arc<dyn Device>,
+        block_size: usize,
+    }
+
+    impl BlockSizeDevice {
+        fn new(block_size: usize) -> Self {
+            Self {
+                inner: NoopDeviceBuilder::new(16 * PAGE).build().unwrap(),
+                block_size,
+            }
+        }
+    }
+
+    impl Device for BlockSizeDevice {
+        fn capacity(&self) -> usize {
+            self.inner.capacity()
+        }
+
+        fn allocated(&self) -> usize {
+            self.inner.allocated()
+        }
+
+        fn create_partition(&self, size: usize) -> IoResult<Arc<dyn Partition>> {
+            self.inner.create_partition(size)
+        }
+
+        fn partitions(&self) -> usize {
+            self.inner.partitions()
+        }
+
+        fn partition(&self, id: PartitionId) -> Arc<dyn Partition> {
+            self.inner.partition(id)
+        }
+
+        fn statistics(&self) -> &Arc<Statistics> {
+            self.inner.statistics()
+        }
+
+        fn logical_block_size(&self) -> usize {
+            self.block_size
+        }
+    }
+
+    #[test]
+    fn test_validate_alignment() {
+        validate_alignment(&BlockSizeDevice::new(512)).unwrap();
+        validate_alignment(&BlockSizeDevice::new(PAGE)).unwrap();
+
+        let res = validate_alignment(&BlockSizeDevice::new(8192));
+        assert!(
+            matches!(
+                res,
+                Err(IoError::Alignment {
+                    align: PAGE,
+                    block_size: 8192
+                })
+            ),
+            "{res:?}"
+        );
+        assert!(matches!(
+            validate_alignment(&BlockSizeDevice::new(0)),
+            Err(IoError::Alignment { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_alignment_combined() {
+        use crate::io::device::combined::CombinedDeviceBuilder;
+
+        let device = CombinedDeviceBuilder::new()
+            .with_device(Arc::new(BlockSizeDevice::new(512)))
+            .with_device(Arc::new(BlockSizeDevice::new(8192)))
+            .build()
+            .unwrap();
+        assert_eq!(device.logical_block_size(), 8192);
+        assert!(validate_alignment(device.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_query_logical_block_size() {
+        use crate::io::device::{file::FileDeviceBuilder, fs::FsDeviceBuilder};
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = File::create(dir.path().join("file")).unwrap();
+        assert!(query_logical_block_size(&file).is_power_of_two());
+
+        let device = FileDeviceBuilder::new(dir.path().join("device"))
+            .with_capacity(4 * PAGE)
+            .build()
+            .unwrap();
+        assert_eq!(device.logical_block_size(), query_logical_block_size(&file));
+
+        let device = FsDeviceBuilder::new(dir.path().join("fs"))
+            .with_capacity(4 * PAGE)
+            .build()
+            .unwrap();
+        assert_eq!(device.logical_block_size(), query_logical_block_size(&file));
+        // The probe file is removed.
+        assert_eq!(std::fs::read_dir(dir.path().join("fs")).unwrap().count(), 0);
+    }
+}
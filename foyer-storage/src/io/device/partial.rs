@@ -18,6 +18,7 @@ use crate::{
     io::{
         device::{statistics::Statistics, Device, DeviceBuilder, Partition, PartitionId},
         error::IoResult,
+        IoAlignment,
     },
     IoError,
 };
@@ -108,6 +109,14 @@ impl Device for PartialDevice {
     fn statistics(&self) -> &Arc<Statistics> {
         self.inner.statistics()
     }
+
+    fn logical_block_size(&self) -> usize {
+        self.inner.logical_block_size()
+    }
+
+    fn alignment(&self) -> IoAlignment {
+        self.inner.alignment()
+    }
 }
 
 #[derive(Debug)]
@@ -132,4 +141,8 @@ impl Partition for PartialPartition {
     fn statistics(&self) -> &Arc<Statistics> {
         self.inner.statistics()
     }
+
+    fn alignment(&self) -> IoAlignment {
+        self.inner.alignment()
+    }
 }
@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use std::{
-    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+    num::NonZeroUsize,
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering},
     time::Duration,
 };
 
 use fastant::{Atomic, Instant};
+use parking_lot::Mutex;
 
 use crate::Throttle;
 
@@ -79,15 +81,148 @@ impl Metric {
     }
 }
 
+/// Token bucket rate limiter that queues the IOs over the budget instead of rejecting them.
+///
+/// The bucket holds up to one second of budget for bursts. Each IO reserves its bytes on submission and waits until the
+/// bucket is refilled enough, so the IOs proceed in the submission order.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: Option<NonZeroUsize>,
+    /// The time when all reserved budget is paid off.
+    paid_off: Mutex<Option<std::time::Instant>>,
+    consumed: AtomicUsize,
+}
+
+impl RateLimiter {
+    /// Duration of the budget that the bucket holds for bursts.
+    const BURST: Duration = Duration::from_secs(1);
+
+    /// Create a new rate limiter with the given rate in bytes per second. `None` means unlimited.
+    pub fn new(rate: Option<NonZeroUsize>) -> Self {
+        Self {
+            rate,
+            paid_off: Mutex::new(None),
+            consumed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the rate limit in bytes per second. `None` means unlimited.
+    pub fn rate(&self) -> Option<NonZeroUsize> {
+        self.rate
+    }
+
+    /// Get the bytes that have acquired the budget.
+    pub fn consumed(&self) -> usize {
+        self.consumed.load(Ordering::Relaxed)
+    }
+
+    /// Reserve the budget for the given bytes, return the duration to wait before the IO can proceed.
+    fn reserve(&self, bytes: usize, now: std::time::Instant) -> Duration {
+        self.consumed.fetch_add(bytes, Ordering::Relaxed);
+        let Some(rate) = self.rate else {
+            return Duration::ZERO;
+        };
+        let cost = Duration::from_secs_f64(bytes as f64 / rate.get() as f64);
+
+        let mut paid_off = self.paid_off.lock();
+        // The bucket is full if all reserved budget has been paid off.
+        let start = match *paid_off {
+            Some(paid_off) if paid_off > now => paid_off,
+            _ => now,
+        };
+        let end = start + cost;
+        *paid_off = Some(end);
+        (end - now).saturating_sub(Self::BURST)
+    }
+
+    /// Wait until there is budget for the given bytes.
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve(bytes, std::time::Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Error score threshold that marks the device as [`DeviceHealth::Degraded`].
+const DEGRADED_ERROR_SCORE: usize = 4;
+/// Error score threshold that marks the device as [`DeviceHealth::Failed`] and fences it.
+const FAILED_ERROR_SCORE: usize = 16;
+
+/// The health state of the device, tracked from the results of recent IOs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceHealth {
+    /// The device works as expected.
+    Healthy,
+    /// The device has returned errors recently, but is still serving IOs.
+    Degraded,
+    /// The device keeps returning errors and is fenced.
+    ///
+    /// New IOs on a fenced device are rejected fast with [`crate::IoError::Device`].
+    Failed,
+}
+
+/// Tracks the recent error rate of the device.
+///
+/// Each failed IO increases the error score by 1 and each successful IO decreases it by 1, so the score only keeps
+/// growing while errors dominate the recent IOs. Once the score reaches [`FAILED_ERROR_SCORE`], the device is fenced
+/// until [`Statistics::reset_health`] is called.
+#[derive(Debug, Default)]
+struct Health {
+    score: AtomicUsize,
+    fenced: AtomicBool,
+}
+
+impl Health {
+    fn record(&self, ok: bool) {
+        if self.fenced.load(Ordering::Relaxed) {
+            return;
+        }
+        let score = if ok {
+            match self
+                .score
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| v.checked_sub(1))
+            {
+                Ok(v) => v - 1,
+                Err(v) => v,
+            }
+        } else {
+            self.score.fetch_add(1, Ordering::Relaxed) + 1
+        };
+        if score >= FAILED_ERROR_SCORE {
+            self.fenced.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self) -> DeviceHealth {
+        if self.fenced.load(Ordering::Relaxed) {
+            DeviceHealth::Failed
+        } else if self.score.load(Ordering::Relaxed) >= DEGRADED_ERROR_SCORE {
+            DeviceHealth::Degraded
+        } else {
+            DeviceHealth::Healthy
+        }
+    }
+
+    fn reset(&self) {
+        self.score.store(0, Ordering::Relaxed);
+        self.fenced.store(false, Ordering::Relaxed);
+    }
+}
+
 /// The statistics of the device.
 #[derive(Debug)]
 pub struct Statistics {
     throttle: Throttle,
+    health: Health,
 
     disk_write_bytes: Metric,
     disk_read_bytes: Metric,
     disk_write_ios: Metric,
     disk_read_ios: Metric,
+
+    write_rate_limiter: RateLimiter,
+    read_rate_limiter: RateLimiter,
 }
 
 impl Statistics {
@@ -97,12 +232,17 @@ impl Statistics {
         let disk_read_bytes = Metric::new(throttle.read_throughput.map(|v| v.get()).unwrap_or_default() as f64);
         let disk_write_ios = Metric::new(throttle.write_iops.map(|v| v.get()).unwrap_or_default() as f64);
         let disk_read_ios = Metric::new(throttle.read_iops.map(|v| v.get()).unwrap_or_default() as f64);
+        let write_rate_limiter = RateLimiter::new(throttle.write_rate_limit);
+        let read_rate_limiter = RateLimiter::new(throttle.read_rate_limit);
         Self {
             throttle,
+            health: Health::default(),
             disk_write_bytes,
             disk_read_bytes,
             disk_write_ios,
             disk_read_ios,
+            write_rate_limiter,
+            read_rate_limiter,
         }
     }
 
@@ -166,4 +306,130 @@ impl Statistics {
     pub fn throttle(&self) -> &Throttle {
         &self.throttle
     }
+
+    /// Get the rate limiter for the writes, configured by [`Throttle::write_rate_limit`].
+    pub fn write_rate_limiter(&self) -> &RateLimiter {
+        &self.write_rate_limiter
+    }
+
+    /// Get the rate limiter for the reads, configured by [`Throttle::read_rate_limit`].
+    pub fn read_rate_limiter(&self) -> &RateLimiter {
+        &self.read_rate_limiter
+    }
+
+    /// Record the result of a completed IO and update the device health.
+    pub fn record_io_result(&self, ok: bool) {
+        self.health.record(ok);
+    }
+
+    /// Get the health of the device.
+    pub fn health(&self) -> DeviceHealth {
+        self.health.load()
+    }
+
+    /// Check if the device is fenced and rejects new IOs.
+    pub fn is_fenced(&self) -> bool {
+        self.health() == DeviceHealth::Failed
+    }
+
+    /// Reset the device health to [`DeviceHealth::Healthy`] and lift the fence.
+    pub fn reset_health(&self) {
+        self.health.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_util::future::join_all;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_reserve() {
+        const KB: usize = 1024;
+
+        let limiter = RateLimiter::new(NonZeroUsize::new(100 * KB));
+        let now = std::time::Instant::now();
+
+        // Up to one second of budget proceeds immediately.
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(10 * KB, now), Duration::ZERO);
+        }
+        // The IOs over the budget wait in the submission order.
+        assert_eq!(limiter.reserve(10 * KB, now), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(20 * KB, now), Duration::from_millis(300));
+        // The bucket is refilled as time goes by.
+        let later = now + Duration::from_millis(300);
+        assert_eq!(limiter.reserve(10 * KB, later), Duration::from_millis(100));
+        let idle = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(10 * KB, idle), Duration::ZERO);
+        assert_eq!(limiter.consumed(), 150 * KB);
+
+        let unlimited = RateLimiter::new(None);
+        assert_eq!(unlimited.reserve(usize::MAX, now), Duration::ZERO);
+        assert_eq!(unlimited.rate(), None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_rate_limiter_spread_burst() {
+        const KB: usize = 1024;
+
+        let statistics = Arc::new(Statistics::new(Throttle::new().with_write_rate_limit(64 * KB)));
+        let limiter = statistics.write_rate_limiter();
+        assert_eq!(limiter.rate(), NonZeroUsize::new(64 * KB));
+
+        // A burst of 128 KiB writes with a 64 KiB/s limit, the first half proceeds immediately.
+        let start = std::time::Instant::now();
+        let elapsed = join_all((0..16).map(|_| async {
+            limiter.acquire(8 * KB).await;
+            start.elapsed()
+        }))
+        .await;
+
+        assert!(elapsed[7] < Duration::from_millis(500), "{elapsed:?}");
+        assert!(elapsed[15] >= Duration::from_millis(900), "{elapsed:?}");
+        assert!(elapsed.is_sorted(), "{elapsed:?}");
+        assert_eq!(limiter.consumed(), 128 * KB);
+    }
+
+    #[test]
+    fn test_device_health() {
+        let statistics = Statistics::new(Throttle::default());
+        assert_eq!(statistics.health(), DeviceHealth::Healthy);
+
+        for _ in 0..DEGRADED_ERROR_SCORE {
+            statistics.record_io_result(false);
+        }
+        assert_eq!(statistics.health(), DeviceHealth::Degraded);
+
+        // Successful IOs recover a degraded device.
+        for _ in 0..DEGRADED_ERROR_SCORE {
+            statistics.record_io_result(true);
+        }
+        assert_eq!(statistics.health(), DeviceHealth::Healthy);
+
+        // Occasional errors mixed with successful IOs do not fail the device.
+        for _ in 0..FAILED_ERROR_SCORE * 4 {
+            statistics.record_io_result(false);
+            statistics.record_io_result(true);
+        }
+        assert_eq!(statistics.health(), DeviceHealth::Healthy);
+
+        for _ in 0..FAILED_ERROR_SCORE {
+            statistics.record_io_result(false);
+        }
+        assert_eq!(statistics.health(), DeviceHealth::Failed);
+        assert!(statistics.is_fenced());
+
+        // A fenced device stays failed until it is reset explicitly.
+        for _ in 0..FAILED_ERROR_SCORE {
+            statistics.record_io_result(true);
+        }
+        assert_eq!(statistics.health(), DeviceHealth::Failed);
+
+        statistics.reset_health();
+        assert_eq!(statistics.health(), DeviceHealth::Healthy);
+    }
 }
@@ -88,6 +88,18 @@ pub struct Throttle {
     /// The maximum read throughput for the device.
     #[cfg_attr(feature = "clap", clap(long))]
     pub read_throughput: Option<NonZeroUsize>,
+    /// The write rate limit for the device in bytes per second.
+    ///
+    /// Unlike `write_throughput`, the writes over the rate limit are queued until there is budget instead of being
+    /// rejected.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub write_rate_limit: Option<NonZeroUsize>,
+    /// The read rate limit for the device in bytes per second.
+    ///
+    /// Unlike `read_throughput`, the reads over the rate limit are queued until there is budget instead of being
+    /// rejected.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub read_rate_limit: Option<NonZeroUsize>,
     /// The iops counter for the device.
     #[cfg_attr(feature = "clap", clap(long, default_value = "PerIo"))]
     pub iops_counter: IopsCounter,
@@ -107,6 +119,8 @@ impl Throttle {
             read_iops: None,
             write_throughput: None,
             read_throughput: None,
+            write_rate_limit: None,
+            read_rate_limit: None,
             iops_counter: IopsCounter::PerIo,
         }
     }
@@ -135,6 +149,18 @@ impl Throttle {
         self
     }
 
+    /// Set the write rate limit for the device in bytes per second.
+    pub fn with_write_rate_limit(mut self, rate: usize) -> Self {
+        self.write_rate_limit = NonZeroUsize::new(rate);
+        self
+    }
+
+    /// Set the read rate limit for the device in bytes per second.
+    pub fn with_read_rate_limit(mut self, rate: usize) -> Self {
+        self.read_rate_limit = NonZeroUsize::new(rate);
+        self
+    }
+
     /// Set the iops counter for the device.
     pub fn with_iops_counter(mut self, counter: IopsCounter) -> Self {
         self.iops_counter = counter;
@@ -0,0 +1,246 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{ops::Range, sync::Arc};
+
+use foyer_common::bits;
+use futures_util::future::join_all;
+use itertools::Itertools;
+
+use crate::io::{
+    bytes::{IoSlice, IoSliceMut},
+    device::Partition,
+    engine::IoEngine,
+    error::{IoError, IoResult},
+    PAGE,
+};
+
+/// A read request of a range of a partition.
+///
+/// NOTE: `offset` and `len` must be 4K aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadRequest {
+    /// Offset of the range in the partition.
+    pub offset: u64,
+    /// Length of the range.
+    pub len: usize,
+}
+
+impl ReadRequest {
+    fn range(&self) -> Range<u64> {
+        self.offset..self.offset + self.len as u64
+    }
+}
+
+/// A device read merged from the contiguous or overlapping read requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MergedRead {
+    offset: u64,
+    len: usize,
+    /// Indices of the merged read requests.
+    requests: Vec<usize>,
+}
+
+/// Read coalescer that merges the contiguous or overlapping read requests of a batch into fewer device reads.
+///
+/// Scans usually read many adjacent blocks, merging them into larger reads reduces the syscalls or the completions of
+/// the I/O engine. The results are scattered back to the requests as slices of the merged buffers, without copying.
+#[derive(Debug, Clone)]
+pub struct ReadCoalescer {
+    io_engine: Arc<dyn IoEngine>,
+    max_merged_size: usize,
+}
+
+impl ReadCoalescer {
+    /// Default max size of a merged read.
+    pub const DEFAULT_MAX_MERGED_SIZE: usize = 1024 * 1024;
+
+    /// Create a read coalescer that reads with the given I/O engine.
+    pub fn new(io_engine: Arc<dyn IoEngine>) -> Self {
+        Self {
+            io_engine,
+            max_merged_size: Self::DEFAULT_MAX_MERGED_SIZE,
+        }
+    }
+
+    /// Set the max size of a merged read.
+    ///
+    /// The requests are not merged if the merged read would exceed the max size. A single request that exceeds the
+    /// max size is read as is.
+    ///
+    /// The size will be aligned up to 4K.
+    ///
+    /// Default: 1 MiB.
+    pub fn with_max_merged_size(mut self, max_merged_size: usize) -> Self {
+        self.max_merged_size = bits::align_up(PAGE, max_merged_size.max(PAGE));
+        self
+    }
+
+    /// Read the requested ranges of the partition, merging the contiguous or overlapping ranges.
+    ///
+    /// Returns the results in the order of the requests.
+    pub async fn read(&self, partition: &dyn Partition, requests: &[ReadRequest]) -> Vec<IoResult<IoSlice>> {
+        let merged = self.merge(requests);
+        let handles = merged
+            .iter()
+            .map(|read| {
+                self.io_engine
+                    .read(Box::new(IoSliceMut::new(read.len)), partition, read.offset)
+            })
+            .collect_vec();
+
+        let mut results = std::iter::repeat_with(|| None).take(requests.len()).collect_vec();
+        for (read, (buf, res)) in merged.iter().zip_eq(join_all(handles).await) {
+            match res {
+                Ok(()) => {
+                    let buf = buf.try_into_io_slice_mut().unwrap().into_io_slice();
+                    for &i in read.requests.iter() {
+                        let start = (requests[i].offset - read.offset) as usize;
+                        results[i] = Some(Ok(buf.slice(start..start + requests[i].len)));
+                    }
+                }
+                Err(e) => {
+                    let e = Arc::new(e);
+                    for &i in read.requests.iter() {
+                        results[i] = Some(Err(IoError::other(e.clone())));
+                    }
+                }
+            }
+        }
+        results.into_iter().map(|res| res.unwrap()).collect()
+    }
+
+    fn merge(&self, requests: &[ReadRequest]) -> Vec<MergedRead> {
+        let mut merged: Vec<MergedRead> = vec![];
+        for i in (0..requests.len()).sorted_by_key(|&i| requests[i].offset) {
+            let request = requests[i];
+            bits::assert_aligned(PAGE as u64, request.offset);
+            bits::assert_aligned(PAGE, request.len);
+
+            let range = request.range();
+            if let Some(last) = merged.last_mut() {
+                let end = last.offset + last.len as u64;
+                let merged_end = end.max(range.end);
+                if range.start <= end && (merged_end - last.offset) as usize <= self.max_merged_size {
+                    last.len = (merged_end - last.offset) as usize;
+                    last.requests.push(i);
+                    continue;
+                }
+            }
+            merged.push(MergedRead {
+                offset: range.start,
+                len: request.len,
+                requests: vec![i],
+            });
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::io::{
+        bytes::{IoBuf, IoBufMut},
+        device::{file::FileDeviceBuilder, DeviceBuilder},
+        engine::{psync::PsyncIoEngineBuilder, IoEngineBuilder, IoHandle},
+    };
+
+    /// An I/O engine that counts the reads.
+    #[derive(Debug)]
+    struct CountingIoEngine {
+        inner: Arc<dyn IoEngine>,
+        reads: AtomicUsize,
+    }
+
+    impl IoEngine for CountingIoEngine {
+        fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.inner.read(buf, partition, offset)
+        }
+
+        fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+            self.inner.write(buf, partition, offset)
+        }
+    }
+
+    fn request(page: u64, pages: usize) -> ReadRequest {
+        ReadRequest {
+            offset: page * PAGE as u64,
+            len: pages * PAGE,
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_read_coalescer() {
+        let dir = tempdir().unwrap();
+        let device = FileDeviceBuilder::new(dir.path().join("test_file"))
+            .with_capacity(16 * PAGE)
+            .build()
+            .unwrap();
+        let partition = device.create_partition(16 * PAGE).unwrap();
+        let engine = Arc::new(CountingIoEngine {
+            inner: PsyncIoEngineBuilder::new().build().await.unwrap(),
+            reads: AtomicUsize::new(0),
+        });
+
+        // Fill each page with its index.
+        let mut buf = IoSliceMut::new(16 * PAGE);
+        for (i, page) in buf.chunks_mut(PAGE).enumerate() {
+            page.fill(i as u8);
+        }
+        let (_, res) = engine.write(Box::new(buf), partition.as_ref(), 0).await;
+        res.unwrap();
+
+        let assert_pages = |slice: &IoSlice, first: u8| {
+            for (i, page) in slice.chunks(PAGE).enumerate() {
+                assert!(page.iter().all(|b| *b == first + i as u8));
+            }
+        };
+
+        // Three adjacent block reads become one device read.
+        let coalescer = ReadCoalescer::new(engine.clone());
+        let requests = [request(5, 1), request(3, 1), request(4, 1)];
+        let results = coalescer.read(partition.as_ref(), &requests).await;
+        assert_eq!(engine.reads.swap(0, Ordering::Relaxed), 1);
+        for (request, res) in requests.iter().zip_eq(results) {
+            let slice = res.unwrap();
+            assert_eq!(slice.len(), PAGE);
+            assert_pages(&slice, (request.offset / PAGE as u64) as u8);
+        }
+
+        // Overlapping reads are merged, while the disjoint read is not.
+        let requests = [request(0, 2), request(1, 2), request(8, 1)];
+        let results = coalescer.read(partition.as_ref(), &requests).await;
+        assert_eq!(engine.reads.swap(0, Ordering::Relaxed), 2);
+        for (request, res) in requests.iter().zip_eq(results) {
+            let slice = res.unwrap();
+            assert_eq!(slice.len(), request.len);
+            assert_pages(&slice, (request.offset / PAGE as u64) as u8);
+        }
+
+        // The merged size is bounded.
+        let coalescer = coalescer.with_max_merged_size(2 * PAGE);
+        let requests = (0..6).map(|page| request(page, 1)).collect_vec();
+        let results = coalescer.read(partition.as_ref(), &requests).await;
+        assert_eq!(engine.reads.swap(0, Ordering::Relaxed), 3);
+        for (page, res) in results.into_iter().enumerate() {
+            assert_pages(&res.unwrap(), page as u8);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use futures_core::future::BoxFuture;
+use futures_util::FutureExt;
+
+use crate::io::{
+    bytes::{IoB, IoBuf, IoBufMut},
+    device::{memory::MemoryPartition, Partition},
+    engine::{IoEngine, IoEngineBuilder, IoHandle},
+    error::{IoError, IoResult},
+};
+
+/// Builder for the I/O engine of the memory device.
+#[derive(Debug, Default)]
+pub struct MemoryIoEngineBuilder;
+
+impl IoEngineBuilder for MemoryIoEngineBuilder {
+    fn build(self) -> BoxFuture<'static, IoResult<Arc<dyn IoEngine>>> {
+        async move { Ok(Arc::new(MemoryIoEngine) as Arc<dyn IoEngine>) }.boxed()
+    }
+}
+
+/// The I/O engine that reads and writes the partitions of the memory device in place.
+///
+/// The I/Os on partitions other than [`MemoryPartition`] fail.
+#[derive(Debug)]
+pub struct MemoryIoEngine;
+
+fn memory_partition(partition: &dyn Partition) -> IoResult<&MemoryPartition> {
+    MemoryPartition::downcast(partition).ok_or_else(|| {
+        IoError::other(format!(
+            "memory io engine only supports memory partitions, partition: {partition:?}"
+        ))
+    })
+}
+
+impl IoEngine for MemoryIoEngine {
+    fn read(&self, mut buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+        let res = memory_partition(partition).and_then(|partition| partition.read(&mut buf, offset));
+        async move {
+            let buf: Box<dyn IoB> = buf.into_iob();
+            (buf, res)
+        }
+        .boxed()
+        .into()
+    }
+
+    fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+        let res = memory_partition(partition).and_then(|partition| partition.write(&buf, offset));
+        async move {
+            let buf: Box<dyn IoB> = buf.into_iob();
+            (buf, res)
+        }
+        .boxed()
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{
+        bytes::IoSliceMut,
+        device::{memory::MemoryDeviceBuilder, noop::NoopPartition, validate_alignment, DeviceBuilder},
+        IoAlignment, PAGE,
+    };
+
+    #[test_log::test(tokio::test)]
+    async fn test_memory_io_engine() {
+        let device = MemoryDeviceBuilder::new(16 * PAGE).build().unwrap();
+        let partition = device.create_partition(4 * PAGE).unwrap();
+        let engine = MemoryIoEngineBuilder.build().await.unwrap();
+
+        let mut buf = IoSliceMut::new(2 * PAGE);
+        buf[..PAGE].fill(1);
+        buf[PAGE..].fill(2);
+        let (_, res) = engine
+            .write(Box::new(buf.into_io_slice()), partition.as_ref(), PAGE as u64)
+            .await;
+        res.unwrap();
+
+        let (buf, res) = engine
+            .read(Box::new(IoSliceMut::new(4 * PAGE)), partition.as_ref(), 0)
+            .await;
+        res.unwrap();
+        assert!(buf[..PAGE].iter().all(|b| *b == 0));
+        assert!(buf[PAGE..2 * PAGE].iter().all(|b| *b == 1));
+        assert!(buf[2 * PAGE..3 * PAGE].iter().all(|b| *b == 2));
+        assert!(buf[3 * PAGE..].iter().all(|b| *b == 0));
+
+        // Unaligned I/O is rejected.
+        let (_, res) = engine
+            .read(Box::new(IoSliceMut::new(PAGE)), partition.as_ref(), 512)
+            .await;
+        assert!(matches!(res, Err(IoError::Unaligned { offset: 512, .. })));
+
+        // I/O on other partitions is rejected.
+        let (_, res) = engine
+            .read(Box::new(IoSliceMut::new(PAGE)), &NoopPartition::default(), 0)
+            .await;
+        assert!(matches!(res, Err(IoError::Other(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_memory_io_engine_alignment() {
+        let engine = MemoryIoEngineBuilder.build().await.unwrap();
+
+        for align in [512, 8192] {
+            let alignment = IoAlignment::new(align).unwrap();
+            let device = MemoryDeviceBuilder::new(16 * align)
+                .with_alignment(alignment)
+                .build()
+                .unwrap();
+            assert_eq!(device.alignment(), alignment);
+            let partition = device.create_partition(4 * align).unwrap();
+
+            let mut buf = IoSliceMut::with_alignment(align, alignment);
+            buf.fill(42);
+            let (_, res) = engine
+                .write(Box::new(buf.into_io_slice()), partition.as_ref(), align as u64)
+                .await;
+            res.unwrap();
+
+            let (buf, res) = engine
+                .read(
+                    Box::new(IoSliceMut::with_alignment(2 * align, alignment)),
+                    partition.as_ref(),
+                    0,
+                )
+                .await;
+            res.unwrap();
+            assert!(buf[..align].iter().all(|b| *b == 0));
+            assert!(buf[align..].iter().all(|b| *b == 42));
+
+            let (_, res) = engine
+                .read(
+                    Box::new(IoSliceMut::with_alignment(align, alignment)),
+                    partition.as_ref(),
+                    align as u64 / 2,
+                )
+                .await;
+            assert!(matches!(res, Err(IoError::Unaligned { .. })));
+        }
+
+        for align in [512, 8192] {
+            let device = MemoryDeviceBuilder::new(16 * PAGE)
+                .with_alignment(IoAlignment::new(align).unwrap())
+                .build()
+                .unwrap();
+            validate_alignment(device.as_ref()).unwrap();
+        }
+    }
+}
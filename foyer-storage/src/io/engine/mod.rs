@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod coalesce;
+pub mod memory;
 pub mod monitor;
 pub mod noop;
 pub mod psync;
@@ -25,10 +27,17 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures_core::future::BoxFuture;
+use futures_util::{
+    future::{join_all, Shared},
+    FutureExt,
+};
+use parking_lot::Mutex;
 use pin_project::pin_project;
+use tokio::sync::oneshot;
 
 use crate::io::{
     bytes::{IoB, IoBuf, IoBufMut},
@@ -64,6 +73,110 @@ impl Future for IoHandle {
     }
 }
 
+/// Ordering mode of the writes of an I/O engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderingMode {
+    /// Writes are issued as soon as they are submitted, and may complete in any order.
+    #[default]
+    Relaxed,
+    /// A write is only issued after the previously submitted write completes and is durable, so a later write is never
+    /// durable before an earlier one.
+    ///
+    /// Each write is followed by a data sync of the file before it completes, e.g. `fdatasync(2)` with the psync engine
+    /// and a linked `IORING_OP_FSYNC` with the io_uring engine.
+    ///
+    /// The writes are serialized, which trades the write parallelism for the ordering. Reads are not affected.
+    Ordered,
+}
+
+/// Dependency chain of the writes of an I/O engine with [`OrderingMode::Ordered`].
+#[derive(Debug, Default)]
+pub(crate) struct WriteChain {
+    /// Completion of the last chained write and all writes before it.
+    tail: Mutex<Option<Shared<BoxFuture<'static, ()>>>>,
+}
+
+impl WriteChain {
+    /// Chain the write after the previously chained writes.
+    ///
+    /// The write future must not issue the write before it is polled. The next write proceeds after the returned
+    /// future completes or is dropped, and all writes before it complete or are dropped.
+    pub(crate) fn chain<F>(&self, write: F) -> impl Future<Output = F::Output> + Send + 'static
+    where
+        F: Future + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel::<()>();
+        let prev = {
+            let mut tail = self.tail.lock();
+            let prev = tail.take();
+            // Track the completion independently of the write future, so dropping the write doesn't break the chain.
+            let done = {
+                let prev = prev.clone();
+                async move {
+                    if let Some(prev) = prev {
+                        prev.await;
+                    }
+                    let _ = rx.await;
+                }
+            };
+            *tail = Some(done.boxed().shared());
+            prev
+        };
+        async move {
+            if let Some(prev) = prev {
+                prev.await;
+            }
+            let output = write.await;
+            drop(tx);
+            output
+        }
+    }
+}
+
+/// An I/O operation of a batch submitted with [`IoEngine::submit_batch`].
+pub enum IoOp<'a> {
+    /// Read data into the buffer from the specified partition and offset.
+    Read {
+        /// Buffer to read into.
+        buf: Box<dyn IoBufMut>,
+        /// Partition to read from.
+        partition: &'a dyn Partition,
+        /// Offset in the partition.
+        offset: u64,
+    },
+    /// Write data from the buffer to the specified partition and offset.
+    Write {
+        /// Buffer to write from.
+        buf: Box<dyn IoBuf>,
+        /// Partition to write to.
+        partition: &'a dyn Partition,
+        /// Offset in the partition.
+        offset: u64,
+    },
+}
+
+impl Debug for IoOp<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read { buf, partition, offset } => f
+                .debug_struct("Read")
+                .field("len", &buf.len())
+                .field("partition", &partition.id())
+                .field("offset", offset)
+                .finish(),
+            Self::Write { buf, partition, offset } => f
+                .debug_struct("Write")
+                .field("len", &buf.len())
+                .field("partition", &partition.id())
+                .field("offset", offset)
+                .finish(),
+        }
+    }
+}
+
+/// Buffers and results of a batch submitted with [`IoEngine::submit_batch`], in the order of the operations.
+pub type IoBatchResult = Vec<(Box<dyn IoB>, IoResult<()>)>;
+
 /// I/O engine builder trait.
 pub trait IoEngineBuilder: Send + Sync + 'static + Debug {
     /// Build an I/O engine from the given configuration.
@@ -76,6 +189,34 @@ pub trait IoEngine: Send + Sync + 'static + Debug {
     fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle;
     /// Write data from the buffer to the specified block and offset.
     fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle;
+
+    /// Submit a batch of I/O operations.
+    ///
+    /// All operations are submitted before any of them is awaited, so the engines with a submission queue can submit
+    /// them together, e.g. the io_uring based engine submits the operations queued to its thread with a single
+    /// `io_uring_enter`. The operations of a batch are not ordered, so a batch must not contain overlapping operations
+    /// that include a write.
+    ///
+    /// Returns the buffers and the results in the order of the operations.
+    fn submit_batch(&self, ops: Vec<IoOp<'_>>) -> BoxFuture<'static, IoBatchResult> {
+        let handles = ops
+            .into_iter()
+            .map(|op| match op {
+                IoOp::Read { buf, partition, offset } => self.read(buf, partition, offset),
+                IoOp::Write { buf, partition, offset } => self.write(buf, partition, offset),
+            })
+            .collect::<Vec<_>>();
+        join_all(handles).boxed()
+    }
+
+    /// Cumulative time spent waiting for a free submission slot.
+    ///
+    /// A growing value indicates that the I/O depth of the engine is a bottleneck.
+    ///
+    /// Default: always zero for engines without a bounded submission queue.
+    fn submit_stall_total(&self) -> Duration {
+        Duration::ZERO
+    }
 }
 
 #[cfg(test)]
@@ -120,6 +261,115 @@ mod tests {
         assert_eq!(b1, b2);
     }
 
+    async fn test_submit_batch(engine: Arc<dyn IoEngine>, device: &dyn Device) {
+        let page = |v: u8| {
+            let mut buf = IoSliceMut::new(4 * KIB);
+            buf.fill(v);
+            buf
+        };
+        let partition = device.partition(1);
+
+        let (_, res) = engine.write(Box::new(page(1)), partition.as_ref(), 0).await;
+        res.unwrap();
+        let (_, res) = engine
+            .write(Box::new(page(2)), partition.as_ref(), 4 * KIB as u64)
+            .await;
+        res.unwrap();
+
+        let ops = vec![
+            IoOp::Write {
+                buf: Box::new(page(3).into_io_slice()),
+                partition: partition.as_ref(),
+                offset: 8 * KIB as u64,
+            },
+            IoOp::Read {
+                buf: Box::new(IoSliceMut::new(4 * KIB)),
+                partition: partition.as_ref(),
+                offset: 0,
+            },
+            IoOp::Write {
+                buf: Box::new(page(4).into_io_slice()),
+                partition: partition.as_ref(),
+                offset: 12 * KIB as u64,
+            },
+            IoOp::Read {
+                buf: Box::new(IoSliceMut::new(4 * KIB)),
+                partition: partition.as_ref(),
+                offset: 4 * KIB as u64,
+            },
+        ];
+        let results = engine.submit_batch(ops).await;
+        assert_eq!(results.len(), 4);
+
+        // The buffers are returned in the order of the operations.
+        for ((buf, res), v) in results.into_iter().zip([3u8, 1, 4, 2]) {
+            res.unwrap();
+            assert!(buf.iter().all(|b| *b == v));
+        }
+
+        let (buf, res) = engine
+            .read(Box::new(IoSliceMut::new(8 * KIB)), partition.as_ref(), 8 * KIB as u64)
+            .await;
+        res.unwrap();
+        let buf = buf.try_into_io_slice_mut().unwrap();
+        assert!(buf[..4 * KIB].iter().all(|b| *b == 3));
+        assert!(buf[4 * KIB..].iter().all(|b| *b == 4));
+    }
+
+    async fn test_ordered_writes(engine: Arc<dyn IoEngine>, device: &dyn Device) {
+        const WRITES: usize = 8;
+
+        let partition = device.partition(2);
+
+        // The writes overlap from the start of the partition, and each write is smaller than the previous one. The
+        // earlier writes are larger, so they would complete later and overwrite the later writes without the ordering.
+        // The handles are polled in the reverse order.
+        let handles = (0..WRITES)
+            .map(|i| {
+                let mut buf = IoSliceMut::new((WRITES - i) * 16 * KIB);
+                buf.fill(i as u8);
+                engine.write(Box::new(buf.into_io_slice()), partition.as_ref(), 0)
+            })
+            .collect::<Vec<_>>();
+        for (_, res) in join_all(handles.into_iter().rev()).await {
+            res.unwrap();
+        }
+
+        // Each 16 KiB range holds the data of the last write that covers it, so every write is ordered after all the
+        // writes before it.
+        let (buf, res) = engine
+            .read(Box::new(IoSliceMut::new(WRITES * 16 * KIB)), partition.as_ref(), 0)
+            .await;
+        res.unwrap();
+        for (i, range) in buf.chunks(16 * KIB).enumerate() {
+            assert!(range.iter().all(|b| *b as usize == WRITES - 1 - i));
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_write_chain() {
+        let chain = WriteChain::default();
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let record = |i: usize, delay: Duration| {
+            let order = order.clone();
+            async move {
+                tokio::time::sleep(delay).await;
+                order.lock().push(i);
+            }
+        };
+
+        // The earlier write is delayed, and the later write is polled first.
+        let w1 = chain.chain(record(1, Duration::from_millis(50)));
+        let w2 = chain.chain(record(2, Duration::ZERO));
+        // A dropped write doesn't block the writes after it.
+        drop(chain.chain(record(3, Duration::ZERO)));
+        let w4 = chain.chain(record(4, Duration::ZERO));
+
+        join_all([w4.boxed(), w2.boxed(), w1.boxed()]).await;
+        assert_eq!(*order.lock(), vec![1, 2, 4]);
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_io_engine() {
         let dir = tempdir().unwrap();
@@ -134,12 +384,32 @@ mod tests {
                 .build()
                 .await
                 .unwrap();
-            test_read_write(engine, device.as_ref()).await;
+            test_read_write(engine.clone(), device.as_ref()).await;
+            test_submit_batch(engine, device.as_ref()).await;
+
+            let engine = UringIoEngineBuilder::new()
+                .with_threads(4)
+                .with_io_depth(64)
+                .with_ordering_mode(OrderingMode::Ordered)
+                .build()
+                .await
+                .unwrap();
+            test_read_write(engine.clone(), device.as_ref()).await;
+            test_ordered_writes(engine, device.as_ref()).await;
         }
 
         let path = dir.path().join("test_file_1");
         let device = build_test_file_device(&path).unwrap();
         let engine = PsyncIoEngineBuilder::new().build().await.unwrap();
-        test_read_write(engine, device.as_ref()).await;
+        test_read_write(engine.clone(), device.as_ref()).await;
+        test_submit_batch(engine, device.as_ref()).await;
+
+        let engine = PsyncIoEngineBuilder::new()
+            .with_ordering_mode(OrderingMode::Ordered)
+            .build()
+            .await
+            .unwrap();
+        test_read_write(engine.clone(), device.as_ref()).await;
+        test_ordered_writes(engine, device.as_ref()).await;
     }
 }
@@ -12,14 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt::Debug, sync::Arc, time::Instant};
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use foyer_common::metrics::Metrics;
+use foyer_common::{bits, metrics::Metrics};
+use futures_core::future::BoxFuture;
+use futures_util::FutureExt;
 
 use crate::io::{
-    bytes::{IoBuf, IoBufMut},
-    device::Partition,
-    engine::{IoEngine, IoHandle},
+    bytes::{IoB, IoBuf, IoBufMut},
+    device::{statistics::Statistics, Partition},
+    engine::{IoBatchResult, IoEngine, IoHandle, IoOp},
+    error::IoError,
+    IoAlignment,
 };
 
 #[derive(Debug)]
@@ -40,6 +48,46 @@ impl MonitoredIoEngine {
     }
 }
 
+/// Check the buffer and the offset are aligned to the IO alignment of the device, but at least 4K, which is the same
+/// layout as the block engine uses and satisfies direct I/O.
+///
+/// Buffers are allocated with an explicit alignment by [`crate::io::bytes`], so this is only a guard against misuse.
+fn debug_assert_aligned<B: IoB + ?Sized>(buf: &B, partition: &dyn Partition, offset: u64) {
+    let align = partition.alignment().max(IoAlignment::default()).get();
+    let (ptr, len) = buf.as_raw_parts();
+    bits::debug_assert_aligned(align, ptr as usize);
+    bits::debug_assert_aligned(align, len);
+    bits::debug_assert_aligned(align as u64, offset);
+}
+
+/// Reject the IO fast on a fenced device.
+fn fenced(buf: Box<dyn IoB>, statistics: &Statistics) -> IoHandle {
+    let health = statistics.health();
+    async move { (buf, Err(IoError::Device { health })) }.boxed().into()
+}
+
+/// Record the IO result to the device health on completion.
+fn track(handle: IoHandle, statistics: Arc<Statistics>) -> IoHandle {
+    async move {
+        let (buf, res) = handle.await;
+        statistics.record_io_result(res.is_ok());
+        (buf, res)
+    }
+    .boxed()
+    .into()
+}
+
+/// Keep the span open until the IO completes, so it measures the IO latency instead of the submission.
+#[cfg(feature = "tracing")]
+fn in_span<F>(future: F, span: fastrace::Span) -> BoxFuture<'static, F::Output>
+where
+    F: std::future::Future + Send + 'static,
+{
+    use fastrace::future::FutureExt as _;
+
+    future.in_span(span).boxed()
+}
+
 impl Debug for MonitoredIoEngine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MonitoredIoEngine")
@@ -53,7 +101,27 @@ impl IoEngine for MonitoredIoEngine {
         let now = Instant::now();
         let bytes = buf.len();
 
-        let handle = self.inner.io_engine.read(buf, partition, offset);
+        #[cfg(feature = "tracing")]
+        let span = fastrace::Span::enter_with_local_parent("foyer::storage::io::engine::monitor::read")
+            .with_properties(|| {
+                [
+                    ("partition", partition.id().to_string()),
+                    ("offset", offset.to_string()),
+                    ("bytes", bytes.to_string()),
+                ]
+            });
+
+        debug_assert_aligned(&*buf, partition, offset);
+
+        if partition.statistics().is_fenced() {
+            return fenced(buf.into_iob(), partition.statistics());
+        }
+        let handle = track(
+            self.inner.io_engine.read(buf, partition, offset),
+            partition.statistics().clone(),
+        );
+        #[cfg(feature = "tracing")]
+        let handle = in_span(handle, span).into();
 
         partition.statistics().record_disk_read(bytes);
         self.inner.metrics.storage_disk_read.increase(1);
@@ -70,7 +138,27 @@ impl IoEngine for MonitoredIoEngine {
         let now = Instant::now();
         let bytes = buf.len();
 
-        let handle = self.inner.io_engine.write(buf, partition, offset);
+        #[cfg(feature = "tracing")]
+        let span = fastrace::Span::enter_with_local_parent("foyer::storage::io::engine::monitor::write")
+            .with_properties(|| {
+                [
+                    ("partition", partition.id().to_string()),
+                    ("offset", offset.to_string()),
+                    ("bytes", bytes.to_string()),
+                ]
+            });
+
+        debug_assert_aligned(&*buf, partition, offset);
+
+        if partition.statistics().is_fenced() {
+            return fenced(buf.into_iob(), partition.statistics());
+        }
+        let handle = track(
+            self.inner.io_engine.write(buf, partition, offset),
+            partition.statistics().clone(),
+        );
+        #[cfg(feature = "tracing")]
+        let handle = in_span(handle, span).into();
 
         partition.statistics().record_disk_write(bytes);
         self.inner.metrics.storage_disk_write.increase(1);
@@ -82,4 +170,233 @@ impl IoEngine for MonitoredIoEngine {
 
         handle
     }
+
+    fn submit_batch(&self, ops: Vec<IoOp<'_>>) -> BoxFuture<'static, IoBatchResult> {
+        let now = Instant::now();
+
+        #[cfg(feature = "tracing")]
+        let span = fastrace::Span::enter_with_local_parent("foyer::storage::io::engine::monitor::submit_batch")
+            .with_property(|| ("ops", ops.len().to_string()));
+
+        // Results of the operations rejected on the fenced devices, `None` for the forwarded ones.
+        let mut rejected = Vec::with_capacity(ops.len());
+        let mut forwarded = Vec::with_capacity(ops.len());
+        let mut statistics = Vec::with_capacity(ops.len());
+        let (mut reads, mut writes) = (0, 0);
+        for op in ops {
+            let (partition, bytes) = match &op {
+                IoOp::Read { buf, partition, offset } => {
+                    debug_assert_aligned(&**buf, *partition, *offset);
+                    (*partition, buf.len())
+                }
+                IoOp::Write { buf, partition, offset } => {
+                    debug_assert_aligned(&**buf, *partition, *offset);
+                    (*partition, buf.len())
+                }
+            };
+
+            if partition.statistics().is_fenced() {
+                let buf = match op {
+                    IoOp::Read { buf, .. } => buf.into_iob(),
+                    IoOp::Write { buf, .. } => buf.into_iob(),
+                };
+                let health = partition.statistics().health();
+                rejected.push(Some((buf, Err(IoError::Device { health }))));
+                continue;
+            }
+
+            if let IoOp::Read { .. } = op {
+                partition.statistics().record_disk_read(bytes);
+                self.inner.metrics.storage_disk_read_bytes.increase(bytes as u64);
+                reads += 1;
+            } else {
+                partition.statistics().record_disk_write(bytes);
+                self.inner.metrics.storage_disk_write_bytes.increase(bytes as u64);
+                writes += 1;
+            }
+            statistics.push(partition.statistics().clone());
+            forwarded.push(op);
+            rejected.push(None);
+        }
+
+        let batch = self.inner.io_engine.submit_batch(forwarded);
+
+        let elapsed = now.elapsed().as_secs_f64();
+        self.inner.metrics.storage_disk_read.increase(reads);
+        self.inner.metrics.storage_disk_write.increase(writes);
+        for _ in 0..reads {
+            self.inner.metrics.storage_disk_read_duration.record(elapsed);
+        }
+        for _ in 0..writes {
+            self.inner.metrics.storage_disk_write_duration.record(elapsed);
+        }
+
+        let future = async move {
+            // Record the IO results to the device health on completion, the same as `track`.
+            let mut results = batch.await.into_iter().zip(statistics).map(|((buf, res), statistics)| {
+                statistics.record_io_result(res.is_ok());
+                (buf, res)
+            });
+            rejected
+                .into_iter()
+                .map(|res| res.unwrap_or_else(|| results.next().unwrap()))
+                .collect()
+        };
+        #[cfg(feature = "tracing")]
+        let future = in_span(future, span);
+        future.boxed()
+    }
+
+    fn submit_stall_total(&self) -> Duration {
+        self.inner.io_engine.submit_stall_total()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::io::{
+        bytes::IoSliceMut,
+        device::{noop::NoopPartition, statistics::DeviceHealth},
+    };
+
+    /// An I/O engine that fails all IOs while `failing` is set.
+    #[derive(Debug, Default)]
+    struct FailingIoEngine {
+        failing: AtomicBool,
+        batches: AtomicUsize,
+    }
+
+    impl FailingIoEngine {
+        fn handle(&self, buf: Box<dyn IoB>) -> IoHandle {
+            let res = if self.failing.load(Ordering::Relaxed) {
+                Err(IoError::other("injected failure"))
+            } else {
+                Ok(())
+            };
+            async move { (buf, res) }.boxed().into()
+        }
+    }
+
+    impl IoEngine for FailingIoEngine {
+        fn read(&self, buf: Box<dyn IoBufMut>, _: &dyn Partition, _: u64) -> IoHandle {
+            self.handle(buf.into_iob())
+        }
+
+        fn write(&self, buf: Box<dyn IoBuf>, _: &dyn Partition, _: u64) -> IoHandle {
+            self.handle(buf.into_iob())
+        }
+
+        fn submit_batch(&self, ops: Vec<IoOp<'_>>) -> BoxFuture<'static, IoBatchResult> {
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            let handles = ops
+                .into_iter()
+                .map(|op| match op {
+                    IoOp::Read { buf, .. } => self.handle(buf.into_iob()),
+                    IoOp::Write { buf, .. } => self.handle(buf.into_iob()),
+                })
+                .collect::<Vec<_>>();
+            join_all(handles).boxed()
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_fence_failed_device() {
+        let engine = Arc::new(FailingIoEngine::default());
+        let monitored = MonitoredIoEngine::new(engine.clone(), Arc::new(Metrics::noop()));
+        let partition = NoopPartition::default();
+
+        let (_, res) = monitored.read(Box::new(IoSliceMut::new(4096)), &partition, 0).await;
+        assert!(res.is_ok());
+        assert_eq!(partition.statistics().health(), DeviceHealth::Healthy);
+
+        engine.failing.store(true, Ordering::Relaxed);
+        while partition.statistics().health() != DeviceHealth::Failed {
+            let (_, res) = monitored.read(Box::new(IoSliceMut::new(4096)), &partition, 0).await;
+            assert!(matches!(res, Err(IoError::Other(_))));
+        }
+
+        // IOs on the fenced device are rejected without reaching the inner engine.
+        engine.failing.store(false, Ordering::Relaxed);
+        let (_, res) = monitored.read(Box::new(IoSliceMut::new(4096)), &partition, 0).await;
+        assert!(matches!(
+            res,
+            Err(IoError::Device {
+                health: DeviceHealth::Failed
+            })
+        ));
+        let (_, res) = monitored
+            .write(Box::new(IoSliceMut::new(4096).into_io_slice()), &partition, 0)
+            .await;
+        assert!(matches!(res, Err(IoError::Device { .. })));
+
+        partition.statistics().reset_health();
+        let (_, res) = monitored.read(Box::new(IoSliceMut::new(4096)), &partition, 0).await;
+        assert!(res.is_ok());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_submit_batch() {
+        let engine = Arc::new(FailingIoEngine::default());
+        let monitored = MonitoredIoEngine::new(engine.clone(), Arc::new(Metrics::noop()));
+        let healthy = NoopPartition::default();
+        let fenced = NoopPartition::default();
+        while !fenced.statistics().is_fenced() {
+            fenced.statistics().record_io_result(false);
+        }
+
+        // The operations on the fenced device are rejected, the rest are forwarded to the inner engine as one batch.
+        let ops = vec![
+            IoOp::Read {
+                buf: Box::new(IoSliceMut::new(4096)),
+                partition: &healthy,
+                offset: 0,
+            },
+            IoOp::Write {
+                buf: Box::new(IoSliceMut::new(4096).into_io_slice()),
+                partition: &fenced,
+                offset: 0,
+            },
+            IoOp::Write {
+                buf: Box::new(IoSliceMut::new(4096).into_io_slice()),
+                partition: &healthy,
+                offset: 4096,
+            },
+        ];
+        let results = monitored.submit_batch(ops).await;
+        assert_eq!(engine.batches.load(Ordering::Relaxed), 1);
+        assert!(results[0].1.is_ok());
+        assert!(matches!(results[1].1, Err(IoError::Device { .. })));
+        assert!(results[2].1.is_ok());
+        assert_eq!(healthy.statistics().disk_read_ios(), 1);
+        assert_eq!(healthy.statistics().disk_write_ios(), 1);
+        assert_eq!(fenced.statistics().disk_write_ios(), 0);
+
+        // The results of the batch are recorded to the device health.
+        engine.failing.store(true, Ordering::Relaxed);
+        while !healthy.statistics().is_fenced() {
+            let ops = vec![IoOp::Read {
+                buf: Box::new(IoSliceMut::new(4096)),
+                partition: &healthy,
+                offset: 0,
+            }];
+            let results = monitored.submit_batch(ops).await;
+            assert!(matches!(results[0].1, Err(IoError::Other(_))));
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test_log::test(tokio::test)]
+    #[should_panic]
+    async fn test_unaligned_offset() {
+        let engine = Arc::new(FailingIoEngine::default());
+        let monitored = MonitoredIoEngine::new(engine, Arc::new(Metrics::noop()));
+        let partition = NoopPartition::default();
+
+        let _ = monitored.read(Box::new(IoSliceMut::new(4096)), &partition, 1).await;
+    }
 }
@@ -27,7 +27,7 @@ use crate::{
     io::{
         bytes::{IoB, IoBuf, IoBufMut},
         device::Partition,
-        engine::{IoEngine, IoEngineBuilder, IoHandle},
+        engine::{IoEngine, IoEngineBuilder, IoHandle, OrderingMode, WriteChain},
         error::{IoError, IoResult},
     },
     RawFile,
@@ -74,6 +74,7 @@ impl DerefMut for FileHandle {
 #[derive(Debug)]
 pub struct PsyncIoEngineBuilder {
     handle: Option<tokio::runtime::Handle>,
+    ordering_mode: OrderingMode,
 }
 
 impl Default for PsyncIoEngineBuilder {
@@ -85,7 +86,10 @@ impl Default for PsyncIoEngineBuilder {
 impl PsyncIoEngineBuilder {
     /// Create a new synchronous I/O engine builder with default configurations.
     pub fn new() -> Self {
-        Self { handle: None }
+        Self {
+            handle: None,
+            ordering_mode: OrderingMode::default(),
+        }
     }
 
     /// Set the Tokio runtime to use for blocking operations.
@@ -95,13 +99,28 @@ impl PsyncIoEngineBuilder {
         self.handle = Some(handle);
         self
     }
+
+    /// Set the ordering mode of the writes.
+    ///
+    /// With [`OrderingMode::Ordered`], a write is only issued after the previous write completes, and is synced with
+    /// `fdatasync(2)` before it completes.
+    ///
+    /// Default: [`OrderingMode::Relaxed`].
+    pub fn with_ordering_mode(mut self, ordering_mode: OrderingMode) -> Self {
+        self.ordering_mode = ordering_mode;
+        self
+    }
 }
 
 impl IoEngineBuilder for PsyncIoEngineBuilder {
     fn build(self) -> BoxFuture<'static, IoResult<Arc<dyn IoEngine>>> {
         async move {
             let handle = self.handle.unwrap_or_else(tokio::runtime::Handle::current);
-            let engine = PsyncIoEngine { handle };
+            let write_chain = match self.ordering_mode {
+                OrderingMode::Relaxed => None,
+                OrderingMode::Ordered => Some(WriteChain::default()),
+            };
+            let engine = PsyncIoEngine { handle, write_chain };
             let engine: Arc<dyn IoEngine> = Arc::new(engine);
             Ok(engine)
         }
@@ -112,6 +131,7 @@ impl IoEngineBuilder for PsyncIoEngineBuilder {
 /// The synchronous I/O engine that uses pread(2)/pwrite(2) and tokio thread pool for reading and writing.
 pub struct PsyncIoEngine {
     handle: tokio::runtime::Handle,
+    write_chain: Option<WriteChain>,
 }
 
 impl Debug for PsyncIoEngine {
@@ -160,7 +180,8 @@ impl IoEngine for PsyncIoEngine {
         let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
         let file = FileHandle::from(raw);
         let runtime = self.handle.clone();
-        async move {
+        let sync = self.write_chain.is_some();
+        let write = async move {
             let res = match runtime
                 .spawn_blocking(move || {
                     #[cfg(target_family = "windows")]
@@ -173,6 +194,10 @@ impl IoEngine for PsyncIoEngine {
                         use std::os::unix::fs::FileExt;
                         file.write_all_at(slice, offset).map_err(IoError::from)?;
                     };
+                    // Make the chained write durable before the next one is issued.
+                    if sync {
+                        file.sync_data().map_err(IoError::from)?;
+                    }
                     Ok(())
                 })
                 .await
@@ -182,8 +207,10 @@ impl IoEngine for PsyncIoEngine {
             };
             let buf: Box<dyn IoB> = buf.into_iob();
             (buf, res)
+        };
+        match self.write_chain.as_ref() {
+            Some(chain) => chain.chain(write).boxed().into(),
+            None => write.boxed().into(),
         }
-        .boxed()
-        .into()
     }
 }
@@ -13,21 +13,30 @@
 // limitations under the License.
 
 use std::{
+    collections::VecDeque,
     fmt::Debug,
-    sync::{mpsc, Arc},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use core_affinity::CoreId;
 use futures_core::future::BoxFuture;
-use futures_util::FutureExt;
-use io_uring::{opcode, types::Fd, IoUring};
+use futures_util::{future::join_all, FutureExt};
+use io_uring::{
+    opcode, squeue,
+    types::{self, Fd},
+    IoUring,
+};
 use tokio::sync::oneshot;
 
 use crate::{
     io::{
         bytes::{IoB, IoBuf, IoBufMut},
         device::Partition,
-        engine::{IoEngine, IoEngineBuilder, IoHandle},
+        engine::{IoBatchResult, IoEngine, IoEngineBuilder, IoHandle, IoOp, OrderingMode, WriteChain},
         error::{IoError, IoResult},
     },
     RawFile,
@@ -44,6 +53,7 @@ pub struct UringIoEngineBuilder {
     sqpoll_idle: u32,
     iopoll: bool,
     weight: f64,
+    ordering_mode: OrderingMode,
 }
 
 impl Default for UringIoEngineBuilder {
@@ -64,6 +74,7 @@ impl UringIoEngineBuilder {
             sqpoll_idle: 10,
             iopoll: false,
             weight: 1.0,
+            ordering_mode: OrderingMode::default(),
         }
     }
 
@@ -115,6 +126,17 @@ impl UringIoEngineBuilder {
         self
     }
 
+    /// Set the ordering mode of the writes.
+    ///
+    /// With [`OrderingMode::Ordered`], a write is only submitted to the ring after the previous write completes, and is
+    /// linked with an `IORING_OP_FSYNC` that syncs its data before it completes.
+    ///
+    /// Default: [`OrderingMode::Relaxed`].
+    pub fn with_ordering_mode(mut self, ordering_mode: OrderingMode) -> Self {
+        self.ordering_mode = ordering_mode;
+        self
+    }
+
     /// Enable or disable SQ polling.
     ///
     /// FYI:
@@ -158,6 +180,9 @@ impl IoEngineBuilder for UringIoEngineBuilder {
             if self.threads == 0 {
                 return Err(IoError::other(anyhow::anyhow!("shards must be greater than 0")));
             }
+            if self.io_depth == 0 {
+                return Err(IoError::other(anyhow::anyhow!("io depth must be greater than 0")));
+            }
 
             let (read_txs, read_rxs): (Vec<mpsc::SyncSender<_>>, Vec<mpsc::Receiver<_>>) = (0..self.threads)
                 .map(|_| {
@@ -173,7 +198,14 @@ impl IoEngineBuilder for UringIoEngineBuilder {
                 })
                 .unzip();
 
-            for (i, (read_rx, write_rx)) in read_rxs.into_iter().zip(write_rxs.into_iter()).enumerate() {
+            let (batch_txs, batch_rxs): (Vec<mpsc::SyncSender<_>>, Vec<mpsc::Receiver<_>>) = (0..self.threads)
+                .map(|_| {
+                    let (tx, rx) = mpsc::sync_channel(4096);
+                    (tx, rx)
+                })
+                .unzip();
+
+            for (i, ((read_rx, write_rx), batch_rx)) in read_rxs.into_iter().zip(write_rxs).zip(batch_rxs).enumerate() {
                 let mut builder = IoUring::builder();
                 if self.iopoll {
                     builder.setup_iopoll();
@@ -186,10 +218,24 @@ impl IoEngineBuilder for UringIoEngineBuilder {
                     }
                 }
                 let cpu = if self.cpus.is_empty() { None } else { Some(self.cpus[i]) };
-                let uring = builder.build(self.io_depth as _)?;
+                // `io_uring_setup(2)` fails with `ENOSYS` or `EPERM` if io_uring is not compiled in or is disabled.
+                // A chained write takes 2 submission queue entries with the linked sync, and there is at most one of it
+                // in flight.
+                let entries = match self.ordering_mode {
+                    OrderingMode::Relaxed => self.io_depth,
+                    OrderingMode::Ordered => self.io_depth + 1,
+                };
+                let uring = builder.build(entries as _).map_err(|e| {
+                    IoError::other(anyhow::anyhow!(
+                        "failed to set up io_uring, io_uring may be unavailable on the running kernel, use the psync \
+                         io engine instead: {e}"
+                    ))
+                })?;
                 let shard = UringIoEngineShard {
                     read_rx,
                     write_rx,
+                    batch_rx,
+                    pending: VecDeque::new(),
                     uring,
                     io_depth: self.io_depth,
                     weight: self.weight,
@@ -207,7 +253,17 @@ impl IoEngineBuilder for UringIoEngineBuilder {
                     })?;
             }
 
-            let engine = UringIoEngine { read_txs, write_txs };
+            let write_chain = match self.ordering_mode {
+                OrderingMode::Relaxed => None,
+                OrderingMode::Ordered => Some(WriteChain::default()),
+            };
+            let engine = UringIoEngine {
+                read_txs,
+                write_txs,
+                batch_txs,
+                submit_stall_ns: Arc::default(),
+                write_chain,
+            };
             let engine = Arc::new(engine);
             Ok(engine as Arc<dyn IoEngine>)
         }
@@ -219,8 +275,15 @@ impl IoEngineBuilder for UringIoEngineBuilder {
 enum UringIoType {
     Read,
     Write,
+    /// A write linked with a data sync, it completes after both the write and the sync complete.
+    SyncWrite,
 }
 
+/// Tag of the user data of the write of a [`UringIoType::SyncWrite`], to tell it from the linked sync.
+///
+/// The io contexts are boxed, so the lowest bit of the pointer is always zero.
+const LINKED_WRITE_TAG: u64 = 1;
+
 struct RawBuf {
     ptr: *mut u8,
     len: usize,
@@ -239,11 +302,19 @@ struct UringIoCtx {
     io_type: UringIoType,
     rbuf: RawBuf,
     addr: RawFileAddress,
+    /// Completions left before the io context is released.
+    cqes: usize,
+    /// Result of the io. The error of the write of a [`UringIoType::SyncWrite`] takes precedence over the error of
+    /// the linked sync, which is canceled if the write fails.
+    res: i32,
 }
 
 struct UringIoEngineShard {
     read_rx: mpsc::Receiver<UringIoCtx>,
     write_rx: mpsc::Receiver<UringIoCtx>,
+    batch_rx: mpsc::Receiver<Vec<UringIoCtx>>,
+    /// Io contexts of the received batches that are not pushed to the submission queue yet.
+    pending: VecDeque<UringIoCtx>,
     weight: f64,
     uring: IoUring,
     io_depth: usize,
@@ -259,7 +330,22 @@ impl UringIoEngineShard {
                     break 'prepare;
                 }
 
-                let ctx = if (self.read_inflight as f64) < self.write_inflight as f64 * self.weight {
+                let ctx = match self.pending.pop_front() {
+                    Some(ctx) => Some(ctx),
+                    None => match self.batch_rx.try_recv() {
+                        Err(mpsc::TryRecvError::Disconnected) => return,
+                        Ok(ctxs) => {
+                            // Push the whole batch before submitting, as long as the io depth allows.
+                            self.pending.extend(ctxs);
+                            continue 'prepare;
+                        }
+                        Err(mpsc::TryRecvError::Empty) => None,
+                    },
+                };
+
+                let ctx = if ctx.is_some() {
+                    ctx
+                } else if (self.read_inflight as f64) < self.write_inflight as f64 * self.weight {
                     match self.read_rx.try_recv() {
                         Err(mpsc::TryRecvError::Disconnected) => return,
                         Ok(ctx) => Some(ctx),
@@ -286,26 +372,44 @@ impl UringIoEngineShard {
                     None => break 'prepare,
                 };
 
-                let ctx = Box::new(ctx);
+                let mut ctx = Box::new(ctx);
 
                 let fd = Fd(ctx.addr.file.0);
-                let sqe = match ctx.io_type {
+                let (sqe, sync) = match ctx.io_type {
                     UringIoType::Read => {
                         self.read_inflight += 1;
-                        opcode::Read::new(fd, ctx.rbuf.ptr, ctx.rbuf.len as _)
+                        let sqe = opcode::Read::new(fd, ctx.rbuf.ptr, ctx.rbuf.len as _)
                             .offset(ctx.addr.offset)
-                            .build()
+                            .build();
+                        (sqe, None)
                     }
                     UringIoType::Write => {
                         self.write_inflight += 1;
-                        opcode::Write::new(fd, ctx.rbuf.ptr, ctx.rbuf.len as _)
+                        let sqe = opcode::Write::new(fd, ctx.rbuf.ptr, ctx.rbuf.len as _)
+                            .offset(ctx.addr.offset)
+                            .build();
+                        (sqe, None)
+                    }
+                    UringIoType::SyncWrite => {
+                        self.write_inflight += 1;
+                        ctx.cqes = 2;
+                        let sqe = opcode::Write::new(fd, ctx.rbuf.ptr, ctx.rbuf.len as _)
                             .offset(ctx.addr.offset)
                             .build()
+                            .flags(squeue::Flags::IO_LINK);
+                        let sync = opcode::Fsync::new(fd).flags(types::FsyncFlags::DATASYNC).build();
+                        (sqe, Some(sync))
                     }
                 };
                 let data = Box::into_raw(ctx) as u64;
-                let sqe = sqe.user_data(data);
-                unsafe { self.uring.submission().push(&sqe).unwrap() }
+                match sync {
+                    None => unsafe { self.uring.submission().push(&sqe.user_data(data)).unwrap() },
+                    Some(sync) => unsafe {
+                        let mut sq = self.uring.submission();
+                        sq.push(&sqe.user_data(data | LINKED_WRITE_TAG)).unwrap();
+                        sq.push(&sync.user_data(data)).unwrap();
+                    },
+                }
             }
 
             if self.read_inflight + self.write_inflight > 0 {
@@ -314,16 +418,27 @@ impl UringIoEngineShard {
 
             for cqe in self.uring.completion() {
                 let data = cqe.user_data();
-                let ctx = unsafe { Box::from_raw(data as *mut UringIoCtx) };
+                let ptr = (data & !LINKED_WRITE_TAG) as *mut UringIoCtx;
+
+                // The io context is released after its last completion.
+                let ctx = unsafe { &mut *ptr };
+                let res = cqe.result();
+                if res < 0 && (data & LINKED_WRITE_TAG != 0 || ctx.res >= 0) {
+                    ctx.res = res;
+                }
+                ctx.cqes -= 1;
+                if ctx.cqes > 0 {
+                    continue;
+                }
+                let ctx = unsafe { Box::from_raw(ptr) };
 
                 match ctx.io_type {
                     UringIoType::Read => self.read_inflight -= 1,
-                    UringIoType::Write => self.write_inflight -= 1,
+                    UringIoType::Write | UringIoType::SyncWrite => self.write_inflight -= 1,
                 }
 
-                let res = cqe.result();
-                if res < 0 {
-                    let err = IoError::from_raw_os_error(res);
+                if ctx.res < 0 {
+                    let err = IoError::from_raw_os_error(ctx.res);
                     let _ = ctx.tx.send(Err(err));
                 } else {
                     let _ = ctx.tx.send(Ok(()));
@@ -337,6 +452,10 @@ impl UringIoEngineShard {
 pub struct UringIoEngine {
     read_txs: Vec<mpsc::SyncSender<UringIoCtx>>,
     write_txs: Vec<mpsc::SyncSender<UringIoCtx>>,
+    batch_txs: Vec<mpsc::SyncSender<Vec<UringIoCtx>>>,
+    /// Cumulative time in nanoseconds spent waiting for the full submission queues.
+    submit_stall_ns: Arc<AtomicU64>,
+    write_chain: Option<WriteChain>,
 }
 
 impl Debug for UringIoEngine {
@@ -346,54 +465,139 @@ impl Debug for UringIoEngine {
 }
 
 impl UringIoEngine {
-    fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+    /// Send the io context to the engine thread, record the time blocked on the full submission queue if any.
+    fn submit<T>(submit_stall_ns: &AtomicU64, tx: &mpsc::SyncSender<T>, ctx: T) {
+        let ctx = match tx.try_send(ctx) {
+            Err(mpsc::TrySendError::Full(ctx)) => ctx,
+            Ok(()) | Err(mpsc::TrySendError::Disconnected(_)) => return,
+        };
+        let now = Instant::now();
+        let _ = tx.send(ctx);
+        submit_stall_ns.fetch_add(now.elapsed().as_nanos() as _, Ordering::Relaxed);
+    }
+
+    /// Build the io context of a read, and the receiver of its result.
+    fn prepare_read(
+        buf: &dyn IoBufMut,
+        partition: &dyn Partition,
+        offset: u64,
+    ) -> (UringIoCtx, oneshot::Receiver<IoResult<()>>) {
         let (tx, rx) = oneshot::channel();
-        let shard = &self.read_txs[partition.id() as usize % self.read_txs.len()];
         let (ptr, len) = buf.as_raw_parts();
         let rbuf = RawBuf { ptr, len };
         let (file, offset) = partition.translate(offset);
         let addr = RawFileAddress { file, offset };
-        let _ = shard.send(UringIoCtx {
+        let ctx = UringIoCtx {
             tx,
             io_type: UringIoType::Read,
             rbuf,
             addr,
-        });
+            cqes: 1,
+            res: 0,
+        };
+        (ctx, rx)
+    }
+
+    /// Wait for the result of the io, and return it with the buffer.
+    fn complete(buf: Box<dyn IoB>, rx: oneshot::Receiver<IoResult<()>>) -> IoHandle {
         async move {
             let res = match rx.await {
                 Ok(res) => res,
                 Err(e) => Err(IoError::other(e)),
             };
-            let buf: Box<dyn IoB> = buf.into_iob();
             (buf, res)
         }
         .boxed()
         .into()
     }
 
-    fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+    fn read(&self, buf: Box<dyn IoBufMut>, partition: &dyn Partition, offset: u64) -> IoHandle {
+        let shard = &self.read_txs[partition.id() as usize % self.read_txs.len()];
+        let (ctx, rx) = Self::prepare_read(&*buf, partition, offset);
+        Self::submit(&self.submit_stall_ns, shard, ctx);
+        Self::complete(buf.into_iob(), rx)
+    }
+
+    /// Send the operations to the engine threads with one message per thread, so each thread pushes them to the
+    /// submission queue together and submits them with a single `io_uring_enter`, as long as the io depth allows.
+    ///
+    /// With [`OrderingMode::Ordered`], each write waits for the previous one, so the writes are not batched.
+    fn submit_batch(&self, ops: Vec<IoOp<'_>>) -> BoxFuture<'static, IoBatchResult> {
+        let mut batches = (0..self.batch_txs.len()).map(|_| vec![]).collect::<Vec<_>>();
+        let mut handles = Vec::with_capacity(ops.len());
+        for op in ops {
+            let (shard, ctx, handle) = match op {
+                IoOp::Write { buf, partition, offset } if self.write_chain.is_some() => {
+                    handles.push(self.write(buf, partition, offset));
+                    continue;
+                }
+                IoOp::Read { buf, partition, offset } => {
+                    let (ctx, rx) = Self::prepare_read(&*buf, partition, offset);
+                    (partition.id(), ctx, Self::complete(buf.into_iob(), rx))
+                }
+                IoOp::Write { buf, partition, offset } => {
+                    let (ctx, rx) = Self::prepare_write(&*buf, partition, offset);
+                    (partition.id(), ctx, Self::complete(buf.into_iob(), rx))
+                }
+            };
+            batches[shard as usize % self.batch_txs.len()].push(ctx);
+            handles.push(handle);
+        }
+        for (tx, batch) in self.batch_txs.iter().zip(batches) {
+            if !batch.is_empty() {
+                Self::submit(&self.submit_stall_ns, tx, batch);
+            }
+        }
+        join_all(handles).boxed()
+    }
+
+    /// Build the io context of a write, and the receiver of its result.
+    fn prepare_write(
+        buf: &dyn IoBuf,
+        partition: &dyn Partition,
+        offset: u64,
+    ) -> (UringIoCtx, oneshot::Receiver<IoResult<()>>) {
         let (tx, rx) = oneshot::channel();
-        let shard = &self.write_txs[partition.id() as usize % self.write_txs.len()];
         let (ptr, len) = buf.as_raw_parts();
         let rbuf = RawBuf { ptr, len };
         let (file, offset) = partition.translate(offset);
         let addr = RawFileAddress { file, offset };
-        let _ = shard.send(UringIoCtx {
+        let ctx = UringIoCtx {
             tx,
             io_type: UringIoType::Write,
             rbuf,
             addr,
-        });
-        async move {
-            let res = match rx.await {
-                Ok(res) => res,
-                Err(e) => Err(IoError::other(e)),
-            };
-            let buf: Box<dyn IoB> = buf.into_iob();
-            (buf, res)
-        }
-        .boxed()
-        .into()
+            cqes: 1,
+            res: 0,
+        };
+        (ctx, rx)
+    }
+
+    fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
+        let shard = &self.write_txs[partition.id() as usize % self.write_txs.len()];
+        let (mut ctx, rx) = Self::prepare_write(&*buf, partition, offset);
+
+        let Some(chain) = self.write_chain.as_ref() else {
+            Self::submit(&self.submit_stall_ns, shard, ctx);
+            return Self::complete(buf.into_iob(), rx);
+        };
+
+        // Submit the write to the ring only after the previous write completes, and sync it before it completes.
+        ctx.io_type = UringIoType::SyncWrite;
+        let shard = shard.clone();
+        let submit_stall_ns = self.submit_stall_ns.clone();
+        chain
+            .chain(async move {
+                Self::submit(&submit_stall_ns, &shard, ctx);
+                let res = match rx.await {
+                    Ok(res) => res,
+                    Err(e) => Err(IoError::other(e)),
+                };
+                let buf: Box<dyn IoB> = buf.into_iob();
+                (buf, res)
+            })
+            .boxed()
+            .into()
     }
 }
 
@@ -405,4 +609,147 @@ impl IoEngine for UringIoEngine {
     fn write(&self, buf: Box<dyn IoBuf>, partition: &dyn Partition, offset: u64) -> IoHandle {
         self.write(buf, partition, offset)
     }
+
+    fn submit_batch(&self, ops: Vec<IoOp<'_>>) -> BoxFuture<'static, IoBatchResult> {
+        self.submit_batch(ops)
+    }
+
+    fn submit_stall_total(&self) -> Duration {
+        Duration::from_nanos(self.submit_stall_ns.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{
+        bytes::IoSliceMut,
+        device::{file::FileDeviceBuilder, noop::NoopPartition, DeviceBuilder},
+        engine::IoOp,
+        PAGE,
+    };
+
+    #[test]
+    fn test_submit_stall_total() {
+        // Nobody drains the submission queue of one slot until the test does.
+        let (read_tx, read_rx) = mpsc::sync_channel(1);
+        let (write_tx, _write_rx) = mpsc::sync_channel(1);
+        let (batch_tx, _batch_rx) = mpsc::sync_channel(1);
+        let engine = UringIoEngine {
+            read_txs: vec![read_tx],
+            write_txs: vec![write_tx],
+            batch_txs: vec![batch_tx],
+            submit_stall_ns: Arc::default(),
+            write_chain: None,
+        };
+        let partition = NoopPartition::default();
+
+        let _h1 = engine.read(Box::new(IoSliceMut::new(PAGE)), &partition, 0);
+        assert_eq!(engine.submit_stall_total(), Duration::ZERO);
+
+        let delay = Duration::from_millis(10);
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                std::thread::sleep(delay);
+                read_rx.recv().unwrap();
+            });
+            let _h2 = engine.read(Box::new(IoSliceMut::new(PAGE)), &partition, 0);
+        });
+        assert!(engine.submit_stall_total() >= delay);
+    }
+
+    #[test]
+    fn test_submit_batch_single_message() {
+        let (read_tx, read_rx) = mpsc::sync_channel(16);
+        let (write_tx, write_rx) = mpsc::sync_channel(16);
+        let (batch_tx, batch_rx) = mpsc::sync_channel(16);
+        let engine = UringIoEngine {
+            read_txs: vec![read_tx],
+            write_txs: vec![write_tx],
+            batch_txs: vec![batch_tx],
+            submit_stall_ns: Arc::default(),
+            write_chain: None,
+        };
+        let partition = NoopPartition::default();
+
+        let ops = (0..4)
+            .map(|i| IoOp::Read {
+                buf: Box::new(IoSliceMut::new(PAGE)),
+                partition: &partition,
+                offset: (i * PAGE) as u64,
+            })
+            .chain((0..4).map(|i| IoOp::Write {
+                buf: Box::new(IoSliceMut::new(PAGE).into_io_slice()),
+                partition: &partition,
+                offset: ((i + 4) * PAGE) as u64,
+            }))
+            .collect();
+        let _results = engine.submit_batch(ops);
+
+        // The whole batch is handed over to the engine thread at once.
+        let batch = batch_rx.try_recv().unwrap();
+        assert_eq!(
+            batch.iter().map(|ctx| ctx.io_type).collect::<Vec<_>>(),
+            [[UringIoType::Read; 4], [UringIoType::Write; 4]].concat()
+        );
+        assert!(batch_rx.try_recv().is_err());
+        assert!(read_rx.try_recv().is_err());
+        assert!(write_rx.try_recv().is_err());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_io_depth_zero() {
+        let res = UringIoEngineBuilder::new().with_io_depth(0).build().await;
+        assert!(matches!(res, Err(IoError::Other(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_concurrent_batch_read() {
+        const PAGES: usize = 64;
+
+        let dir = tempfile::tempdir().unwrap();
+        let device = FileDeviceBuilder::new(dir.path().join("uring"))
+            .with_capacity(PAGES * PAGE)
+            .build()
+            .unwrap();
+        let partition = device.create_partition(PAGES * PAGE).unwrap();
+        // A shallow io depth makes the batch exceed the submission queue.
+        let engine = UringIoEngineBuilder::new()
+            .with_threads(2)
+            .with_io_depth(4)
+            .build()
+            .await
+            .unwrap();
+
+        let ops = (0..PAGES)
+            .map(|i| {
+                let mut buf = IoSliceMut::new(PAGE);
+                buf.fill(i as u8);
+                IoOp::Write {
+                    buf: Box::new(buf.into_io_slice()),
+                    partition: partition.as_ref(),
+                    offset: (i * PAGE) as u64,
+                }
+            })
+            .collect();
+        for (_, res) in engine.submit_batch(ops).await {
+            res.unwrap();
+        }
+
+        // Read the pages in the reverse order.
+        let ops = (0..PAGES)
+            .rev()
+            .map(|i| IoOp::Read {
+                buf: Box::new(IoSliceMut::new(PAGE)),
+                partition: partition.as_ref(),
+                offset: (i * PAGE) as u64,
+            })
+            .collect();
+        let results = engine.submit_batch(ops).await;
+        assert_eq!(results.len(), PAGES);
+        for ((buf, res), i) in results.into_iter().zip((0..PAGES).rev()) {
+            res.unwrap();
+            assert!(buf.iter().all(|b| *b == i as u8));
+        }
+    }
 }
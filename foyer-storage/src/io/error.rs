@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::io::device::statistics::DeviceHealth;
+
 /// I/O related error from foyer disk cache io module.
 #[derive(Debug, thiserror::Error)]
 pub enum IoError {
@@ -28,6 +30,49 @@ pub enum IoError {
         /// Required bytes for the operation.
         required: usize,
     },
+    /// The device is fenced after persistent failures and rejects new I/O.
+    #[error("Device is unavailable: health={health:?}")]
+    Device {
+        /// Device health.
+        health: DeviceHealth,
+    },
+    /// The IO alignment does not satisfy the logical block size of the device.
+    #[error(
+        "I/O alignment {align} is not a multiple of the device logical block size {block_size}, use a device with a \
+         logical block size that divides {align}"
+    )]
+    Alignment {
+        /// IO alignment in bytes.
+        align: usize,
+        /// Device logical block size in bytes.
+        block_size: usize,
+    },
+    /// The I/O alignment is not a power of two or is too small.
+    #[error("Invalid I/O alignment {align}, the alignment must be a power of two and at least 512")]
+    InvalidAlignment {
+        /// IO alignment in bytes.
+        align: usize,
+    },
+    /// The offset or the length of the I/O is not aligned to the I/O alignment.
+    #[error("Unaligned I/O: offset={offset}, len={len}, align={align}")]
+    Unaligned {
+        /// Offset of the I/O in bytes.
+        offset: u64,
+        /// Length of the I/O in bytes.
+        len: usize,
+        /// IO alignment in bytes.
+        align: usize,
+    },
+    /// The I/O exceeds the range of the partition.
+    #[error("I/O out of range: offset={offset}, len={len}, size={size}")]
+    OutOfRange {
+        /// Offset of the I/O in bytes.
+        offset: u64,
+        /// Length of the I/O in bytes.
+        len: usize,
+        /// Size of the partition in bytes.
+        size: usize,
+    },
     /// Other I/O error/
     #[error("Other error: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
@@ -16,5 +16,54 @@ pub mod bytes;
 pub mod device;
 pub mod engine;
 pub mod error;
+pub mod pool;
+
+use foyer_common::bits;
+
+use crate::io::error::{IoError, IoResult};
 
 pub const PAGE: usize = 4096;
+
+/// Alignment of the offsets, the lengths and the buffers of the direct I/Os.
+///
+/// The alignment must be a power of two and at least [`IoAlignment::MIN`]. 4K is used by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IoAlignment(usize);
+
+impl Default for IoAlignment {
+    fn default() -> Self {
+        Self(PAGE)
+    }
+}
+
+impl IoAlignment {
+    /// The minimal supported alignment, the logical block size of most block devices.
+    pub const MIN: usize = 512;
+
+    /// Create an I/O alignment, returns an error if the alignment is not a power of two or is less than
+    /// [`IoAlignment::MIN`].
+    pub fn new(align: usize) -> IoResult<Self> {
+        if align < Self::MIN || !bits::is_pow2(align) {
+            return Err(IoError::InvalidAlignment { align });
+        }
+        Ok(Self(align))
+    }
+
+    /// Get the alignment in bytes.
+    pub fn get(&self) -> usize {
+        self.0
+    }
+
+    /// Check that the alignment satisfies the logical block size of a device.
+    ///
+    /// The direct I/Os on the device fail if the alignment is not a multiple of the logical block size.
+    pub fn validate_block_size(&self, block_size: usize) -> IoResult<()> {
+        if block_size == 0 || self.0 % block_size != 0 {
+            return Err(IoError::Alignment {
+                align: self.0,
+                block_size,
+            });
+        }
+        Ok(())
+    }
+}
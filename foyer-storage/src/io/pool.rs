@@ -0,0 +1,315 @@
+// Copyright 2025 foyer Project Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use foyer_common::bits;
+use parking_lot::Mutex;
+
+use crate::io::{
+    bytes::{IoB, IoBuf, IoBufMut, Raw},
+    IoAlignment,
+};
+
+#[derive(Debug, Default)]
+struct Retained {
+    buffers: HashMap<usize, Vec<Raw>>,
+    bytes: usize,
+}
+
+/// A bounded pool that recycles aligned I/O buffers.
+///
+/// Buffers are grouped by their aligned capacities, and are handed out as [`PooledIoBuffer`] guards that return to
+/// the pool on drop. Buffers that would make the pool exceed its max retained bytes are freed instead.
+#[derive(Debug)]
+pub struct IoBufferPool {
+    max_retained_bytes: usize,
+    alignment: IoAlignment,
+    zeroed: bool,
+
+    retained: Mutex<Retained>,
+
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl IoBufferPool {
+    /// Create an I/O buffer pool that retains at most `max_retained_bytes` bytes of idle buffers.
+    pub fn new(max_retained_bytes: usize) -> Self {
+        Self {
+            max_retained_bytes,
+            alignment: IoAlignment::default(),
+            zeroed: true,
+            retained: Mutex::new(Retained::default()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Set the alignment of the buffers.
+    ///
+    /// Default: [`IoAlignment::default`], which is 4K.
+    pub fn with_alignment(mut self, alignment: IoAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Zero the buffers on acquire, so that the prior contents of the reused buffers are never exposed.
+    ///
+    /// Buffers that are fully overwritten before use (e.g. reads into the whole buffer) can opt out of zeroing.
+    ///
+    /// Default: `true`.
+    pub fn with_zeroed(mut self, zeroed: bool) -> Self {
+        self.zeroed = zeroed;
+        self
+    }
+
+    /// Acquire a buffer with **AT LEAST** `size` bytes from the pool, allocate a new one if there is none.
+    ///
+    /// The size of the buffer is aligned up to the alignment of the pool.
+    pub fn acquire(self: &Arc<Self>, size: usize) -> PooledIoBuffer {
+        let capacity = bits::align_up(self.alignment.get(), size.max(1));
+
+        let reused = {
+            let mut retained = self.retained.lock();
+            let raw = retained.buffers.get_mut(&capacity).and_then(|buffers| buffers.pop());
+            if raw.is_some() {
+                retained.bytes -= capacity;
+            }
+            raw
+        };
+
+        let mut raw = match reused {
+            Some(raw) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                raw
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Raw::with_alignment(capacity, self.alignment)
+            }
+        };
+        if self.zeroed {
+            raw.fill(0);
+        }
+
+        PooledIoBuffer {
+            raw: Some(raw),
+            pool: self.clone(),
+        }
+    }
+
+    fn release(&self, raw: Raw) {
+        let capacity = raw.len();
+        let mut retained = self.retained.lock();
+        if retained.bytes + capacity > self.max_retained_bytes {
+            return;
+        }
+        retained.bytes += capacity;
+        retained.buffers.entry(capacity).or_default().push(raw);
+    }
+
+    /// Get the bytes of the idle buffers retained by the pool.
+    pub fn retained_bytes(&self) -> usize {
+        self.retained.lock().bytes
+    }
+
+    /// Get the count of acquires that reuse a retained buffer.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Get the count of acquires that allocate a new buffer.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// An aligned I/O buffer acquired from [`IoBufferPool`], returns to the pool on drop.
+#[derive(Debug)]
+pub struct PooledIoBuffer {
+    raw: Option<Raw>,
+    pool: Arc<IoBufferPool>,
+}
+
+impl Drop for PooledIoBuffer {
+    fn drop(&mut self) {
+        if let Some(raw) = self.raw.take() {
+            self.pool.release(raw);
+        }
+    }
+}
+
+impl Deref for PooledIoBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.raw.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledIoBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.raw.as_mut().unwrap()
+    }
+}
+
+impl AsRef<[u8]> for PooledIoBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for PooledIoBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut *self
+    }
+}
+
+impl IoB for PooledIoBuffer {
+    fn as_raw_parts(&self) -> (*mut u8, usize) {
+        self.raw.as_ref().unwrap().as_raw_parts()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl IoBuf for PooledIoBuffer {
+    fn into_iob(self: Box<Self>) -> Box<dyn IoB> {
+        self
+    }
+}
+
+impl IoBufMut for PooledIoBuffer {
+    fn into_iob(self: Box<Self>) -> Box<dyn IoB> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::PAGE;
+
+    #[test]
+    fn test_io_buffer_pool_reuse() {
+        const WARMUP: usize = 4;
+        const ROUNDS: usize = 10000;
+
+        let pool = Arc::new(IoBufferPool::new(64 * PAGE));
+
+        for _ in 0..WARMUP {
+            let bufs = (0..WARMUP).map(|_| pool.acquire(4 * PAGE)).collect::<Vec<_>>();
+            drop(bufs);
+        }
+        let misses = pool.misses();
+        assert_eq!(misses, WARMUP);
+        assert_eq!(pool.retained_bytes(), WARMUP * 4 * PAGE);
+
+        let ptr = pool.acquire(4 * PAGE).as_ptr();
+        for _ in 0..ROUNDS {
+            let mut buf = pool.acquire(4 * PAGE);
+            assert_eq!(buf.len(), 4 * PAGE);
+            assert_eq!(buf.as_ptr() as usize % PAGE, 0);
+            buf.fill(1);
+            // The pool hands out the most recently released buffer.
+            assert_eq!(buf.as_ptr(), ptr);
+        }
+        assert_eq!(pool.misses(), misses);
+        assert_eq!(pool.hits(), WARMUP * (WARMUP - 1) + ROUNDS + 1);
+
+        // Buffers of other sizes do not share the retained buffers.
+        let buf = pool.acquire(PAGE + 1);
+        assert_eq!(buf.len(), 2 * PAGE);
+        assert_eq!(pool.misses(), misses + 1);
+    }
+
+    #[test]
+    fn test_io_buffer_pool_bounded() {
+        let pool = Arc::new(IoBufferPool::new(4 * PAGE));
+
+        let bufs = (0..3).map(|_| pool.acquire(2 * PAGE)).collect::<Vec<_>>();
+        assert_eq!(pool.retained_bytes(), 0);
+        drop(bufs);
+        // Only 2 buffers fit in the pool, the last one is freed.
+        assert_eq!(pool.retained_bytes(), 4 * PAGE);
+
+        let _b1 = pool.acquire(2 * PAGE);
+        let _b2 = pool.acquire(2 * PAGE);
+        let _b3 = pool.acquire(2 * PAGE);
+        assert_eq!(pool.hits(), 2);
+        assert_eq!(pool.misses(), 4);
+        assert_eq!(pool.retained_bytes(), 0);
+    }
+
+    #[test]
+    fn test_io_buffer_pool_zeroed() {
+        // Reused buffers are zeroed by default.
+        let pool = Arc::new(IoBufferPool::new(4 * PAGE));
+        pool.acquire(PAGE).fill(42);
+        let buf = pool.acquire(PAGE);
+        assert_eq!(pool.hits(), 1);
+        assert!(buf.iter().all(|b| *b == 0));
+        drop(buf);
+
+        let pool = Arc::new(
+            IoBufferPool::new(4 * PAGE)
+                .with_alignment(IoAlignment::new(512).unwrap())
+                .with_zeroed(false),
+        );
+        pool.acquire(512).fill(42);
+        let buf = pool.acquire(512);
+        assert_eq!(buf.len(), 512);
+        assert_eq!(pool.hits(), 1);
+        assert!(buf.iter().all(|b| *b == 42));
+    }
+
+    #[test]
+    fn test_io_buffer_pool_concurrent() {
+        const THREADS: usize = 8;
+
+        let pool = Arc::new(IoBufferPool::new(THREADS * PAGE));
+        let handles = (0..THREADS)
+            .map(|i| {
+                let pool = pool.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let mut buf = pool.acquire(PAGE);
+                        buf.fill(i as u8);
+                        assert!(buf.iter().all(|b| *b == i as u8));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+        assert!(pool.misses() <= THREADS);
+        assert_eq!(pool.hits() + pool.misses(), THREADS * 1000);
+    }
+}
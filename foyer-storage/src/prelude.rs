@@ -34,19 +34,25 @@ pub use crate::{
             combined::CombinedDeviceBuilder,
             file::FileDeviceBuilder,
             fs::FsDeviceBuilder,
+            memory::{MemoryDevice, MemoryDeviceBuilder, MemoryPartition},
             noop::NoopDeviceBuilder,
             partial::PartialDeviceBuilder,
-            statistics::Statistics,
+            statistics::{DeviceHealth, RateLimiter, Statistics},
             throttle::{IopsCounter, Throttle},
-            Device, DeviceBuilder, RawFile,
+            validate_alignment, Device, DeviceBuilder, RawFile, DEFAULT_LOGICAL_BLOCK_SIZE,
         },
         engine::{
+            coalesce::{ReadCoalescer, ReadRequest},
+            memory::{MemoryIoEngine, MemoryIoEngineBuilder},
             noop::{NoopIoEngine, NoopIoEngineBuilder},
             psync::{PsyncIoEngine, PsyncIoEngineBuilder},
-            IoEngine, IoEngineBuilder, IoHandle,
+            IoBatchResult, IoEngine, IoEngineBuilder, IoHandle, IoOp, OrderingMode,
         },
         error::{IoError, IoResult},
+        pool::{IoBufferPool, PooledIoBuffer},
+        IoAlignment,
     },
     runtime::Runtime,
+    serde::ChecksumAlgorithm,
     store::{RuntimeOptions, Store, StoreBuilder, TokioRuntimeOptions},
 };
@@ -22,10 +22,60 @@ use crate::{
     error::{Error, Result},
 };
 
+/// The checksum algorithm of the disk cache entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ChecksumAlgorithm {
+    /// Use crc32c checksum, which is hardware-accelerated on most platforms.
+    #[default]
+    Crc32c,
+    /// Use xxhash64 checksum.
+    XxHash64,
+}
+
+impl ChecksumAlgorithm {
+    /// Get the u8 that represent the checksum algorithm.
+    ///
+    /// The entries written before the checksum algorithm is configurable are checksummed by xxhash64, so xxhash64
+    /// takes 0.
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            Self::XxHash64 => 0,
+            Self::Crc32c => 1,
+        }
+    }
+}
+
+impl From<ChecksumAlgorithm> for u8 {
+    fn from(value: ChecksumAlgorithm) -> Self {
+        value.to_u8()
+    }
+}
+
+impl TryFrom<u8> for ChecksumAlgorithm {
+    type Error = Error;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::XxHash64),
+            1 => Ok(Self::Crc32c),
+            _ => Err(Error::ChecksumAlgorithmNotSupported(value)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Checksummer;
 
 impl Checksummer {
+    pub fn checksum(algorithm: ChecksumAlgorithm, buf: &[u8]) -> u64 {
+        match algorithm {
+            ChecksumAlgorithm::Crc32c => crc32c::crc32c(buf) as u64,
+            ChecksumAlgorithm::XxHash64 => Self::checksum64(buf),
+        }
+    }
+
     pub fn checksum64(buf: &[u8]) -> u64 {
         XxHash64::oneshot(0, buf)
     }
@@ -164,7 +214,7 @@ impl EntryDeserializer {
         ken_len: usize,
         value_len: usize,
         compression: Compression,
-        checksum: Option<u64>,
+        checksum: Option<(ChecksumAlgorithm, u64)>,
     ) -> Result<(K, V)>
     where
         K: StorageKey,
@@ -178,8 +228,8 @@ impl EntryDeserializer {
         }
 
         // calculate checksum if needed
-        if let Some(expected) = checksum {
-            let get = Checksummer::checksum64(&buffer[..value_len + ken_len]);
+        if let Some((algorithm, expected)) = checksum {
+            let get = Checksummer::checksum(algorithm, &buffer[..value_len + ken_len]);
             if expected != get {
                 return Err(Error::ChecksumMismatch { expected, get });
             }
@@ -228,3 +278,42 @@ impl EntryDeserializer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_mismatch() {
+        for algorithm in [ChecksumAlgorithm::Crc32c, ChecksumAlgorithm::XxHash64] {
+            let mut buf = vec![];
+            let info = EntrySerializer::serialize(&42u64, &vec![42u8; 1024], Compression::None, &mut buf).unwrap();
+            let checksum = Checksummer::checksum(algorithm, &buf);
+
+            let (key, value) = EntryDeserializer::deserialize::<u64, Vec<u8>>(
+                &buf,
+                info.key_len,
+                info.value_len,
+                Compression::None,
+                Some((algorithm, checksum)),
+            )
+            .unwrap();
+            assert_eq!(key, 42);
+            assert_eq!(value, vec![42u8; 1024]);
+
+            // Corrupt a single byte of the value.
+            buf[info.value_len / 2] ^= 1;
+            let res = EntryDeserializer::deserialize::<u64, Vec<u8>>(
+                &buf,
+                info.key_len,
+                info.value_len,
+                Compression::None,
+                Some((algorithm, checksum)),
+            );
+            assert!(
+                matches!(res, Err(Error::ChecksumMismatch { expected, .. }) if expected == checksum),
+                "algorithm: {algorithm:?}"
+            );
+        }
+    }
+}
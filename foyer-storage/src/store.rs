@@ -24,6 +24,7 @@ use std::{
 use equivalent::Equivalent;
 use foyer_common::{
     code::{HashBuilder, StorageKey, StorageValue},
+    hasher::hash_with_domain,
     metrics::Metrics,
     properties::{Age, Populated, Properties},
     runtime::BackgroundShutdownRuntime,
@@ -69,6 +70,7 @@ where
     P: Properties,
 {
     hasher: Arc<S>,
+    hash_domain: u64,
 
     keeper: Keeper<K, V, P>,
     engine: Arc<dyn Engine<K, V, P>>,
@@ -165,7 +167,7 @@ where
     {
         let now = Instant::now();
 
-        let hash = self.inner.hasher.hash_one(key);
+        let hash = self.hash(key);
 
         if let Some(piece) = self.inner.keeper.get(hash, key) {
             tracing::trace!(hash, "[store]: load from keeper");
@@ -241,7 +243,7 @@ where
     {
         let now = Instant::now();
 
-        let hash = self.inner.hasher.hash_one(key);
+        let hash = self.hash(key);
         self.inner.engine.delete(hash);
 
         self.inner.metrics.storage_delete.increase(1);
@@ -258,7 +260,7 @@ where
     where
         Q: Hash + Equivalent<K> + ?Sized,
     {
-        let hash = self.inner.hasher.hash_one(key);
+        let hash = self.hash(key);
         self.inner.engine.may_contains(hash)
     }
 
@@ -307,6 +309,14 @@ where
     pub fn is_enabled(&self) -> bool {
         self.inner.engine.type_id() != TypeId::of::<Arc<NoopEngine<K, V, P>>>()
     }
+
+    /// Hash the key the same way as the in-memory cache does.
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        hash_with_domain(self.inner.hasher.hash_one(key), self.inner.hash_domain)
+    }
 }
 
 /// Tokio runtime configuration.
@@ -534,8 +544,10 @@ where
 
         let keeper = Keeper::new(memory.shards());
         let hasher = memory.hash_builder().clone();
+        let hash_domain = memory.hash_domain();
         let inner = StoreInner {
             hasher,
+            hash_domain,
             keeper,
             engine,
             compression,
@@ -191,6 +191,19 @@ where
         }
     }
 
+    /// Set the hash domain of the hybrid cache.
+    ///
+    /// See [`CacheBuilder::with_hash_domain`].
+    pub fn with_hash_domain(self, hash_domain: u64) -> Self {
+        let builder = self.builder.with_hash_domain(hash_domain);
+        HybridCacheBuilderPhaseMemory {
+            name: self.name,
+            options: self.options,
+            metrics: self.metrics,
+            builder,
+        }
+    }
+
     /// Set in-memory cache weighter.
     pub fn with_weighter(self, weighter: impl Weighter<K, V>) -> Self {
         let builder = self.builder.with_weighter(weighter);
@@ -222,6 +235,19 @@ where
         }
     }
 
+    /// Set the max weight of the entries that can be admitted into the in-memory cache.
+    ///
+    /// See [`CacheBuilder::with_max_admit_weight`].
+    pub fn with_max_admit_weight(self, max_admit_weight: usize) -> Self {
+        let builder = self.builder.with_max_admit_weight(max_admit_weight);
+        HybridCacheBuilderPhaseMemory {
+            name: self.name,
+            options: self.options,
+            metrics: self.metrics,
+            builder,
+        }
+    }
+
     /// Continue to modify the disk cache configurations.
     pub fn storage(self) -> HybridCacheBuilderPhaseStorage<K, V, S> {
         let memory = self.builder.build();
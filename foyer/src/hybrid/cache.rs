@@ -91,6 +91,10 @@ macro_rules! try_cancel {
 }
 
 /// Entry properties for in-memory only cache.
+///
+/// The cost is not supported, the cost-aware eviction algorithms treat all entries of the hybrid cache as the same
+/// cost. The ttl is not supported either, for the disk cache doesn't track the expiration, the entries of the hybrid
+/// cache never expire.
 #[derive(Debug, Clone, Default)]
 pub struct HybridCacheProperties {
     disposable: bool,
@@ -16,6 +16,11 @@
 pub use crate::common::tracing::TracingOptions;
 #[cfg(target_os = "linux")]
 pub use crate::storage::{UringIoEngine, UringIoEngineBuilder};
+#[cfg(feature = "custom-eviction")]
+pub use crate::{
+    common::metrics::Metrics,
+    memory::{Data, Error as MemoryError, Eviction, Op, RawCache, RawCacheConfig, RawCacheEntry, Record, StateOwner},
+};
 pub use crate::{
     common::{
         buf::{BufExt, BufMutExt},
@@ -31,16 +36,18 @@ pub use crate::{
         writer::{HybridCacheStorageWriter, HybridCacheWriter},
     },
     memory::{
-        Cache, CacheBuilder, CacheEntry, CacheProperties, EvictionConfig, FetchState, FifoConfig, Filter, LfuConfig,
-        LruConfig, S3FifoConfig, Weighter,
+        Cache, CacheBuilder, CacheEntry, CacheProperties, CacheStats, CacheView, Capacity, DecayLfuConfig,
+        EvictionConfig, FetchState, Fetcher, FifoConfig, Filter, FrequencyBackend, LfuConfig, Lookup, LruConfig,
+        QueueTag, S3FifoConfig, Weighter,
     },
     storage::{
-        AdmitAll, Block, BlockEngineBuilder, BlockStatistics, CombinedDeviceBuilder, Compression, Device,
-        DeviceBuilder, Engine, EngineBuildContext, EngineConfig, EstimatedSize, EvictionInfo, EvictionPicker,
-        FifoPicker, FileDeviceBuilder, FsDeviceBuilder, InvalidRatioPicker, IoEngine, IoEngineBuilder, IoError,
-        IoHandle, IoResult, IopsCounter, Load, NoopDeviceBuilder, NoopIoEngine, NoopIoEngineBuilder,
-        PartialDeviceBuilder, PsyncIoEngine, PsyncIoEngineBuilder, RawFile, RecoverMode, RejectAll, Runtime,
-        RuntimeOptions, Statistics, StorageFilter, StorageFilterCondition, StorageFilterResult, Store, StoreBuilder,
-        Throttle, TokioRuntimeOptions,
+        AdmitAll, Block, BlockEngineBuilder, BlockStatistics, ChecksumAlgorithm, CombinedDeviceBuilder, Compression,
+        Device, DeviceBuilder, Engine, EngineBuildContext, EngineConfig, EstimatedSize, EvictionInfo, EvictionPicker,
+        FifoPicker, FileDeviceBuilder, FsDeviceBuilder, InvalidRatioPicker, IoAlignment, IoBufferPool, IoEngine,
+        IoEngineBuilder, IoError, IoHandle, IoOp, IoResult, IopsCounter, Load, MemoryDevice, MemoryDeviceBuilder,
+        MemoryIoEngine, MemoryIoEngineBuilder, MemoryPartition, NoopDeviceBuilder, NoopIoEngine, NoopIoEngineBuilder,
+        OrderingMode, PartialDeviceBuilder, PooledIoBuffer, PsyncIoEngine, PsyncIoEngineBuilder, RateLimiter, RawFile,
+        RecoverMode, RejectAll, Runtime, RuntimeOptions, Statistics, StorageFilter, StorageFilterCondition,
+        StorageFilterResult, Store, StoreBuilder, Throttle, TokioRuntimeOptions,
     },
 };